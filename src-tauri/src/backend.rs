@@ -0,0 +1,305 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::borrow::Cow;
+use std::time::Duration;
+
+use crate::error::AppError;
+use crate::http::{self, RetryExt};
+use crate::ollama::{self, ChatMessage, ChatResponse, EmbeddingResponse, NdjsonBuffer};
+use crate::openai_backend::OpenAiCompatibleBackend;
+use crate::settings;
+
+/// `keep_alive` as Ollama expects it: a number of seconds or a duration string
+pub(crate) fn keep_alive_value(keep_alive: &str) -> serde_json::Value {
+    keep_alive
+        .parse::<i64>()
+        .map(|secs| json!(secs))
+        .unwrap_or_else(|_| json!(keep_alive))
+}
+
+/// Which server answers chat and embedding requests
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    #[default]
+    Ollama,
+    /// Any server speaking the OpenAI `/v1` API: LM Studio, llamafile, vLLM, text-generation-webui
+    OpenaiCompatible,
+    /// GGUF models run in-process by llama.cpp; needs the `embedded-llm` build feature
+    Embedded,
+}
+
+/// Advanced generation options; unset fields use the server's defaults
+///
+/// User defaults live in `AppSettings::generation`, and a request can override
+/// any field. The OpenAI-compatible backend only supports `seed` and `stop`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationOptions {
+    pub seed: Option<i64>,
+    /// Generation stops when the model outputs any of these
+    pub stop: Vec<String>,
+    pub repeat_penalty: Option<f32>,
+    /// Tokens looked back at for the repeat penalty
+    pub repeat_last_n: Option<i32>,
+    /// 0 = off, 1 = Mirostat, 2 = Mirostat 2.0
+    pub mirostat: Option<u8>,
+    pub mirostat_tau: Option<f32>,
+    pub mirostat_eta: Option<f32>,
+    /// Context window to allocate
+    pub num_ctx: Option<u32>,
+    /// Layers to offload to the GPU (0 = CPU only)
+    pub num_gpu: Option<i32>,
+    /// How long the model stays loaded after a request, e.g. "5m", "1h" or "-1" (forever)
+    pub keep_alive: Option<String>,
+}
+
+impl GenerationOptions {
+    /// These options, with unset fields filled in from `defaults`
+    pub fn or(self, defaults: &GenerationOptions) -> GenerationOptions {
+        GenerationOptions {
+            seed: self.seed.or(defaults.seed),
+            stop: if self.stop.is_empty() { defaults.stop.clone() } else { self.stop },
+            repeat_penalty: self.repeat_penalty.or(defaults.repeat_penalty),
+            repeat_last_n: self.repeat_last_n.or(defaults.repeat_last_n),
+            mirostat: self.mirostat.or(defaults.mirostat),
+            mirostat_tau: self.mirostat_tau.or(defaults.mirostat_tau),
+            mirostat_eta: self.mirostat_eta.or(defaults.mirostat_eta),
+            num_ctx: self.num_ctx.or(defaults.num_ctx),
+            num_gpu: self.num_gpu.or(defaults.num_gpu),
+            keep_alive: self.keep_alive.or_else(|| defaults.keep_alive.clone()),
+        }
+    }
+}
+
+/// Sampling options shared by every backend; backends ignore what they don't support
+#[derive(Debug, Clone)]
+pub struct ChatOptions {
+    pub temperature: f32,
+    pub max_tokens: u32,
+    pub top_p: f32,
+    pub generation: GenerationOptions,
+    pub timeout: Duration,
+}
+
+/// Called with each piece of a streamed answer, and `done` on the last one
+pub type ChunkSink<'a> = dyn FnMut(&str, bool) + Send + 'a;
+
+/// Token counts and timings reported for a streamed answer, when the backend has them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatUsage {
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// Time the server spent reading the prompt
+    pub prompt_eval: Option<Duration>,
+    /// Time the server spent generating the answer
+    pub eval: Option<Duration>,
+}
+
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn chat(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<String, AppError>;
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_chunk: &mut ChunkSink<'_>,
+    ) -> Result<ChatUsage, AppError>;
+
+    async fn embed(&self, model: &str, text: &str) -> Result<Vec<f64>, AppError>;
+}
+
+/// Backend selected in settings
+///
+/// The host of an OpenAI-compatible server is registered with the HTTP guard,
+/// the same way a LAN Ollama host is.
+pub fn from_settings(app_handle: &tauri::AppHandle) -> Box<dyn LlmBackend> {
+    let settings = settings::read_settings(app_handle);
+    match settings.llm_backend {
+        BackendKind::Ollama => Box::new(OllamaBackend {
+            base_url: ollama::ollama_url(app_handle),
+        }),
+        BackendKind::OpenaiCompatible => {
+            let base_url = settings.openai_base_url.trim_end_matches('/').to_string();
+            if let Some(host) = reqwest::Url::parse(&base_url).ok().and_then(|u| u.host_str().map(String::from)) {
+                http::set_backend_host(&host);
+            }
+            Box::new(OpenAiCompatibleBackend {
+                base_url,
+                api_key: Some(settings.openai_api_key).filter(|key| !key.trim().is_empty()),
+            })
+        }
+        BackendKind::Embedded => Box::new(crate::local_llm::LocalBackend::from_settings(app_handle)),
+    }
+}
+
+pub struct OllamaBackend {
+    base_url: String,
+}
+
+/// One line of the /api/chat stream, borrowing strings from the received bytes where possible
+#[derive(Debug, Deserialize)]
+struct ChatStreamLine<'a> {
+    #[serde(borrow)]
+    message: Option<ChatStreamMessage<'a>>,
+    #[serde(default)]
+    done: bool,
+    #[serde(borrow)]
+    error: Option<Cow<'a, str>>,
+    // Only on the final line; durations are in nanoseconds
+    prompt_eval_count: Option<u64>,
+    prompt_eval_duration: Option<u64>,
+    eval_count: Option<u64>,
+    eval_duration: Option<u64>,
+}
+
+/// Message delta in a streamed chunk; the OpenAI-compatible stream uses the same shape
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChatStreamMessage<'a> {
+    #[serde(borrow)]
+    pub(crate) content: Option<Cow<'a, str>>,
+}
+
+impl OllamaBackend {
+    fn chat_body(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions, stream: bool) -> serde_json::Value {
+        let generation = &options.generation;
+        let mut body = json!({
+            "model": model,
+            "messages": messages,
+            "stream": stream,
+            "options": {
+                "temperature": options.temperature,
+                "num_predict": options.max_tokens,
+                "top_p": options.top_p,
+            }
+        });
+
+        let fields = [
+            ("seed", generation.seed.map(|v| json!(v))),
+            ("repeat_penalty", generation.repeat_penalty.map(|v| json!(v))),
+            ("repeat_last_n", generation.repeat_last_n.map(|v| json!(v))),
+            ("mirostat", generation.mirostat.map(|v| json!(v))),
+            ("mirostat_tau", generation.mirostat_tau.map(|v| json!(v))),
+            ("mirostat_eta", generation.mirostat_eta.map(|v| json!(v))),
+            ("num_ctx", generation.num_ctx.map(|v| json!(v))),
+            ("num_gpu", generation.num_gpu.map(|v| json!(v))),
+            ("stop", (!generation.stop.is_empty()).then(|| json!(generation.stop))),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                body["options"][key] = value;
+            }
+        }
+        if let Some(keep_alive) = &generation.keep_alive {
+            body["keep_alive"] = keep_alive_value(keep_alive);
+        }
+        body
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OllamaBackend {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn chat(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<String, AppError> {
+        let response = http::post(&format!("{}/api/chat", self.base_url))?
+            .json(&self.chat_body(model, messages, options, false))
+            .timeout(options.timeout)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::ollama_request("Chat request", e))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ollama_status("Chat", model, response.status()));
+        }
+
+        let data: ChatResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Parse(format!("Failed to parse response: {}", e)))?;
+        Ok(data.message.content)
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_chunk: &mut ChunkSink<'_>,
+    ) -> Result<ChatUsage, AppError> {
+        let response = http::post(&format!("{}/api/chat", self.base_url))?
+            .json(&self.chat_body(model, messages, options, true))
+            .timeout(options.timeout)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::ollama_request("Chat request", e))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ollama_status("Chat", model, response.status()));
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut buffer = NdjsonBuffer::default();
+        let mut usage = ChatUsage::default();
+
+        while let Some(chunk_result) = stream.next().await {
+            let chunk = chunk_result.map_err(|e| AppError::ollama_request("Chat stream", e))?;
+
+            buffer.feed(&chunk, |line| {
+                match serde_json::from_slice::<ChatStreamLine>(line) {
+                    Ok(data) => {
+                        if data.done {
+                            usage = ChatUsage {
+                                prompt_tokens: data.prompt_eval_count,
+                                completion_tokens: data.eval_count,
+                                prompt_eval: data.prompt_eval_duration.map(Duration::from_nanos),
+                                eval: data.eval_duration.map(Duration::from_nanos),
+                            };
+                        }
+                        if let Some(content) = data.message.and_then(|m| m.content) {
+                            on_chunk(&content, data.done);
+                        }
+                        if let Some(error) = data.error {
+                            return Err(AppError::ollama_error(&error));
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to parse JSON line: {}", e);
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(usage)
+    }
+
+    async fn embed(&self, model: &str, text: &str) -> Result<Vec<f64>, AppError> {
+        let response = http::post(&format!("{}/api/embeddings", self.base_url))?
+            .json(&json!({
+                "model": model,
+                "prompt": text,
+            }))
+            .timeout(http::read_timeout())
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::ollama_request("Embedding request", e))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ollama_status("Embedding", model, response.status()));
+        }
+
+        let data: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Parse(format!("Failed to parse response: {}", e)))?;
+        Ok(data.embedding)
+    }
+}
@@ -0,0 +1,482 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::bundle_crypto::{decrypt, encrypt, FORMAT_VERSION};
+use crate::bundle_merge::merge;
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::secure_delete;
+use crate::storage::snapshot;
+use crate::vectorstore::{now_secs, VectorStore};
+
+/// Passphrases shorter than this are rejected on export
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Tables copied from `vectors.db` with their keys, parents first so foreign keys resolve
+///
+/// `watched_folders` stays behind: its paths are folders on the exporting machine,
+/// and resuming watches on them here would fail or pick up unrelated files. The
+/// embedding cache (`embedding_cache.db`) is a cache and isn't bundled either.
+const VECTOR_TABLES: &[(&str, &[&str])] = &[
+    ("indexes", &["id"]),
+    ("embeddings", &["index_id", "chunk_id"]),
+    ("document_sources", &["index_id"]),
+    ("library_documents", &["path"]),
+    ("workspaces", &["id"]),
+    ("workspace_documents", &["workspace_id", "index_id"]),
+    ("chunk_feedback", &["conversation_id", "message_id", "index_id", "chunk_id"]),
+];
+/// Tables copied from `conversations.db` with their keys
+const CONVERSATION_TABLES: &[(&str, &[&str])] = &[
+    ("conversations", &["id"]),
+    ("messages", &["conversation_id", "position"]),
+    ("memory", &["conversation_id"]),
+    ("conversation_stats", &["conversation_id", "model"]),
+    ("context_documents", &["id"]),
+    ("answer_candidates", &["conversation_id", "message_id", "position"]),
+    ("retrieval_traces", &["message_id"]),
+];
+
+const VECTORS_ENTRY: &str = "vectors.db";
+const CONVERSATIONS_ENTRY: &str = "conversations.db";
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u8,
+    app_version: String,
+    created_at: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BundleReport {
+    path: String,
+    /// Size of the encrypted file (export only)
+    bytes: u64,
+    indexes: usize,
+    chunks: usize,
+    workspaces: usize,
+    conversations: usize,
+}
+
+fn count(conn: &Connection, sql: &str) -> Result<usize, String> {
+    conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+        .map(|n| n as usize)
+        .map_err(|e| format!("Failed to count rows: {}", e))
+}
+
+/// Zip the manifest and the database snapshots in `databases` (entry name, file) to `archive`
+fn pack(archive: &Path, manifest: &Manifest, databases: &[(&str, &Path)]) -> Result<(), String> {
+    let file = File::create(archive).map_err(|e| format!("Failed to write bundle: {}", e))?;
+    let mut zip = zip::ZipWriter::new(BufWriter::new(file));
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest = serde_json::to_vec_pretty(manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(MANIFEST_ENTRY, options)
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    zip.write_all(&manifest).map_err(|e| format!("Failed to write bundle: {}", e))?;
+    for (name, path) in databases {
+        zip.start_file(*name, options)
+            .map_err(|e| format!("Failed to write bundle: {}", e))?;
+        let mut snapshot = File::open(path).map_err(|e| format!("Failed to read database snapshot: {}", e))?;
+        std::io::copy(&mut snapshot, &mut zip).map_err(|e| format!("Failed to write bundle: {}", e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to write bundle: {}", e))?
+        .flush()
+        .map_err(|e| format!("Failed to write bundle: {}", e))
+}
+
+/// Zip and encrypt `databases` into a bundle at `dest`; returns the bundle's size
+///
+/// Snapshots are streamed through a temporary archive in `temp`, so memory use
+/// doesn't grow with the size of the stores.
+fn write_bundle(
+    dest: &Path,
+    passphrase: &str,
+    manifest: &Manifest,
+    databases: &[(&str, &Path)],
+    temp: &Path,
+) -> Result<u64, String> {
+    let archive = temp.join(format!("bundle-{}-{}.zip", std::process::id(), now_secs()));
+    let result = pack(&archive, manifest, databases).and_then(|()| {
+        let mut input = BufReader::new(File::open(&archive).map_err(|e| format!("Failed to read bundle: {}", e))?);
+        let mut output =
+            BufWriter::new(File::create(dest).map_err(|e| format!("Failed to write bundle file: {}", e))?);
+        let written = encrypt(&mut input, &mut output, passphrase)
+            .and_then(|()| output.flush().map_err(|e| format!("Failed to write bundle file: {}", e)));
+        drop(output);
+        if written.is_err() {
+            let _ = fs::remove_file(dest);
+        }
+        written
+    });
+    remove_snapshot(&archive);
+    result?;
+    fs::metadata(dest)
+        .map(|m| m.len())
+        .map_err(|e| format!("Failed to read bundle file: {}", e))
+}
+
+/// Databases unpacked from a bundle; securely deleted when dropped
+struct Unpacked {
+    manifest: Manifest,
+    vectors: PathBuf,
+    conversations: PathBuf,
+}
+
+impl Drop for Unpacked {
+    fn drop(&mut self) {
+        remove_snapshot(&self.vectors);
+        remove_snapshot(&self.conversations);
+    }
+}
+
+/// Decrypt the bundle at `path` and unpack its databases into `temp`
+fn read_bundle(path: &Path, passphrase: &str, temp: &Path) -> Result<Unpacked, String> {
+    let id = format!("import-{}-{}", std::process::id(), now_secs());
+    let archive = temp.join(format!("{}.zip", id));
+    let result = (|| {
+        let mut input = BufReader::new(File::open(path).map_err(|e| format!("Failed to read bundle file: {}", e))?);
+        let mut output = BufWriter::new(File::create(&archive).map_err(|e| format!("Failed to unpack bundle: {}", e))?);
+        decrypt(&mut input, &mut output, passphrase)?;
+        output.flush().map_err(|e| format!("Failed to unpack bundle: {}", e))?;
+        drop(output);
+        unpack(&archive, temp, &id)
+    })();
+    remove_snapshot(&archive);
+    result
+}
+
+fn unpack(archive: &Path, temp: &Path, id: &str) -> Result<Unpacked, String> {
+    let file = File::open(archive).map_err(|e| format!("Failed to read bundle contents: {}", e))?;
+    let mut zip = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read bundle contents: {}", e))?;
+    let manifest: Manifest = serde_json::from_reader(
+        zip.by_name(MANIFEST_ENTRY)
+            .map_err(|e| format!("Bundle is missing {}: {}", MANIFEST_ENTRY, e))?,
+    )
+    .map_err(|e| format!("Failed to parse bundle manifest: {}", e))?;
+
+    // Anything written from here on is removed with `unpacked` if a later entry fails
+    let unpacked = Unpacked {
+        manifest,
+        vectors: temp.join(format!("{}-{}", id, VECTORS_ENTRY)),
+        conversations: temp.join(format!("{}-{}", id, CONVERSATIONS_ENTRY)),
+    };
+    for (name, dest) in [(VECTORS_ENTRY, &unpacked.vectors), (CONVERSATIONS_ENTRY, &unpacked.conversations)] {
+        let mut entry = zip
+            .by_name(name)
+            .map_err(|e| format!("Bundle is missing {}: {}", name, e))?;
+        let mut file = File::create(dest).map_err(|e| format!("Failed to unpack bundle: {}", e))?;
+        std::io::copy(&mut entry, &mut file).map_err(|e| format!("Failed to read {} from bundle: {}", name, e))?;
+    }
+    Ok(unpacked)
+}
+
+/// Count what an unpacked bundle holds
+fn bundle_report(unpacked: &Unpacked) -> Result<BundleReport, String> {
+    let mut report = BundleReport::default();
+    let bundle = Connection::open(&unpacked.vectors).map_err(|e| format!("Failed to open bundle database: {}", e))?;
+    report.indexes = count(&bundle, "SELECT COUNT(*) FROM indexes")?;
+    report.chunks = count(&bundle, "SELECT COUNT(*) FROM embeddings")?;
+    report.workspaces = count(&bundle, "SELECT COUNT(*) FROM workspaces")?;
+    let bundle =
+        Connection::open(&unpacked.conversations).map_err(|e| format!("Failed to open bundle database: {}", e))?;
+    report.conversations = count(&bundle, "SELECT COUNT(*) FROM conversations")?;
+    Ok(report)
+}
+
+/// Export document indexes, embeddings, workspaces and chat history as one encrypted file
+///
+/// The databases are snapshotted, zipped and encrypted with XChaCha20-Poly1305
+/// under a key derived from `passphrase` with Argon2id. The passphrase is never
+/// stored; without it the bundle can't be opened.
+#[tauri::command]
+pub async fn export_workspace_bundle(
+    app_handle: tauri::AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<BundleReport, AppError> {
+    log::info!("Exporting workspace bundle to {}", path);
+
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Other(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN)));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let temp = secure_delete::temp_dir(&app_handle)?;
+        let id = format!("bundle-{}-{}", std::process::id(), now_secs());
+        let vectors_path = temp.join(format!("{}-{}", id, VECTORS_ENTRY));
+        let conversations_path = temp.join(format!("{}-{}", id, CONVERSATIONS_ENTRY));
+        let mut report = BundleReport {
+            path: path.clone(),
+            ..Default::default()
+        };
+
+        let result = (|| {
+            // Each store is locked only while it's snapshotted, not while the bundle is written
+            {
+                let store = app_handle.state::<VectorStore>();
+                let conn = store.conn();
+                report.indexes = count(&conn, "SELECT COUNT(*) FROM indexes")?;
+                report.chunks = count(&conn, "SELECT COUNT(*) FROM embeddings")?;
+                report.workspaces = count(&conn, "SELECT COUNT(*) FROM workspaces")?;
+                snapshot(&conn, &vectors_path)?;
+            }
+            {
+                let store = app_handle.state::<ConversationStore>();
+                let conn = store.conn();
+                report.conversations = count(&conn, "SELECT COUNT(*) FROM conversations")?;
+                snapshot(&conn, &conversations_path)?;
+            }
+            let manifest = Manifest {
+                format_version: FORMAT_VERSION,
+                app_version: app_handle.package_info().version.to_string(),
+                created_at: now_secs(),
+            };
+            report.bytes = write_bundle(
+                Path::new(&path),
+                &passphrase,
+                &manifest,
+                &[
+                    (VECTORS_ENTRY, vectors_path.as_path()),
+                    (CONVERSATIONS_ENTRY, conversations_path.as_path()),
+                ],
+                &temp,
+            )?;
+            Ok::<_, String>(())
+        })();
+
+        remove_snapshot(&vectors_path);
+        remove_snapshot(&conversations_path);
+        result?;
+
+        log::info!(
+            "Exported {} indexes, {} chunks and {} conversations ({} bytes)",
+            report.indexes,
+            report.chunks,
+            report.conversations,
+            report.bytes
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Bundle export task failed: {}", e))?
+}
+
+/// Import a bundle from `export_workspace_bundle` into this machine's stores
+///
+/// Contents are merged: documents, workspaces and conversations with the same
+/// id are updated to the bundle's copy, everything else is kept. No re-indexing
+/// is needed as long as both machines use the same embedding model.
+#[tauri::command]
+pub async fn import_workspace_bundle(
+    app_handle: tauri::AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<BundleReport, AppError> {
+    log::info!("Importing workspace bundle from {}", path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let temp = secure_delete::temp_dir(&app_handle)?;
+        let unpacked = read_bundle(Path::new(&path), &passphrase, &temp)?;
+        log::info!(
+            "Bundle created at {} by version {}",
+            unpacked.manifest.created_at,
+            unpacked.manifest.app_version
+        );
+
+        let mut report = bundle_report(&unpacked)?;
+        report.path = path;
+        merge(&mut app_handle.state::<VectorStore>().conn(), &unpacked.vectors, VECTOR_TABLES)?;
+        merge(&mut app_handle.state::<ConversationStore>().conn(), &unpacked.conversations, CONVERSATION_TABLES)?;
+
+        log::info!(
+            "Imported {} indexes, {} chunks and {} conversations",
+            report.indexes,
+            report.chunks,
+            report.conversations
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Bundle import task failed: {}", e))?
+}
+
+/// Securely delete a temporary database or archive and its SQLite side files
+fn remove_snapshot(path: &Path) {
+    for suffix in ["", "-journal", "-wal", "-shm"] {
+        let file = PathBuf::from(format!("{}{}", path.display(), suffix));
+        if file.exists() {
+            if let Err(e) = secure_delete::secure_delete(&file) {
+                log::warn!("Failed to remove {}: {}", file.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{feedback, folder_watch, library, retrieval_trace, workspace};
+
+    const PASSPHRASE: &str = "correct horse battery";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("privatepdf-test-bundle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// In-memory stores with every table the app creates
+    fn stores() -> (VectorStore, ConversationStore) {
+        let vectors = VectorStore::open(Path::new(":memory:")).unwrap();
+        workspace::init(&vectors).unwrap();
+        folder_watch::init(&vectors).unwrap();
+        library::init(&vectors).unwrap();
+        feedback::init(&vectors).unwrap();
+        let conversations = ConversationStore::open(Path::new(":memory:")).unwrap();
+        retrieval_trace::init(&conversations).unwrap();
+        (vectors, conversations)
+    }
+
+    fn add_document(vectors: &Connection, conversations: &Connection, name: &str) {
+        vectors
+            .execute_batch(&format!(
+                "INSERT INTO indexes (id, name, dimension, created_at) VALUES ('doc', '{0}', 2, 0);
+                 INSERT INTO embeddings (index_id, chunk_id, text, vector) VALUES ('doc', 'c0', 'chunk of {0}', x'00');
+                 INSERT INTO workspaces (id, name, created_at) VALUES ('ws', '{0}', 0);
+                 INSERT INTO workspace_documents (workspace_id, index_id, name, added_at) VALUES ('ws', 'doc', '{0}', 0);",
+                name
+            ))
+            .unwrap();
+        conversations
+            .execute_batch(&format!(
+                "INSERT INTO conversations (id, title, created_at, updated_at) VALUES ('conv', '{0}', 0, 0);
+                 INSERT INTO messages (conversation_id, position, id, role, content, timestamp)
+                 VALUES ('conv', 0, 'm0', 'user', 'about {0}', 0);",
+                name
+            ))
+            .unwrap();
+    }
+
+    fn export(vectors: &VectorStore, conversations: &ConversationStore, dir: &Path) -> PathBuf {
+        let vectors_path = dir.join(VECTORS_ENTRY);
+        let conversations_path = dir.join(CONVERSATIONS_ENTRY);
+        snapshot(&vectors.conn(), &vectors_path).unwrap();
+        snapshot(&conversations.conn(), &conversations_path).unwrap();
+        let manifest = Manifest {
+            format_version: FORMAT_VERSION,
+            app_version: "test".to_string(),
+            created_at: 0,
+        };
+        let dest = dir.join("workspace.ppdf");
+        write_bundle(
+            &dest,
+            PASSPHRASE,
+            &manifest,
+            &[
+                (VECTORS_ENTRY, vectors_path.as_path()),
+                (CONVERSATIONS_ENTRY, conversations_path.as_path()),
+            ],
+            dir,
+        )
+        .unwrap();
+        dest
+    }
+
+    fn text(conn: &Connection, sql: &str) -> String {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let dir = scratch_dir("round-trip");
+        let (vectors, conversations) = stores();
+        add_document(&vectors.conn(), &conversations.conn(), "report");
+        let bundle = export(&vectors, &conversations, &dir);
+
+        let (target_vectors, target_conversations) = stores();
+        let unpacked = read_bundle(&bundle, PASSPHRASE, &dir).unwrap();
+        let report = bundle_report(&unpacked).unwrap();
+        assert_eq!((report.indexes, report.chunks, report.workspaces, report.conversations), (1, 1, 1, 1));
+        merge(&mut target_vectors.conn(), &unpacked.vectors, VECTOR_TABLES).unwrap();
+        merge(&mut target_conversations.conn(), &unpacked.conversations, CONVERSATION_TABLES).unwrap();
+        let (vectors_path, conversations_path) = (unpacked.vectors.clone(), unpacked.conversations.clone());
+        drop(unpacked);
+        assert!(!vectors_path.exists() && !conversations_path.exists());
+
+        let conn = target_vectors.conn();
+        assert_eq!(text(&conn, "SELECT name FROM indexes WHERE id = 'doc'"), "report");
+        assert_eq!(text(&conn, "SELECT text FROM embeddings WHERE chunk_id = 'c0'"), "chunk of report");
+        assert_eq!(
+            text(&conn, "SELECT e.chunk_id FROM embeddings_fts f JOIN embeddings e ON e.id = f.rowid WHERE embeddings_fts MATCH 'report'"),
+            "c0"
+        );
+        assert_eq!(
+            text(&target_conversations.conn(), "SELECT content FROM messages WHERE conversation_id = 'conv'"),
+            "about report"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let dir = scratch_dir("passphrase");
+        let (vectors, conversations) = stores();
+        let bundle = export(&vectors, &conversations, &dir);
+        let error = read_bundle(&bundle, "not the passphrase", &dir).err().unwrap();
+        assert_eq!(error, "Wrong passphrase or corrupted bundle");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_local_rows_that_reference_merged_ones() {
+        let dir = scratch_dir("merge");
+        let (vectors, conversations) = stores();
+        add_document(&vectors.conn(), &conversations.conn(), "from bundle");
+        let bundle = export(&vectors, &conversations, &dir);
+
+        let (local_vectors, local_conversations) = stores();
+        add_document(&local_vectors.conn(), &local_conversations.conn(), "local");
+        local_vectors
+            .conn()
+            .execute_batch(
+                "INSERT INTO chunk_feedback (index_id, chunk_id, conversation_id, message_id, rating, created_at)
+                 VALUES ('doc', 'c0', 'conv', 'm1', 1, 0);
+                 INSERT INTO document_sources (index_id, path, hash, indexed_at) VALUES ('doc', '/local.pdf', 'doc', 0);
+                 INSERT INTO watched_folders (path, workspace_id, created_at) VALUES ('/inbox', 'ws', 0);",
+            )
+            .unwrap();
+        local_conversations
+            .conn()
+            .execute(
+                "INSERT INTO answer_candidates (conversation_id, message_id, position, content, created_at)
+                 VALUES ('conv', 'm1', 0, 'draft', 0)",
+                [],
+            )
+            .unwrap();
+
+        let unpacked = read_bundle(&bundle, PASSPHRASE, &dir).unwrap();
+        merge(&mut local_vectors.conn(), &unpacked.vectors, VECTOR_TABLES).unwrap();
+        merge(&mut local_conversations.conn(), &unpacked.conversations, CONVERSATION_TABLES).unwrap();
+
+        let conn = local_vectors.conn();
+        assert_eq!(text(&conn, "SELECT name FROM indexes WHERE id = 'doc'"), "from bundle");
+        assert_eq!(text(&conn, "SELECT text FROM embeddings WHERE chunk_id = 'c0'"), "chunk of from bundle");
+        assert_eq!(text(&conn, "SELECT chunk_id FROM chunk_feedback WHERE index_id = 'doc'"), "c0");
+        assert_eq!(text(&conn, "SELECT path FROM document_sources WHERE index_id = 'doc'"), "/local.pdf");
+        assert_eq!(text(&conn, "SELECT path FROM watched_folders WHERE workspace_id = 'ws'"), "/inbox");
+        let conn = local_conversations.conn();
+        assert_eq!(text(&conn, "SELECT title FROM conversations WHERE id = 'conv'"), "from bundle");
+        assert_eq!(text(&conn, "SELECT content FROM answer_candidates WHERE conversation_id = 'conv'"), "draft");
+        drop(unpacked);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
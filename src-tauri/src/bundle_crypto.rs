@@ -0,0 +1,155 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::XChaCha20Poly1305;
+use std::io::{Read, Write};
+
+/// File signature and format version at the start of every bundle
+const MAGIC: &[u8; 8] = b"PPDFBNDL";
+pub(crate) const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+/// STREAM nonce prefix: the 24-byte XChaCha20 nonce minus the chunk counter and last-chunk flag
+const NONCE_LEN: usize = 19;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+/// Plaintext bytes per encrypted chunk, so a bundle is never held in memory whole
+const CHUNK_LEN: usize = 64 * 1024;
+/// Poly1305 tag added to every chunk
+const TAG_LEN: usize = 16;
+
+/// Derive the 256-bit cipher key from the passphrase with Argon2id
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+/// Read until `buf` is full or the input ends; returns the number of bytes read
+fn read_full(input: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// `MAGIC | version | salt | nonce | chunks`
+///
+/// The input is encrypted `CHUNK_LEN` bytes at a time with XChaCha20-Poly1305 in
+/// the STREAM construction: each chunk is authenticated on its own and the last
+/// one is marked, so reordered, dropped or truncated chunks fail to decrypt.
+pub(crate) fn encrypt(input: &mut impl Read, output: &mut impl Write, passphrase: &str) -> Result<(), String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let write_error = |e: std::io::Error| format!("Failed to write bundle file: {}", e);
+    output.write_all(MAGIC).map_err(write_error)?;
+    output.write_all(&[FORMAT_VERSION]).map_err(write_error)?;
+    output.write_all(&salt).map_err(write_error)?;
+    output.write_all(&nonce).map_err(write_error)?;
+
+    let key = derive_key(passphrase, &salt)?;
+    let mut encryptor = EncryptorBE32::<XChaCha20Poly1305>::new(&key.into(), &nonce.into());
+    let mut buffer = vec![0u8; CHUNK_LEN];
+    loop {
+        let read = read_full(input, &mut buffer).map_err(|e| format!("Failed to read bundle contents: {}", e))?;
+        // A short chunk, even an empty one, is always the last, so a file cut at a
+        // chunk boundary is caught on import
+        if read < CHUNK_LEN {
+            let chunk = encryptor
+                .encrypt_last(&buffer[..read])
+                .map_err(|_| "Failed to encrypt bundle".to_string())?;
+            return output.write_all(&chunk).map_err(write_error);
+        }
+        let chunk = encryptor
+            .encrypt_next(buffer.as_slice())
+            .map_err(|_| "Failed to encrypt bundle".to_string())?;
+        output.write_all(&chunk).map_err(write_error)?;
+    }
+}
+
+pub(crate) fn decrypt(input: &mut impl Read, output: &mut impl Write, passphrase: &str) -> Result<(), String> {
+    let mut header = [0u8; HEADER_LEN];
+    let read = read_full(input, &mut header).map_err(|e| format!("Failed to read bundle file: {}", e))?;
+    if read < HEADER_LEN || &header[..MAGIC.len()] != MAGIC {
+        return Err("Not a PrivatePDF workspace bundle".to_string());
+    }
+    let version = header[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(format!("Unsupported bundle version {}; update PrivatePDF to import it", version));
+    }
+    let salt = &header[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&header[MAGIC.len() + 1 + SALT_LEN..]);
+
+    let key = derive_key(passphrase, salt)?;
+    let mut decryptor = DecryptorBE32::<XChaCha20Poly1305>::new(&key.into(), &nonce.into());
+    let corrupted = |_| "Wrong passphrase or corrupted bundle".to_string();
+    let write_error = |e: std::io::Error| format!("Failed to unpack bundle: {}", e);
+    let mut buffer = vec![0u8; CHUNK_LEN + TAG_LEN];
+    loop {
+        let read = read_full(input, &mut buffer).map_err(|e| format!("Failed to read bundle file: {}", e))?;
+        if read < buffer.len() {
+            let chunk = decryptor.decrypt_last(&buffer[..read]).map_err(corrupted)?;
+            return output.write_all(&chunk).map_err(write_error);
+        }
+        let chunk = decryptor.decrypt_next(buffer.as_slice()).map_err(corrupted)?;
+        output.write_all(&chunk).map_err(write_error)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const PASSPHRASE: &str = "correct horse battery";
+
+    fn encrypted(plaintext: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        encrypt(&mut Cursor::new(plaintext), &mut out, PASSPHRASE).unwrap();
+        out
+    }
+
+    fn decrypted(data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        decrypt(&mut Cursor::new(data), &mut out, PASSPHRASE).map(|()| out)
+    }
+
+    #[test]
+    fn chunked_encryption_round_trips() {
+        for len in [0, 10, CHUNK_LEN, CHUNK_LEN * 2 + 123] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            assert_eq!(decrypted(&encrypted(&plaintext)).unwrap(), plaintext, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn truncated_or_tampered_bundles_are_rejected() {
+        let plaintext = vec![7u8; CHUNK_LEN * 2 + 123];
+        let data = encrypted(&plaintext);
+
+        // Cut inside the last chunk, and exactly after a full chunk
+        assert!(decrypted(&data[..data.len() - 10]).is_err());
+        assert!(decrypted(&data[..HEADER_LEN + CHUNK_LEN + TAG_LEN]).is_err());
+
+        let mut tampered = data.clone();
+        tampered[HEADER_LEN + CHUNK_LEN + 5] ^= 1;
+        assert!(decrypted(&tampered).is_err());
+
+        let mut extended = data.clone();
+        extended.extend_from_slice(b"trailing");
+        assert!(decrypted(&extended).is_err());
+
+        assert_eq!(decrypted(&data[..HEADER_LEN - 1]).err().unwrap(), "Not a PrivatePDF workspace bundle");
+    }
+}
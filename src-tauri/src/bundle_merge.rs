@@ -0,0 +1,73 @@
+use rusqlite::Connection;
+use std::path::Path;
+
+use crate::storage;
+
+/// Columns present in both the live table and the bundle's copy of it
+fn shared_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let columns = |schema: &str| -> Result<Vec<String>, String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA {}.table_info({})", schema, table))
+            .map_err(|e| format!("Failed to read table info: {}", e))?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| format!("Failed to read table info: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read table info: {}", e))?;
+        Ok(names)
+    };
+    let live = columns("main")?;
+    Ok(columns("bundle")?.into_iter().filter(|c| live.contains(c)).collect())
+}
+
+/// Merge the bundle's rows into the live database; rows with the same key are updated
+///
+/// This is an upsert rather than `INSERT OR REPLACE`: REPLACE deletes the old row
+/// first, and the `ON DELETE CASCADE` keys would take local rows that reference it
+/// (feedback on an index, folders watched into a workspace) along with it.
+pub(crate) fn merge(conn: &mut Connection, snapshot: &Path, tables: &[(&str, &[&str])]) -> Result<(), String> {
+    storage::attach_plain(conn, snapshot, "bundle")
+        .map_err(|e| format!("Failed to open bundle database: {}", e))?;
+
+    let result = (|| {
+        let tx = conn.transaction().map_err(|e| format!("Failed to start import: {}", e))?;
+        for (table, keys) in tables {
+            let columns: Vec<String> = shared_columns(&tx, table)?
+                .into_iter()
+                // Embedding row ids are local; (index_id, chunk_id) identifies a chunk
+                .filter(|c| !(*table == "embeddings" && c == "id"))
+                .collect();
+            if !keys.iter().all(|key| columns.iter().any(|c| c == key)) {
+                log::warn!("Skipping {}: not in the bundle or missing its key columns", table);
+                continue;
+            }
+            let updates: Vec<String> = columns
+                .iter()
+                .filter(|c| !keys.contains(&c.as_str()))
+                .map(|c| format!("{0} = excluded.{0}", c))
+                .collect();
+            let on_conflict = if updates.is_empty() {
+                "DO NOTHING".to_string()
+            } else {
+                format!("DO UPDATE SET {}", updates.join(", "))
+            };
+            // `WHERE true` keeps SQLite from parsing ON CONFLICT as part of the SELECT
+            tx.execute(
+                &format!(
+                    "INSERT INTO main.{0} ({1}) SELECT {1} FROM bundle.{0} WHERE true ON CONFLICT ({2}) {3}",
+                    table,
+                    columns.join(", "),
+                    keys.join(", "),
+                    on_conflict
+                ),
+                [],
+            )
+            .map_err(|e| format!("Failed to import {}: {}", table, e))?;
+        }
+        tx.commit().map_err(|e| format!("Failed to commit import: {}", e))
+    })();
+
+    conn.execute("DETACH DATABASE bundle", [])
+        .map_err(|e| format!("Failed to close bundle database: {}", e))?;
+    result
+}
@@ -0,0 +1,196 @@
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::hardware::{self, HardwareInfo};
+
+const MB: u64 = 1024 * 1024;
+const BYTES_PER_GB: u64 = 1024 * MB;
+
+/// Bundled with the app so the download screen works before Ollama or the network is up
+struct CatalogModel {
+    name: &'static str,
+    display_name: &'static str,
+    /// "chat" or "embedding"
+    kind: &'static str,
+    /// "light", "medium" or "large"
+    tier: &'static str,
+    params: &'static str,
+    size_bytes: u64,
+    min_ram_gb: u64,
+    /// VRAM needed to run fully on the GPU
+    min_vram_gb: u64,
+    vision: bool,
+    description: &'static str,
+}
+
+const CATALOG: &[CatalogModel] = &[
+    CatalogModel {
+        name: "gemma3:1b-it-qat",
+        display_name: "Light (Gemma 3 1B QAT)",
+        kind: "chat",
+        tier: "light",
+        params: "1B",
+        size_bytes: 530 * MB,
+        min_ram_gb: 4,
+        min_vram_gb: 2,
+        vision: false,
+        description: "Fast responses on older laptops",
+    },
+    CatalogModel {
+        name: "llama3.2:3b",
+        display_name: "Light (Llama 3.2 3B)",
+        kind: "chat",
+        tier: "light",
+        params: "3B",
+        size_bytes: 2048 * MB,
+        min_ram_gb: 6,
+        min_vram_gb: 3,
+        vision: false,
+        description: "Small general-purpose model with good English answers",
+    },
+    CatalogModel {
+        name: "gemma3:4b-it-q4_K_M",
+        display_name: "Medium (Gemma 3 4B Q4)",
+        kind: "chat",
+        tier: "medium",
+        params: "4B",
+        size_bytes: 2458 * MB,
+        min_ram_gb: 8,
+        min_vram_gb: 4,
+        vision: true,
+        description: "Balanced speed and quality; reads images",
+    },
+    CatalogModel {
+        name: "qwen3-vl:8b-instruct-q4_K_M",
+        display_name: "Large (Qwen 3 VL 8B Q4)",
+        kind: "chat",
+        tier: "large",
+        params: "8B",
+        size_bytes: 5325 * MB,
+        min_ram_gb: 8,
+        min_vram_gb: 6,
+        vision: true,
+        description: "Best quality, optimized for long context RAG; reads images",
+    },
+    CatalogModel {
+        name: "qwen3:14b",
+        display_name: "Large (Qwen 3 14B Q4)",
+        kind: "chat",
+        tier: "large",
+        params: "14B",
+        size_bytes: 9523 * MB,
+        min_ram_gb: 16,
+        min_vram_gb: 10,
+        vision: false,
+        description: "Strongest reasoning for workstations with a large GPU",
+    },
+    CatalogModel {
+        name: "nomic-embed-text",
+        display_name: "Nomic Embed Text",
+        kind: "embedding",
+        tier: "light",
+        params: "137M",
+        size_bytes: 274 * MB,
+        min_ram_gb: 2,
+        min_vram_gb: 1,
+        vision: false,
+        description: "Default embedding model for document search",
+    },
+    CatalogModel {
+        name: "mxbai-embed-large",
+        display_name: "mxbai Embed Large",
+        kind: "embedding",
+        tier: "medium",
+        params: "335M",
+        size_bytes: 670 * MB,
+        min_ram_gb: 4,
+        min_vram_gb: 1,
+        vision: false,
+        description: "Higher retrieval accuracy at about twice the indexing time",
+    },
+];
+
+#[derive(Debug, Serialize)]
+pub struct CatalogEntry {
+    name: &'static str,
+    display_name: &'static str,
+    kind: &'static str,
+    tier: &'static str,
+    params: &'static str,
+    size_bytes: u64,
+    min_ram_bytes: u64,
+    min_vram_bytes: u64,
+    vision: bool,
+    description: &'static str,
+    /// Enough system memory to run at all
+    fits: bool,
+    /// Fits entirely in GPU memory
+    gpu_accelerated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelCatalog {
+    hardware: HardwareInfo,
+    models: Vec<CatalogEntry>,
+    /// Largest chat model that fits, preferring one that runs on the GPU
+    recommended_chat: Option<&'static str>,
+}
+
+fn entry(model: &CatalogModel, hardware: &HardwareInfo) -> CatalogEntry {
+    let min_ram_bytes = model.min_ram_gb * BYTES_PER_GB;
+    let min_vram_bytes = model.min_vram_gb * BYTES_PER_GB;
+    CatalogEntry {
+        name: model.name,
+        display_name: model.display_name,
+        kind: model.kind,
+        tier: model.tier,
+        params: model.params,
+        size_bytes: model.size_bytes,
+        min_ram_bytes,
+        min_vram_bytes,
+        vision: model.vision,
+        description: model.description,
+        fits: hardware.total_memory_bytes() >= min_ram_bytes,
+        gpu_accelerated: hardware.max_vram_bytes().is_some_and(|v| v >= min_vram_bytes),
+    }
+}
+
+/// Pick the largest chat model that fits, GPU-accelerated ones first
+fn recommend_chat(models: &[CatalogEntry]) -> Option<&'static str> {
+    let candidates = || models.iter().filter(|m| m.kind == "chat" && m.fits);
+    candidates()
+        .filter(|m| m.gpu_accelerated)
+        .max_by_key(|m| m.size_bytes)
+        .or_else(|| candidates().max_by_key(|m| m.size_bytes))
+        .map(|m| m.name)
+}
+
+/// Curated, bundled list of recommended models filtered by `detect_hardware`
+///
+/// Models that don't fit in RAM are left out unless `include_all` is set, in
+/// which case they're returned with `fits: false`.
+#[tauri::command]
+pub async fn get_model_catalog(include_all: Option<bool>) -> Result<ModelCatalog, AppError> {
+    log::info!("Building model catalog");
+
+    let hardware = tauri::async_runtime::spawn_blocking(hardware::probe)
+        .await
+        .map_err(|e| format!("Hardware detection task failed: {}", e))?;
+
+    let mut models: Vec<CatalogEntry> = CATALOG.iter().map(|m| entry(m, &hardware)).collect();
+    if !include_all.unwrap_or(false) {
+        models.retain(|m| m.fits);
+    }
+    let recommended_chat = recommend_chat(&models);
+
+    log::info!(
+        "Model catalog: {} models, recommended chat {:?}",
+        models.len(),
+        recommended_chat
+    );
+    Ok(ModelCatalog {
+        hardware,
+        models,
+        recommended_chat,
+    })
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+
+use crate::chunking::estimate_tokens;
+use crate::context_window;
+use crate::conversations::{ConversationMemory, ConversationMessage, ConversationStore};
+use crate::error::AppError;
+use crate::ollama::ChatMessage;
+use crate::rag::{complete, context_message};
+use crate::scheduler::Lane;
+use crate::settings::{self, AppSettings};
+
+const REWRITE_PROMPT: &str = "Rewrite the user's last question as a standalone search query for their \
+documents. Resolve pronouns and references like \"the second one\" or \"that section\" using the conversation. \
+Keep names, numbers and technical terms exactly. If the question is already standalone, return it unchanged. \
+Reply with the query only.";
+
+/// Most recent messages shown to the query rewriter
+const REWRITE_HISTORY_MESSAGES: usize = 6;
+/// Longer messages are cut for the rewriter; it only needs what they refer to
+const REWRITE_MESSAGE_CHARS: usize = 1000;
+
+const MEMORY_PROMPT: &str = "You maintain the running summary of a conversation between a user and an assistant \
+about their documents. Update the summary with the new turns: keep facts, decisions, open questions and anything \
+the user asked to remember; drop small talk. Reply with the updated summary only.";
+
+#[derive(Debug, Serialize)]
+pub struct ChatContext {
+    /// Ready to send: memory summary (if any), recent turns, then the new question
+    messages: Vec<ChatMessage>,
+    /// Leading messages represented by the summary instead of verbatim
+    summarized_messages: usize,
+    estimated_tokens: usize,
+}
+
+/// Rewrite a follow-up question into a standalone search query using recent history
+///
+/// Returns None when there's no history to resolve against. Failures fall back
+/// to the original question rather than failing the query.
+pub(crate) async fn rewrite_query(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    history: &[ConversationMessage],
+    question: &str,
+) -> Option<String> {
+    let turns: Vec<&ConversationMessage> = history
+        .iter()
+        .filter(|m| m.role == "user" || m.role == "assistant")
+        // The frontend may have saved the question already
+        .filter(|m| !(m.role == "user" && m.content.trim() == question.trim()))
+        .collect();
+    if turns.is_empty() {
+        return None;
+    }
+
+    let mut text = String::from("Conversation:\n");
+    for turn in &turns[turns.len().saturating_sub(REWRITE_HISTORY_MESSAGES)..] {
+        let speaker = if turn.role == "user" { "User" } else { "Assistant" };
+        let content: String = turn.content.trim().chars().take(REWRITE_MESSAGE_CHARS).collect();
+        text.push_str(&format!("{}: {}\n", speaker, content));
+    }
+    text.push_str(&format!("\nLast question: {}", question.trim()));
+
+    match complete(Lane::Interactive, app_handle, settings, REWRITE_PROMPT, &text).await {
+        Ok(rewritten) => {
+            let rewritten = rewritten.trim().trim_matches('"').trim().to_string();
+            (!rewritten.is_empty() && rewritten != question.trim()).then_some(rewritten)
+        }
+        Err(e) => {
+            log::warn!("Query rewriting failed, using the question as is: {}", e);
+            None
+        }
+    }
+}
+
+fn message_tokens(message: &ConversationMessage) -> usize {
+    // A few tokens of per-message overhead in the chat template
+    estimate_tokens(&message.content) + 4
+}
+
+/// Fold `turns` into the running summary with the model
+async fn update_memory(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    summary: &str,
+    turns: &[ConversationMessage],
+) -> Result<String, String> {
+    let mut text = String::new();
+    if !summary.is_empty() {
+        text.push_str(&format!("Current summary:\n{}\n\n", summary));
+    }
+    text.push_str("New turns:\n");
+    for turn in turns {
+        let speaker = if turn.role == "user" { "User" } else { "Assistant" };
+        text.push_str(&format!("{}: {}\n", speaker, turn.content.trim()));
+    }
+    complete(Lane::Interactive, app_handle, settings, MEMORY_PROMPT, &text).await
+}
+
+/// Chat history for a new question, summarizing older turns when it gets too long
+///
+/// While the stored history fits the chat model's history budget (a share of its
+/// detected context window) it is returned verbatim.
+/// Beyond that, the oldest turns are folded into a rolling summary (kept in the
+/// conversation store, so each turn is only summarized once) and only the most
+/// recent turns are sent as-is. Text pasted into the conversation with
+/// `ingest_clipboard` or `ingest_image` is added after the summary.
+#[tauri::command]
+pub async fn build_chat_context(
+    conversation_id: String,
+    new_question: String,
+    app_handle: tauri::AppHandle,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<ChatContext, AppError> {
+    log::info!("Building chat context for conversation {}", conversation_id);
+
+    let mut history: Vec<ConversationMessage> = conversations
+        .get(&conversation_id)?
+        .map(|c| c.messages)
+        .unwrap_or_default();
+    // The frontend may have saved the question already
+    if history
+        .last()
+        .is_some_and(|m| m.role == "user" && m.content.trim() == new_question.trim())
+    {
+        history.pop();
+    }
+
+    let mut memory = conversations.memory(&conversation_id)?.unwrap_or_default();
+    if memory.summarized_count > history.len() {
+        // History was edited or truncated since the summary was written
+        log::info!("Conversation {} changed, discarding its memory", conversation_id);
+        memory = ConversationMemory::default();
+    }
+
+    let turns = |m: &&ConversationMessage| m.role == "user" || m.role == "assistant";
+    let settings = settings::read_settings(&app_handle);
+    let history_budget = context_window::for_model(&app_handle, &settings, &settings.ollama_model)
+        .await
+        .history_tokens;
+    // Recent turns kept verbatim once older ones are folded into the summary
+    let recent_budget = history_budget / 2;
+
+    let question_tokens = estimate_tokens(&new_question);
+    let recent_tokens: usize = history[memory.summarized_count..].iter().filter(turns).map(message_tokens).sum();
+
+    if estimate_tokens(&memory.summary) + recent_tokens + question_tokens > history_budget {
+        // Keep the newest turns that fit the recent budget; summarize everything before them
+        let mut keep_from = history.len();
+        let mut kept = 0;
+        while keep_from > memory.summarized_count {
+            let tokens = message_tokens(&history[keep_from - 1]);
+            if kept + tokens > recent_budget {
+                break;
+            }
+            kept += tokens;
+            keep_from -= 1;
+        }
+
+        let older: Vec<ConversationMessage> = history[memory.summarized_count..keep_from]
+            .iter()
+            .filter(turns)
+            .cloned()
+            .collect();
+        if !older.is_empty() {
+            log::info!("Summarizing {} older messages of {}", older.len(), conversation_id);
+            memory.summary = update_memory(&app_handle, &settings, &memory.summary, &older).await?;
+        }
+        memory.summarized_count = keep_from;
+        conversations.save_memory(&conversation_id, &memory)?;
+    }
+
+    let mut messages = Vec::new();
+    if !memory.summary.is_empty() {
+        messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: format!("Summary of the earlier conversation:\n{}", memory.summary),
+            images: Vec::new(),
+        });
+    }
+    messages.extend(context_message(&conversations, &conversation_id)?);
+    for message in history[memory.summarized_count..].iter().filter(turns) {
+        messages.push(ChatMessage {
+            role: message.role.clone(),
+            content: message.content.clone(),
+            images: Vec::new(),
+        });
+    }
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: new_question,
+        images: Vec::new(),
+    });
+
+    let estimated_tokens = messages.iter().map(|m| estimate_tokens(&m.content) + 4).sum();
+    Ok(ChatContext {
+        messages,
+        summarized_messages: memory.summarized_count,
+        estimated_tokens,
+    })
+}
@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::error::AppError;
+
+/// Separators the recursive strategy tries, coarsest first
+const RECURSIVE_SEPARATORS: &[&str] = &["\n\n", "\n", ". ", "? ", "! ", "; ", ", ", " "];
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStrategy {
+    /// Pack whole sentences up to `chunk_size` characters
+    Sentence,
+    /// Pack whole paragraphs (blank-line separated) up to `chunk_size` characters
+    Paragraph,
+    /// Pack tokens up to `chunk_size` tokens
+    Token,
+    /// Split on paragraphs, then lines, sentences and words until pieces fit `chunk_size` characters
+    Recursive,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Chunk {
+    index: usize,
+    text: String,
+    /// Character offsets of the chunk in the input text
+    start: usize,
+    end: usize,
+    /// Estimated token count of the chunk
+    token_count: usize,
+}
+
+impl Chunk {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
+
+/// Estimate the token count of a text
+///
+/// Counts words and punctuation marks, with long words counted as roughly one
+/// token per four characters. This tracks BPE and WordPiece tokenizers closely
+/// enough for chunk budgets without shipping a model vocabulary.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word_len = 0;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word_len += 1;
+            continue;
+        }
+        tokens += word_tokens(word_len);
+        word_len = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + word_tokens(word_len)
+}
+
+fn word_tokens(len: usize) -> usize {
+    match len {
+        0 => 0,
+        1..=6 => 1,
+        _ => len.div_ceil(4),
+    }
+}
+
+/// Byte ranges of sentences, each including its trailing whitespace
+fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let at_boundary = matches!(c, '.' | '!' | '?' | '\n')
+            && !matches!(chars.peek(), Some(&(_, next)) if !next.is_whitespace());
+        if !at_boundary {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if !next.is_whitespace() {
+                break;
+            }
+            end = j + next.len_utf8();
+            chars.next();
+        }
+        pieces.push(start..end);
+        start = end;
+    }
+    if start < text.len() {
+        pieces.push(start..text.len());
+    }
+    pieces
+}
+
+/// Byte ranges of paragraphs, split at blank lines
+fn paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim().is_empty() && !text[start..offset].trim().is_empty() {
+            pieces.push(start..offset);
+            start = offset;
+        }
+    }
+    if start < text.len() {
+        pieces.push(start..text.len());
+    }
+    pieces
+}
+
+/// Byte ranges of single tokens (a word or punctuation mark plus trailing whitespace)
+fn tokens(text: &str) -> Vec<Range<usize>> {
+    let mut pieces: Vec<Range<usize>> = Vec::new();
+    let mut in_word = false;
+
+    for (i, c) in text.char_indices() {
+        let starts_token = if c.is_whitespace() {
+            false
+        } else if c.is_alphanumeric() {
+            !in_word
+        } else {
+            true
+        };
+        in_word = c.is_alphanumeric();
+
+        if starts_token {
+            if let Some(last) = pieces.last_mut() {
+                last.end = i;
+            }
+            pieces.push(i..text.len());
+        }
+    }
+    match pieces.first_mut() {
+        // Leading whitespace belongs to the first token
+        Some(first) => first.start = 0,
+        None if !text.is_empty() => pieces.push(0..text.len()),
+        None => {}
+    }
+    pieces
+}
+
+/// Split `range` until every piece fits in `max_chars`, trying coarser separators first
+fn split_recursive(text: &str, range: Range<usize>, separators: &[&str], max_chars: usize, out: &mut Vec<Range<usize>>) {
+    let slice = &text[range.clone()];
+    if slice.chars().count() <= max_chars {
+        out.push(range);
+        return;
+    }
+
+    let Some((separator, rest)) = separators.split_first() else {
+        // No separator left: hard split on character boundaries
+        let mut start = range.start;
+        let mut count = 0;
+        for (i, _) in slice.char_indices() {
+            if count == max_chars {
+                out.push(start..range.start + i);
+                start = range.start + i;
+                count = 0;
+            }
+            count += 1;
+        }
+        out.push(start..range.end);
+        return;
+    };
+
+    if !slice.contains(separator) {
+        split_recursive(text, range, rest, max_chars, out);
+        return;
+    }
+
+    let mut start = range.start;
+    for part in slice.split_inclusive(separator) {
+        let end = start + part.len();
+        split_recursive(text, start..end, rest, max_chars, out);
+        start = end;
+    }
+}
+
+/// Greedily pack consecutive pieces into chunks of at most `size`, repeating up to
+/// `overlap` worth of trailing pieces at the start of the next chunk
+fn pack<F>(pieces: &[Range<usize>], size: usize, overlap: usize, measure: F) -> Vec<Range<usize>>
+where
+    F: Fn(&Range<usize>) -> usize,
+{
+    let sizes: Vec<usize> = pieces.iter().map(&measure).collect();
+    let mut chunks = Vec::new();
+    let mut first = 0;
+
+    while first < pieces.len() {
+        // Always take at least one piece, even if it alone exceeds the budget
+        let mut last = first;
+        let mut total = sizes[first];
+        while last + 1 < pieces.len() && total + sizes[last + 1] <= size {
+            last += 1;
+            total += sizes[last];
+        }
+        chunks.push(pieces[first].start..pieces[last].end);
+
+        if last + 1 == pieces.len() {
+            break;
+        }
+
+        // Step back over trailing pieces that fit in the overlap, but always make progress
+        let mut next = last + 1;
+        let mut carried = 0;
+        while next > first + 1 && carried + sizes[next - 1] <= overlap {
+            next -= 1;
+            carried += sizes[next];
+        }
+        first = next;
+    }
+    chunks
+}
+
+/// Split text into chunks with the given strategy
+///
+/// `chunk_size` and `overlap` are measured in tokens for `Token` and in characters
+/// for the other strategies. Chunks never cut through a sentence or paragraph
+/// unless a single one is larger than `chunk_size`.
+pub fn chunk(text: &str, strategy: ChunkStrategy, chunk_size: usize, overlap: usize) -> Vec<Chunk> {
+    let chunk_size = chunk_size.max(1);
+    let overlap = overlap.min(chunk_size.saturating_sub(1));
+    let char_len = |r: &Range<usize>| text[r.clone()].chars().count();
+
+    let ranges = match strategy {
+        ChunkStrategy::Sentence => pack(&sentences(text), chunk_size, overlap, char_len),
+        ChunkStrategy::Paragraph => {
+            // Oversized paragraphs fall back to sentence boundaries
+            let pieces: Vec<Range<usize>> = paragraphs(text)
+                .into_iter()
+                .flat_map(|p| {
+                    if char_len(&p) <= chunk_size {
+                        vec![p]
+                    } else {
+                        sentences(&text[p.clone()])
+                            .into_iter()
+                            .map(|s| p.start + s.start..p.start + s.end)
+                            .collect()
+                    }
+                })
+                .collect();
+            pack(&pieces, chunk_size, overlap, char_len)
+        }
+        ChunkStrategy::Token => pack(&tokens(text), chunk_size, overlap, |r| {
+            estimate_tokens(&text[r.clone()]).max(1)
+        }),
+        ChunkStrategy::Recursive => {
+            let mut pieces = Vec::new();
+            split_recursive(text, 0..text.len(), RECURSIVE_SEPARATORS, chunk_size, &mut pieces);
+            pack(&pieces, chunk_size, overlap, char_len)
+        }
+    };
+
+    let mut chunks = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        let raw = &text[range.clone()];
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let leading = raw.len() - raw.trim_start().len();
+        let start = text[..range.start + leading].chars().count();
+        chunks.push(Chunk {
+            index: chunks.len(),
+            text: trimmed.to_string(),
+            start,
+            end: start + trimmed.chars().count(),
+            token_count: estimate_tokens(trimmed),
+        });
+    }
+    chunks
+}
+
+/// Split document text into chunks for embedding
+#[tauri::command]
+pub async fn chunk_text(
+    text: String,
+    strategy: ChunkStrategy,
+    chunk_size: usize,
+    overlap: Option<usize>,
+) -> Result<Vec<Chunk>, AppError> {
+    log::info!("Chunking {} chars ({:?}, size {}, overlap {:?})", text.len(), strategy, chunk_size, overlap);
+
+    let chunks = tauri::async_runtime::spawn_blocking(move || chunk(&text, strategy, chunk_size, overlap.unwrap_or(0)))
+        .await
+        .map_err(|e| format!("Chunking task failed: {}", e))?;
+
+    log::info!("Produced {} chunks", chunks.len());
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pieces<'a>(text: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|r| &text[r.clone()]).collect()
+    }
+
+    fn texts(chunks: &[Chunk]) -> Vec<&str> {
+        chunks.iter().map(Chunk::text).collect()
+    }
+
+    #[test]
+    fn estimates_words_and_punctuation() {
+        assert_eq!(estimate_tokens("Hello, world!"), 4);
+        assert_eq!(estimate_tokens("internationalization"), 5);
+        assert_eq!(estimate_tokens("   "), 0);
+    }
+
+    #[test]
+    fn sentences_keep_decimals_together() {
+        let text = "Pi is 3.14. Done! Next";
+        assert_eq!(pieces(text, &sentences(text)), vec!["Pi is 3.14. ", "Done! ", "Next"]);
+    }
+
+    #[test]
+    fn paragraphs_split_at_blank_lines() {
+        let text = "A\nB\n\n\nC\n";
+        assert_eq!(pieces(text, &paragraphs(text)), vec!["A\nB\n\n", "\nC\n"]);
+    }
+
+    #[test]
+    fn tokens_cover_the_whole_text() {
+        let text = "  Hi, you";
+        assert_eq!(pieces(text, &tokens(text)), vec!["  Hi", ", ", "you"]);
+        assert!(tokens("").is_empty());
+    }
+
+    #[test]
+    fn sentence_chunks_pack_whole_sentences() {
+        let text = "One two. Three four. Five six.";
+        let chunks = chunk(text, ChunkStrategy::Sentence, 22, 0);
+        assert_eq!(texts(&chunks), vec!["One two. Three four.", "Five six."]);
+        for c in &chunks {
+            assert_eq!(&text[c.start()..c.end()], c.text());
+        }
+    }
+
+    #[test]
+    fn overlap_repeats_trailing_sentences() {
+        let chunks = chunk("One two. Three four. Five six.", ChunkStrategy::Sentence, 22, 12);
+        assert_eq!(texts(&chunks), vec!["One two. Three four.", "Three four. Five six."]);
+    }
+
+    #[test]
+    fn oversized_pieces_are_kept_or_hard_split() {
+        assert_eq!(texts(&chunk("abcdefghij", ChunkStrategy::Sentence, 4, 0)), vec!["abcdefghij"]);
+        assert_eq!(texts(&chunk("abcdefghij", ChunkStrategy::Recursive, 4, 0)), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn recursive_prefers_coarse_separators() {
+        let chunks = chunk("aaa bbb\n\nccc ddd", ChunkStrategy::Recursive, 8, 0);
+        assert_eq!(texts(&chunks), vec!["aaa bbb", "ccc ddd"]);
+    }
+
+    #[test]
+    fn token_chunks_respect_the_budget() {
+        let chunks = chunk("one two three four five", ChunkStrategy::Token, 2, 0);
+        assert_eq!(texts(&chunks), vec!["one two", "three four", "five"]);
+    }
+
+    #[test]
+    fn offsets_count_characters() {
+        let text = "Ünïcödé. Straße.";
+        let chunks = chunk(text, ChunkStrategy::Sentence, 9, 0);
+        assert_eq!(texts(&chunks), vec!["Ünïcödé.", "Straße."]);
+        assert_eq!((chunks[1].start(), chunks[1].end()), (9, 16));
+        let second: String = text.chars().skip(chunks[1].start()).take(chunks[1].end() - chunks[1].start()).collect();
+        assert_eq!(second, chunks[1].text());
+    }
+
+    #[test]
+    fn large_overlap_still_makes_progress() {
+        let chunks = chunk("A. B. C. D. E.", ChunkStrategy::Sentence, 3, 100);
+        assert_eq!(chunks.last().map(Chunk::text), Some("E."));
+        assert!(chunk("", ChunkStrategy::Paragraph, 10, 0).is_empty());
+    }
+}
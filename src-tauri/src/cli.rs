@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::backend::BackendKind;
+use crate::ingest;
+use crate::ollama;
+use crate::ollama_service;
+use crate::rag::{self, Answer};
+use crate::settings::{self, AppSettings};
+use crate::vectorstore::VectorStore;
+
+pub const USAGE: &str = "Usage: privatepdf --ask <question> --file <path> [--file <path>...] [options]
+       privatepdf --mcp [--portable]
+
+Answers a question about each document without opening a window. A folder
+passed to --file stands for the supported documents directly inside it.
+
+Options:
+  --model <name>   Chat model to use instead of the one in settings
+  --top-k <n>      Excerpts to retrieve per document
+  --json           Print one JSON object per document instead of text
+  --portable       Keep data in data/ next to the executable
+
+--mcp serves the indexed documents to MCP clients over stdin and stdout. It
+has to be turned on in the app's settings first.";
+
+/// How long to wait for an Ollama server started for the run to answer
+const SERVER_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A question asked from the command line
+#[derive(Debug)]
+pub struct CliRequest {
+    question: String,
+    paths: Vec<PathBuf>,
+    model: Option<String>,
+    top_k: Option<usize>,
+    json: bool,
+}
+
+/// A run without a window
+#[derive(Debug)]
+pub enum Headless {
+    /// `--ask`: answer a question and exit
+    Ask(CliRequest),
+    /// `--mcp`: serve MCP clients until stdin closes
+    Mcp,
+}
+
+/// Read a headless request from the command line
+///
+/// Returns None without `--ask` or `--mcp`, so the app starts normally (file
+/// associations and the OS pass their own arguments then).
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Headless>, String> {
+    let args: Vec<String> = args.into_iter().collect();
+    if args.iter().any(|arg| arg == "--mcp") {
+        return match args.iter().find(|arg| *arg != "--mcp" && *arg != "--portable") {
+            Some(arg) => Err(format!("Unknown argument with --mcp: {}", arg)),
+            None => Ok(Some(Headless::Mcp)),
+        };
+    }
+    if !args.iter().any(|arg| arg == "--ask") {
+        return Ok(None);
+    }
+
+    let mut question = None;
+    let mut paths = Vec::new();
+    let mut model = None;
+    let mut top_k = None;
+    let mut json = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--ask" => question = Some(value()?),
+            "--file" => paths.push(PathBuf::from(value()?)),
+            "--model" => model = Some(value()?),
+            "--top-k" => {
+                let n = value()?;
+                top_k = Some(n.parse::<usize>().ok().filter(|&n| n > 0).ok_or_else(|| format!("Invalid --top-k: {}", n))?);
+            }
+            "--json" => json = true,
+            "--portable" => {}
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    let question = question.filter(|q| !q.trim().is_empty()).ok_or("The question is empty")?;
+    if paths.is_empty() {
+        return Err("No document given; pass one or more --file".to_string());
+    }
+    Ok(Some(Headless::Ask(CliRequest {
+        question,
+        paths,
+        model,
+        top_k,
+        json,
+    })))
+}
+
+/// Write to the terminal the app was started from
+///
+/// Release builds on Windows are GUI programs with no console of their own.
+/// Output that is redirected to a file or pipe works without this.
+#[cfg(target_os = "windows")]
+pub fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // Fails harmlessly when there's no parent console or one is already attached
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_console() {}
+
+/// Documents named on the command line, with folders expanded to the supported files in them
+fn documents(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut documents = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|file| file.is_file() && ingest::is_supported(file))
+                .collect();
+            files.sort();
+            documents.extend(files);
+        } else if path.is_file() {
+            documents.push(path.clone());
+        } else {
+            return Err(format!("{} doesn't exist", path.display()));
+        }
+    }
+    if documents.is_empty() {
+        return Err("No supported documents found".to_string());
+    }
+    Ok(documents)
+}
+
+/// Start the local Ollama server if the run needs it
+///
+/// Like any server the app starts, it's stopped on exit when `stop_on_exit` is set.
+pub(crate) async fn ensure_server(app_handle: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
+    if settings.llm_backend != BackendKind::Ollama {
+        return Ok(());
+    }
+    let base_url = ollama::ollama_url(app_handle);
+    if ollama_service::probe_version(&base_url).await.is_ok() {
+        return Ok(());
+    }
+
+    log::info!("Ollama isn't running, starting it for this run");
+    ollama_service::start_ollama_service(app_handle.clone()).await?;
+    let deadline = Instant::now() + SERVER_START_TIMEOUT;
+    while Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        if ollama_service::probe_version(&base_url).await.is_ok() {
+            return Ok(());
+        }
+    }
+    Err(format!("Ollama didn't start within {} seconds", SERVER_START_TIMEOUT.as_secs()))
+}
+
+/// One line of `--json` output
+#[derive(Debug, Serialize)]
+struct DocumentAnswer {
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_id: Option<String>,
+    #[serde(flatten)]
+    answer: Option<Answer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Index a document if needed and answer the question from it
+async fn ask(app_handle: &tauri::AppHandle, request: &CliRequest, path: &Path) -> Result<(String, Answer), String> {
+    let document_id = ingest::index_now(app_handle, path).await?;
+    let mut settings = settings::read_settings_for(app_handle, None, Some(&document_id));
+    if let Some(model) = &request.model {
+        settings.ollama_model = model.clone();
+    }
+    let store = app_handle.state::<VectorStore>();
+    let answer = rag::answer(app_handle, &settings, &store, &document_id, &request.question, request.top_k).await?;
+    Ok((document_id, answer))
+}
+
+fn print_text(path: &Path, answer: &Answer, with_header: bool) {
+    if with_header {
+        println!("==> {} <==", path.display());
+    }
+    println!("{}", answer.answer);
+    let pages = answer.pages();
+    if !pages.is_empty() {
+        let pages: Vec<String> = pages.iter().map(u32::to_string).collect();
+        println!("\nSources: p. {}", pages.join(", "));
+    }
+    if with_header {
+        println!();
+    }
+}
+
+/// Answer the request for every document and print the results; returns the exit code
+async fn run(app_handle: &tauri::AppHandle, request: CliRequest) -> i32 {
+    let documents = match documents(&request.paths) {
+        Ok(documents) => documents,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    if let Err(e) = ensure_server(app_handle, &settings::read_settings(app_handle)).await {
+        eprintln!("{}", e);
+        return 1;
+    }
+
+    let mut failed = 0;
+    for path in &documents {
+        let result = ask(app_handle, &request, path).await;
+        if let Ok((_, answer)) = &result {
+            if answer.refused {
+                eprintln!("{}: the answer isn't supported by the document (strict grounding)", path.display());
+            }
+        }
+        match (request.json, result) {
+            (true, result) => {
+                let (document_id, answer, error) = match result {
+                    Ok((document_id, answer)) => (Some(document_id), Some(answer), None),
+                    Err(e) => (None, None, Some(e)),
+                };
+                failed += usize::from(error.is_some());
+                let line = DocumentAnswer {
+                    file: path.display().to_string(),
+                    document_id,
+                    answer,
+                    error,
+                };
+                match serde_json::to_string(&line) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => eprintln!("Failed to serialize the answer: {}", e),
+                }
+            }
+            (false, Ok((_, answer))) => print_text(path, &answer, documents.len() > 1),
+            (false, Err(e)) => {
+                failed += 1;
+                eprintln!("{}: {}", path.display(), e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Handle a command-line request in the background and exit with its status
+pub fn start(app_handle: tauri::AppHandle, request: Headless) {
+    match request {
+        Headless::Ask(request) => {
+            tauri::async_runtime::spawn(async move {
+                let code = run(&app_handle, request).await;
+                app_handle.exit(code);
+            });
+        }
+        Headless::Mcp => crate::mcp::start(app_handle),
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::Cursor;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::conversations::{ContextDocument, ConversationStore};
+use crate::error::AppError;
+use crate::ocr;
+use crate::vectorstore::now_secs;
+
+/// Longest text kept from one paste; the whole of it goes into every prompt
+const MAX_CONTEXT_CHARS: usize = 20_000;
+const TITLE_CHARS: usize = 60;
+
+/// What was on the clipboard
+enum Clip {
+    Text(String),
+    /// Encoded as PNG for OCR
+    Image(Vec<u8>),
+}
+
+fn new_context_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("ctx_{:x}", nanos)
+}
+
+/// First non-empty line, shortened, as the document's title
+fn title_of(content: &str, fallback: &str) -> String {
+    let line = content.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or(fallback);
+    let title: String = line.chars().take(TITLE_CHARS).collect();
+    if title.len() < line.len() {
+        format!("{}…", title)
+    } else {
+        title
+    }
+}
+
+fn read_clipboard() -> Result<Clip, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+    if let Ok(text) = clipboard.get_text() {
+        if !text.trim().is_empty() {
+            return Ok(Clip::Text(text));
+        }
+    }
+    let image = clipboard
+        .get_image()
+        .map_err(|_| "The clipboard holds no text or image".to_string())?;
+    let rgba = image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned())
+        .ok_or("Clipboard image has an unexpected size")?;
+    let mut png = Vec::new();
+    rgba.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode clipboard image: {}", e))?;
+    Ok(Clip::Image(png))
+}
+
+/// Store text as a context document of the conversation, trimmed to `MAX_CONTEXT_CHARS`
+fn add_document(
+    conversations: &ConversationStore,
+    conversation_id: &str,
+    kind: &str,
+    content: &str,
+) -> Result<ContextDocument, String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(match kind {
+            "screenshot" => "No text was found in the image".to_string(),
+            _ => "Nothing to add: the text is empty".to_string(),
+        });
+    }
+    let fallback = if kind == "screenshot" { "Screenshot" } else { "Pasted text" };
+    let document = ContextDocument {
+        id: new_context_id(),
+        kind: kind.to_string(),
+        title: title_of(content, fallback),
+        content: content.chars().take(MAX_CONTEXT_CHARS).collect(),
+        created_at: now_secs(),
+    };
+    if document.content.len() < content.len() {
+        log::warn!("Pasted content cut to {} characters", MAX_CONTEXT_CHARS);
+    }
+    conversations.add_context(conversation_id, &document)?;
+    log::info!("Added {} context ({} chars) to {}", kind, document.content.len(), conversation_id);
+    Ok(document)
+}
+
+/// Add the clipboard's contents to a conversation as a context document
+///
+/// Text is added as is; an image (a screenshot of an error dialog, say) is run
+/// through OCR first. The document is sent with every later question in the
+/// conversation until it's removed.
+#[tauri::command]
+pub async fn ingest_clipboard(
+    conversation_id: String,
+    languages: Option<Vec<String>>,
+    app_handle: tauri::AppHandle,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<ContextDocument, AppError> {
+    log::info!("Adding clipboard contents to {}", conversation_id);
+
+    let (kind, content) = tauri::async_runtime::spawn_blocking(move || match read_clipboard()? {
+        Clip::Text(text) => Ok::<_, String>(("text", text)),
+        Clip::Image(png) => Ok(("screenshot", ocr::recognize_image(&app_handle, &png, languages)?)),
+    })
+    .await
+    .map_err(|e| format!("Clipboard task failed: {}", e))??;
+
+    Ok(add_document(&conversations, &conversation_id, kind, &content)?)
+}
+
+/// Read the text in a PNG or JPEG image (a pasted or dropped screenshot) and add
+/// it to a conversation as a context document
+#[tauri::command]
+pub async fn ingest_image(
+    conversation_id: String,
+    image_bytes: Vec<u8>,
+    languages: Option<Vec<String>>,
+    app_handle: tauri::AppHandle,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<ContextDocument, AppError> {
+    log::info!("Adding image ({} bytes) to {}", image_bytes.len(), conversation_id);
+
+    let content = tauri::async_runtime::spawn_blocking(move || ocr::recognize_image(&app_handle, &image_bytes, languages))
+        .await
+        .map_err(|e| format!("OCR task failed: {}", e))??;
+
+    Ok(add_document(&conversations, &conversation_id, "screenshot", &content)?)
+}
+
+/// Pasted text and screenshots added to a conversation
+#[tauri::command]
+pub async fn list_context_documents(
+    conversation_id: String,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<Vec<ContextDocument>, AppError> {
+    Ok(conversations.context_documents(&conversation_id)?)
+}
+
+/// Stop sending a pasted document with the conversation's questions
+#[tauri::command]
+pub async fn remove_context_document(
+    conversation_id: String,
+    document_id: String,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<bool, AppError> {
+    log::info!("Removing context document {} from {}", document_id, conversation_id);
+    Ok(conversations.remove_context(&conversation_id, &document_id)?)
+}
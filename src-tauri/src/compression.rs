@@ -0,0 +1,75 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Value, ValueRef};
+
+/// Text shorter than this is stored as is; a zstd frame costs more than it saves
+const MIN_COMPRESSED_BYTES: usize = 512;
+const LEVEL: i32 = 3;
+
+/// Text written to a column as a zstd-compressed BLOB when that makes it smaller
+///
+/// SQLite columns hold any type, so short text, text written before compression
+/// and text that doesn't compress stay TEXT, and `StoredText` reads both back.
+pub struct Compressed<'a>(pub &'a str);
+
+impl ToSql for Compressed<'_> {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        if self.0.len() >= MIN_COMPRESSED_BYTES {
+            if let Ok(packed) = zstd::bulk::compress(self.0.as_bytes(), LEVEL) {
+                if packed.len() < self.0.len() {
+                    return Ok(ToSqlOutput::Owned(Value::Blob(packed)));
+                }
+            }
+        }
+        Ok(ToSqlOutput::Borrowed(ValueRef::Text(self.0.as_bytes())))
+    }
+}
+
+/// Text read from a column written with `Compressed`
+pub struct StoredText(pub String);
+
+impl FromSql for StoredText {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Blob(packed) => {
+                let bytes = zstd::stream::decode_all(packed).map_err(|e| FromSqlError::Other(Box::new(e)))?;
+                String::from_utf8(bytes)
+                    .map(StoredText)
+                    .map_err(|e| FromSqlError::Other(Box::new(e)))
+            }
+            other => String::column_result(other).map(StoredText),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn round_trip(text: &str) -> (String, String) {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (body TEXT NOT NULL)").unwrap();
+        conn.execute("INSERT INTO t (body) VALUES (?1)", [Compressed(text)]).unwrap();
+        conn.query_row("SELECT body, typeof(body) FROM t", [], |row| {
+            Ok((row.get::<_, StoredText>(0)?.0, row.get(1)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn long_text_is_stored_compressed() {
+        let text = "The quarterly report shows revenue growth in every region. ".repeat(40);
+        assert_eq!(round_trip(&text), (text, "blob".to_string()));
+    }
+
+    #[test]
+    fn short_text_is_stored_as_text() {
+        assert_eq!(round_trip("Short answer."), ("Short answer.".to_string(), "text".to_string()));
+    }
+
+    #[test]
+    fn plain_rows_from_older_databases_still_read() {
+        let conn = Connection::open_in_memory().unwrap();
+        let text: StoredText = conn.query_row("SELECT 'written before compression'", [], |row| row.get(0)).unwrap();
+        assert_eq!(text.0, "written before compression");
+    }
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::backend::BackendKind;
+use crate::error::AppError;
+use crate::ollama_models;
+use crate::settings::{self, AppSettings};
+
+/// Context when the model's size can't be looked up (other backends, Ollama not running)
+const FALLBACK_NUM_CTX: u32 = 8192;
+/// Share of the context kept for chat history; the rest holds the system prompt,
+/// retrieved excerpts and the answer
+const HISTORY_SHARE: usize = 5;
+/// Share of the context for retrieved excerpts
+const RETRIEVAL_SHARE: usize = 4;
+/// Rough size of one retrieved chunk with its citation header
+const CHUNK_TOKENS: usize = 800;
+const MIN_TOP_K: usize = 2;
+const MAX_TOP_K: usize = 8;
+
+/// Context sizes for a model, from what Ollama reports about it
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelContext {
+    model: String,
+    /// Longest context the model was trained for
+    context_length: Option<u64>,
+    /// e.g. "1.0B"
+    parameter_size: Option<String>,
+    /// Context requested from the server
+    pub num_ctx: u32,
+    /// Tokens of chat history sent verbatim before older turns are summarized
+    pub history_tokens: usize,
+    /// Chunks retrieved per question when the caller doesn't ask for a number
+    pub retrieval_top_k: usize,
+}
+
+impl ModelContext {
+    fn new(model: &str, context_length: Option<u64>, parameter_size: Option<String>) -> Self {
+        let by_size = parameter_size.as_deref().and_then(billions).map_or(FALLBACK_NUM_CTX, |b| {
+            // The KV cache grows with the context, and machines running small
+            // models usually can't spare the memory for a long one
+            if b < 3.0 {
+                4096
+            } else if b < 10.0 {
+                8192
+            } else {
+                16384
+            }
+        });
+        let num_ctx = match context_length {
+            Some(max) => by_size.min(max.min(u32::MAX as u64) as u32),
+            None => by_size,
+        };
+        let tokens = num_ctx as usize;
+        Self {
+            model: model.to_string(),
+            context_length,
+            parameter_size,
+            num_ctx,
+            history_tokens: tokens / HISTORY_SHARE,
+            retrieval_top_k: (tokens / RETRIEVAL_SHARE / CHUNK_TOKENS).clamp(MIN_TOP_K, MAX_TOP_K),
+        }
+    }
+}
+
+/// Parameter count in billions from Ollama's "8.0B" / "567M" notation
+fn billions(size: &str) -> Option<f64> {
+    let size = size.trim();
+    let (number, scale) = match size.chars().last()?.to_ascii_uppercase() {
+        'B' => (&size[..size.len() - 1], 1.0),
+        'M' => (&size[..size.len() - 1], 0.001),
+        'T' => (&size[..size.len() - 1], 1000.0),
+        _ => (size, 1e-9),
+    };
+    number.trim().parse::<f64>().ok().map(|n| n * scale)
+}
+
+/// Detected contexts by model name, filled when a model is selected or first used
+#[derive(Default)]
+pub struct ContextWindows {
+    models: Mutex<HashMap<String, ModelContext>>,
+}
+
+impl ContextWindows {
+    fn get(&self, model: &str) -> Option<ModelContext> {
+        self.models.lock().unwrap_or_else(|e| e.into_inner()).get(model).cloned()
+    }
+
+    fn insert(&self, context: ModelContext) {
+        self.models
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(context.model.clone(), context);
+    }
+
+    /// Drop a model's entry after it's deleted or updated
+    pub fn forget(&self, model: &str) {
+        self.models.lock().unwrap_or_else(|e| e.into_inner()).remove(model);
+    }
+}
+
+/// Context sizes for `model`, asking Ollama the first time
+///
+/// Falls back to a conservative default for other backends and when Ollama
+/// can't be reached; that result isn't cached, so the next call tries again.
+pub async fn for_model(app_handle: &tauri::AppHandle, settings: &AppSettings, model: &str) -> ModelContext {
+    let windows = app_handle.state::<ContextWindows>();
+    if let Some(context) = windows.get(model) {
+        return context;
+    }
+    if settings.llm_backend != BackendKind::Ollama {
+        return ModelContext::new(model, None, None);
+    }
+
+    match ollama_models::fetch_model_info(app_handle, model.to_string()).await {
+        Ok(info) => {
+            let context = ModelContext::new(model, info.context_length, info.details.parameter_size);
+            log::info!(
+                "Context for {}: num_ctx {} (trained {:?}, {:?} parameters)",
+                model,
+                context.num_ctx,
+                context.context_length,
+                context.parameter_size
+            );
+            windows.insert(context.clone());
+            context
+        }
+        Err(e) => {
+            log::warn!("Failed to detect the context length of {}: {}", model, e);
+            ModelContext::new(model, None, None)
+        }
+    }
+}
+
+/// Detect the context window of a model (the selected chat model by default)
+///
+/// Called when a model is selected so the first question doesn't wait for it.
+/// Chats, retrieval and history truncation then size themselves from the result.
+#[tauri::command]
+pub async fn detect_model_context(model: Option<String>, app_handle: tauri::AppHandle) -> Result<ModelContext, AppError> {
+    let settings = settings::read_settings(&app_handle);
+    let model = model.unwrap_or_else(|| settings.ollama_model.clone());
+    log::info!("Detecting context window of {}", model);
+    Ok(for_model(&app_handle, &settings, &model).await)
+}
@@ -0,0 +1,98 @@
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::ollama_chat::ChatStats;
+use crate::vectorstore::now_secs;
+
+/// Totals over the streamed answers of one conversation with one model
+#[derive(Debug, Serialize)]
+pub struct ModelStats {
+    model: String,
+    backend: String,
+    responses: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    /// Over the answers whose generation speed could be measured
+    average_tokens_per_second: Option<f64>,
+    average_total_ms: u64,
+}
+
+impl ConversationStore {
+    /// Add a streamed answer's token counts and timings to the conversation's totals
+    pub fn record_stats(&self, id: &str, stats: &ChatStats) -> Result<(), String> {
+        // Tokens and time of answers with a known speed, so the average stays a true rate
+        let (timed_tokens, generation_ms) = match (stats.completion_tokens, stats.tokens_per_second) {
+            (Some(tokens), Some(rate)) if rate > 0.0 => (tokens, (tokens as f64 / rate * 1000.0) as u64),
+            _ => (0, 0),
+        };
+        self.conn()
+            .execute(
+                "INSERT INTO conversation_stats (conversation_id, model, backend, responses, prompt_tokens,
+                    completion_tokens, timed_tokens, generation_ms, total_ms, updated_at)
+                 VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(conversation_id, model) DO UPDATE SET
+                    backend = excluded.backend,
+                    responses = responses + 1,
+                    prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                    completion_tokens = completion_tokens + excluded.completion_tokens,
+                    timed_tokens = timed_tokens + excluded.timed_tokens,
+                    generation_ms = generation_ms + excluded.generation_ms,
+                    total_ms = total_ms + excluded.total_ms,
+                    updated_at = excluded.updated_at",
+                params![
+                    id,
+                    stats.model,
+                    stats.backend,
+                    stats.prompt_tokens.unwrap_or(0) as i64,
+                    stats.completion_tokens.unwrap_or(0) as i64,
+                    timed_tokens as i64,
+                    generation_ms as i64,
+                    stats.total_ms as i64,
+                    now_secs()
+                ],
+            )
+            .map_err(|e| format!("Failed to save chat stats: {}", e))?;
+        Ok(())
+    }
+
+    pub fn stats(&self, id: &str) -> Result<Vec<ModelStats>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT model, backend, responses, prompt_tokens, completion_tokens, timed_tokens,
+                        generation_ms, total_ms
+                 FROM conversation_stats WHERE conversation_id = ?1 ORDER BY updated_at DESC",
+            )
+            .map_err(|e| format!("Failed to read chat stats: {}", e))?;
+        let stats = stmt
+            .query_map(params![id], |row| {
+                let responses = row.get::<_, i64>(2)? as u64;
+                let timed_tokens = row.get::<_, i64>(5)? as f64;
+                let generation_ms = row.get::<_, i64>(6)? as f64;
+                Ok(ModelStats {
+                    model: row.get(0)?,
+                    backend: row.get(1)?,
+                    responses,
+                    prompt_tokens: row.get::<_, i64>(3)? as u64,
+                    completion_tokens: row.get::<_, i64>(4)? as u64,
+                    average_tokens_per_second: (generation_ms > 0.0).then(|| timed_tokens / generation_ms * 1000.0),
+                    average_total_ms: row.get::<_, i64>(7)? as u64 / responses.max(1),
+                })
+            })
+            .map_err(|e| format!("Failed to read chat stats: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read chat stats: {}", e))?;
+        Ok(stats)
+    }
+}
+
+/// Token usage and generation speed of a conversation's answers, per model
+#[tauri::command]
+pub async fn get_conversation_stats(
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<Vec<ModelStats>, AppError> {
+    Ok(store.stats(&conversation_id)?)
+}
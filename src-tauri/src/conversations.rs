@@ -0,0 +1,481 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::compression::{Compressed, StoredText};
+use crate::error::AppError;
+use crate::vectorstore::now_secs;
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+
+    CREATE TABLE IF NOT EXISTS conversations (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        document_id TEXT,
+        model TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS messages (
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        id TEXT NOT NULL,
+        role TEXT NOT NULL,
+        content TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        sources TEXT,
+        PRIMARY KEY (conversation_id, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS memory (
+        conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+        summary TEXT NOT NULL,
+        summarized_count INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+
+    -- Answers can be streamed before the frontend saves the conversation, so no foreign key
+    CREATE TABLE IF NOT EXISTS conversation_stats (
+        conversation_id TEXT NOT NULL,
+        model TEXT NOT NULL,
+        backend TEXT NOT NULL,
+        responses INTEGER NOT NULL,
+        prompt_tokens INTEGER NOT NULL,
+        completion_tokens INTEGER NOT NULL,
+        timed_tokens INTEGER NOT NULL,
+        generation_ms INTEGER NOT NULL,
+        total_ms INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (conversation_id, model)
+    );
+
+    -- Pasted text and screenshots added to one conversation; no foreign key, like the stats
+    CREATE TABLE IF NOT EXISTS context_documents (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        title TEXT NOT NULL,
+        content TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_context_documents_conversation ON context_documents(conversation_id);
+
+    -- Answers generated for the same question and excerpts; the saved message holds the one shown
+    CREATE TABLE IF NOT EXISTS answer_candidates (
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        message_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        temperature REAL,
+        seed INTEGER,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (conversation_id, message_id, position)
+    );
+";
+
+/// Chat sessions mirrored from the frontend (`conversations.db` in the app data dir)
+///
+/// Lets backend features such as export work from a conversation id instead of
+/// receiving the whole transcript over IPC.
+pub struct ConversationStore {
+    conn: Mutex<Connection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    /// Unix time in milliseconds
+    pub timestamp: i64,
+    /// Search results the answer was grounded on, as sent by the frontend
+    #[serde(default)]
+    pub sources: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub document_id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Unix time in milliseconds
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[serde(default)]
+    pub messages: Vec<ConversationMessage>,
+}
+
+/// Rolling summary of the oldest turns of a conversation
+#[derive(Debug, Clone, Default)]
+pub struct ConversationMemory {
+    pub summary: String,
+    /// Number of leading messages the summary covers
+    pub summarized_count: usize,
+}
+
+/// Text added to a conversation outside of any indexed document
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextDocument {
+    pub id: String,
+    /// "text" for pasted text, "screenshot" for text read from an image
+    pub kind: String,
+    pub title: String,
+    pub content: String,
+    /// Seconds since the epoch
+    pub created_at: i64,
+}
+
+/// One of the answers generated for an assistant message
+#[derive(Debug, Clone, Serialize)]
+pub struct AnswerCandidate {
+    /// 0 is the original answer
+    pub position: usize,
+    pub content: String,
+    /// Sampling used for the answer; unknown for the original
+    pub temperature: Option<f32>,
+    pub seed: Option<i64>,
+    /// Seconds since the epoch
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationSummary {
+    id: String,
+    title: String,
+    document_id: Option<String>,
+    updated_at: i64,
+    message_count: usize,
+}
+
+impl ConversationStore {
+    /// Open (or create) the store at `path`
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = crate::storage::open(path).map_err(|e| format!("Failed to open conversation store: {}", e))?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .map_err(|e| format!("Failed to configure conversation store: {}", e))?;
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to initialize conversation store: {}", e))?;
+
+        log::info!("Conversation store opened at {}", path.display());
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Insert or replace a conversation and all its messages
+    pub fn save(&self, conversation: &Conversation) -> Result<(), String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        tx.execute(
+            "INSERT INTO conversations (id, title, document_id, model, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                document_id = excluded.document_id,
+                model = excluded.model,
+                updated_at = excluded.updated_at",
+            params![
+                conversation.id,
+                conversation.title,
+                conversation.document_id,
+                conversation.model,
+                conversation.created_at,
+                conversation.updated_at
+            ],
+        )
+        .map_err(|e| format!("Failed to save conversation: {}", e))?;
+
+        tx.execute("DELETE FROM messages WHERE conversation_id = ?1", params![conversation.id])
+            .map_err(|e| format!("Failed to save conversation: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO messages (conversation_id, position, id, role, content, timestamp, sources)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )
+                .map_err(|e| format!("Failed to prepare insert: {}", e))?;
+            for (position, message) in conversation.messages.iter().enumerate() {
+                let sources = (!message.sources.is_empty())
+                    .then(|| serde_json::to_string(&message.sources).ok())
+                    .flatten();
+                stmt.execute(params![
+                    conversation.id,
+                    position as i64,
+                    message.id,
+                    message.role,
+                    Compressed(&message.content),
+                    message.timestamp,
+                    sources
+                ])
+                .map_err(|e| format!("Failed to save message: {}", e))?;
+            }
+        }
+        tx.commit().map_err(|e| format!("Failed to commit conversation: {}", e))
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Conversation>, String> {
+        let conn = self.conn();
+        let conversation = conn
+            .query_row(
+                "SELECT id, title, document_id, model, created_at, updated_at FROM conversations WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(Conversation {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        document_id: row.get(2)?,
+                        model: row.get(3)?,
+                        created_at: row.get(4)?,
+                        updated_at: row.get(5)?,
+                        messages: Vec::new(),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read conversation: {}", e))?;
+
+        let Some(mut conversation) = conversation else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, role, content, timestamp, sources FROM messages
+                 WHERE conversation_id = ?1 ORDER BY position",
+            )
+            .map_err(|e| format!("Failed to read messages: {}", e))?;
+        conversation.messages = stmt
+            .query_map(params![id], |row| {
+                let sources: Option<String> = row.get(4)?;
+                Ok(ConversationMessage {
+                    id: row.get(0)?,
+                    role: row.get(1)?,
+                    content: row.get::<_, StoredText>(2)?.0,
+                    timestamp: row.get(3)?,
+                    sources: sources
+                        .and_then(|s| serde_json::from_str(&s).ok())
+                        .unwrap_or_default(),
+                })
+            })
+            .map_err(|e| format!("Failed to read messages: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read messages: {}", e))?;
+
+        Ok(Some(conversation))
+    }
+
+    pub fn memory(&self, id: &str) -> Result<Option<ConversationMemory>, String> {
+        self.conn()
+            .query_row(
+                "SELECT summary, summarized_count FROM memory WHERE conversation_id = ?1",
+                params![id],
+                |row| {
+                    Ok(ConversationMemory {
+                        summary: row.get(0)?,
+                        summarized_count: row.get::<_, i64>(1)? as usize,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read conversation memory: {}", e))
+    }
+
+    pub fn save_memory(&self, id: &str, memory: &ConversationMemory) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO memory (conversation_id, summary, summarized_count, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![id, memory.summary, memory.summarized_count as i64, now_secs()],
+            )
+            .map_err(|e| format!("Failed to save conversation memory: {}", e))?;
+        Ok(())
+    }
+
+    pub fn add_context(&self, id: &str, document: &ContextDocument) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "INSERT INTO context_documents (id, conversation_id, kind, title, content, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    document.id,
+                    id,
+                    document.kind,
+                    document.title,
+                    Compressed(&document.content),
+                    document.created_at
+                ],
+            )
+            .map_err(|e| format!("Failed to save context document: {}", e))?;
+        Ok(())
+    }
+
+    /// Context documents of a conversation, oldest first
+    pub fn context_documents(&self, id: &str) -> Result<Vec<ContextDocument>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, kind, title, content, created_at FROM context_documents
+                 WHERE conversation_id = ?1 ORDER BY created_at, rowid",
+            )
+            .map_err(|e| format!("Failed to read context documents: {}", e))?;
+        let documents = stmt
+            .query_map(params![id], |row| {
+                Ok(ContextDocument {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    title: row.get(2)?,
+                    content: row.get::<_, StoredText>(3)?.0,
+                    created_at: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to read context documents: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read context documents: {}", e))?;
+        Ok(documents)
+    }
+
+    pub fn remove_context(&self, id: &str, document_id: &str) -> Result<bool, String> {
+        let removed = self
+            .conn()
+            .execute(
+                "DELETE FROM context_documents WHERE conversation_id = ?1 AND id = ?2",
+                params![id, document_id],
+            )
+            .map_err(|e| format!("Failed to remove context document: {}", e))?;
+        Ok(removed > 0)
+    }
+
+    /// Answers generated for a message, oldest first
+    pub fn candidates(&self, id: &str, message_id: &str) -> Result<Vec<AnswerCandidate>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT position, content, temperature, seed, created_at FROM answer_candidates
+                 WHERE conversation_id = ?1 AND message_id = ?2 ORDER BY position",
+            )
+            .map_err(|e| format!("Failed to read answer candidates: {}", e))?;
+        let candidates = stmt
+            .query_map(params![id, message_id], |row| {
+                Ok(AnswerCandidate {
+                    position: row.get::<_, i64>(0)? as usize,
+                    content: row.get::<_, StoredText>(1)?.0,
+                    temperature: row.get::<_, Option<f64>>(2)?.map(|t| t as f32),
+                    seed: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to read answer candidates: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read answer candidates: {}", e))?;
+        Ok(candidates)
+    }
+
+    /// Store another answer for a message, after the ones it already has
+    pub fn add_candidate(
+        &self,
+        id: &str,
+        message_id: &str,
+        content: &str,
+        temperature: Option<f32>,
+        seed: Option<i64>,
+    ) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "INSERT INTO answer_candidates (conversation_id, message_id, position, content, temperature, seed, created_at)
+                 VALUES (?1, ?2,
+                    (SELECT COUNT(*) FROM answer_candidates WHERE conversation_id = ?1 AND message_id = ?2),
+                    ?3, ?4, ?5, ?6)",
+                params![id, message_id, Compressed(content), temperature.map(f64::from), seed, now_secs()],
+            )
+            .map_err(|e| format!("Failed to save answer candidate: {}", e))?;
+        Ok(())
+    }
+}
+
+/// Save (or update) a chat session so backend features can use it
+#[tauri::command]
+pub async fn save_conversation(
+    store: tauri::State<'_, ConversationStore>,
+    conversation: Conversation,
+) -> Result<(), AppError> {
+    log::info!("Saving conversation {} ({} messages)", conversation.id, conversation.messages.len());
+    Ok(store.save(&conversation)?)
+}
+
+#[tauri::command]
+pub async fn get_conversation(
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<Option<Conversation>, AppError> {
+    Ok(store.get(&conversation_id)?)
+}
+
+/// Saved conversations, most recently updated first
+#[tauri::command]
+pub async fn list_conversations(
+    store: tauri::State<'_, ConversationStore>,
+) -> Result<Vec<ConversationSummary>, AppError> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id, c.title, c.document_id, c.updated_at,
+                    (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+             FROM conversations c ORDER BY c.updated_at DESC",
+        )
+        .map_err(|e| format!("Failed to list conversations: {}", e))?;
+
+    let summaries = stmt
+        .query_map([], |row| {
+            Ok(ConversationSummary {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                document_id: row.get(2)?,
+                updated_at: row.get(3)?,
+                message_count: row.get::<_, i64>(4)? as usize,
+            })
+        })
+        .map_err(|e| format!("Failed to list conversations: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to list conversations: {}", e))?;
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub async fn delete_conversation(
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<(), AppError> {
+    log::info!("Deleting conversation {}", conversation_id);
+    let conn = store.conn();
+    conn.execute("DELETE FROM conversations WHERE id = ?1", params![conversation_id])
+        .map_err(|e| format!("Failed to delete conversation: {}", e))?;
+    conn.execute("DELETE FROM conversation_stats WHERE conversation_id = ?1", params![conversation_id])
+        .map_err(|e| format!("Failed to delete conversation: {}", e))?;
+    conn.execute("DELETE FROM context_documents WHERE conversation_id = ?1", params![conversation_id])
+        .map_err(|e| format!("Failed to delete conversation: {}", e))?;
+    conn.execute("DELETE FROM retrieval_traces WHERE conversation_id = ?1", params![conversation_id])
+        .map_err(|e| format!("Failed to delete conversation: {}", e))?;
+    Ok(())
+}
+
+/// Answers generated for an assistant message by `regenerate_answer`, original first
+#[tauri::command]
+pub async fn get_answer_candidates(
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+    message_id: String,
+) -> Result<Vec<AnswerCandidate>, AppError> {
+    Ok(store.candidates(&conversation_id, &message_id)?)
+}
@@ -0,0 +1,129 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use tauri::Manager;
+
+use crate::backend::BackendKind;
+use crate::error::AppError;
+use crate::hardware::{self, HardwareInfo};
+use crate::ollama;
+use crate::ollama_service;
+use crate::settings;
+use crate::supervisor::{self, OllamaHealth, OllamaSupervisor};
+use crate::vectorstore::now_secs;
+
+/// Request errors kept for diagnostics
+const MAX_ERRORS: usize = 50;
+/// Longer lines are cut so a stray payload can't fill the report
+const MAX_LINE_CHARS: usize = 400;
+
+static RECENT_ERRORS: Mutex<VecDeque<RecordedError>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedError {
+    at: i64,
+    code: &'static str,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OllamaDiagnostics {
+    url: String,
+    version: Option<String>,
+    health: OllamaHealth,
+    /// stderr of the server PrivatePDF started, oldest first
+    server_log: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostics {
+    generated_at: i64,
+    app_version: String,
+    hardware: HardwareInfo,
+    backend: BackendKind,
+    chat_model: String,
+    ollama: OllamaDiagnostics,
+    recent_errors: Vec<RecordedError>,
+}
+
+/// Remember a failed request; called by the `AppError` request constructors
+pub fn record_error(code: &'static str, message: &str) {
+    let mut errors = RECENT_ERRORS.lock().unwrap_or_else(|e| e.into_inner());
+    if errors.len() == MAX_ERRORS {
+        errors.pop_front();
+    }
+    errors.push_back(RecordedError {
+        at: now_secs(),
+        code,
+        message: message.to_string(),
+    });
+}
+
+/// Quoted prompt, message or document text as it can appear in server debug logs
+fn content_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r#"(?i)\b(prompt|content|text|messages|input|query)(\s*[=:]\s*)("(?:[^"\\]|\\.)*"|\[.*\]|\S+)"#)
+            .expect("valid content pattern")
+    })
+}
+
+/// Remove document and chat content, and the user's home directory, from a log line
+fn scrub(line: &str) -> String {
+    let mut line = content_pattern().replace_all(line, "$1$2<redacted>").into_owned();
+    for var in ["HOME", "USERPROFILE"] {
+        if let Some(home) = std::env::var(var).ok().filter(|h| h.len() > 1) {
+            line = line.replace(&home, "~");
+        }
+    }
+    if line.chars().count() > MAX_LINE_CHARS {
+        line = line.chars().take(MAX_LINE_CHARS).collect::<String>() + "…";
+    }
+    line
+}
+
+/// Gather app version, OS and hardware, Ollama state and logs, and recent errors
+///
+/// Meant to be attached to bug reports. Prompts, chat messages and document text
+/// are redacted from every log line and error, and home directory paths are
+/// shortened to `~`. API keys and other settings aren't included.
+#[tauri::command]
+pub async fn get_diagnostics(app_handle: tauri::AppHandle) -> Result<Diagnostics, AppError> {
+    log::info!("Collecting diagnostics");
+
+    let hardware = tauri::async_runtime::spawn_blocking(hardware::probe)
+        .await
+        .map_err(|e| format!("Hardware detection task failed: {}", e))?;
+    let settings = settings::read_settings(&app_handle);
+    let url = ollama::ollama_url(&app_handle);
+    let version = match settings.llm_backend {
+        BackendKind::Ollama => ollama_service::probe_version(&url).await.ok(),
+        BackendKind::OpenaiCompatible | BackendKind::Embedded => None,
+    };
+
+    let recent_errors = RECENT_ERRORS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|e| RecordedError {
+            message: scrub(&e.message),
+            ..e.clone()
+        })
+        .collect();
+
+    Ok(Diagnostics {
+        generated_at: now_secs(),
+        app_version: app_handle.package_info().version.to_string(),
+        hardware,
+        backend: settings.llm_backend,
+        chat_model: settings.ollama_model,
+        ollama: OllamaDiagnostics {
+            url,
+            version,
+            health: app_handle.state::<OllamaSupervisor>().health(),
+            server_log: supervisor::server_log().iter().map(|l| scrub(l)).collect(),
+        },
+        recent_errors,
+    })
+}
@@ -0,0 +1,59 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use crate::vectorstore::{now_secs, VectorStore};
+
+/// The file an index was built from, as it was when indexed
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentSource {
+    pub index_id: String,
+    pub path: String,
+    /// Modification time in seconds since the epoch
+    pub mtime: Option<i64>,
+    /// BLAKE3 hash of the file content
+    pub hash: String,
+}
+
+impl VectorStore {
+    /// Record the file an index was built from, replacing any earlier record
+    pub fn set_source(&self, source: &DocumentSource) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO document_sources (index_id, path, mtime, hash, indexed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![source.index_id, source.path, source.mtime, source.hash, now_secs()],
+            )
+            .map_err(|e| format!("Failed to record document source: {}", e))?;
+        Ok(())
+    }
+
+    fn query_source(&self, sql: &str, key: &str) -> Result<Option<DocumentSource>, String> {
+        self.conn()
+            .query_row(sql, params![key], |row| {
+                Ok(DocumentSource {
+                    index_id: row.get(0)?,
+                    path: row.get(1)?,
+                    mtime: row.get(2)?,
+                    hash: row.get(3)?,
+                })
+            })
+            .optional()
+            .map_err(|e| format!("Failed to read document source: {}", e))
+    }
+
+    pub fn source(&self, index_id: &str) -> Result<Option<DocumentSource>, String> {
+        self.query_source(
+            "SELECT index_id, path, mtime, hash FROM document_sources WHERE index_id = ?1",
+            index_id,
+        )
+    }
+
+    /// The index most recently built from `path`
+    pub fn source_by_path(&self, path: &str) -> Result<Option<DocumentSource>, String> {
+        self.query_source(
+            "SELECT index_id, path, mtime, hash FROM document_sources WHERE path = ?1
+             ORDER BY indexed_at DESC LIMIT 1",
+            path,
+        )
+    }
+}
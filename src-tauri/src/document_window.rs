@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::error::AppError;
+use crate::{ingest, settings, theme, window_state};
+
+/// Label of the window created from tauri.conf.json
+pub const MAIN_WINDOW: &str = "main";
+
+/// Documents shown in their own windows, by window label
+#[derive(Default)]
+pub struct DocumentWindows {
+    documents: Mutex<HashMap<String, String>>,
+}
+
+impl DocumentWindows {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.documents.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// One window per path: opening the same document again focuses its window
+fn label_for(path: &str) -> String {
+    format!("doc-{}", &blake3::hash(path.as_bytes()).to_hex()[..16])
+}
+
+/// Handle dropped files, OS theme changes and closing for a window
+///
+/// Events raised here go to the window they concern, so a file dropped on one
+/// document's window doesn't show up as queued in the others.
+pub fn watch(window: &WebviewWindow) {
+    let app_handle = window.app_handle().clone();
+    let window_clone = window.clone();
+    window.on_window_event(move |event| match event {
+        tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+            log::info!("{} files dropped on {}", paths.len(), window_clone.label());
+            let queue = app_handle.state::<ingest::IngestQueue>();
+            let queued: Vec<String> = paths
+                .iter()
+                .filter(|path| ingest::is_supported(path))
+                .filter(|path| match queue.enqueue(path.to_path_buf(), false) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        log::error!("Failed to queue {}: {}", path.display(), e);
+                        false
+                    }
+                })
+                .map(|path| path.display().to_string())
+                .collect();
+            // Let the frontend show the queued files; progress follows as indexing_* events
+            let _ = window_clone.emit_to(window_clone.label(), "indexing_queued", queued);
+        }
+        tauri::WindowEvent::ThemeChanged(os_theme) => theme::os_theme_changed(&window_clone, *os_theme),
+        tauri::WindowEvent::CloseRequested { .. } if window_clone.label() == MAIN_WINDOW => {
+            window_state::save(&window_clone);
+        }
+        tauri::WindowEvent::Destroyed => {
+            app_handle.state::<DocumentWindows>().lock().remove(window_clone.label());
+        }
+        _ => {}
+    });
+}
+
+/// Open a document in a window of its own, or focus the window already showing it
+///
+/// Each window has its own chat: stream chunks, chat stats and progress events
+/// of requests made from a window are only sent to that window. The new window
+/// asks `get_window_document` which document to load. Returns the window label.
+#[tauri::command]
+pub async fn open_document_window(path: String, app_handle: tauri::AppHandle) -> Result<String, AppError> {
+    log::info!("Opening {} in its own window", path);
+
+    let file = Path::new(&path);
+    if !file.is_file() {
+        return Err(AppError::Io(format!("{} doesn't exist", path)));
+    }
+    let label = label_for(&path);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        window.unminimize().ok();
+        window.set_focus().map_err(|e| format!("Failed to focus window: {}", e))?;
+        return Ok(label);
+    }
+
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+    // Registered first so the page can ask for its document as soon as it loads
+    app_handle.state::<DocumentWindows>().lock().insert(label.clone(), path.clone());
+    let window = WebviewWindowBuilder::new(&app_handle, &label, WebviewUrl::default())
+        .title(format!("{} - PrivatePDF", name))
+        .inner_size(1200.0, 850.0)
+        .min_inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| {
+            app_handle.state::<DocumentWindows>().lock().remove(&label);
+            format!("Failed to open window: {}", e)
+        })?;
+    theme::apply(&window, &settings::read_settings(&app_handle).theme);
+    watch(&window);
+    Ok(label)
+}
+
+/// Path of the document the calling window was opened for; null in the main window
+#[tauri::command]
+pub async fn get_window_document(
+    window: WebviewWindow,
+    windows: tauri::State<'_, DocumentWindows>,
+) -> Result<Option<String>, AppError> {
+    Ok(windows.lock().get(window.label()).cloned())
+}
@@ -0,0 +1,417 @@
+use docx_rs::{DocumentChild, Paragraph, ParagraphChild, RunChild, Table, TableCellContent, TableChild, TableRowChild};
+use epub::doc::EpubDoc;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::html_text::{html_title, html_to_text, mhtml_html, readable_text};
+use crate::http::{self, RetryExt};
+
+/// A structural block of a Word document, in document order
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocxBlock {
+    /// Level 1-9 from the Heading styles; the Title style is level 1
+    Heading { level: u8, text: String },
+    Paragraph { text: String },
+    Table { rows: Vec<Vec<String>> },
+}
+
+#[derive(Debug, Serialize)]
+pub struct DocxText {
+    blocks: Vec<DocxBlock>,
+    /// The blocks rendered as Markdown, ready for chunking
+    text: String,
+}
+
+/// One page of an ingested document: a chapter of an e-book, a section of a
+/// Markdown file or web page, or a form-feed separated page of a text file
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentPage {
+    /// 1-based, like PDF page numbers
+    page_number: u32,
+    title: Option<String>,
+    text: String,
+}
+
+/// Same shape as `pdf::PdfText`, so non-PDF documents go through the same chunking
+#[derive(Debug, Serialize)]
+pub struct DocumentText {
+    /// "epub", "markdown", "text", "docx" or "html"
+    format: &'static str,
+    title: Option<String>,
+    page_count: usize,
+    pages: Vec<DocumentPage>,
+}
+
+/// Heading level from a paragraph style id ("Heading2", "Title", ...)
+fn heading_level(style: &str) -> Option<u8> {
+    if style.eq_ignore_ascii_case("title") {
+        return Some(1);
+    }
+    let level = style.strip_prefix("Heading").or_else(|| style.strip_prefix("heading"))?;
+    level.trim().parse().ok().filter(|l| (1..=9).contains(l))
+}
+
+fn collect_text(children: &[ParagraphChild], out: &mut String) {
+    for child in children {
+        match child {
+            ParagraphChild::Run(run) => {
+                for run_child in &run.children {
+                    match run_child {
+                        RunChild::Text(text) => out.push_str(&text.text),
+                        RunChild::Tab(_) => out.push('\t'),
+                        RunChild::Break(_) => out.push('\n'),
+                        _ => {}
+                    }
+                }
+            }
+            ParagraphChild::Hyperlink(link) => collect_text(&link.children, out),
+            _ => {}
+        }
+    }
+}
+
+fn paragraph_text(paragraph: &Paragraph) -> String {
+    let mut text = String::new();
+    collect_text(&paragraph.children, &mut text);
+    text.trim().to_string()
+}
+
+fn paragraph_block(paragraph: &Paragraph) -> Option<DocxBlock> {
+    let text = paragraph_text(paragraph);
+    if text.is_empty() {
+        return None;
+    }
+    let level = paragraph.property.style.as_ref().and_then(|s| heading_level(&s.val));
+    Some(match level {
+        Some(level) => DocxBlock::Heading { level, text },
+        None => DocxBlock::Paragraph { text },
+    })
+}
+
+/// Table rows as cell text; nested tables are flattened into their cell
+fn table_rows(table: &Table) -> Vec<Vec<String>> {
+    table
+        .rows
+        .iter()
+        .map(|TableChild::TableRow(row)| {
+            row.cells
+                .iter()
+                .map(|TableRowChild::TableCell(cell)| {
+                    let mut parts = Vec::new();
+                    for content in &cell.children {
+                        match content {
+                            TableCellContent::Paragraph(p) => parts.push(paragraph_text(p)),
+                            TableCellContent::Table(t) => {
+                                parts.extend(table_rows(t).into_iter().map(|r| r.join(" ")));
+                            }
+                            _ => {}
+                        }
+                    }
+                    parts.retain(|p| !p.is_empty());
+                    parts.join(" ")
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\t'], " ")
+}
+
+/// Render rows as a Markdown table with the first row as header; empty if there are no cells
+pub(crate) fn markdown_table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+    let mut out = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = (0..columns)
+            .map(|c| row.get(c).map(|t| markdown_cell(t)).unwrap_or_default())
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        if i == 0 {
+            out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+        }
+    }
+    out
+}
+
+/// Render blocks as Markdown, matching what pandoc conversions produce
+fn to_markdown(blocks: &[DocxBlock]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            DocxBlock::Heading { level, text } => {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(*level as usize), text));
+            }
+            DocxBlock::Paragraph { text } => {
+                out.push_str(text);
+                out.push_str("\n\n");
+            }
+            DocxBlock::Table { rows } => {
+                let table = markdown_table(rows);
+                if !table.is_empty() {
+                    out.push_str(&table);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+fn docx_blocks(docx: &docx_rs::Docx) -> Vec<DocxBlock> {
+    docx.document
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            DocumentChild::Paragraph(p) => paragraph_block(p),
+            DocumentChild::Table(t) => {
+                let rows = table_rows(t);
+                (!rows.is_empty()).then_some(DocxBlock::Table { rows })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Extract headings, paragraphs and tables from a .docx file
+#[tauri::command]
+pub async fn extract_docx_text(path: String) -> Result<DocxText, AppError> {
+    log::info!("Extracting DOCX text: {}", path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read document: {}", e))?;
+        let docx = docx_rs::read_docx(&bytes).map_err(|e| format!("Failed to parse DOCX: {}", e))?;
+
+        let blocks = docx_blocks(&docx);
+
+        let text = to_markdown(&blocks);
+        log::info!("Extracted {} blocks ({} chars) from DOCX", blocks.len(), text.len());
+        Ok(DocxText { blocks, text })
+    })
+    .await
+    .map_err(|e| format!("DOCX extraction task failed: {}", e))?
+}
+
+fn read_epub(path: &str) -> Result<DocumentText, String> {
+    let mut doc = EpubDoc::new(path).map_err(|e| format!("Failed to open EPUB: {}", e))?;
+    let title = doc.get_title();
+
+    let mut pages = Vec::new();
+    for index in 0..doc.get_num_chapters() {
+        if !doc.set_current_chapter(index) {
+            continue;
+        }
+        let Some((html, _mime)) = doc.get_current_str() else {
+            log::warn!("Failed to read EPUB chapter {}", index + 1);
+            continue;
+        };
+        let text = html_to_text(&html);
+        // Cover pages and image-only chapters have no text to index
+        if text.is_empty() {
+            continue;
+        }
+        pages.push(DocumentPage {
+            page_number: pages.len() as u32 + 1,
+            title: html_title(&html),
+            text,
+        });
+    }
+
+    Ok(DocumentText {
+        format: "epub",
+        title,
+        page_count: pages.len(),
+        pages,
+    })
+}
+
+/// Pages of a saved web page, split at its headings
+fn html_document(html: &str) -> DocumentText {
+    let pages = markdown_pages(&readable_text(html));
+    DocumentText {
+        format: "html",
+        title: html_title(html).or_else(|| pages.first().and_then(|p| p.title.clone())),
+        page_count: pages.len(),
+        pages,
+    }
+}
+
+/// Split Markdown into sections at level 1 and 2 headings
+fn markdown_pages(markdown: &str) -> Vec<DocumentPage> {
+    let mut sections: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    let mut in_code = false;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let heading = (!in_code)
+            .then(|| line.strip_prefix("# ").or_else(|| line.strip_prefix("## ")))
+            .flatten();
+        if let Some(heading) = heading {
+            sections.push((Some(heading.trim().to_string()), String::new()));
+        }
+        let (_, text) = sections.last_mut().expect("sections is never empty");
+        text.push_str(line);
+        text.push('\n');
+    }
+
+    sections
+        .into_iter()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .enumerate()
+        .map(|(i, (title, text))| DocumentPage {
+            page_number: i as u32 + 1,
+            title,
+            text: text.trim().to_string(),
+        })
+        .collect()
+}
+
+/// Split plain text into pages at form feeds, as printed text exports use them
+fn text_pages(text: &str) -> Vec<DocumentPage> {
+    text.split('\u{000C}')
+        .filter(|page| !page.trim().is_empty())
+        .enumerate()
+        .map(|(i, page)| DocumentPage {
+            page_number: i as u32 + 1,
+            title: None,
+            text: page.trim().to_string(),
+        })
+        .collect()
+}
+
+fn read_text_file(path: &str) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read document: {}", e))?;
+    // Notes exported from other tools aren't always valid UTF-8
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(text.trim_start_matches('\u{FEFF}').to_string())
+}
+
+/// Extensions `read_document` handles
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "epub", "md", "markdown", "txt", "text", "docx", "html", "htm", "xhtml", "mhtml", "mht",
+];
+
+impl DocumentText {
+    /// Page numbers and text of every page, for indexing on the Rust side
+    pub fn into_page_texts(self) -> Vec<(u32, String)> {
+        self.pages.into_iter().map(|p| (p.page_number, p.text)).collect()
+    }
+}
+
+/// Read an EPUB, Markdown, plain-text, DOCX or saved web page into pages
+pub fn read_document(path: &str) -> Result<DocumentText, String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let document = match extension.as_str() {
+        "epub" => read_epub(path)?,
+        "md" | "markdown" => {
+            let pages = markdown_pages(&read_text_file(path)?);
+            DocumentText {
+                format: "markdown",
+                title: pages.first().and_then(|p| p.title.clone()),
+                page_count: pages.len(),
+                pages,
+            }
+        }
+        "txt" | "text" => {
+            let pages = text_pages(&read_text_file(path)?);
+            DocumentText {
+                format: "text",
+                title: None,
+                page_count: pages.len(),
+                pages,
+            }
+        }
+        "docx" => {
+            let bytes = std::fs::read(path).map_err(|e| format!("Failed to read document: {}", e))?;
+            let docx = docx_rs::read_docx(&bytes).map_err(|e| format!("Failed to parse DOCX: {}", e))?;
+            let text = to_markdown(&docx_blocks(&docx));
+            let pages = markdown_pages(&text);
+            DocumentText {
+                format: "docx",
+                title: pages.first().and_then(|p| p.title.clone()),
+                page_count: pages.len(),
+                pages,
+            }
+        }
+        "html" | "htm" | "xhtml" => html_document(&read_text_file(path)?),
+        "mhtml" | "mht" => html_document(&mhtml_html(&read_text_file(path)?)?),
+        "pdf" => return Err("Use extract_text for PDF files".to_string()),
+        other => return Err(format!("Unsupported document type: .{}", other)),
+    };
+
+    log::info!("Extracted {} pages from {} document", document.page_count, document.format);
+    Ok(document)
+}
+
+/// Extract EPUB, Markdown, plain-text, DOCX and HTML/MHTML files into pages
+///
+/// Produces the same page structure as `extract_text` does for PDFs, so the
+/// frontend chunks and indexes every format the same way.
+#[tauri::command]
+pub async fn extract_document(path: String) -> Result<DocumentText, AppError> {
+    log::info!("Extracting document text: {}", path);
+
+    Ok(tauri::async_runtime::spawn_blocking(move || read_document(&path))
+        .await
+        .map_err(|e| AppError::Other(format!("Document extraction task failed: {}", e)))??)
+}
+
+/// Extract the article text of a saved web page (HTML or MHTML) into pages
+///
+/// Menus, cookie banners, sidebars and footers are dropped and the text is split
+/// at its headings, so it chunks like any other document. Takes a local path or
+/// `file://` URL; `http(s)` URLs are fetched through the network allowlist, so
+/// only pages on localhost work without saving them first.
+#[tauri::command]
+pub async fn extract_html_text(path_or_url: String) -> Result<DocumentText, AppError> {
+    log::info!("Extracting web page text: {}", path_or_url);
+
+    if path_or_url.starts_with("http://") || path_or_url.starts_with("https://") {
+        let response = http::get(&path_or_url)?
+            .timeout(http::read_timeout())
+            .send_with_retry()
+            .await
+            .map_err(|e| format!("Failed to fetch page: {}", e))?;
+        if !response.status().is_success() {
+            return Err(AppError::status("Fetching page", response.status()));
+        }
+        let html = response.text().await.map_err(|e| format!("Failed to read page: {}", e))?;
+        let document = html_document(&html);
+        log::info!("Extracted {} sections from {}", document.page_count, path_or_url);
+        return Ok(document);
+    }
+
+    let path = match reqwest::Url::parse(&path_or_url) {
+        Ok(url) if url.scheme() == "file" => url
+            .to_file_path()
+            .map_err(|_| format!("Invalid file URL: {}", path_or_url))?
+            .to_string_lossy()
+            .into_owned(),
+        _ => path_or_url,
+    };
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    if !["html", "htm", "xhtml", "mhtml", "mht"].contains(&extension.as_str()) {
+        return Err(AppError::Unsupported(format!("Not a web page: {}", path)));
+    }
+
+    Ok(tauri::async_runtime::spawn_blocking(move || read_document(&path))
+        .await
+        .map_err(|e| AppError::Other(format!("Web page extraction task failed: {}", e)))??)
+}
@@ -0,0 +1,231 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::compression::{Compressed, StoredText};
+use crate::error::AppError;
+use crate::vectorstore::{decode_vector, encode_vector, now_secs};
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+
+    CREATE TABLE IF NOT EXISTS documents (
+        hash TEXT PRIMARY KEY,
+        model TEXT,
+        dimension INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS chunks (
+        hash TEXT NOT NULL REFERENCES documents(hash) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        text TEXT NOT NULL,
+        metadata TEXT,
+        vector BLOB NOT NULL,
+        PRIMARY KEY (hash, position)
+    );
+";
+
+/// Chunk embeddings of previously processed documents, keyed by the BLAKE3 hash
+/// of the file (`embedding_cache.db` in the app data dir)
+///
+/// Reopening an unchanged document reuses its chunks and embeddings instead of
+/// sending every chunk to the embedding model again.
+///
+/// Only indexing uses it, so the database is opened on first use rather than at startup.
+pub struct EmbeddingCache {
+    path: PathBuf,
+    conn: OnceLock<Mutex<Connection>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChunkInput {
+    pub text: String,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CachedChunk {
+    text: String,
+    metadata: Option<serde_json::Value>,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CachedDocument {
+    hash: String,
+    /// Embedding model the vectors were produced with
+    model: Option<String>,
+    dimension: usize,
+    created_at: i64,
+    chunks: Vec<CachedChunk>,
+}
+
+impl EmbeddingCache {
+    /// A cache at `path`, opened (or created) the first time it's used
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            conn: OnceLock::new(),
+        }
+    }
+
+    fn open(path: &Path) -> Result<Connection, String> {
+        let conn = crate::storage::open(path).map_err(|e| format!("Failed to open embedding cache: {}", e))?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .map_err(|e| format!("Failed to configure embedding cache: {}", e))?;
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to initialize embedding cache: {}", e))?;
+
+        log::info!("Embedding cache opened at {}", path.display());
+        Ok(conn)
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>, String> {
+        if self.conn.get().is_none() {
+            // Two threads racing here both open the database; the loser's connection is dropped
+            let _ = self.conn.set(Mutex::new(Self::open(&self.path)?));
+        }
+        let conn = self.conn.get().expect("embedding cache connection was just set");
+        Ok(conn.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn get(&self, hash: &str) -> Result<Option<CachedDocument>, String> {
+        let conn = self.conn()?;
+        let header = conn
+            .query_row(
+                "SELECT model, dimension, created_at FROM documents WHERE hash = ?1",
+                params![hash],
+                |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read embedding cache: {}", e))?;
+
+        let Some((model, dimension, created_at)) = header else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare("SELECT text, metadata, vector FROM chunks WHERE hash = ?1 ORDER BY position")
+            .map_err(|e| format!("Failed to read embedding cache: {}", e))?;
+        let chunks = stmt
+            .query_map(params![hash], |row| {
+                let metadata: Option<String> = row.get(1)?;
+                let vector: Vec<u8> = row.get(2)?;
+                Ok(CachedChunk {
+                    text: row.get::<_, StoredText>(0)?.0,
+                    metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+                    embedding: decode_vector(&vector),
+                })
+            })
+            .map_err(|e| format!("Failed to read embedding cache: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read embedding cache: {}", e))?;
+
+        Ok(Some(CachedDocument {
+            hash: hash.to_string(),
+            model,
+            dimension: dimension as usize,
+            created_at,
+            chunks,
+        }))
+    }
+
+    /// Replace the cached chunks of a document in one transaction
+    pub fn store(
+        &self,
+        hash: &str,
+        model: Option<&str>,
+        chunks: &[ChunkInput],
+        embeddings: &[Vec<f32>],
+    ) -> Result<usize, String> {
+        if chunks.len() != embeddings.len() {
+            return Err(format!(
+                "Got {} chunks but {} embeddings",
+                chunks.len(),
+                embeddings.len()
+            ));
+        }
+        let dimension = embeddings.first().map(|e| e.len()).unwrap_or(0);
+        if embeddings.iter().any(|e| e.len() != dimension) {
+            return Err("Embeddings have inconsistent dimensions".to_string());
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+        tx.execute("DELETE FROM documents WHERE hash = ?1", params![hash])
+            .map_err(|e| format!("Failed to replace cached document: {}", e))?;
+        tx.execute(
+            "INSERT INTO documents (hash, model, dimension, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![hash, model, dimension as i64, now_secs()],
+        )
+        .map_err(|e| format!("Failed to cache document: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare("INSERT INTO chunks (hash, position, text, metadata, vector) VALUES (?1, ?2, ?3, ?4, ?5)")
+                .map_err(|e| format!("Failed to prepare insert: {}", e))?;
+            for (position, (chunk, embedding)) in chunks.iter().zip(embeddings).enumerate() {
+                let metadata = chunk.metadata.as_ref().map(|m| m.to_string());
+                stmt.execute(params![hash, position as i64, Compressed(&chunk.text), metadata, encode_vector(embedding)])
+                    .map_err(|e| format!("Failed to cache chunk: {}", e))?;
+            }
+        }
+        tx.commit().map_err(|e| format!("Failed to commit cache: {}", e))?;
+
+        Ok(chunks.len())
+    }
+}
+
+/// BLAKE3 hash of a file's contents as a hex string
+pub fn hash_file(path: &str) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Content hash used as the cache key for a document
+#[tauri::command]
+pub async fn hash_document(path: String) -> Result<String, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || hash_file(&path))
+        .await
+        .map_err(|e| AppError::Other(format!("Hashing task failed: {}", e)))??)
+}
+
+/// Cached chunks and embeddings for a document hash, if it was processed before
+#[tauri::command]
+pub async fn get_cached_document(
+    cache: tauri::State<'_, EmbeddingCache>,
+    hash: String,
+) -> Result<Option<CachedDocument>, AppError> {
+    let document = cache.get(&hash)?;
+    log::info!(
+        "Embedding cache {} for {}",
+        if document.is_some() { "hit" } else { "miss" },
+        hash
+    );
+    Ok(document)
+}
+
+/// Store a document's chunks and their embeddings under its content hash
+#[tauri::command]
+pub async fn store_document_cache(
+    cache: tauri::State<'_, EmbeddingCache>,
+    hash: String,
+    chunks: Vec<ChunkInput>,
+    embeddings: Vec<Vec<f32>>,
+    model: Option<String>,
+) -> Result<usize, AppError> {
+    log::info!("Caching {} chunk embeddings for {}", chunks.len(), hash);
+    Ok(cache.store(&hash, model.as_deref(), &chunks, &embeddings)?)
+}
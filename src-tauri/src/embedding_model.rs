@@ -0,0 +1,71 @@
+use serde::Serialize;
+use serde_json::json;
+use tauri::Emitter;
+
+use crate::backend::BackendKind;
+use crate::error::AppError;
+use crate::ollama_models;
+use crate::settings::{self, AppSettings};
+use crate::vectorstore::VectorStore;
+
+/// Embedding model used when the `embedding_model` setting is empty
+pub const DEFAULT: &str = "nomic-embed-text";
+
+/// Embedding model from settings, used for indexing and queries alike
+pub fn from_settings(settings: &AppSettings) -> String {
+    match settings.embedding_model.trim() {
+        "" => DEFAULT.to_string(),
+        model => model.to_string(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingModelStatus {
+    model: String,
+    /// Whether Ollama has the model; None with other backends, which can't be checked
+    installed: Option<bool>,
+    /// Indexes built with a different embedding model, which queries can't search until re-indexed
+    mismatched_indexes: usize,
+}
+
+/// Check that the configured embedding model is installed
+///
+/// Emits `embedding_model_missing` when it isn't, so the UI can offer to pull it,
+/// and counts the indexes built with another model.
+#[tauri::command]
+pub async fn check_embedding_model(
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<EmbeddingModelStatus, AppError> {
+    let settings = settings::read_settings(&app_handle);
+    let model = from_settings(&settings);
+    log::info!("Checking embedding model {}", model);
+
+    let installed = match settings.llm_backend {
+        BackendKind::Ollama => {
+            let models = ollama_models::list_ollama_models(app_handle.clone()).await?;
+            let latest = format!("{}:latest", model);
+            Some(models.iter().any(|m| m.name == model || m.name == latest))
+        }
+        _ => None,
+    };
+    if installed == Some(false) {
+        log::warn!("Embedding model {} is not installed", model);
+        app_handle.emit("embedding_model_missing", json!({ "model": model })).ok();
+    }
+
+    let mismatched_indexes = store
+        .conn()
+        .query_row(
+            "SELECT COUNT(*) FROM indexes WHERE model IS NOT NULL AND model != ?1",
+            [&model],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|e| format!("Failed to read indexes: {}", e))? as usize;
+
+    Ok(EmbeddingModelStatus {
+        model,
+        installed,
+        mismatched_indexes,
+    })
+}
@@ -0,0 +1,152 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Error returned by commands, serialized as `{ "code": "OLLAMA_UNREACHABLE", "message": "..." }`
+///
+/// The frontend branches on `code`; `message` is meant for the user. Converts to
+/// and from `String`, so modules that still use string errors can call commands
+/// that return it with `?`, and vice versa.
+#[derive(Debug)]
+pub enum AppError {
+    /// The Ollama server isn't running or can't be reached
+    OllamaUnreachable(String),
+    /// No Ollama binary was found to start
+    OllamaNotInstalled(String),
+    /// The requested model isn't installed
+    ModelNotFound(String),
+    Timeout(String),
+    /// Any other connection failure
+    Network(String),
+    /// The server answered with an error status
+    Http { status: u16, message: String },
+    Io(String),
+    Parse(String),
+    Cancelled(String),
+    ChecksumMismatch(String),
+    /// Not possible on this platform or with this configuration
+    Unsupported(String),
+    Other(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::OllamaUnreachable(_) => "OLLAMA_UNREACHABLE",
+            AppError::OllamaNotInstalled(_) => "OLLAMA_NOT_INSTALLED",
+            AppError::ModelNotFound(_) => "MODEL_NOT_FOUND",
+            AppError::Timeout(_) => "TIMEOUT",
+            AppError::Network(_) => "NETWORK",
+            AppError::Http { .. } => "HTTP",
+            AppError::Io(_) => "IO",
+            AppError::Parse(_) => "PARSE",
+            AppError::Cancelled(_) => "CANCELLED",
+            AppError::ChecksumMismatch(_) => "CHECKSUM_MISMATCH",
+            AppError::Unsupported(_) => "UNSUPPORTED",
+            AppError::Other(_) => "OTHER",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::OllamaUnreachable(m)
+            | AppError::OllamaNotInstalled(m)
+            | AppError::ModelNotFound(m)
+            | AppError::Timeout(m)
+            | AppError::Network(m)
+            | AppError::Io(m)
+            | AppError::Parse(m)
+            | AppError::Cancelled(m)
+            | AppError::ChecksumMismatch(m)
+            | AppError::Unsupported(m)
+            | AppError::Other(m) => m,
+            AppError::Http { message, .. } => message,
+        }
+    }
+
+    /// A request to a server other than Ollama that failed before a response arrived
+    pub fn request(what: &str, e: reqwest::Error) -> Self {
+        let message = format!("{} failed: {}", what, e);
+        let error = if e.is_timeout() {
+            AppError::Timeout(message)
+        } else if e.is_decode() {
+            AppError::Parse(message)
+        } else {
+            AppError::Network(message)
+        };
+        error.recorded()
+    }
+
+    /// A request to the Ollama API that failed; a refused connection means the server isn't running
+    pub fn ollama_request(what: &str, e: reqwest::Error) -> Self {
+        if e.is_connect() {
+            AppError::OllamaUnreachable(format!("{} failed: Ollama is not running ({})", what, e)).recorded()
+        } else {
+            Self::request(what, e)
+        }
+    }
+
+    /// An error status from the Ollama API; 404 means the model isn't installed
+    pub fn ollama_status(what: &str, model: &str, status: reqwest::StatusCode) -> Self {
+        if status == reqwest::StatusCode::NOT_FOUND {
+            AppError::ModelNotFound(format!("Model not found: {}", model))
+        } else {
+            Self::status(what, status)
+        }
+    }
+
+    pub fn status(what: &str, status: reqwest::StatusCode) -> Self {
+        AppError::Http {
+            status: status.as_u16(),
+            message: format!("{} failed: HTTP {}", what, status),
+        }
+        .recorded()
+    }
+
+    /// An `error` field in an Ollama response body
+    pub fn ollama_error(error: &str) -> Self {
+        // Ollama reports unknown models as "model 'x' not found" or, when pulling,
+        // "pull model manifest: file does not exist"
+        let error = if error.contains("not found") || error.contains("does not exist") {
+            AppError::ModelNotFound(format!("Ollama error: {}", error))
+        } else {
+            AppError::Other(format!("Ollama error: {}", error))
+        };
+        error.recorded()
+    }
+
+    /// Keep this error for `get_diagnostics`
+    fn recorded(self) -> Self {
+        crate::diagnostics::record_error(self.code(), self.message());
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
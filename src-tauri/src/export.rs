@@ -0,0 +1,313 @@
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+
+use crate::conversations::{Conversation, ConversationMessage, ConversationStore};
+use crate::error::AppError;
+
+/// Longest source excerpt quoted under an answer
+const MAX_SNIPPET_CHARS: usize = 200;
+
+// A4 layout for the PDF export
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const BODY_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 5.0;
+/// Characters per line at the body size; Helvetica averages about half an em per character
+const WRAP_CHARS: usize = 95;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Pdf,
+}
+
+/// A source cited under an answer
+#[derive(Debug, Serialize)]
+struct Citation {
+    document: Option<String>,
+    page: Option<u64>,
+    snippet: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportResult {
+    path: String,
+    messages: usize,
+    bytes: u64,
+}
+
+/// First string/number found at any of the given JSON pointers
+fn pick<'a>(value: &'a serde_json::Value, pointers: &[&str]) -> Option<&'a serde_json::Value> {
+    pointers
+        .iter()
+        .filter_map(|p| value.pointer(p))
+        .find(|v| !v.is_null())
+}
+
+/// Pull document, page and snippet out of a frontend search result
+fn citation(source: &serde_json::Value) -> Citation {
+    let snippet = pick(source, &["/snippet", "/chunk/text", "/text"])
+        .and_then(|v| v.as_str())
+        .map(|s| {
+            let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+            if s.chars().count() > MAX_SNIPPET_CHARS {
+                format!("{}…", s.chars().take(MAX_SNIPPET_CHARS).collect::<String>())
+            } else {
+                s
+            }
+        });
+
+    Citation {
+        document: pick(source, &["/documentName", "/document", "/chunk/documentId"])
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        page: pick(source, &["/pageNumber", "/chunk/pageNumber", "/page", "/metadata/page"]).and_then(|v| v.as_u64()),
+        snippet,
+    }
+}
+
+fn citation_label(citation: &Citation) -> String {
+    match (&citation.document, citation.page) {
+        (Some(doc), Some(page)) => format!("{}, p. {}", doc, page),
+        (Some(doc), None) => doc.clone(),
+        (None, Some(page)) => format!("p. {}", page),
+        (None, None) => "source".to_string(),
+    }
+}
+
+fn role_label(role: &str) -> &str {
+    match role {
+        "user" => "You",
+        "assistant" => "Assistant",
+        "system" => "System",
+        other => other,
+    }
+}
+
+fn format_time(millis: i64) -> String {
+    // Seconds precision is enough for an archive; no timezone database needed
+    let secs = millis / 1000;
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", y, m, d, rem / 3600, (rem % 3600) / 60)
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+fn to_markdown(conversation: &Conversation) -> String {
+    let mut out = format!("# {}\n\n", conversation.title);
+    out.push_str(&format!("_Exported from PrivatePDF · {}_\n\n", format_time(conversation.updated_at)));
+    if let Some(model) = &conversation.model {
+        out.push_str(&format!("Model: `{}`\n\n", model));
+    }
+
+    for message in &conversation.messages {
+        out.push_str(&format!(
+            "## {} · {}\n\n{}\n\n",
+            role_label(&message.role),
+            format_time(message.timestamp),
+            message.content.trim()
+        ));
+
+        if !message.sources.is_empty() {
+            out.push_str("**Sources**\n\n");
+            for (i, source) in message.sources.iter().enumerate() {
+                let citation = citation(source);
+                out.push_str(&format!("{}. {}", i + 1, citation_label(&citation)));
+                if let Some(snippet) = &citation.snippet {
+                    out.push_str(&format!(" — \"{}\"", snippet));
+                }
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn to_json(conversation: &Conversation) -> Result<String, String> {
+    let messages: Vec<serde_json::Value> = conversation
+        .messages
+        .iter()
+        .map(|m: &ConversationMessage| {
+            serde_json::json!({
+                "id": m.id,
+                "role": m.role,
+                "content": m.content,
+                "timestamp": m.timestamp,
+                "citations": m.sources.iter().map(citation).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "id": conversation.id,
+        "title": conversation.title,
+        "document_id": conversation.document_id,
+        "model": conversation.model,
+        "created_at": conversation.created_at,
+        "updated_at": conversation.updated_at,
+        "messages": messages,
+    }))
+    .map_err(|e| format!("Failed to serialize conversation: {}", e))
+}
+
+/// Wrap text to `width` characters, keeping paragraph breaks
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Writes lines top to bottom, adding pages as needed
+struct PdfWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+}
+
+impl PdfWriter {
+    fn line(&mut self, text: &str, size: f32, bold: bool) {
+        if self.y < MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.use_text(text, size, Mm(MARGIN), Mm(self.y), font);
+        self.y -= LINE_HEIGHT * size / BODY_SIZE;
+    }
+
+    fn paragraph(&mut self, text: &str, size: f32, bold: bool) {
+        let width = (WRAP_CHARS as f32 * BODY_SIZE / size) as usize;
+        for line in wrap(text, width) {
+            self.line(&line, size, bold);
+        }
+    }
+
+    fn gap(&mut self) {
+        self.y -= LINE_HEIGHT;
+    }
+}
+
+/// Render the conversation to a simple paginated PDF
+///
+/// Uses the PDF base fonts, so no font files are bundled; characters outside
+/// Latin-1 may not render.
+fn write_pdf(conversation: &Conversation, path: &str) -> Result<(), String> {
+    let (doc, page, layer) = PdfDocument::new(&conversation.title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let regular = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| format!("Failed to load PDF font: {}", e))?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| format!("Failed to load PDF font: {}", e))?;
+    let layer = doc.get_page(page).get_layer(layer);
+
+    let mut pdf = PdfWriter {
+        doc,
+        layer,
+        regular,
+        bold,
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    pdf.paragraph(&conversation.title, 16.0, true);
+    pdf.paragraph(
+        &format!("Exported from PrivatePDF, {}", format_time(conversation.updated_at)),
+        8.0,
+        false,
+    );
+    pdf.gap();
+
+    for message in &conversation.messages {
+        pdf.paragraph(
+            &format!("{} - {}", role_label(&message.role), format_time(message.timestamp)),
+            BODY_SIZE,
+            true,
+        );
+        pdf.paragraph(message.content.trim(), BODY_SIZE, false);
+
+        if !message.sources.is_empty() {
+            pdf.gap();
+            pdf.paragraph("Sources", 8.0, true);
+            for (i, source) in message.sources.iter().enumerate() {
+                let citation = citation(source);
+                let mut text = format!("[{}] {}", i + 1, citation_label(&citation));
+                if let Some(snippet) = &citation.snippet {
+                    text.push_str(&format!(" - \"{}\"", snippet));
+                }
+                pdf.paragraph(&text, 8.0, false);
+            }
+        }
+        pdf.gap();
+    }
+
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    pdf.doc
+        .save(&mut BufWriter::new(file))
+        .map_err(|e| format!("Failed to write PDF: {}", e))
+}
+
+/// Export a saved conversation with its citations as Markdown, JSON or PDF
+#[tauri::command]
+pub async fn export_conversation(
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+    format: ExportFormat,
+    path: String,
+) -> Result<ExportResult, AppError> {
+    log::info!("Exporting conversation {} as {:?}", conversation_id, format);
+
+    let conversation = store
+        .get(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let messages = conversation.messages.len();
+
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || match format {
+        ExportFormat::Markdown => std::fs::write(&target, to_markdown(&conversation))
+            .map_err(|e| format!("Failed to write export: {}", e)),
+        ExportFormat::Json => std::fs::write(&target, to_json(&conversation)?)
+            .map_err(|e| format!("Failed to write export: {}", e)),
+        ExportFormat::Pdf => write_pdf(&conversation, &target),
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))??;
+
+    let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    log::info!("Exported {} messages to {} ({} bytes)", messages, path, bytes);
+    Ok(ExportResult { path, messages, bytes })
+}
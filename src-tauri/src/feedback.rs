@@ -0,0 +1,173 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::vectorstore::{now_secs, SearchHit, VectorStore};
+
+/// Feedback lives in `vectors.db` so it's deleted with the index it rates.
+/// One row per chunk per answer, so rating an answer again replaces its votes.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS chunk_feedback (
+        index_id TEXT NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+        chunk_id TEXT NOT NULL,
+        conversation_id TEXT NOT NULL,
+        message_id TEXT NOT NULL,
+        rating INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (conversation_id, message_id, index_id, chunk_id)
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_chunk_feedback_index ON chunk_feedback(index_id);
+";
+
+/// Score change per net vote; hybrid scores run from 0 to 1
+const BOOST_PER_VOTE: f32 = 0.05;
+/// Net votes beyond this don't move a chunk further, so one document can't be
+/// dominated by a few heavily-rated chunks
+const MAX_NET_VOTES: i64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    /// The chunk supported a good answer
+    Helpful,
+    /// The chunk was irrelevant or led to a wrong answer
+    Wrong,
+    /// Withdraw earlier feedback for this answer
+    None,
+}
+
+impl Rating {
+    fn value(self) -> i64 {
+        match self {
+            Rating::Helpful => 1,
+            Rating::Wrong => -1,
+            Rating::None => 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackRecorded {
+    /// Chunks the rating was stored for
+    recorded: usize,
+    /// Chunk ids that weren't found in any index
+    unknown: Vec<String>,
+}
+
+/// Create the feedback table in the vector store database
+pub fn init(store: &VectorStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize feedback: {}", e))
+}
+
+/// Net votes per chunk of an index, as score adjustments
+fn boosts(store: &VectorStore, index_id: &str) -> Result<HashMap<String, f32>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare("SELECT chunk_id, SUM(rating) FROM chunk_feedback WHERE index_id = ?1 GROUP BY chunk_id")
+        .map_err(|e| format!("Failed to read feedback: {}", e))?;
+    let boosts = stmt
+        .query_map(params![index_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Failed to read feedback: {}", e))?
+        .filter_map(|r| r.ok())
+        .filter(|(_, net)| *net != 0)
+        .map(|(chunk_id, net)| (chunk_id, net.clamp(-MAX_NET_VOTES, MAX_NET_VOTES) as f32 * BOOST_PER_VOTE))
+        .collect();
+    Ok(boosts)
+}
+
+/// Adjust hit scores by the feedback recorded for their chunks and re-sort
+pub fn apply(store: &VectorStore, index_id: &str, hits: &mut [SearchHit]) {
+    let boosts = match boosts(store, index_id) {
+        Ok(boosts) if !boosts.is_empty() => boosts,
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    for hit in hits.iter_mut() {
+        if let Some(boost) = boosts.get(&hit.chunk_id) {
+            hit.score += boost;
+        }
+    }
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// Index holding a chunk: the conversation's document when it has one, else any index with that chunk id
+fn chunk_index(store: &VectorStore, document_id: Option<&str>, chunk_id: &str) -> Result<Option<String>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT index_id FROM embeddings WHERE chunk_id = ?1")
+        .map_err(|e| format!("Failed to look up chunk: {}", e))?;
+    let indexes = stmt
+        .query_map(params![chunk_id], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to look up chunk: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to look up chunk: {}", e))?;
+    Ok(match document_id {
+        Some(id) if indexes.iter().any(|i| i == id) => Some(id.to_string()),
+        _ => indexes.into_iter().next(),
+    })
+}
+
+/// Rate the chunks an answer was grounded on
+///
+/// Chunks marked helpful rank higher in later searches of the same document and
+/// ones marked wrong rank lower. Rating the same answer again replaces the earlier
+/// rating; `none` withdraws it.
+#[tauri::command]
+pub async fn record_feedback(
+    conversation_id: String,
+    message_id: String,
+    chunk_ids: Vec<String>,
+    rating: Rating,
+    store: tauri::State<'_, VectorStore>,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<FeedbackRecorded, AppError> {
+    log::info!(
+        "Recording {:?} feedback for {} chunks of message {} in {}",
+        rating,
+        chunk_ids.len(),
+        message_id,
+        conversation_id
+    );
+
+    let document_id = conversations.get(&conversation_id)?.and_then(|c| c.document_id);
+    let mut recorded = 0;
+    let mut unknown = Vec::new();
+
+    for chunk_id in chunk_ids {
+        let Some(index_id) = chunk_index(&store, document_id.as_deref(), &chunk_id)? else {
+            unknown.push(chunk_id);
+            continue;
+        };
+        let conn = store.conn();
+        let result = if rating == Rating::None {
+            conn.execute(
+                "DELETE FROM chunk_feedback
+                 WHERE conversation_id = ?1 AND message_id = ?2 AND index_id = ?3 AND chunk_id = ?4",
+                params![conversation_id, message_id, index_id, chunk_id],
+            )
+        } else {
+            conn.execute(
+                "INSERT OR REPLACE INTO chunk_feedback
+                    (index_id, chunk_id, conversation_id, message_id, rating, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![index_id, chunk_id, conversation_id, message_id, rating.value(), now_secs()],
+            )
+        };
+        result.map_err(|e| format!("Failed to record feedback: {}", e))?;
+        recorded += 1;
+    }
+
+    if !unknown.is_empty() {
+        log::warn!("Feedback for {} unknown chunks was ignored", unknown.len());
+    }
+    Ok(FeedbackRecorded { recorded, unknown })
+}
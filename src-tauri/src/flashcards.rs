@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::error::AppError;
+use crate::ollama::ChatMessage;
+use crate::ollama_chat;
+use crate::scheduler::Lane;
+
+/// Upper bound on cards per request so the answer fits the model's output budget
+const MAX_CARDS: usize = 50;
+
+/// A passage of the document the cards are generated from
+#[derive(Debug, Deserialize)]
+pub struct FlashcardSource {
+    pub text: String,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Flashcard {
+    pub front: String,
+    pub back: String,
+    /// Citation shown on the card back, e.g. "Report.pdf, p. 12"
+    #[serde(default)]
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeneratedCard {
+    front: String,
+    back: String,
+    #[serde(default)]
+    source: Option<usize>,
+}
+
+fn build_prompt(sources: &[FlashcardSource], count: usize, cloze: bool) -> String {
+    let mut prompt = String::new();
+    prompt.push_str(&format!(
+        "Create {} flashcards covering the key points of the numbered passages below.\n",
+        count
+    ));
+    if cloze {
+        prompt.push_str(
+            "Each card is a cloze deletion: put the full sentence in \"front\" with the key term wrapped as {{c1::term}}, and a short explanation in \"back\".\n",
+        );
+    } else {
+        prompt.push_str("Each card has a question in \"front\" and a concise answer in \"back\".\n");
+    }
+    prompt.push_str(
+        "Set \"source\" to the number of the passage the card comes from.\n\
+         Reply with JSON only, in the form {\"cards\": [{\"front\": \"...\", \"back\": \"...\", \"source\": 1}]}.\n\n",
+    );
+    for (i, source) in sources.iter().enumerate() {
+        prompt.push_str(&format!("[{}] {}\n\n", i + 1, source.text.trim()));
+    }
+    prompt
+}
+
+/// Pull the JSON object out of a model reply that may include extra prose or code fences
+fn parse_cards(reply: &str) -> Result<Vec<GeneratedCard>, String> {
+    let start = reply.find('{').ok_or("Model reply contained no JSON")?;
+    let end = reply.rfind('}').ok_or("Model reply contained no JSON")?;
+
+    #[derive(Deserialize)]
+    struct Cards {
+        cards: Vec<GeneratedCard>,
+    }
+
+    serde_json::from_str::<Cards>(&reply[start..=end])
+        .map(|c| c.cards)
+        .map_err(|e| format!("Failed to parse generated cards: {}", e))
+}
+
+/// Generate Q&A or cloze flashcards from document passages with the local model
+#[tauri::command]
+pub async fn generate_flashcards(
+    app_handle: tauri::AppHandle,
+    model: String,
+    document: String,
+    sources: Vec<FlashcardSource>,
+    count: usize,
+    cloze: Option<bool>,
+) -> Result<Vec<Flashcard>, AppError> {
+    if sources.is_empty() {
+        return Err(AppError::Other("No passages to generate flashcards from".to_string()));
+    }
+    let count = count.clamp(1, MAX_CARDS);
+    let cloze = cloze.unwrap_or(false);
+    log::info!("Generating {} flashcards from {} passages of {}", count, sources.len(), document);
+
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You write study flashcards. Only use facts stated in the passages.".to_string(),
+            images: Vec::new(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: build_prompt(&sources, count, cloze),
+            images: Vec::new(),
+        },
+    ];
+
+    let reply = ollama_chat::chat_in(Lane::Background, model, messages, Some(0.3), Some(4096), None, None, None, app_handle).await?;
+    let generated = parse_cards(&reply)?;
+
+    let cards: Vec<Flashcard> = generated
+        .into_iter()
+        .filter(|c| !c.front.trim().is_empty() && !c.back.trim().is_empty())
+        .take(count)
+        .map(|c| {
+            let page = c
+                .source
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| sources.get(i))
+                .and_then(|s| s.page);
+            let source = match page {
+                Some(page) => format!("{}, p. {}", document, page),
+                None => document.clone(),
+            };
+            Flashcard {
+                front: c.front.trim().to_string(),
+                back: c.back.trim().to_string(),
+                source,
+            }
+        })
+        .collect();
+
+    log::info!("Generated {} flashcards", cards.len());
+    Ok(cards)
+}
+
+/// Escape a field for Anki's tab-separated import with HTML enabled
+fn anki_field(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\t', " ")
+        .replace('\n', "<br>")
+}
+
+/// Export flashcards as a tab-separated deck that Anki imports directly
+///
+/// The file uses Anki's header lines (separator, deck, note type), so File > Import
+/// needs no manual column mapping. Citations are appended to each card back.
+#[tauri::command]
+pub async fn export_flashcards(
+    cards: Vec<Flashcard>,
+    path: String,
+    deck_name: String,
+    cloze: Option<bool>,
+) -> Result<usize, AppError> {
+    let cloze = cloze.unwrap_or(false);
+
+    let mut out = String::new();
+    out.push_str("#separator:tab\n#html:true\n");
+    out.push_str(&format!("#deck:{}\n", deck_name.replace('\n', " ")));
+    out.push_str(if cloze { "#notetype:Cloze\n" } else { "#notetype:Basic\n" });
+    out.push_str("#tags column:3\n");
+
+    for card in &cards {
+        let mut back = anki_field(&card.back);
+        if !card.source.is_empty() {
+            back.push_str(&format!("<br><br><small>Source: {}</small>", anki_field(&card.source)));
+        }
+        out.push_str(&format!("{}\t{}\tprivatepdf\n", anki_field(&card.front), back));
+    }
+
+    fs::write(&path, out).map_err(|e| format!("Failed to write deck: {}", e))?;
+    log::info!("Exported {} flashcards to {}", cards.len(), path);
+    Ok(cards.len())
+}
@@ -0,0 +1,314 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tokio::sync::mpsc;
+
+use crate::error::AppError;
+use crate::ingest;
+use crate::library;
+use crate::vectorstore::{now_secs, VectorStore};
+use crate::workspace;
+
+/// Watched folders live in `vectors.db` with the workspaces they feed
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS watched_folders (
+        path TEXT PRIMARY KEY,
+        workspace_id TEXT NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+        created_at INTEGER NOT NULL
+    );
+";
+
+/// Time between size checks of a new file; scanners and copies take a while to finish writing
+const SETTLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Checks in a row the size must stay the same before the file is indexed
+const SETTLE_CHECKS: u32 = 2;
+/// A file still growing after this long is indexed as it is
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedFolder {
+    path: String,
+    workspace_id: String,
+    /// The folder is being watched now; false if it was missing at startup
+    active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DocumentAdded {
+    document_id: String,
+    path: String,
+    name: String,
+    workspace_id: String,
+}
+
+struct Watch {
+    workspace_id: String,
+    /// Watching stops when this is dropped, which also ends the folder's worker
+    _watcher: notify::RecommendedWatcher,
+}
+
+/// Folders being watched, by path
+#[derive(Default)]
+pub struct FolderWatches {
+    watches: Mutex<HashMap<PathBuf, Watch>>,
+}
+
+impl FolderWatches {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Watch>> {
+        self.watches.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_active(&self, folder: &Path) -> bool {
+        self.lock().contains_key(folder)
+    }
+
+    fn stop(&self, folder: &Path) -> bool {
+        self.lock().remove(folder).is_some()
+    }
+
+    /// Stop watching the folders that feed a deleted workspace
+    pub fn stop_workspace(&self, workspace_id: &str) {
+        self.lock().retain(|_, watch| watch.workspace_id != workspace_id);
+    }
+
+    /// Watch `folder` and queue its files that were never indexed
+    fn start(&self, app_handle: &tauri::AppHandle, folder: &Path, workspace_id: &str) -> Result<(), String> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let events = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths.into_iter().filter(|path| is_candidate(path)) {
+                    let _ = events.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Folder watch error: {}", e),
+        })
+        .map_err(|e| format!("Failed to watch {}: {}", folder.display(), e))?;
+        watcher
+            .watch(folder, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", folder.display(), e))?;
+
+        // Files dropped in while the app wasn't running
+        let store = app_handle.state::<VectorStore>();
+        let mut missed = 0;
+        if let Ok(entries) = std::fs::read_dir(folder) {
+            for path in entries.flatten().map(|entry| entry.path()).filter(|path| is_candidate(path)) {
+                if store.source_by_path(&path.display().to_string())?.is_none() {
+                    let _ = sender.send(path);
+                    missed += 1;
+                }
+            }
+        }
+        log::info!("Watching {} for workspace {} ({} files to add)", folder.display(), workspace_id, missed);
+
+        tauri::async_runtime::spawn(worker(app_handle.clone(), workspace_id.to_string(), receiver));
+        self.lock().insert(
+            folder.to_path_buf(),
+            Watch {
+                workspace_id: workspace_id.to_string(),
+                _watcher: watcher,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Create the watched folder table in the vector store database
+pub fn init(store: &VectorStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize watched folders: {}", e))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// A supported document that isn't hidden or an editor's lock file
+fn is_candidate(path: &Path) -> bool {
+    let name = file_name(path);
+    !name.starts_with('.') && !name.starts_with("~$") && path.is_file() && ingest::is_supported(path)
+}
+
+/// Wait for a file to stop growing; false if it went away in the meantime
+async fn settled(path: &Path) -> bool {
+    let started = Instant::now();
+    let mut last_size = None;
+    let mut stable = 0;
+    loop {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        let size = metadata.len();
+        if size > 0 && last_size == Some(size) {
+            stable += 1;
+            if stable >= SETTLE_CHECKS {
+                return true;
+            }
+        } else {
+            stable = 0;
+        }
+        if started.elapsed() > SETTLE_TIMEOUT {
+            log::warn!("{} is still changing, indexing it as it is", path.display());
+            return true;
+        }
+        last_size = Some(size);
+        tokio::time::sleep(SETTLE_INTERVAL).await;
+    }
+}
+
+/// Index a file and add it to the workspace; None if it was already there
+async fn add(app_handle: &tauri::AppHandle, workspace_id: &str, path: &Path) -> Result<Option<DocumentAdded>, String> {
+    let document_id = ingest::index_now(app_handle, path).await?;
+    let store = app_handle.state::<VectorStore>();
+    if workspace::contains(&store, workspace_id, &document_id)? {
+        return Ok(None);
+    }
+
+    let name = file_name(path);
+    let path = path.display().to_string();
+    workspace::add_document(&store, workspace_id, &document_id, &name, Some(&path))?;
+    if let Err(e) = library::record_open(&store, &path, Some(&document_id), None, None) {
+        log::warn!("Failed to add {} to library: {}", path, e);
+    }
+    log::info!("Added {} from a watched folder to workspace {}", name, workspace_id);
+    Ok(Some(DocumentAdded {
+        document_id,
+        path,
+        name,
+        workspace_id: workspace_id.to_string(),
+    }))
+}
+
+/// Index a folder's new files one at a time, in the order they appeared
+///
+/// A file usually raises several events while it's written; the ones after the
+/// first find it already in the workspace and are dropped.
+async fn worker(app_handle: tauri::AppHandle, workspace_id: String, mut receiver: mpsc::UnboundedReceiver<PathBuf>) {
+    while let Some(path) = receiver.recv().await {
+        if !settled(&path).await {
+            continue;
+        }
+        match add(&app_handle, &workspace_id, &path).await {
+            Ok(Some(added)) => {
+                app_handle.emit("document_added", added).ok();
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to add {} from a watched folder: {}", path.display(), e),
+        }
+    }
+}
+
+fn saved_folders(store: &VectorStore) -> Result<Vec<(String, String)>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare("SELECT path, workspace_id FROM watched_folders ORDER BY created_at")
+        .map_err(|e| format!("Failed to read watched folders: {}", e))?;
+    let folders = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to read watched folders: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read watched folders: {}", e))?;
+    Ok(folders)
+}
+
+/// Start watching the folders saved by earlier sessions
+///
+/// A folder that's missing (an unplugged drive, say) stays saved and shows as
+/// inactive until it's watched again.
+pub fn resume(app_handle: &tauri::AppHandle) {
+    let folders = match saved_folders(&app_handle.state::<VectorStore>()) {
+        Ok(folders) => folders,
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    let watches = app_handle.state::<FolderWatches>();
+    for (path, workspace_id) in folders {
+        if let Err(e) = watches.start(app_handle, Path::new(&path), &workspace_id) {
+            log::warn!("{}", e);
+        }
+    }
+}
+
+/// Add new documents that appear in `path` to a workspace automatically
+///
+/// Meant for "drop scans here" folders. Files already in the folder that were
+/// never indexed are added too. Each added document emits `document_added`.
+/// The folder keeps being watched across restarts until `unwatch_folder`.
+#[tauri::command]
+pub async fn watch_folder(
+    path: String,
+    workspace_id: String,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    watches: tauri::State<'_, FolderWatches>,
+) -> Result<WatchedFolder, AppError> {
+    log::info!("Watching {} for workspace {}", path, workspace_id);
+
+    let folder = PathBuf::from(&path);
+    if !folder.is_absolute() || !folder.is_dir() {
+        return Err(AppError::Io(format!("{} is not a folder", path)));
+    }
+    if !workspace::exists(&store, &workspace_id)? {
+        return Err(AppError::Other(format!("Workspace not found: {}", workspace_id)));
+    }
+
+    // Watching again moves the folder to another workspace
+    watches.stop(&folder);
+    watches.start(&app_handle, &folder, &workspace_id)?;
+    store
+        .conn()
+        .execute(
+            "INSERT OR REPLACE INTO watched_folders (path, workspace_id, created_at) VALUES (?1, ?2, ?3)",
+            params![path, workspace_id, now_secs()],
+        )
+        .map_err(|e| format!("Failed to save watched folder: {}", e))?;
+
+    Ok(WatchedFolder {
+        path,
+        workspace_id,
+        active: true,
+    })
+}
+
+/// Stop watching a folder; documents already added stay in the workspace
+#[tauri::command]
+pub async fn unwatch_folder(
+    path: String,
+    store: tauri::State<'_, VectorStore>,
+    watches: tauri::State<'_, FolderWatches>,
+) -> Result<bool, AppError> {
+    log::info!("No longer watching {}", path);
+    let stopped = watches.stop(Path::new(&path));
+    let removed = store
+        .conn()
+        .execute("DELETE FROM watched_folders WHERE path = ?1", params![path])
+        .map_err(|e| format!("Failed to remove watched folder: {}", e))?;
+    Ok(stopped || removed > 0)
+}
+
+#[tauri::command]
+pub async fn list_watched_folders(
+    store: tauri::State<'_, VectorStore>,
+    watches: tauri::State<'_, FolderWatches>,
+) -> Result<Vec<WatchedFolder>, AppError> {
+    Ok(saved_folders(&store)?
+        .into_iter()
+        .map(|(path, workspace_id)| WatchedFolder {
+            active: watches.is_active(Path::new(&path)),
+            path,
+            workspace_id,
+        })
+        .collect())
+}
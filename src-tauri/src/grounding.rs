@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+
+use crate::embedding_model;
+use crate::ollama_chat;
+use crate::scheduler::Lane;
+use crate::settings::AppSettings;
+use crate::vectorstore::{self, SearchHit, VectorStore};
+
+/// Added to the system prompt in strict grounding mode
+pub const GROUNDING_PROMPT: &str = "Strict mode: every statement in your answer must come from the excerpts \
+and cite them. Don't add background knowledge, assumptions or advice of your own, and don't fill gaps by \
+guessing. Before answering, check each sentence against the excerpts and drop any you can't support. If the \
+excerpts don't answer the question, reply only: \"The document doesn't say.\"";
+
+/// Sentences need this many content words to count as a claim; shorter ones are
+/// connective ("Here is what I found.")
+const MIN_CLAIM_WORDS: usize = 4;
+/// Share of a claim's content words that one excerpt must contain
+const MIN_WORD_OVERLAP: f32 = 0.5;
+/// Cosine similarity to an excerpt that supports a claim worded differently
+const MIN_SIMILARITY: f32 = 0.7;
+/// Claims without word overlap that are embedded for a second look, to keep the check quick
+const MAX_EMBEDDED_CLAIMS: usize = 8;
+/// In strict mode, answers with a lower share of supported claims are refused
+pub const MIN_GROUNDING_SCORE: f32 = 0.6;
+
+/// Words too common to show that a claim came from an excerpt
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been", "being", "but",
+    "by", "can", "could", "did", "do", "does", "doesn", "don", "each", "for", "from", "had", "has", "have",
+    "he", "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "may", "more", "most", "must", "no",
+    "not", "of", "on", "or", "other", "our", "she", "should", "so", "some", "such", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "those", "to", "under", "up", "was", "we",
+    "were", "what", "when", "where", "which", "while", "who", "will", "with", "would", "you", "your",
+];
+
+/// How much of an answer the retrieved excerpts back up
+#[derive(Debug)]
+pub struct Grounding {
+    /// Share of the answer's claims found in the excerpts, 0 to 1
+    pub score: f32,
+    /// Claims no excerpt supports
+    pub unsupported: Vec<String>,
+}
+
+/// Lowercased content words, with a plural "s" dropped so "invoices" matches "invoice"
+fn content_words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .map(|w| match w.strip_suffix('s') {
+            Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+            _ => w,
+        })
+        .collect()
+}
+
+/// Remove citation markers like [2] or [1, 3] so they don't count as words
+fn strip_citations(sentence: &str) -> String {
+    let mut text = String::with_capacity(sentence.len());
+    let mut rest = sentence;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find(']') else {
+            break;
+        };
+        let inner = &rest[open + 1..open + close];
+        text.push_str(&rest[..open]);
+        if !inner.chars().all(|c| c.is_ascii_digit() || c == ',' || c == ' ' || c == '-') {
+            text.push_str(&rest[open..=open + close]);
+        }
+        rest = &rest[open + close + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Whether the text ends in a dotted abbreviation like "e.g." or "i.e."
+fn ends_with_abbreviation(text: &str) -> bool {
+    let word = text.rsplit(char::is_whitespace).next().unwrap_or("");
+    let letters: Vec<&str> = word.trim_start_matches('(').split('.').collect();
+    // "e.g." splits into ["e", "g", ""]
+    match letters.split_last() {
+        Some((&"", initials)) => initials.len() >= 2 && initials.iter().all(|l| l.chars().count() == 1),
+        _ => false,
+    }
+}
+
+/// Sentences of the answer that state something, without citations or list markers
+fn claims(answer: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    for line in answer.lines() {
+        let mut current = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            current.push(c);
+            // "3.5" and "e.g." mid-sentence aren't sentence ends; a period before a space is
+            if matches!(c, '.' | '!' | '?')
+                && !matches!(chars.peek(), Some(next) if !next.is_whitespace())
+                && !(c == '.' && ends_with_abbreviation(&current))
+            {
+                sentences.push(std::mem::take(&mut current));
+            }
+        }
+        sentences.push(current);
+    }
+
+    sentences
+        .into_iter()
+        .map(|s| {
+            let s = strip_citations(&s);
+            s.trim()
+                .trim_start_matches(|c: char| matches!(c, '-' | '*' | '•' | '#') || c.is_whitespace())
+                .trim()
+                .to_string()
+        })
+        .filter(|s| content_words(s).len() >= MIN_CLAIM_WORDS)
+        .collect()
+}
+
+/// Highest share of the claim's words found in any one excerpt
+fn word_overlap(claim: &HashSet<String>, excerpts: &[HashSet<String>]) -> f32 {
+    if claim.is_empty() {
+        return 0.0;
+    }
+    excerpts
+        .iter()
+        .map(|excerpt| claim.intersection(excerpt).count() as f32 / claim.len() as f32)
+        .fold(0.0, f32::max)
+}
+
+/// Check the answer's claims against the excerpts it was given
+///
+/// A claim is supported when one excerpt contains most of its words. Claims
+/// that fail that are embedded and compared with the excerpts' stored vectors,
+/// which catches paraphrases. An answer with no claims, like the refusal the
+/// strict prompt asks for, scores 1.
+pub async fn check(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    store: &VectorStore,
+    document_id: &str,
+    answer: &str,
+    hits: &[SearchHit],
+) -> Grounding {
+    let claims = claims(answer);
+    if claims.is_empty() {
+        return Grounding {
+            score: 1.0,
+            unsupported: Vec::new(),
+        };
+    }
+
+    let excerpts: Vec<HashSet<String>> = hits.iter().map(|hit| content_words(&hit.text)).collect();
+    let mut unsupported: Vec<String> = claims
+        .iter()
+        .filter(|claim| word_overlap(&content_words(claim), &excerpts) < MIN_WORD_OVERLAP)
+        .cloned()
+        .collect();
+
+    if !unsupported.is_empty() {
+        let chunk_ids: Vec<String> = hits.iter().map(|hit| hit.chunk_id.clone()).collect();
+        let vectors = store.vectors(document_id, &chunk_ids).unwrap_or_else(|e| {
+            log::warn!("Grounding check can't compare embeddings: {}", e);
+            Vec::new()
+        });
+        if !vectors.is_empty() {
+            let model = embedding_model::from_settings(settings);
+            let mut paraphrased = HashSet::new();
+            for (i, claim) in unsupported.iter().enumerate().take(MAX_EMBEDDED_CLAIMS) {
+                let embedding = match ollama_chat::embed_in(Lane::Interactive, model.clone(), claim.clone(), app_handle).await {
+                    Ok(embedding) => embedding,
+                    Err(e) => {
+                        log::warn!("Failed to embed claim for grounding check: {}", e);
+                        break;
+                    }
+                };
+                let claim_vector: Vec<f32> = embedding.iter().map(|&v| v as f32).collect();
+                let claim_norm = vectorstore::norm(&claim_vector);
+                let best = vectors
+                    .iter()
+                    .map(|v| vectorstore::cosine(&claim_vector, claim_norm, v))
+                    .fold(0.0, f32::max);
+                if best >= MIN_SIMILARITY {
+                    paraphrased.insert(i);
+                }
+            }
+            unsupported = unsupported
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !paraphrased.contains(i))
+                .map(|(_, claim)| claim)
+                .collect();
+        }
+    }
+
+    let score = (claims.len() - unsupported.len()) as f32 / claims.len() as f32;
+    log::info!(
+        "Grounding: {}/{} claims supported (score {:.2})",
+        claims.len() - unsupported.len(),
+        claims.len(),
+        score
+    );
+    Grounding { score, unsupported }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> HashSet<String> {
+        content_words(text)
+    }
+
+    #[test]
+    fn content_words_drop_stop_words_and_plurals() {
+        let found = words("The invoices were sent to the Customers in March.");
+        let expected: HashSet<String> = ["invoice", "sent", "customer", "march"].iter().map(|w| w.to_string()).collect();
+        assert_eq!(found, expected);
+        // "ss" endings and short words keep their "s"
+        assert!(words("business gas").contains("business"));
+        assert!(words("business gas").contains("gas"));
+    }
+
+    #[test]
+    fn citation_markers_are_removed() {
+        assert_eq!(strip_citations("Revenue grew 12% [1, 3]."), "Revenue grew 12% .");
+        assert_eq!(strip_citations("See [2-4] and [5]"), "See  and ");
+        assert_eq!(strip_citations("An [optional] field"), "An [optional] field");
+        assert_eq!(strip_citations("Unclosed [3"), "Unclosed [3");
+    }
+
+    #[test]
+    fn answers_split_into_claims() {
+        let answer = "Here is what I found.\n\
+            - Revenue grew 3.5 percent in the third quarter [1].\n\
+            - Operating costs fell sharply after the restructuring, e.g. rent! Staff numbers stayed flat overall.";
+        assert_eq!(
+            claims(answer),
+            [
+                "Revenue grew 3.5 percent in the third quarter .",
+                "Operating costs fell sharply after the restructuring, e.g. rent!",
+                "Staff numbers stayed flat overall.",
+            ]
+        );
+        assert!(claims("The document doesn't say.").is_empty());
+    }
+
+    #[test]
+    fn overlap_is_the_best_single_excerpt() {
+        let claim = words("Revenue grew strongly in Europe");
+        let excerpts = [words("Revenue fell in Asia"), words("Strong growth: revenue grew in Europe")];
+        assert_eq!(word_overlap(&claim, &excerpts), 0.75);
+        assert_eq!(word_overlap(&claim, &[]), 0.0);
+        assert_eq!(word_overlap(&HashSet::new(), &excerpts), 0.0);
+    }
+}
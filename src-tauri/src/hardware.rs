@@ -0,0 +1,159 @@
+use serde::Serialize;
+use std::process::Command;
+use sysinfo::System;
+
+use crate::error::AppError;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+const BYTES_PER_MIB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    name: String,
+    /// "nvidia", "amd", "apple" or "intel"
+    vendor: String,
+    /// Dedicated memory; for Apple Silicon, the share of unified memory Metal can use
+    vram_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HardwareInfo {
+    os: String,
+    arch: String,
+    cpu_threads: usize,
+    total_memory_bytes: u64,
+    available_memory_bytes: u64,
+    gpus: Vec<GpuInfo>,
+}
+
+impl HardwareInfo {
+    pub fn total_memory_bytes(&self) -> u64 {
+        self.total_memory_bytes
+    }
+
+    /// Largest VRAM among detected GPUs
+    pub fn max_vram_bytes(&self) -> Option<u64> {
+        self.gpus.iter().filter_map(|g| g.vram_bytes).max()
+    }
+}
+
+fn quiet_command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// NVIDIA GPUs via nvidia-smi, which ships with the driver on every platform
+fn nvidia_gpus() -> Vec<GpuInfo> {
+    let output = match quiet_command("nvidia-smi")
+        .args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, mib) = line.rsplit_once(',')?;
+            Some(GpuInfo {
+                name: name.trim().to_string(),
+                vendor: "nvidia".to_string(),
+                vram_bytes: mib.trim().parse::<u64>().ok().map(|m| m * BYTES_PER_MIB),
+            })
+        })
+        .collect()
+}
+
+/// AMD and Intel GPUs from the DRM sysfs entries
+#[cfg(target_os = "linux")]
+fn drm_gpus() -> Vec<GpuInfo> {
+    let entries = match std::fs::read_dir("/sys/class/drm") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut gpus = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // card0, card1, ... but not connectors like card0-HDMI-A-1
+        if !name.starts_with("card") || name.contains('-') {
+            continue;
+        }
+        let device = entry.path().join("device");
+        let vendor = match std::fs::read_to_string(device.join("vendor")).unwrap_or_default().trim() {
+            "0x1002" => "amd",
+            "0x8086" => "intel",
+            _ => continue,
+        };
+        let vram_bytes = std::fs::read_to_string(device.join("mem_info_vram_total"))
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        gpus.push(GpuInfo {
+            name: format!("{} GPU ({})", vendor.to_uppercase(), name),
+            vendor: vendor.to_string(),
+            vram_bytes,
+        });
+    }
+    gpus
+}
+
+#[cfg_attr(not(all(target_os = "macos", target_arch = "aarch64")), allow(unused_variables))]
+fn detect_gpus(total_memory_bytes: u64) -> Vec<GpuInfo> {
+    #[allow(unused_mut)]
+    let mut gpus = nvidia_gpus();
+
+    #[cfg(target_os = "linux")]
+    gpus.extend(drm_gpus());
+
+    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    gpus.push(GpuInfo {
+        name: "Apple Silicon".to_string(),
+        vendor: "apple".to_string(),
+        // Metal's default working set limit is about three quarters of unified memory
+        vram_bytes: Some(total_memory_bytes / 4 * 3),
+    });
+
+    gpus
+}
+
+/// Collect RAM, CPU and GPU details (blocking; GPU probing runs external tools)
+pub fn probe() -> HardwareInfo {
+    let mut system = System::new();
+    system.refresh_memory();
+    let total_memory_bytes = system.total_memory();
+
+    HardwareInfo {
+        os: System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()),
+        arch: std::env::consts::ARCH.to_string(),
+        cpu_threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        total_memory_bytes,
+        available_memory_bytes: system.available_memory(),
+        gpus: detect_gpus(total_memory_bytes),
+    }
+}
+
+/// Detect RAM, CPU threads and GPUs (with VRAM where the driver reports it)
+#[tauri::command]
+pub async fn detect_hardware() -> Result<HardwareInfo, AppError> {
+    log::info!("Detecting hardware");
+
+    let info = tauri::async_runtime::spawn_blocking(probe)
+        .await
+        .map_err(|e| format!("Hardware detection task failed: {}", e))?;
+    log::info!(
+        "Hardware: {} MiB RAM, {} threads, {} GPU(s)",
+        info.total_memory_bytes / BYTES_PER_MIB,
+        info.cpu_threads,
+        info.gpus.len()
+    );
+    Ok(info)
+}
@@ -0,0 +1,313 @@
+use base64::Engine;
+
+/// Elements whose content is never visible text
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "head", "svg"];
+/// Elements that start a new line of text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "section", "article",
+    "table", "ul", "ol", "hr", "dt", "dd", "figcaption",
+];
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "mdash" => Some('—'),
+        "ndash" => Some('–'),
+        "hellip" => Some('…'),
+        "lsquo" => Some('‘'),
+        "rsquo" => Some('’'),
+        "ldquo" => Some('“'),
+        "rdquo" => Some('”'),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let value = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(value)
+        }
+    }
+}
+
+/// Plain text of an (X)HTML document, one line per block element
+///
+/// A small tag stripper rather than a DOM parser: e-book chapters are simple,
+/// well-formed XHTML, and only the text matters for chunking.
+pub fn html_to_text(html: &str) -> String {
+    strip_tags(html, false, |name, _| SKIPPED_ELEMENTS.contains(&name))
+}
+
+/// Tag stripper behind `html_to_text` and `readable_text`
+///
+/// Elements for which `skip(name, tag)` is true are dropped with everything
+/// inside them. With `headings`, `<h1>`-`<h3>` become Markdown headings so the
+/// text can be split into sections.
+fn strip_tags(html: &str, headings: bool, skip: impl Fn(&str, &str) -> bool) -> String {
+    let mut out = String::new();
+    // The skipped element and how many of the same name are open inside it
+    let mut skipping: Option<(String, usize)> = None;
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let Some(end) = rest.find('>') else { break };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            let closing = tag.starts_with('/');
+            let self_closing = tag.ends_with('/');
+            let name: String = tag
+                .trim_start_matches('/')
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_ascii_lowercase();
+
+            if let Some((skipped, depth)) = &mut skipping {
+                if name == *skipped {
+                    if closing && *depth == 0 {
+                        skipping = None;
+                    } else if closing {
+                        *depth -= 1;
+                    } else if !self_closing {
+                        *depth += 1;
+                    }
+                }
+                continue;
+            }
+            if !closing && !self_closing && skip(&name, tag) {
+                skipping = Some((name, 0));
+                continue;
+            }
+            if BLOCK_ELEMENTS.contains(&name.as_str()) && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            if headings && !closing {
+                if let Some(level) = ["h1", "h2", "h3"].iter().position(|h| *h == name) {
+                    out.push_str(&format!("{} ", "#".repeat(level + 1)));
+                }
+            }
+            continue;
+        }
+
+        if skipping.is_some() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        if c == '&' {
+            let end = rest.char_indices().take(12).find(|&(_, c)| c == ';').map(|(i, _)| i);
+            if let Some(end) = end {
+                if let Some(decoded) = decode_entity(&rest[1..end]) {
+                    out.push(decoded);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        out.push(if c.is_whitespace() && c != '\n' { ' ' } else { c });
+        rest = &rest[c.len_utf8()..];
+    }
+
+    // Collapse the whitespace left behind by markup indentation
+    out.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        // A heading marker whose heading had no text
+        .filter(|line| !(line.is_empty() || headings && line.chars().all(|c| c == '#')))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of the first `<title>`, `<h1>` or `<h2>` in a chapter
+pub(crate) fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    ["title", "h1", "h2"].iter().find_map(|tag| {
+        let open = lower.find(&format!("<{}", tag))?;
+        let start = open + lower[open..].find('>')? + 1;
+        let end = start + lower[start..].find(&format!("</{}", tag))?;
+        let title = html_to_text(&html[start..end]);
+        (!title.is_empty()).then_some(title)
+    })
+}
+
+/// Page furniture dropped from saved web pages, on top of `SKIPPED_ELEMENTS`
+const BOILERPLATE_ELEMENTS: &[&str] = &[
+    "nav", "footer", "aside", "form", "noscript", "iframe", "button", "select", "template", "dialog",
+];
+/// `class`/`id` words marking menus, banners and other non-content blocks
+const BOILERPLATE_HINTS: &[&str] = &[
+    "sidebar", "menu", "navbar", "breadcrumb", "cookie", "consent", "banner", "advert", "sponsor", "share",
+    "social", "related", "recommend", "newsletter", "subscribe", "comment", "popup", "modal", "footer",
+];
+
+/// Whether a tag's class or id names it as boilerplate ("site-menu", "cookie_banner", ...)
+fn is_boilerplate(tag: &str) -> bool {
+    let lower = tag.to_ascii_lowercase();
+    ["class=", "id="].iter().any(|attr| {
+        let Some(start) = lower.find(attr).map(|i| i + attr.len()) else {
+            return false;
+        };
+        let value = lower[start..].trim_start_matches(['"', '\'']);
+        let value = &value[..value.find(['"', '\'', '>']).unwrap_or(value.len())];
+        value
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| BOILERPLATE_HINTS.contains(&word))
+    })
+}
+
+/// Inner HTML of each `<name>` element, outermost only
+///
+/// `lower` is `html` lowercased, so tag names match in any case.
+fn elements<'a>(html: &'a str, lower: &str, name: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}", name), format!("</{}", name));
+    // `<main` must not match `<mainframe`
+    let is_open = |at: usize| lower[at + open.len()..].starts_with(|c: char| c == '>' || c.is_whitespace());
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = lower[from..].find(&open).map(|i| from + i) {
+        from = start + open.len();
+        if !is_open(start) {
+            continue;
+        }
+        let Some(body) = lower[start..].find('>').map(|i| start + i + 1) else { break };
+
+        // Walk nested elements of the same name to the matching close
+        let mut depth = 0;
+        let mut at = body;
+        let end = loop {
+            let Some(next_close) = lower[at..].find(&close).map(|i| at + i) else {
+                break html.len();
+            };
+            match lower[at..].find(&open).map(|i| at + i) {
+                Some(next_open) if next_open < next_close => {
+                    if is_open(next_open) {
+                        depth += 1;
+                    }
+                    at = next_open + open.len();
+                }
+                _ if depth == 0 => break next_close,
+                _ => {
+                    depth -= 1;
+                    at = next_close + close.len();
+                }
+            }
+        };
+        found.push(&html[body..end]);
+        from = from.max(end);
+    }
+    found
+}
+
+/// The part of a web page holding the article
+///
+/// `<main>` when there is one, else the `<article>` with the most text, else
+/// `<body>`; boilerplate inside it is removed by `readable_text`.
+fn main_region(html: &str) -> &str {
+    let lower = html.to_ascii_lowercase();
+    if let Some(main) = elements(html, &lower, "main").into_iter().next() {
+        return main;
+    }
+    let articles = elements(html, &lower, "article");
+    if let Some(article) = articles.into_iter().max_by_key(|a| html_to_text(a).len()) {
+        return article;
+    }
+    elements(html, &lower, "body").into_iter().next().unwrap_or(html)
+}
+
+/// Article text of a saved web page as Markdown, without menus, banners and footers
+///
+/// A readability-style pass over the tag stripper: the main region is picked
+/// first, then navigation, forms and blocks whose class or id marks them as page
+/// furniture are dropped. Lines repeated across the page (share buttons, "Read
+/// more") are kept only once.
+pub fn readable_text(html: &str) -> String {
+    let text = strip_tags(main_region(html), true, |name, tag| {
+        SKIPPED_ELEMENTS.contains(&name) || BOILERPLATE_ELEMENTS.contains(&name) || is_boilerplate(tag)
+    });
+    let mut seen = std::collections::HashSet::new();
+    text.lines()
+        .filter(|line| line.starts_with('#') || line.len() > 80 || seen.insert(*line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decode quoted-printable text, as MHTML parts are usually encoded
+fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // Soft line break
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(b'=');
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Headers and body of a MIME part
+fn mime_part(part: &str) -> (String, &str) {
+    let split = ["\r\n\r\n", "\n\n"]
+        .iter()
+        .filter_map(|sep| part.find(sep).map(|i| (i, sep.len())))
+        .min();
+    match split {
+        Some((i, len)) => (part[..i].to_ascii_lowercase(), &part[i + len..]),
+        None => (String::new(), part),
+    }
+}
+
+/// The HTML of an MHTML web archive (Chrome's "Save as single file")
+///
+/// Only the first `text/html` part is read; images and stylesheets in the other
+/// parts don't carry text.
+pub(crate) fn mhtml_html(archive: &str) -> Result<String, String> {
+    let (headers, _) = mime_part(archive);
+    let boundary = headers.find("boundary=").map(|i| {
+        let start = i + "boundary=".len();
+        // The headers were lowercased; take the boundary from the original text
+        let value = archive[start..].trim_start_matches('"');
+        value[..value.find(['"', ';', '\r', '\n']).unwrap_or(value.len())].to_string()
+    });
+
+    let parts: Vec<&str> = match &boundary {
+        Some(boundary) => archive.split(&format!("--{}", boundary)).skip(1).collect(),
+        None => vec![archive],
+    };
+    for part in parts {
+        let (headers, body) = mime_part(part.trim_start_matches(['\r', '\n']));
+        if !headers.contains("content-type: text/html") {
+            continue;
+        }
+        let bytes = if headers.contains("content-transfer-encoding: quoted-printable") {
+            decode_quoted_printable(body)
+        } else if headers.contains("content-transfer-encoding: base64") {
+            let cleaned: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(cleaned)
+                .map_err(|e| format!("Failed to decode web archive: {}", e))?
+        } else {
+            body.as_bytes().to_vec()
+        };
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Err("Web archive has no HTML part".to_string())
+}
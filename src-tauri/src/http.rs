@@ -0,0 +1,392 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::proxy::{self, ProxySettings};
+
+/// How long an idle keep-alive connection stays in the pool
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Idle connections kept per host (embedding batches fire many small requests at localhost)
+const POOL_MAX_IDLE_PER_HOST: usize = 16;
+/// TCP keep-alive probe interval for long-lived streaming connections
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+const MAX_REDIRECTS: usize = 10;
+/// How many blocked requests are kept for the isolation report
+const MAX_BLOCKED_LOG: usize = 100;
+
+/// Hosts used for inference (the local Ollama API)
+pub const LOCAL_HOSTS: &[&str] = &["127.0.0.1", "localhost", "::1", "[::1]"];
+/// Hosts used only for explicit, user-initiated downloads (Ollama installer ZIP and its redirects)
+/// and model update checks (manifests on the Ollama registry)
+pub const DOWNLOAD_HOSTS: &[&str] = &[
+    "github.com",
+    "objects.githubusercontent.com",
+    "release-assets.githubusercontent.com",
+    "registry.ollama.ai",
+];
+
+/// Shared client, rebuilt when the network settings change
+static CLIENT: Mutex<Option<reqwest::Client>> = Mutex::new(None);
+static NETWORK: Mutex<Option<NetworkSettings>> = Mutex::new(None);
+static BLOCKED: Mutex<Vec<BlockedRequest>> = Mutex::new(Vec::new());
+/// Ollama host from settings, allowed in addition to localhost
+static INFERENCE_HOST: Mutex<Option<String>> = Mutex::new(None);
+/// Host of the OpenAI-compatible server from settings, when that backend is selected
+static BACKEND_HOST: Mutex<Option<String>> = Mutex::new(None);
+/// Hosts of the WebDAV remote sources the user registered
+static REMOTE_HOSTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// `offline_mode` setting: only the Ollama server may be reached
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Timeouts and retry policy for requests to Ollama and other servers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub connect_timeout_secs: u64,
+    /// Longest a chat or embedding request may take; slow CPUs writing long answers need minutes
+    pub read_timeout_secs: u64,
+    /// Limit for quick requests: status checks, model lists, model info
+    pub status_timeout_secs: u64,
+    /// Extra attempts after a refused connection or a 502/503/504
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub backoff_ms: u64,
+    /// Proxy for downloads; local and LAN servers are always reached directly
+    pub proxy: ProxySettings,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 300,
+            status_timeout_secs: 15,
+            retries: 2,
+            backoff_ms: 500,
+            proxy: ProxySettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockedRequest {
+    url: String,
+    reason: String,
+    /// Seconds since the epoch
+    at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkIsolationReport {
+    /// Hosts contacted for inference
+    inference_hosts: Vec<String>,
+    /// Hosts contacted only when the user installs Ollama from the app
+    download_hosts: Vec<String>,
+    /// True when every inference host is a loopback address
+    inference_local_only: bool,
+    /// WebDAV servers documents are imported from
+    remote_source_hosts: Vec<String>,
+    /// Only the Ollama server may be reached; downloads and update checks are refused
+    offline_mode: bool,
+    /// Requests the runtime guard refused since launch
+    blocked_requests: Vec<BlockedRequest>,
+}
+
+fn network() -> NetworkSettings {
+    NETWORK.lock().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// Apply network settings; the shared client is rebuilt on next use if they changed
+pub fn configure(settings: &NetworkSettings) {
+    let mut current = NETWORK.lock().unwrap_or_else(|e| e.into_inner());
+    if current.as_ref() != Some(settings) {
+        log::info!("Applying network settings: {:?}", settings);
+        *current = Some(settings.clone());
+        *CLIENT.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Apply the `offline_mode` setting to the guard
+pub fn set_offline(offline: bool) {
+    if OFFLINE.swap(offline, Ordering::Relaxed) != offline {
+        log::info!("Offline mode {}", if offline { "on" } else { "off" });
+    }
+}
+
+fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Timeout for chat and embedding requests
+pub fn read_timeout() -> Duration {
+    Duration::from_secs(network().read_timeout_secs)
+}
+
+/// Proxy URL for `host` when the request doesn't go through the shared client
+pub fn proxy_for(host: &str) -> Option<reqwest::Url> {
+    proxy::url_for(&network().proxy, host)
+}
+
+/// Timeout for status checks and other quick requests
+pub fn status_timeout() -> Duration {
+    Duration::from_secs(network().status_timeout_secs)
+}
+
+/// Shared HTTP client used for every request to Ollama and for downloads
+///
+/// Reusing one client keeps connections to 127.0.0.1:11434 alive between calls,
+/// so embedding batches don't pay a TCP handshake per chunk. TCP_NODELAY is set
+/// so small streamed chat tokens aren't held back by Nagle's algorithm.
+/// Per-request timeouts are still set by the callers.
+pub fn client() -> reqwest::Client {
+    CLIENT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(|| build_client(&network()))
+        .clone()
+}
+
+/// Build a client from network settings; the one factory every request goes through
+///
+/// reqwest's own proxy detection is turned off in favour of `proxy::build`, which
+/// also reads the OS settings and never proxies localhost.
+fn build_client(settings: &NetworkSettings) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder().no_proxy();
+    if let Some(proxy) = proxy::build(&settings.proxy) {
+        builder = builder.proxy(proxy);
+    }
+    builder
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .tcp_keepalive(TCP_KEEPALIVE)
+        .tcp_nodelay(true)
+        .connect_timeout(Duration::from_secs(settings.connect_timeout_secs))
+        // Redirects are checked against the allowlist too, not just the first URL
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_url(attempt.url().as_str()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        }))
+        .build()
+        .unwrap_or_else(|e| {
+            log::error!("Failed to build tuned HTTP client, using defaults: {}", e);
+            reqwest::Client::new()
+        })
+}
+
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 502..=504)
+}
+
+/// Send a request, retrying refused connections and 502/503/504 with exponential backoff
+///
+/// Timeouts aren't retried, so a slow generation isn't started over. Requests
+/// whose body can't be cloned are sent once.
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
+    let settings = network();
+    let mut attempt = 0;
+    loop {
+        let Some(retry) = request.try_clone().filter(|_| attempt < settings.retries) else {
+            return request.send().await;
+        };
+        match retry.send().await {
+            Ok(response) if !is_retryable_status(response.status()) => return Ok(response),
+            Ok(response) => log::warn!("Request returned {}, retrying", response.status()),
+            Err(e) if e.is_connect() => log::warn!("Connection failed, retrying: {}", e),
+            Err(e) => return Err(e),
+        }
+        tokio::time::sleep(Duration::from_millis(settings.backoff_ms << attempt.min(10))).await;
+        attempt += 1;
+    }
+}
+
+/// `.send_with_retry()` for request builders, see `send`
+pub trait RetryExt {
+    fn send_with_retry(self) -> impl Future<Output = Result<reqwest::Response, reqwest::Error>> + Send;
+}
+
+impl RetryExt for reqwest::RequestBuilder {
+    fn send_with_retry(self) -> impl Future<Output = Result<reqwest::Response, reqwest::Error>> + Send {
+        send(self)
+    }
+}
+
+fn normalize_host(host: &str) -> &str {
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn set_host(slot: &Mutex<Option<String>>, host: &str) {
+    let mut current = slot.lock().unwrap_or_else(|e| e.into_inner());
+    if current.as_deref() != Some(host) {
+        *current = Some(host.to_string());
+    }
+}
+
+fn matches_host(slot: &Mutex<Option<String>>, host: &str) -> bool {
+    slot.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_deref()
+        .map(|configured| normalize_host(configured).eq_ignore_ascii_case(normalize_host(host)))
+        .unwrap_or(false)
+}
+
+/// Allow the Ollama host configured in settings through the guard
+pub fn set_inference_host(host: &str) {
+    set_host(&INFERENCE_HOST, host);
+}
+
+/// Allow the OpenAI-compatible server configured in settings through the guard
+pub fn set_backend_host(host: &str) {
+    set_host(&BACKEND_HOST, host);
+}
+
+fn is_inference_host(host: &str) -> bool {
+    matches_host(&INFERENCE_HOST, host) || matches_host(&BACKEND_HOST, host)
+}
+
+/// Allow the hosts of the registered WebDAV sources through the guard
+pub fn set_remote_hosts(hosts: Vec<String>) {
+    *REMOTE_HOSTS.lock().unwrap_or_else(|e| e.into_inner()) = hosts;
+}
+
+fn is_remote_host(host: &str) -> bool {
+    REMOTE_HOSTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|remote| normalize_host(remote).eq_ignore_ascii_case(normalize_host(host)))
+}
+
+/// Runtime guard: reject any URL whose host isn't localhost, the configured
+/// Ollama or OpenAI-compatible host, a registered WebDAV source or a known
+/// download host
+///
+/// In offline mode only localhost and the configured Ollama host pass, which
+/// also refuses downloads, model update checks, WebDAV sources and a non-local
+/// OpenAI-compatible server. Every outgoing request goes through `get`/`post`, which call this
+/// first. Refused URLs are logged and kept for `verify_network_isolation`.
+pub fn check_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    let host = parsed.host_str().unwrap_or("");
+
+    let reason = if is_offline() {
+        if LOCAL_HOSTS.contains(&host) || matches_host(&INFERENCE_HOST, host) {
+            return Ok(());
+        }
+        format!("offline mode only allows the Ollama server, not '{}'", host)
+    } else {
+        if LOCAL_HOSTS.contains(&host) || DOWNLOAD_HOSTS.contains(&host) || is_inference_host(host) || is_remote_host(host) {
+            return Ok(());
+        }
+        format!("host '{}' is not in the network allowlist", host)
+    };
+    log::warn!("Blocked outgoing request to {}: {}", url, reason);
+
+    let mut blocked = BLOCKED.lock().unwrap_or_else(|e| e.into_inner());
+    if blocked.len() >= MAX_BLOCKED_LOG {
+        blocked.remove(0);
+    }
+    blocked.push(BlockedRequest {
+        url: url.to_string(),
+        reason: reason.clone(),
+        at: crate::vectorstore::now_secs(),
+    });
+
+    Err(format!("Blocked request to {}: {}", url, reason))
+}
+
+/// Start a guarded GET request with the shared client
+pub fn get(url: &str) -> Result<reqwest::RequestBuilder, String> {
+    check_url(url)?;
+    Ok(client().get(url))
+}
+
+/// Start a guarded POST request with the shared client
+pub fn post(url: &str) -> Result<reqwest::RequestBuilder, String> {
+    check_url(url)?;
+    Ok(client().post(url))
+}
+
+/// Start a guarded DELETE request with the shared client
+pub fn delete(url: &str) -> Result<reqwest::RequestBuilder, String> {
+    check_url(url)?;
+    Ok(client().delete(url))
+}
+
+/// Start a guarded request with any method, e.g. WebDAV's PROPFIND
+pub fn request(method: reqwest::Method, url: &str) -> Result<reqwest::RequestBuilder, String> {
+    check_url(url)?;
+    Ok(client().request(method, url))
+}
+
+/// Report which hosts the app is configured to reach and confirm inference stays on localhost
+#[tauri::command]
+pub fn verify_network_isolation(app_handle: tauri::AppHandle) -> NetworkIsolationReport {
+    let mut inference_hosts = vec![crate::ollama::ollama_url(&app_handle)];
+    let settings = crate::settings::read_settings(&app_handle);
+    if settings.llm_backend == crate::backend::BackendKind::OpenaiCompatible {
+        inference_hosts.push(settings.openai_base_url);
+    }
+    let inference_local_only = inference_hosts.iter().all(|url| {
+        reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| LOCAL_HOSTS.contains(&h)))
+            .unwrap_or(false)
+    });
+
+    NetworkIsolationReport {
+        inference_hosts,
+        download_hosts: DOWNLOAD_HOSTS.iter().map(|h| h.to_string()).collect(),
+        inference_local_only,
+        remote_source_hosts: REMOTE_HOSTS.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        offline_mode: is_offline(),
+        blocked_requests: BLOCKED.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked(url: &str) -> bool {
+        check_url(url).is_err()
+    }
+
+    // One test, because the guard's settings are process-wide
+    #[test]
+    fn only_allowlisted_hosts_pass_the_guard() {
+        set_offline(false);
+        set_inference_host("gpu-box.lan");
+        set_remote_hosts(vec!["nas.lan".to_string()]);
+
+        assert!(!blocked("http://127.0.0.1:11434/api/tags"));
+        assert!(!blocked("http://[::1]:11434/api/tags"));
+        assert!(!blocked("http://GPU-box.lan:11434/api/chat"));
+        assert!(!blocked("https://github.com/ollama/ollama/releases"));
+        assert!(!blocked("https://nas.lan/dav/papers/"));
+        assert!(blocked("https://evil.example/exfiltrate"));
+        assert!(blocked("https://github.com.evil.example/"));
+        assert!(blocked("not a url"));
+
+        set_offline(true);
+        assert!(!blocked("http://localhost:11434/api/tags"));
+        assert!(!blocked("http://gpu-box.lan:11434/api/chat"));
+        assert!(blocked("https://github.com/ollama/ollama/releases"));
+        assert!(blocked("https://registry.ollama.ai/v2/library/llama3/manifests/latest"));
+        assert!(blocked("https://nas.lan/dav/papers/"));
+
+        let log = BLOCKED.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let last = log.last().unwrap();
+        assert_eq!(last.url, "https://nas.lan/dav/papers/");
+        assert!(last.reason.starts_with("offline mode"));
+
+        set_offline(false);
+        set_remote_hosts(Vec::new());
+    }
+}
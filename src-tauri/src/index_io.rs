@@ -0,0 +1,426 @@
+use parquet::data_type::{ByteArray, ByteArrayType, FloatType, Int32Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::embedding_cache;
+use crate::embedding_model;
+use crate::error::AppError;
+use crate::ollama_chat;
+use crate::scheduler::Lane;
+use crate::settings;
+use crate::vectorstore::{page_from_metadata, EmbeddingItem, IndexInfo, VectorStore};
+
+/// Parquet layout of an exported index; `embedding` is a standard LIST column,
+/// so pandas, Polars, DuckDB and Arrow read it as an array of floats
+const PARQUET_SCHEMA: &str = "
+    message embeddings {
+        required binary chunk_id (STRING);
+        required binary text (STRING);
+        optional int32 page;
+        optional binary metadata (JSON);
+        required group embedding (LIST) {
+            repeated group list {
+                required float element;
+            }
+        }
+    }
+";
+/// Rows per Parquet row group, so readers can stream large indexes
+const PARQUET_ROW_GROUP: usize = 10_000;
+
+/// Field names other tools use for the parts of a record, checked in order
+const ID_FIELDS: &[&str] = &["chunk_id", "id", "_id", "uuid"];
+const TEXT_FIELDS: &[&str] = &["text", "page_content", "document", "content", "chunk"];
+const VECTOR_FIELDS: &[&str] = &["vector", "embedding", "embeddings", "values"];
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexFormat {
+    /// One chunk per line: `chunk_id`, `text`, `metadata`, `vector`, `page`, `start`, `end`
+    Jsonl,
+    Parquet,
+    /// One JSON file laid out as Chroma's `collection.add()` arguments, which
+    /// LangChain's Chroma store loads as documents with metadata
+    Chroma,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexImport {
+    /// Id of the new index, to query it like any indexed document
+    document_id: String,
+    name: String,
+    chunks: usize,
+    dimension: usize,
+    model: String,
+    /// Records without text or a vector
+    skipped: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexExport {
+    path: String,
+    chunks: usize,
+    dimension: usize,
+    model: Option<String>,
+    bytes: u64,
+}
+
+fn write_jsonl(items: &[EmbeddingItem], path: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut out = BufWriter::new(file);
+    for item in items {
+        serde_json::to_writer(&mut out, item).map_err(|e| format!("Failed to write export: {}", e))?;
+        out.write_all(b"\n").map_err(|e| format!("Failed to write export: {}", e))?;
+    }
+    out.flush().map_err(|e| format!("Failed to write export: {}", e))
+}
+
+fn write_parquet(items: &[EmbeddingItem], path: &str) -> Result<(), String> {
+    let err = |e: parquet::errors::ParquetError| format!("Failed to write Parquet: {}", e);
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(err)?);
+    let props = Arc::new(WriterProperties::builder().build());
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut writer = SerializedFileWriter::new(file, schema, props).map_err(err)?;
+
+    for rows in items.chunks(PARQUET_ROW_GROUP) {
+        let mut group = writer.next_row_group().map_err(err)?;
+        let mut column = 0;
+        while let Some(mut column_writer) = group.next_column().map_err(err)? {
+            match column {
+                0 | 1 => {
+                    let values: Vec<ByteArray> = rows
+                        .iter()
+                        .map(|item| ByteArray::from(if column == 0 { item.chunk_id.as_str() } else { item.text.as_str() }))
+                        .collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(&values, None, None).map_err(err)?;
+                }
+                2 => {
+                    let values: Vec<i32> = rows.iter().filter_map(|item| item.page.map(|p| p as i32)).collect();
+                    let levels: Vec<i16> = rows.iter().map(|item| item.page.is_some() as i16).collect();
+                    column_writer.typed::<Int32Type>().write_batch(&values, Some(&levels), None).map_err(err)?;
+                }
+                3 => {
+                    let values: Vec<ByteArray> = rows
+                        .iter()
+                        .filter_map(|item| item.metadata.as_ref().map(|m| ByteArray::from(m.to_string().into_bytes())))
+                        .collect();
+                    let levels: Vec<i16> = rows.iter().map(|item| item.metadata.is_some() as i16).collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None).map_err(err)?;
+                }
+                _ => {
+                    // Repetition level 0 starts a row's list; definition level 0 is an empty list
+                    let mut values = Vec::new();
+                    let mut definition = Vec::new();
+                    let mut repetition = Vec::new();
+                    for item in rows {
+                        if item.vector.is_empty() {
+                            definition.push(0);
+                            repetition.push(0);
+                        }
+                        for (i, v) in item.vector.iter().enumerate() {
+                            values.push(*v);
+                            definition.push(1);
+                            repetition.push((i > 0) as i16);
+                        }
+                    }
+                    column_writer
+                        .typed::<FloatType>()
+                        .write_batch(&values, Some(&definition), Some(&repetition))
+                        .map_err(err)?;
+                }
+            }
+            column_writer.close().map_err(err)?;
+            column += 1;
+        }
+        group.close().map_err(err)?;
+    }
+    writer.close().map_err(err)?;
+    Ok(())
+}
+
+/// Chroma metadata values must be strings, numbers or booleans; nested values are
+/// kept as JSON text
+fn chroma_metadata(item: &EmbeddingItem, document: &str) -> Map<String, Value> {
+    let mut metadata = Map::new();
+    if let Some(Value::Object(fields)) = &item.metadata {
+        for (key, value) in fields {
+            let value = match value {
+                Value::Null => continue,
+                Value::Array(_) | Value::Object(_) => Value::String(value.to_string()),
+                other => other.clone(),
+            };
+            metadata.insert(key.clone(), value);
+        }
+    }
+    metadata.insert("source".to_string(), json!(document));
+    if let Some(page) = item.page {
+        metadata.insert("page".to_string(), json!(page));
+    }
+    if let (Some(start), Some(end)) = (item.start, item.end) {
+        metadata.insert("start_index".to_string(), json!(start));
+        metadata.insert("end_index".to_string(), json!(end));
+    }
+    metadata
+}
+
+fn write_chroma(items: &[EmbeddingItem], info: &IndexInfo, path: &str) -> Result<(), String> {
+    // Chroma rejects null metadata values
+    let mut metadata = json!({ "hnsw:space": "cosine", "dimension": info.dimension() });
+    if let Some(model) = info.model() {
+        metadata["embedding_model"] = json!(model);
+    }
+    let collection = json!({
+        "name": info.name(),
+        "metadata": metadata,
+        "ids": items.iter().map(|i| &i.chunk_id).collect::<Vec<_>>(),
+        "embeddings": items.iter().map(|i| &i.vector).collect::<Vec<_>>(),
+        "documents": items.iter().map(|i| &i.text).collect::<Vec<_>>(),
+        "metadatas": items.iter().map(|i| chroma_metadata(i, info.name())).collect::<Vec<_>>(),
+    });
+    let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut out = BufWriter::new(file);
+    serde_json::to_writer(&mut out, &collection).map_err(|e| format!("Failed to write export: {}", e))?;
+    out.flush().map_err(|e| format!("Failed to write export: {}", e))
+}
+
+/// Export a document's chunks and their embeddings for use in other tools
+///
+/// `jsonl` has one chunk per line, `parquet` one row per chunk with the vector
+/// as a float list, and `chroma` a JSON file whose `ids`, `embeddings`,
+/// `documents` and `metadatas` can be passed straight to `collection.add()`.
+/// The vectors are the stored ones, so nothing is re-embedded.
+#[tauri::command]
+pub async fn export_index(
+    document_id: String,
+    format: IndexFormat,
+    path: String,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<IndexExport, AppError> {
+    log::info!("Exporting index {} as {:?}", document_id, format);
+
+    let info = store
+        .index_info(&document_id)?
+        .ok_or_else(|| format!("Index not found: {}", document_id))?;
+    let items = store.items(&document_id)?;
+    let chunks = items.len();
+
+    let target = path.clone();
+    let model = info.model().map(str::to_string);
+    let dimension = info.dimension();
+    tauri::async_runtime::spawn_blocking(move || match format {
+        IndexFormat::Jsonl => write_jsonl(&items, &target),
+        IndexFormat::Parquet => write_parquet(&items, &target),
+        IndexFormat::Chroma => write_chroma(&items, &info, &target),
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))??;
+
+    let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    log::info!("Exported {} chunks to {} ({} bytes)", chunks, path, bytes);
+    Ok(IndexExport {
+        path,
+        chunks,
+        dimension,
+        model,
+        bytes,
+    })
+}
+
+/// The first of `names` present in `record`, removed from it
+fn take_field(record: &mut Map<String, Value>, names: &[&str]) -> Option<Value> {
+    names.iter().find_map(|name| record.remove(*name))
+}
+
+/// One record of an external dump as a chunk, or None if it has no text or vector
+///
+/// Fields that aren't the id, text or vector end up in the chunk's metadata, merged
+/// with a `metadata` object (or JSON string, as Parquet dumps store it) if present.
+fn to_item(record: Value, position: usize) -> Option<EmbeddingItem> {
+    let Value::Object(mut record) = record else {
+        return None;
+    };
+    let text = match take_field(&mut record, TEXT_FIELDS)? {
+        Value::String(text) if !text.trim().is_empty() => text,
+        _ => return None,
+    };
+    let vector: Vec<f32> = match take_field(&mut record, VECTOR_FIELDS)? {
+        Value::Array(values) => values.iter().map(|v| v.as_f64().map(|v| v as f32)).collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let chunk_id = match take_field(&mut record, ID_FIELDS) {
+        Some(Value::String(id)) if !id.is_empty() => id,
+        Some(Value::Number(id)) => id.to_string(),
+        _ => format!("chunk_{}", position),
+    };
+
+    let mut metadata = match record.remove("metadata") {
+        Some(Value::Object(fields)) => fields,
+        Some(Value::String(json)) => match serde_json::from_str(&json) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        },
+        _ => Map::new(),
+    };
+    let start = record.remove("start").or_else(|| metadata.get("start_index").cloned());
+    let end = record.remove("end").or_else(|| metadata.get("end_index").cloned());
+    for (key, value) in record {
+        if !value.is_null() {
+            metadata.entry(key).or_insert(value);
+        }
+    }
+    let metadata = Value::Object(metadata);
+
+    Some(EmbeddingItem {
+        chunk_id,
+        text,
+        page: page_from_metadata(Some(&metadata)),
+        start: start.and_then(|v| v.as_u64()).map(|v| v as usize),
+        end: end.and_then(|v| v.as_u64()).map(|v| v as usize),
+        metadata: Some(metadata).filter(|m| m.as_object().is_some_and(|m| !m.is_empty())),
+        vector,
+    })
+}
+
+fn read_jsonl(path: &str) -> Result<Vec<Value>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read file: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|e| format!("Invalid JSON on line {}: {}", i + 1, e))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn read_parquet(path: &str) -> Result<Vec<Value>, String> {
+    let err = |e: parquet::errors::ParquetError| format!("Failed to read Parquet: {}", e);
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let reader = SerializedFileReader::new(file).map_err(err)?;
+    let rows = reader.get_row_iter(None).map_err(err)?;
+    rows.map(|row| row.map(|row| row.to_json_value()).map_err(err)).collect()
+}
+
+/// Records of a Chroma `collection.get()`/`collection.add()` payload, as `export_index` writes it
+fn read_chroma(path: &str) -> Result<Vec<Value>, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let collection: Value = serde_json::from_str(&json).map_err(|e| format!("Invalid Chroma export: {}", e))?;
+    let column = |name: &str| collection[name].as_array().cloned().unwrap_or_default();
+    let (ids, embeddings, documents, metadatas) =
+        (column("ids"), column("embeddings"), column("documents"), column("metadatas"));
+    Ok(embeddings
+        .into_iter()
+        .enumerate()
+        .map(|(i, embedding)| {
+            json!({
+                "id": ids.get(i),
+                "embedding": embedding,
+                "document": documents.get(i),
+                "metadata": metadatas.get(i),
+            })
+        })
+        .collect())
+}
+
+/// Import chunks and their vectors exported from another tool as a new index
+///
+/// Reads `jsonl` (one object per line), `parquet` or a `chroma` collection
+/// payload. Id, text and vector fields are found under their common names
+/// (`id`/`chunk_id`, `text`/`page_content`/`document`, `vector`/`embedding`);
+/// everything else becomes chunk metadata, where page numbers are picked up
+/// for citations.
+///
+/// Questions are embedded with the configured embedding model, so the imported
+/// vectors must come from the same model. Only the dimension can be checked:
+/// it's compared with an embedding of a probe text and a mismatch is refused.
+/// Importing the same file again replaces the index it created.
+#[tauri::command]
+pub async fn import_index(
+    path: String,
+    format: IndexFormat,
+    name: Option<String>,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<IndexImport, AppError> {
+    log::info!("Importing {:?} index from {}", format, path);
+
+    let source = path.clone();
+    let (document_id, records) = tauri::async_runtime::spawn_blocking(move || {
+        let records = match format {
+            IndexFormat::Jsonl => read_jsonl(&source)?,
+            IndexFormat::Parquet => read_parquet(&source)?,
+            IndexFormat::Chroma => read_chroma(&source)?,
+        };
+        Ok::<_, String>((embedding_cache::hash_file(&source)?, records))
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))??;
+
+    let total = records.len();
+    let items: Vec<EmbeddingItem> = records
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, record)| to_item(record, i))
+        .collect();
+    let skipped = total - items.len();
+    let Some(dimension) = items.first().map(|item| item.vector.len()) else {
+        return Err(AppError::Parse(format!("No chunks with text and a vector found in {}", path)));
+    };
+    if let Some(bad) = items.iter().find(|item| item.vector.len() != dimension) {
+        return Err(AppError::Parse(format!(
+            "Chunk {} has dimension {}, the first chunk has {}",
+            bad.chunk_id,
+            bad.vector.len(),
+            dimension
+        )));
+    }
+
+    let model = embedding_model::from_settings(&settings::read_settings(&app_handle));
+    let probe = ollama_chat::embed_in(Lane::Interactive, model.clone(), "dimension check".to_string(), &app_handle)
+        .await
+        .map_err(|e| format!("Failed to check the dimension of {}: {}", model, String::from(e)))?;
+    if probe.len() != dimension {
+        return Err(AppError::Other(format!(
+            "The imported vectors have dimension {} but {} produces {}; set the embedding model they were made with",
+            dimension,
+            model,
+            probe.len()
+        )));
+    }
+
+    let name = name.unwrap_or_else(|| {
+        Path::new(&path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Imported index".to_string())
+    });
+    if store.index_info(&document_id)?.is_some() {
+        store.reset(&document_id, dimension, &model)?;
+    } else {
+        store.create(&document_id, &name, dimension, Some(&model))?;
+    }
+    let chunks = store.add(&document_id, &items)?;
+
+    if skipped > 0 {
+        log::warn!("Skipped {} records without text or a vector", skipped);
+    }
+    log::info!("Imported {} chunks into {} ({})", chunks, document_id, name);
+    Ok(IndexImport {
+        document_id,
+        name,
+        chunks,
+        dimension,
+        model,
+        skipped,
+    })
+}
@@ -0,0 +1,483 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tauri::{Emitter, Manager};
+use tokio::sync::mpsc;
+
+use crate::chunking::{self, ChunkStrategy};
+use crate::document_sources::DocumentSource;
+use crate::documents;
+use crate::embedding_cache;
+use crate::embedding_model;
+use crate::language;
+use crate::library;
+use crate::ollama_chat;
+use crate::pdf;
+use crate::pdf_security;
+use crate::privacy::PiiMasker;
+use crate::progress::ProgressThrottle;
+use crate::reindex;
+use crate::error::AppError;
+use crate::settings;
+use crate::suggestions;
+use crate::vectorstore::{EmbeddingItem, VectorStore};
+
+/// Chunk size and overlap in tokens, matching the frontend's PDF settings
+const CHUNK_TOKENS: usize = 256;
+const CHUNK_OVERLAP_TOKENS: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct IndexingProgress {
+    pub(crate) document_id: Option<String>,
+    pub(crate) path: String,
+    pub(crate) name: String,
+    /// "extracting", "embedding" or "storing"
+    pub(crate) stage: &'static str,
+    pub(crate) percent: f64,
+    /// Files still waiting behind this one
+    pub(crate) queued: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct IndexingComplete {
+    document_id: Option<String>,
+    path: String,
+    name: String,
+    pages: usize,
+    chunks: usize,
+    /// The file was indexed before and its content hasn't changed
+    already_indexed: bool,
+    /// The file changed since it was indexed as `document_id`; `reindex_document` updates it
+    modified: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueStatus {
+    /// Files waiting or being indexed
+    pending: usize,
+}
+
+/// Background queue that indexes dropped files one at a time
+///
+/// Files are extracted, chunked, embedded and stored in the vector store with
+/// their content hash as the document id. Progress is emitted per file as
+/// `indexing_progress` and `indexing_complete`, so chat stays usable meanwhile.
+/// Files queued as sensitive are indexed with PII masked; see `mask_pages`.
+pub struct IngestQueue {
+    sender: mpsc::UnboundedSender<(PathBuf, bool)>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl IngestQueue {
+    /// Create the queue and start its worker
+    pub fn start(app_handle: tauri::AppHandle) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        tauri::async_runtime::spawn(worker(app_handle, receiver, pending.clone()));
+        Self { sender, pending }
+    }
+
+    pub fn enqueue(&self, path: PathBuf, sensitive: bool) -> Result<(), String> {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.sender.send((path, sensitive)).map_err(|_| {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            "Indexing queue has stopped".to_string()
+        })
+    }
+
+    /// Queue the supported files among `paths`; returns the paths that were queued
+    pub fn enqueue_all(&self, paths: Vec<String>, sensitive: bool) -> Result<Vec<String>, String> {
+        let mut queued = Vec::new();
+        for path in paths {
+            if !is_supported(Path::new(&path)) {
+                log::warn!("Skipping unsupported file: {}", path);
+                continue;
+            }
+            self.enqueue(PathBuf::from(&path), sensitive)?;
+            queued.push(path);
+        }
+        Ok(queued)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+}
+
+/// Whether the queue can index this file
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .is_some_and(|e| e == "pdf" || documents::SUPPORTED_EXTENSIONS.contains(&e.as_str()))
+}
+
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+async fn worker(
+    app_handle: tauri::AppHandle,
+    mut receiver: mpsc::UnboundedReceiver<(PathBuf, bool)>,
+    pending: Arc<AtomicUsize>,
+) {
+    while let Some((path, sensitive)) = receiver.recv().await {
+        let name = file_name(&path);
+        log::info!("Indexing {} ({} queued)", path.display(), pending.load(Ordering::SeqCst).saturating_sub(1));
+
+        let complete = match index_file(&app_handle, &path, &name, sensitive, &pending).await {
+            Ok(complete) => complete,
+            Err(e) => {
+                log::error!("Failed to index {}: {}", path.display(), e);
+                IndexingComplete {
+                    document_id: None,
+                    path: path.display().to_string(),
+                    name,
+                    pages: 0,
+                    chunks: 0,
+                    already_indexed: false,
+                    modified: false,
+                    error: Some(e),
+                }
+            }
+        };
+        pending.fetch_sub(1, Ordering::SeqCst);
+        if let Some(document_id) = &complete.document_id {
+            let pages = Some(complete.pages).filter(|&n| n > 0);
+            let store = app_handle.state::<VectorStore>();
+            if let Err(e) = library::record_open(&store, &complete.path, Some(document_id), None, pages) {
+                log::warn!("Failed to add {} to library: {}", complete.path, e);
+            }
+        }
+        if let (Some(document_id), false) = (&complete.document_id, complete.already_indexed) {
+            suggestions::generate_in_background(app_handle.clone(), document_id.clone());
+        }
+        app_handle.emit("indexing_complete", complete).ok();
+    }
+}
+
+/// Modification time of a file in seconds since the epoch
+pub(crate) fn file_mtime(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+/// Text of each page: the PDF text layer, or the pages of another supported document
+pub(crate) fn read_pages(path: &str) -> Result<Vec<(u32, String)>, String> {
+    let is_pdf = Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+    if is_pdf {
+        pdf::page_texts(path)
+    } else {
+        Ok(documents::read_document(path)?.into_page_texts())
+    }
+}
+
+pub(crate) fn chunk_pages(pages: &[(u32, String)]) -> Vec<(u32, chunking::Chunk)> {
+    pages
+        .iter()
+        .flat_map(|(page, text)| {
+            chunking::chunk(text, ChunkStrategy::Token, CHUNK_TOKENS, CHUNK_OVERLAP_TOKENS)
+                .into_iter()
+                .map(move |chunk| (*page, chunk))
+        })
+        .collect()
+}
+
+/// Page text with emails, phone numbers, SSNs and IBANs replaced by placeholders
+///
+/// Sensitive documents are chunked, embedded and stored from this text only, so
+/// the originals never reach the database or a prompt. One masker covers the
+/// whole document, so a value gets the same placeholder on every page, and its
+/// mapping is dropped afterwards. Chunk offsets then refer to the masked text.
+pub(crate) fn mask_pages(pages: &[(u32, String)]) -> Vec<(u32, String)> {
+    let mut masker = PiiMasker::redacting();
+    let masked = pages.iter().map(|(page, text)| (*page, masker.mask(text))).collect();
+    log::info!("Masked {} distinct PII values", masker.masked_count());
+    masked
+}
+
+/// Chunks held in memory before they're written to the store
+const STORE_BATCH: usize = 64;
+
+/// Chunks embedded by `embed_chunks`, waiting in their staging index
+pub(crate) struct Embedded {
+    pub(crate) staging: String,
+    pub(crate) dimension: usize,
+    pub(crate) chunks: usize,
+    /// Chunks whose vector was reused rather than embedded
+    pub(crate) reused: usize,
+}
+
+/// Embed chunks of `document_id` into a staging index; chunks whose text is in `known` reuse that vector
+///
+/// Items are written to the store `STORE_BATCH` at a time, so memory use doesn't
+/// grow with the document; the caller moves them into place with
+/// `VectorStore::promote`. A failed run deletes its staging index. `on_progress`
+/// gets the percentage done after each chunk. If the embedding model isn't
+/// installed, `embedding_model_missing` is emitted so the UI can offer to pull it.
+pub(crate) async fn embed_chunks(
+    app_handle: &tauri::AppHandle,
+    model: &str,
+    document_id: &str,
+    name: &str,
+    chunks: &[(u32, chunking::Chunk)],
+    known: &HashMap<String, Vec<f32>>,
+    mut on_progress: impl FnMut(f64, bool),
+) -> Result<Embedded, String> {
+    let store = app_handle.state::<VectorStore>();
+    let staging = VectorStore::staging_id(document_id);
+    store.delete(&staging)?;
+
+    let result = async {
+        let mut batch = Vec::with_capacity(STORE_BATCH.min(chunks.len()));
+        let mut dimension = 0;
+        let mut reused = 0;
+        for (i, (page, chunk)) in chunks.iter().enumerate() {
+            let vector: Vec<f32> = match known.get(chunk.text()) {
+                Some(vector) => {
+                    reused += 1;
+                    vector.clone()
+                }
+                None => ollama_chat::ollama_embedding(model.to_string(), chunk.text().to_string(), app_handle.clone())
+                    .await
+                    .map_err(|e| {
+                        if matches!(e, AppError::ModelNotFound(_)) {
+                            app_handle.emit("embedding_model_missing", json!({ "model": model })).ok();
+                        }
+                        String::from(e)
+                    })?
+                    .iter()
+                    .map(|&v| v as f32)
+                    .collect(),
+            };
+            if i == 0 {
+                dimension = vector.len();
+                store.create(&staging, name, dimension, Some(model))?;
+            }
+            batch.push(EmbeddingItem {
+                chunk_id: format!("{}-{}", document_id, i),
+                text: chunk.text().to_string(),
+                metadata: Some(json!({ "page": page, "source": name })),
+                vector,
+                page: Some(*page),
+                start: Some(chunk.start()),
+                end: Some(chunk.end()),
+            });
+            if batch.len() == STORE_BATCH || i + 1 == chunks.len() {
+                store.add(&staging, &batch)?;
+                batch.clear();
+            }
+            on_progress((i + 1) as f64 / chunks.len() as f64 * 100.0, i + 1 == chunks.len());
+        }
+        Ok::<_, String>((dimension, reused))
+    }
+    .await;
+
+    match result {
+        Ok((dimension, reused)) => Ok(Embedded {
+            staging,
+            dimension,
+            chunks: chunks.len(),
+            reused,
+        }),
+        Err(e) => {
+            if let Err(cleanup) = store.delete(&staging) {
+                log::warn!("{}", cleanup);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Detect and record the language of a document's text
+///
+/// When an English-only embedding model was used for a document in another
+/// language, `embedding_model_hint` suggests a multilingual one.
+pub(crate) fn record_language(
+    app_handle: &tauri::AppHandle,
+    store: &VectorStore,
+    document_id: &str,
+    pages: &[(u32, String)],
+    model: &str,
+) {
+    let Some(detected) = language::detect_document(pages.iter().map(|(_, text)| text.as_str())) else {
+        return;
+    };
+    log::info!("{} is in {}", document_id, detected.name());
+    if let Err(e) = store.set_language(document_id, detected.code()) {
+        log::warn!("{}", e);
+    }
+    if let Some(hint) = language::embedding_hint(&detected, model) {
+        app_handle
+            .emit("embedding_model_hint", json!({
+                "document_id": document_id,
+                "language": detected,
+                "model": model,
+                "hint": hint
+            }))
+            .ok();
+    }
+}
+
+/// Index a file unless its current content is already indexed
+///
+/// With `sensitive`, an existing index that holds unmasked text is rebuilt masked;
+/// a masked index is kept masked either way.
+async fn index_file(
+    app_handle: &tauri::AppHandle,
+    path: &Path,
+    name: &str,
+    sensitive: bool,
+    pending: &AtomicUsize,
+) -> Result<IndexingComplete, String> {
+    let path_str = path.display().to_string();
+    let mut throttle = ProgressThrottle::new();
+    let emit = |document_id: Option<&str>, stage: &'static str, percent: f64| {
+        app_handle.emit("indexing_progress", IndexingProgress {
+            document_id: document_id.map(str::to_string),
+            path: path_str.clone(),
+            name: name.to_string(),
+            stage,
+            percent,
+            queued: pending.load(Ordering::SeqCst).saturating_sub(1),
+        }).ok();
+    };
+    let complete = |document_id: &str, pages: usize, chunks: usize, already_indexed: bool, modified: bool| {
+        IndexingComplete {
+            document_id: Some(document_id.to_string()),
+            path: path_str.clone(),
+            name: name.to_string(),
+            pages,
+            chunks,
+            already_indexed,
+            modified,
+            error: None,
+        }
+    };
+
+    emit(None, "extracting", 0.0);
+    let store = app_handle.state::<VectorStore>();
+    let mtime = file_mtime(path);
+    let previous = store
+        .source_by_path(&path_str)?
+        .filter(|source| {
+            store
+                .index_info(&source.index_id)
+                .ok()
+                .flatten()
+                .is_some_and(|info| info.is_masked() || !sensitive)
+        });
+
+    // Same path and modification time: the file wasn't touched since it was indexed
+    if let Some(previous) = previous.as_ref().filter(|p| mtime.is_some() && p.mtime == mtime) {
+        let chunks = store.index_info(&previous.index_id)?.map(|i| i.chunk_count()).unwrap_or(0);
+        log::info!("{} is unchanged since it was indexed as {}", name, previous.index_id);
+        return Ok(complete(&previous.index_id, 0, chunks, true, false));
+    }
+
+    let source = path_str.clone();
+    let document_id = tauri::async_runtime::spawn_blocking(move || embedding_cache::hash_file(&source))
+        .await
+        .map_err(|e| format!("Hashing task failed: {}", e))??;
+
+    // The content changed since it was indexed: leave re-indexing to `reindex_document`
+    if let Some(previous) = previous.filter(|p| p.hash != document_id) {
+        log::info!("{} changed since it was indexed as {}", name, previous.index_id);
+        let chunks = store.index_info(&previous.index_id)?.map(|i| i.chunk_count()).unwrap_or(0);
+        return Ok(complete(&previous.index_id, 0, chunks, true, true));
+    }
+
+    pdf_security::warn_active_content(app_handle, path).await;
+
+    let source = path_str.clone();
+    let pages = tauri::async_runtime::spawn_blocking(move || read_pages(&source))
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))??;
+
+    let record_source = || {
+        store.set_source(&DocumentSource {
+            index_id: document_id.clone(),
+            path: path_str.clone(),
+            mtime,
+            hash: document_id.clone(),
+        })
+    };
+
+    if let Some(existing) = store
+        .index_info(&document_id)?
+        .filter(|info| info.chunk_count() > 0 && (info.is_masked() || !sensitive))
+    {
+        log::info!("{} is already indexed as {}", name, document_id);
+        record_source()?;
+        return Ok(complete(&document_id, pages.len(), existing.chunk_count(), true, false));
+    }
+
+    let chunks = if sensitive { chunk_pages(&mask_pages(&pages)) } else { chunk_pages(&pages) };
+    if chunks.is_empty() {
+        return Err(format!("No text found in {}; scanned PDFs need OCR first", name));
+    }
+
+    let model = embedding_model::from_settings(&settings::read_settings(app_handle));
+    let embedded = embed_chunks(app_handle, &model, &document_id, name, &chunks, &HashMap::new(), |percent, last| {
+        if throttle.should_emit(percent, last) {
+            emit(Some(&document_id), "embedding", percent);
+        }
+    })
+    .await?;
+
+    emit(Some(&document_id), "storing", 100.0);
+    store.create(&document_id, name, embedded.dimension, Some(&model))?;
+    store.promote(&embedded.staging, &document_id)?;
+    store.set_masked(&document_id, sensitive)?;
+    record_source()?;
+    record_language(app_handle, &store, &document_id, &pages, &model);
+
+    log::info!("Indexed {} as {}: {} pages, {} chunks", name, document_id, pages.len(), embedded.chunks);
+    Ok(complete(&document_id, pages.len(), embedded.chunks, false, false))
+}
+
+/// Index one file right away, outside the queue, and return its document id
+///
+/// A file that changed since it was indexed is re-indexed, so the id always
+/// refers to the current content.
+pub(crate) async fn index_now(app_handle: &tauri::AppHandle, path: &Path) -> Result<String, String> {
+    if !is_supported(path) {
+        return Err(format!("{} is not a supported document", path.display()));
+    }
+    let complete = index_file(app_handle, path, &file_name(path), false, &AtomicUsize::new(1)).await?;
+    let document_id = complete.document_id.ok_or_else(|| format!("Failed to index {}", path.display()))?;
+    if !complete.modified {
+        return Ok(document_id);
+    }
+    let report = reindex::reindex_document(document_id, None, None, app_handle.clone(), app_handle.state(), app_handle.state()).await?;
+    Ok(report.document_id)
+}
+
+/// Queue files for background indexing; unsupported files are skipped
+///
+/// With `sensitive` the files are indexed with PII masked, and only the masked
+/// text is stored. Returns the paths that were queued.
+#[tauri::command]
+pub async fn enqueue_documents(
+    paths: Vec<String>,
+    sensitive: Option<bool>,
+    queue: tauri::State<'_, IngestQueue>,
+) -> Result<Vec<String>, AppError> {
+    log::info!("Queueing {} files for indexing", paths.len());
+    Ok(queue.enqueue_all(paths, sensitive.unwrap_or(false))?)
+}
+
+/// Number of files waiting or being indexed
+#[tauri::command]
+pub async fn get_ingestion_queue(queue: tauri::State<'_, IngestQueue>) -> Result<QueueStatus, AppError> {
+    Ok(QueueStatus {
+        pending: queue.pending(),
+    })
+}
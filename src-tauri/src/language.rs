@@ -0,0 +1,160 @@
+use serde::Serialize;
+use whatlang::Lang;
+
+use crate::error::AppError;
+use crate::vectorstore::VectorStore;
+
+/// Characters of text looked at; more doesn't make detection more accurate
+const SAMPLE_CHARS: usize = 4000;
+/// Shorter texts are too ambiguous to detect (a question like "GDPR?" says nothing)
+const MIN_CHARS: usize = 12;
+
+/// Embedding models trained on English text; other languages retrieve poorly with them
+const ENGLISH_EMBEDDING_MODELS: &[&str] = &[
+    "nomic-embed-text",
+    "all-minilm",
+    "mxbai-embed-large",
+    "snowflake-arctic-embed",
+];
+/// Suggested in place of an English-only embedding model
+const MULTILINGUAL_EMBEDDING_MODEL: &str = "bge-m3";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Language {
+    /// ISO 639-3 code, e.g. "deu"
+    code: String,
+    /// English name, e.g. "German"
+    name: String,
+    /// 0 to 1
+    confidence: f64,
+    /// Confident enough to act on
+    reliable: bool,
+}
+
+impl Language {
+    fn from_info(info: &whatlang::Info) -> Self {
+        Self {
+            code: info.lang().code().to_string(),
+            name: info.lang().eng_name().to_string(),
+            confidence: info.confidence(),
+            reliable: info.is_reliable(),
+        }
+    }
+
+    /// A language recorded earlier by its code
+    fn from_code(code: &str) -> Option<Self> {
+        let lang = Lang::from_code(code)?;
+        Some(Self {
+            code: lang.code().to_string(),
+            name: lang.eng_name().to_string(),
+            confidence: 1.0,
+            reliable: true,
+        })
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn reliable(&self) -> bool {
+        self.reliable
+    }
+
+    pub fn is_english(&self) -> bool {
+        self.code == Lang::Eng.code()
+    }
+
+    /// Tesseract's name for the language pack, where it differs from ISO 639-3
+    pub fn tesseract_code(&self) -> &str {
+        match self.code.as_str() {
+            "cmn" => "chi_sim",
+            "pes" => "fas",
+            "nob" => "nor",
+            "ydd" => "yid",
+            code => code,
+        }
+    }
+}
+
+/// Detect the language of `text`; None when it's too short or mixed to tell
+pub fn detect(text: &str) -> Option<Language> {
+    let sample: String = text.chars().take(SAMPLE_CHARS).collect();
+    if sample.trim().chars().count() < MIN_CHARS {
+        return None;
+    }
+    whatlang::detect(&sample).map(|info| Language::from_info(&info))
+}
+
+/// Detect the language of a document from the text of its pages
+pub fn detect_document<'a>(pages: impl IntoIterator<Item = &'a str>) -> Option<Language> {
+    let mut sample = String::new();
+    for page in pages {
+        if sample.len() >= SAMPLE_CHARS {
+            break;
+        }
+        sample.push_str(page);
+        sample.push('\n');
+    }
+    detect(&sample).filter(|language| language.reliable)
+}
+
+/// Language of an indexed document, detected from its chunks and recorded the
+/// first time it's asked for (indexes built before detection have none)
+pub fn document_language(store: &VectorStore, document_id: &str) -> Option<Language> {
+    let info = store.index_info(document_id).ok()??;
+    if let Some(code) = info.language() {
+        return Language::from_code(code);
+    }
+    let texts = store.chunk_texts(document_id).ok()?;
+    let language = detect_document(texts.iter().map(String::as_str))?;
+    if let Err(e) = store.set_language(document_id, language.code()) {
+        log::warn!("{}", e);
+    }
+    Some(language)
+}
+
+/// System prompt line asking for answers in the user's language
+///
+/// The question's language wins when it can be told reliably; short questions
+/// fall back to the document's. Nothing is added for English.
+pub fn answer_instruction(question: &str, document: Option<&Language>) -> Option<String> {
+    let question_language = detect(question).filter(|language| language.reliable);
+    let (language, whose) = match (&question_language, document) {
+        (Some(language), _) => (language, "the question"),
+        (None, Some(language)) => (language, "the document"),
+        (None, None) => return None,
+    };
+    if language.is_english() {
+        return None;
+    }
+    Some(format!(
+        "Answer in {}, the language of {}, even where the excerpts are in another language.",
+        language.name(),
+        whose
+    ))
+}
+
+/// Suggest a multilingual embedding model when a non-English document is
+/// indexed with an English-only one
+pub fn embedding_hint(language: &Language, embedding_model: &str) -> Option<String> {
+    let base = embedding_model.split(':').next().unwrap_or(embedding_model);
+    if language.is_english() || !ENGLISH_EMBEDDING_MODELS.contains(&base) {
+        return None;
+    }
+    Some(format!(
+        "This document is in {}; {} is trained on English text, so {} would find passages more reliably",
+        language.name(),
+        embedding_model,
+        MULTILINGUAL_EMBEDDING_MODEL
+    ))
+}
+
+/// Detect the language of a text, such as a question or a pasted passage
+#[tauri::command]
+pub async fn detect_language(text: String) -> Result<Option<Language>, AppError> {
+    Ok(detect(&text))
+}
@@ -1,68 +1,355 @@
 // Import our custom modules
+mod backend;
+mod bundle;
+mod bundle_crypto;
+mod bundle_merge;
+mod catalog;
+mod chat_context;
+mod chunking;
+mod cli;
+mod clipboard;
+mod compression;
+mod context_window;
+mod conversation_stats;
+mod conversations;
+mod diagnostics;
+mod document_sources;
+mod document_window;
+mod documents;
+mod embedding_cache;
+mod embedding_model;
+mod error;
+mod export;
+mod feedback;
+mod flashcards;
+mod folder_watch;
+mod grounding;
+mod hardware;
+mod html_text;
+mod http;
+mod index_io;
+mod ingest;
+mod injection;
+mod language;
+mod library;
+mod local_llm;
+mod logging;
+mod mcp;
+mod model_updates;
+mod obsidian;
+mod ocr;
 mod ollama;
+mod ollama_archive;
+mod ollama_chat;
+mod ollama_install;
+mod ollama_models;
+mod ollama_service;
+mod openai_backend;
+mod pandoc;
+mod pdf;
+mod pdf_annotate;
+mod pdf_images;
+mod pdf_layout;
+mod pdf_security;
+mod pdf_tables;
+mod permissions;
+mod portable;
+mod privacy;
+mod progress;
+mod prompts;
+mod proxy;
+mod rag;
+mod regenerate;
+mod reindex;
+mod remote_files;
+mod remote_sources;
+mod rerank;
+mod rest_api;
+mod retention;
+mod retrieval_trace;
+mod scheduler;
+mod secure_delete;
+mod selection;
 mod settings;
+mod startup;
+mod storage;
+mod stt;
+mod suggestions;
+mod summary;
+mod supervisor;
+mod theme;
+mod tts;
+mod updates;
+mod vector_schema;
+mod vector_search;
+mod vectorstore;
+mod webdav;
+mod window_state;
+mod workspace;
+mod workspace_settings;
 
-use tauri::{Manager, Listener, Emitter};
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+  // Created first so every startup phase is measured from process launch
+  let startup_timings = startup::StartupTimings::new();
+  portable::prepare();
+
+  // `--ask` answers from the command line and `--mcp` serves MCP clients, both without a window
+  let headless = match cli::parse(std::env::args().skip(1)) {
+    Ok(request) => request,
+    Err(e) => {
+      cli::attach_console();
+      eprintln!("{}\n\n{}", e, cli::USAGE);
+      std::process::exit(2);
+    }
+  };
+  let mut context = tauri::generate_context!();
+  // Headless runs print answers or protocol messages to stdout, so logs go to stderr
+  let console_log = if headless.is_some() {
+    cli::attach_console();
+    context.config_mut().app.windows.clear();
+    tauri_plugin_log::TargetKind::Stderr
+  } else {
+    tauri_plugin_log::TargetKind::Stdout
+  };
+
   tauri::Builder::default()
+    .manage(startup_timings)
+    .manage(ollama_chat::ChatStreams::default())
+    .manage(ollama_install::OllamaDownloads::default())
+    .manage(supervisor::OllamaSupervisor::default())
+    .manage(scheduler::RequestScheduler::default())
+    .manage(context_window::ContextWindows::default())
+    .manage(tts::SpeechState::default())
+    .manage(folder_watch::FolderWatches::default())
+    .manage(document_window::DocumentWindows::default())
+    .manage(rest_api::RestApi::default())
     // Register Tauri plugins
     .plugin(tauri_plugin_fs::init())
     .plugin(tauri_plugin_http::init())
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_shell::init())
     .plugin(tauri_plugin_process::init())
+    .plugin(tauri_plugin_updater::Builder::new().build())
     .plugin(
       tauri_plugin_log::Builder::default()
-        .level(log::LevelFilter::Warn) // Only log warnings and errors to reduce log spam
+        // Levels come from the log_level setting (warnings and errors until it's read)
+        .level(log::LevelFilter::Trace)
+        .filter(logging::enabled)
+        .max_file_size(logging::MAX_FILE_BYTES)
+        .rotation_strategy(tauri_plugin_log::RotationStrategy::KeepAll)
         .targets([
-          tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Stdout),
-          tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::LogDir { file_name: None }),
+          tauri_plugin_log::Target::new(console_log),
+          tauri_plugin_log::Target::new(portable::log_target()),
         ])
         .build(),
     )
     // Register our custom commands
     .invoke_handler(tauri::generate_handler![
+      bundle::export_workspace_bundle,
+      bundle::import_workspace_bundle,
+      catalog::get_model_catalog,
+      chat_context::build_chat_context,
+      chunking::chunk_text,
+      clipboard::ingest_clipboard,
+      clipboard::ingest_image,
+      clipboard::list_context_documents,
+      clipboard::remove_context_document,
+      context_window::detect_model_context,
+      conversation_stats::get_conversation_stats,
+      conversations::save_conversation,
+      conversations::get_conversation,
+      conversations::list_conversations,
+      conversations::delete_conversation,
+      conversations::get_answer_candidates,
+      diagnostics::get_diagnostics,
+      document_window::open_document_window,
+      document_window::get_window_document,
+      documents::extract_docx_text,
+      documents::extract_document,
+      documents::extract_html_text,
+      embedding_cache::hash_document,
+      embedding_cache::get_cached_document,
+      embedding_cache::store_document_cache,
+      embedding_model::check_embedding_model,
+      export::export_conversation,
+      feedback::record_feedback,
+      flashcards::generate_flashcards,
+      flashcards::export_flashcards,
+      folder_watch::watch_folder,
+      folder_watch::unwatch_folder,
+      folder_watch::list_watched_folders,
+      hardware::detect_hardware,
+      http::verify_network_isolation,
+      index_io::export_index,
+      index_io::import_index,
+      ingest::enqueue_documents,
+      ingest::get_ingestion_queue,
+      language::detect_language,
+      library::list_recent_documents,
+      library::add_to_library,
+      library::pin_document,
+      library::remove_from_library,
+      local_llm::local_chat_stream,
+      local_llm::local_embedding,
+      logging::set_log_level,
+      model_updates::check_model_updates,
+      model_updates::update_model,
+      obsidian::export_to_obsidian,
       ollama::check_ollama_status,
       ollama::ping_ollama,
-      ollama::start_ollama_service,
-      ollama::stop_ollama_service,
-      ollama::download_ollama_model,
-      ollama::download_ollama_zip,
-      ollama::ollama_chat,
-      ollama::ollama_embedding,
-      ollama::ollama_chat_stream,
+      ollama_chat::ollama_chat,
+      ollama_chat::ollama_embedding,
+      ollama_chat::ollama_chat_stream,
+      ollama_chat::cancel_chat_stream,
+      ollama_install::download_ollama_zip,
+      ollama_install::cancel_ollama_download,
+      ollama_models::download_ollama_model,
+      ollama_models::list_ollama_models,
+      ollama_models::delete_ollama_model,
+      ollama_models::show_model_info,
+      ollama_models::preload_model,
+      ollama_models::unload_model,
+      ollama_service::start_ollama_service,
+      ollama_service::start_and_wait_ollama,
+      ollama_service::stop_ollama_service,
+      ollama_service::restart_ollama_localhost,
+      pandoc::get_pandoc_status,
+      pandoc::convert_with_pandoc,
+      ocr::ocr_pdf,
+      pdf::extract_text,
+      pdf_annotate::annotate_pdf,
+      pdf_images::extract_pdf_images,
+      pdf_security::scan_pdf,
+      pdf_security::sanitize_pdf,
+      pdf_tables::extract_pdf_tables,
+      permissions::get_permission_report,
+      portable::get_portable_mode,
+      prompts::list_prompt_templates,
+      prompts::save_prompt_template,
+      prompts::delete_prompt_template,
+      rag::rag_query,
+      regenerate::regenerate_answer,
+      reindex::reindex_document,
+      remote_sources::add_remote_source,
+      remote_sources::list_remote_sources,
+      remote_sources::remove_remote_source,
+      remote_sources::list_remote_files,
+      remote_sources::import_remote_files,
+      rest_api::get_rest_api_token,
+      rest_api::reset_rest_api_token,
+      retention::apply_retention,
+      retrieval_trace::get_retrieval_trace,
+      scheduler::get_request_queue,
+      secure_delete::purge_temp_data,
+      selection::query_selection,
       settings::save_settings,
       settings::load_settings,
       settings::reset_settings,
+      settings::save_secret,
+      settings::load_secret,
+      startup::get_startup_timings,
+      stt::transcribe_audio,
+      suggestions::generate_suggested_questions,
+      summary::summarize_document,
+      supervisor::get_ollama_health,
+      theme::get_theme,
+      tts::speak,
+      tts::stop_speaking,
+      updates::check_for_updates,
+      vector_search::search_similar,
+      vector_search::keyword_search,
+      vectorstore::create_index,
+      vectorstore::add_embeddings,
+      vectorstore::delete_index,
+      window_state::get_document_zoom,
+      window_state::set_document_zoom,
+      workspace::create_workspace,
+      workspace::list_workspaces,
+      workspace::add_to_workspace,
+      workspace::remove_from_workspace,
+      workspace::delete_workspace,
+      workspace::search_workspace,
+      workspace_settings::save_workspace_settings,
+      workspace_settings::load_workspace_settings,
     ])
-    .setup(|app| {
-      // Get the main window
-      let window = app.get_webview_window("main").unwrap();
+    .setup(move |app| {
+      startup::mark(app.handle(), "plugins_initialized");
+
+      // Get the main window (there is none in headless runs)
+      let window = app.get_webview_window(document_window::MAIN_WINDOW);
+      if let Some(window) = &window {
+        window_state::restore(window);
+        startup::mark(app.handle(), "window_created");
+      }
+
+      let app_settings = settings::read_settings(app.handle());
+      if let Err(e) = logging::apply(&app_settings.log_level) {
+        log::warn!("{}", e);
+      }
+      logging::prune_rotated_logs(app.handle());
+      http::configure(&app_settings.network);
+      http::set_offline(app_settings.offline_mode);
+      ollama::configure(&app_settings);
+      app
+        .state::<scheduler::RequestScheduler>()
+        .set_max_in_flight(app.handle(), app_settings.max_concurrent_requests);
+
+      // Open the on-disk vector store used by the embedding commands
+      let data_dir = portable::app_data_dir(app.handle())?;
+      std::fs::create_dir_all(&data_dir)?;
+      let vector_store = vectorstore::VectorStore::open(&data_dir.join("vectors.db"))?;
+      workspace::init(&vector_store)?;
+      folder_watch::init(&vector_store)?;
+      remote_sources::init(&vector_store)?;
+      library::init(&vector_store)?;
+      feedback::init(&vector_store)?;
+      app.manage(vector_store);
+      app.manage(embedding_cache::EmbeddingCache::new(&data_dir.join("embedding_cache.db")));
+      let conversation_store = conversations::ConversationStore::open(&data_dir.join("conversations.db"))?;
+      retrieval_trace::init(&conversation_store)?;
+      app.manage(conversation_store);
+      startup::mark(app.handle(), "vector_store_opened");
 
-      // Listen for file open events (when user opens PDF/DOC with app)
-      let window_clone = window.clone();
-      app.listen("tauri://file-drop", move |event| {
-        let path_str = event.payload();
-        log::info!("File opened: {}", path_str);
-        // Emit event to frontend with the file path
-        let _ = window_clone.emit("file-opened", path_str);
-      });
+      // Dropped files are indexed one at a time in the background
+      app.manage(ingest::IngestQueue::start(app.handle().clone()));
 
-      // Listen for window close event
-      window.on_window_event(move |event| {
-        if let tauri::WindowEvent::CloseRequested { .. } = event {
-          log::info!("Window closing, stopping Ollama service...");
-          // Stop Ollama service when window closes (blocking to ensure it completes)
-          tauri::async_runtime::block_on(async {
-            let _ = ollama::stop_ollama_service().await;
-          });
+      let Some(window) = window else {
+        if let Some(request) = headless {
+          #[cfg(target_os = "macos")]
+          app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+          cli::start(app.handle().clone(), request);
         }
-      });
+        return Ok(());
+      };
+      theme::apply(&window, &app_settings.theme);
+
+      // Dropped files, OS theme changes and saving the window geometry on close
+      document_window::watch(&window);
+
+      // Live Ollama status for the UI, including servers started outside the app
+      supervisor::start_health_monitor(app.handle().clone());
+
+      // Defer anything non-critical until the window is up
+      startup::run_deferred_init(app.handle().clone());
+      startup::mark(app.handle(), "setup_complete");
 
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(context)
+    .expect("error while building tauri application")
+    .run(|app_handle, event| {
+      // Document windows can outlive the main one, so the server is stopped when the app exits.
+      // Only the server we started is stopped; one the user runs for other tools keeps running
+      if let tauri::RunEvent::Exit = event {
+        if settings::read_settings(app_handle).stop_on_exit {
+          log::info!("Exiting, stopping Ollama service...");
+          app_handle.state::<supervisor::OllamaSupervisor>().stop(app_handle);
+        } else {
+          log::info!("Exiting, leaving Ollama running (stop_on_exit disabled)");
+        }
+      }
+    });
 }
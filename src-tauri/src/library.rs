@@ -0,0 +1,247 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::embedding_cache;
+use crate::error::AppError;
+use crate::pdf;
+use crate::vectorstore::{now_secs, VectorStore};
+
+/// Library entries live in `vectors.db` so their index status can be joined in.
+/// `hash` is the BLAKE3 content hash, which is also the document's index id.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS library_documents (
+        path TEXT PRIMARY KEY,
+        hash TEXT,
+        title TEXT NOT NULL,
+        page_count INTEGER,
+        last_opened INTEGER NOT NULL,
+        pinned INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_library_documents_hash ON library_documents(hash);
+";
+
+/// Entries returned when the caller doesn't ask for a specific number
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub struct LibraryDocument {
+    path: String,
+    hash: Option<String>,
+    title: String,
+    page_count: Option<usize>,
+    /// Seconds since the epoch
+    last_opened: i64,
+    pinned: bool,
+    /// "indexed" or "not_indexed"
+    index_status: &'static str,
+    /// The file is no longer at `path` and wasn't found elsewhere
+    missing: bool,
+}
+
+/// Create the library tables in the vector store database
+pub fn init(store: &VectorStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize library: {}", e))
+}
+
+fn title_from_path(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Record that a document was opened, creating or refreshing its library entry
+///
+/// An entry with the same content at a path that no longer exists is the same file
+/// moved, so it's replaced by this one and keeps its pin.
+pub fn record_open(
+    store: &VectorStore,
+    path: &str,
+    hash: Option<&str>,
+    title: Option<&str>,
+    page_count: Option<usize>,
+) -> Result<(), String> {
+    let conn = store.conn();
+    let mut pinned = false;
+
+    if let Some(hash) = hash {
+        let mut stmt = conn
+            .prepare("SELECT path, pinned FROM library_documents WHERE hash = ?1 AND path != ?2")
+            .map_err(|e| format!("Failed to read library: {}", e))?;
+        let moved: Vec<(String, bool)> = stmt
+            .query_map(params![hash, path], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to read library: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read library: {}", e))?;
+        for (old_path, old_pinned) in moved.into_iter().filter(|(p, _)| !Path::new(p).exists()) {
+            log::info!("{} moved to {}", old_path, path);
+            pinned |= old_pinned;
+            conn.execute("DELETE FROM library_documents WHERE path = ?1", params![old_path])
+                .map_err(|e| format!("Failed to update library: {}", e))?;
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO library_documents (path, hash, title, page_count, last_opened, pinned)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(path) DO UPDATE SET
+            hash = COALESCE(excluded.hash, hash),
+            title = excluded.title,
+            page_count = COALESCE(excluded.page_count, page_count),
+            last_opened = excluded.last_opened,
+            pinned = pinned OR excluded.pinned",
+        params![
+            path,
+            hash,
+            title.map(str::to_string).unwrap_or_else(|| title_from_path(path)),
+            page_count.map(|n| n as i64),
+            now_secs(),
+            pinned,
+        ],
+    )
+    .map_err(|e| format!("Failed to update library: {}", e))?;
+    Ok(())
+}
+
+/// Where a missing file went, if it was indexed again from a path that still exists
+fn relocated(store: &VectorStore, hash: &str) -> Result<Option<String>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare("SELECT path FROM document_sources WHERE hash = ?1 ORDER BY indexed_at DESC")
+        .map_err(|e| format!("Failed to read library: {}", e))?;
+    let paths = stmt
+        .query_map(params![hash], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to read library: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read library: {}", e))?;
+    Ok(paths.into_iter().find(|p| Path::new(p).exists()))
+}
+
+/// Recently opened documents, pinned ones first
+///
+/// Entries whose file has moved are pointed at its new location when the same
+/// content was indexed from there; otherwise they're flagged as missing.
+#[tauri::command]
+pub async fn list_recent_documents(
+    limit: Option<usize>,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<Vec<LibraryDocument>, AppError> {
+    log::info!("Listing recent documents");
+
+    let mut docs = {
+        let conn = store.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT l.path, l.hash, l.title, l.page_count, l.last_opened, l.pinned,
+                        EXISTS (SELECT 1 FROM embeddings e WHERE e.index_id = l.hash)
+                 FROM library_documents l
+                 ORDER BY l.pinned DESC, l.last_opened DESC
+                 LIMIT ?1",
+            )
+            .map_err(|e| format!("Failed to list library: {}", e))?;
+        let docs = stmt.query_map(params![limit.unwrap_or(DEFAULT_LIMIT) as i64], |row| {
+            Ok(LibraryDocument {
+                path: row.get(0)?,
+                hash: row.get(1)?,
+                title: row.get(2)?,
+                page_count: row.get::<_, Option<i64>>(3)?.map(|n| n as usize),
+                last_opened: row.get(4)?,
+                pinned: row.get(5)?,
+                index_status: if row.get(6)? { "indexed" } else { "not_indexed" },
+                missing: false,
+            })
+        })
+        .map_err(|e| format!("Failed to list library: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to list library: {}", e))?;
+        docs
+    };
+
+    for doc in docs.iter_mut().filter(|d| !Path::new(&d.path).exists()) {
+        let new_path = match doc.hash.as_deref() {
+            Some(hash) => relocated(&store, hash)?,
+            None => None,
+        };
+        let Some(new_path) = new_path else {
+            doc.missing = true;
+            continue;
+        };
+        log::info!("Library entry {} moved to {}", doc.path, new_path);
+        // If the new path already has its own entry, the stale one is just dropped
+        let conn = store.conn();
+        conn.execute(
+            "UPDATE OR IGNORE library_documents SET path = ?1 WHERE path = ?2",
+            params![new_path, doc.path],
+        )
+        .and_then(|_| conn.execute("DELETE FROM library_documents WHERE path = ?1", params![doc.path]))
+        .map_err(|e| format!("Failed to update library: {}", e))?;
+        doc.path = new_path;
+    }
+
+    // A relocated entry can duplicate one already listed under its new path
+    let mut seen = std::collections::HashSet::new();
+    docs.retain(|d| seen.insert(d.path.clone()));
+    Ok(docs)
+}
+
+/// Add a document opened in the viewer to the library (indexed files are added automatically)
+#[tauri::command]
+pub async fn add_to_library(
+    path: String,
+    title: Option<String>,
+    page_count: Option<usize>,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<(), AppError> {
+    log::info!("Adding {} to library", path);
+
+    let (hash, page_count) = {
+        let path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let hash = embedding_cache::hash_file(&path)?;
+            let is_pdf = Path::new(&path)
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+            let page_count = page_count.or_else(|| is_pdf.then(|| pdf::page_count(&path).ok()).flatten());
+            Ok::<_, String>((hash, page_count))
+        })
+        .await
+        .map_err(|e| format!("Hashing task failed: {}", e))??
+    };
+
+    Ok(record_open(&store, &path, Some(&hash), title.as_deref(), page_count)?)
+}
+
+/// Pin or unpin a document so it stays at the top of the recent list
+#[tauri::command]
+pub async fn pin_document(path: String, pinned: bool, store: tauri::State<'_, VectorStore>) -> Result<(), AppError> {
+    log::info!("Setting pinned={} for {}", pinned, path);
+
+    let updated = store
+        .conn()
+        .execute(
+            "UPDATE library_documents SET pinned = ?1 WHERE path = ?2",
+            params![pinned, path],
+        )
+        .map_err(|e| format!("Failed to pin document: {}", e))?;
+    if updated == 0 {
+        return Err(AppError::Other(format!("Document not in library: {}", path)));
+    }
+    Ok(())
+}
+
+/// Remove a document from the recent list; its file and index are kept
+#[tauri::command]
+pub async fn remove_from_library(path: String, store: tauri::State<'_, VectorStore>) -> Result<bool, AppError> {
+    log::info!("Removing {} from library", path);
+
+    let removed = store
+        .conn()
+        .execute("DELETE FROM library_documents WHERE path = ?1", params![path])
+        .map_err(|e| format!("Failed to remove document from library: {}", e))?;
+    Ok(removed > 0)
+}
@@ -0,0 +1,397 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::backend::{ChatOptions, ChatUsage, ChunkSink, GenerationOptions, LlmBackend};
+use crate::error::AppError;
+use crate::http;
+use crate::ollama::ChatMessage;
+use crate::ollama_chat::{self, ChatStreams};
+use crate::settings;
+
+/// Runs GGUF models in-process with llama.cpp, for users without Ollama
+///
+/// `model` is a path to a `.gguf` file or the name of one in the model
+/// directories (`gemma3:1b` looks for `gemma3-1b.gguf`): the `local_model_dir`
+/// setting, `models/` in the app data directory, then the models bundled with
+/// the app. The runtime is only compiled in with the `embedded-llm` feature.
+pub struct LocalBackend {
+    model_dirs: Vec<PathBuf>,
+}
+
+impl LocalBackend {
+    pub fn from_settings(app_handle: &tauri::AppHandle) -> Self {
+        let settings = settings::read_settings(app_handle);
+        let mut model_dirs = Vec::new();
+        if !settings.local_model_dir.trim().is_empty() {
+            model_dirs.push(PathBuf::from(settings.local_model_dir.trim()));
+        }
+        if let Ok(dir) = crate::portable::app_data_dir(app_handle) {
+            model_dirs.push(dir.join("models"));
+        }
+        if let Ok(dir) = app_handle.path().resource_dir() {
+            model_dirs.push(dir.join("models"));
+        }
+        Self { model_dirs }
+    }
+
+    fn resolve_model(&self, model: &str) -> Result<PathBuf, AppError> {
+        let direct = Path::new(model);
+        if direct.extension().is_some_and(|e| e.eq_ignore_ascii_case("gguf")) && direct.is_file() {
+            return Ok(direct.to_path_buf());
+        }
+
+        let file_name = format!("{}.gguf", model.replace([':', '/', '\\'], "-"));
+        self.model_dirs
+            .iter()
+            .map(|dir| dir.join(&file_name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                AppError::ModelNotFound(format!(
+                    "Model not found: {} (looked for {} in {})",
+                    model,
+                    file_name,
+                    self.model_dirs
+                        .iter()
+                        .map(|d| d.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })
+    }
+}
+
+#[async_trait]
+impl LlmBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "embedded"
+    }
+
+    async fn chat(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<String, AppError> {
+        let mut reply = String::new();
+        self.chat_stream(model, messages, options, &mut |content, _| reply.push_str(content))
+            .await?;
+        Ok(reply)
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_chunk: &mut ChunkSink<'_>,
+    ) -> Result<ChatUsage, AppError> {
+        let path = self.resolve_model(model)?;
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let (messages, options) = (messages.to_vec(), options.clone());
+
+        // Generation is blocking; pieces come back over the channel. Dropping the
+        // receiver (e.g. on cancel) makes the next send fail, which stops generation.
+        let task = tauri::async_runtime::spawn_blocking(move || {
+            engine::generate(&path, &messages, &options, |piece| sender.send(piece.to_string()).is_ok())
+        });
+        let started = std::time::Instant::now();
+        let mut pieces = 0;
+        while let Some(piece) = receiver.recv().await {
+            pieces += 1;
+            on_chunk(&piece, false);
+        }
+        task.await
+            .map_err(|e| AppError::Other(format!("Local generation task failed: {}", e)))??;
+        on_chunk("", true);
+        // Each piece is one sampled token; the prompt isn't counted separately
+        Ok(ChatUsage {
+            completion_tokens: Some(pieces),
+            eval: Some(started.elapsed()),
+            ..Default::default()
+        })
+    }
+
+    async fn embed(&self, model: &str, text: &str) -> Result<Vec<f64>, AppError> {
+        let path = self.resolve_model(model)?;
+        let text = text.to_string();
+        tauri::async_runtime::spawn_blocking(move || engine::embed(&path, &text))
+            .await
+            .map_err(|e| AppError::Other(format!("Local embedding task failed: {}", e)))?
+    }
+}
+
+#[cfg(feature = "embedded-llm")]
+mod engine {
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{AddBos, LlamaChatMessage, LlamaModel, Special};
+    use llama_cpp_2::sampling::LlamaSampler;
+    use std::num::NonZeroU32;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, OnceLock};
+
+    use super::*;
+
+    /// Context size when the request doesn't set `num_ctx`
+    const DEFAULT_CTX: u32 = 8192;
+    /// Context size for embeddings; longer chunks are truncated
+    const EMBEDDING_CTX: u32 = 2048;
+    /// Models kept loaded (a chat model and an embedding model)
+    const MAX_LOADED: usize = 2;
+
+    static BACKEND: OnceLock<Result<LlamaBackend, String>> = OnceLock::new();
+    static LOADED: Mutex<Vec<(PathBuf, Arc<LlamaModel>)>> = Mutex::new(Vec::new());
+
+    fn backend() -> Result<&'static LlamaBackend, AppError> {
+        BACKEND
+            .get_or_init(|| LlamaBackend::init().map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|e| AppError::Other(format!("Failed to initialize llama.cpp: {}", e)))
+    }
+
+    /// Load a model, reusing it if it's already loaded
+    fn load(path: &Path, gpu_layers: Option<i32>) -> Result<Arc<LlamaModel>, AppError> {
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, model)) = loaded.iter().find(|(p, _)| p == path) {
+            return Ok(model.clone());
+        }
+
+        log::info!("Loading local model {}", path.display());
+        // Offload everything by default; llama.cpp caps this at the model's layer count
+        let layers = gpu_layers.map(|n| n.max(0) as u32).unwrap_or(u32::MAX);
+        let params = LlamaModelParams::default().with_n_gpu_layers(layers);
+        let model = Arc::new(
+            LlamaModel::load_from_file(backend()?, path, &params)
+                .map_err(|e| AppError::Other(format!("Failed to load model {}: {}", path.display(), e)))?,
+        );
+        if loaded.len() == MAX_LOADED {
+            loaded.remove(0);
+        }
+        loaded.push((path.to_path_buf(), model.clone()));
+        Ok(model)
+    }
+
+    fn llama_error(what: &str, e: impl std::fmt::Display) -> AppError {
+        AppError::Other(format!("{} failed: {}", what, e))
+    }
+
+    /// Bytes of the valid UTF-8 prefix of `buffer`; a token can end mid-character
+    fn utf8_prefix(buffer: &[u8]) -> usize {
+        match std::str::from_utf8(buffer) {
+            Ok(_) => buffer.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            // Invalid bytes won't become valid; let them through lossily
+            Err(_) => buffer.len(),
+        }
+    }
+
+    /// Run a chat completion, calling `on_piece` with text as it's generated
+    ///
+    /// Stops at an end-of-generation token, a stop string, `max_tokens`, or when
+    /// `on_piece` returns false.
+    pub fn generate(
+        path: &Path,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        mut on_piece: impl FnMut(&str) -> bool,
+    ) -> Result<(), AppError> {
+        let model = load(path, options.generation.num_gpu)?;
+        let n_ctx = options.generation.num_ctx.unwrap_or(DEFAULT_CTX);
+        let mut ctx = model
+            .new_context(backend()?, LlamaContextParams::default().with_n_ctx(NonZeroU32::new(n_ctx)))
+            .map_err(|e| llama_error("Creating context", e))?;
+
+        let template = model
+            .chat_template(None)
+            .map_err(|e| llama_error("Reading chat template", e))?;
+        let chat = messages
+            .iter()
+            .map(|m| LlamaChatMessage::new(m.role.clone(), m.content.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| llama_error("Building chat", e))?;
+        let prompt = model
+            .apply_chat_template(&template, &chat, true)
+            .map_err(|e| llama_error("Applying chat template", e))?;
+        let tokens = model
+            .str_to_token(&prompt, AddBos::Always)
+            .map_err(|e| llama_error("Tokenizing prompt", e))?;
+        if tokens.len() >= n_ctx as usize {
+            return Err(AppError::Other(format!(
+                "Prompt is {} tokens, more than the {} token context",
+                tokens.len(),
+                n_ctx
+            )));
+        }
+
+        let mut batch = LlamaBatch::new(n_ctx as usize, 1);
+        let last = tokens.len() - 1;
+        for (i, token) in tokens.iter().enumerate() {
+            batch
+                .add(*token, i as i32, &[0], i == last)
+                .map_err(|e| llama_error("Building batch", e))?;
+        }
+        ctx.decode(&mut batch).map_err(|e| llama_error("Decoding prompt", e))?;
+
+        let seed = options.generation.seed.unwrap_or_else(crate::vectorstore::now_secs) as u32;
+        let mut sampler = LlamaSampler::chain_simple([
+            LlamaSampler::top_p(options.top_p, 1),
+            LlamaSampler::temp(options.temperature),
+            LlamaSampler::dist(seed),
+        ]);
+
+        let stops = &options.generation.stop;
+        let mut position = tokens.len() as i32;
+        let mut pending = Vec::new();
+        let mut generated = String::new();
+
+        for _ in 0..options.max_tokens {
+            if position as u32 >= n_ctx {
+                log::warn!("Local model reached the end of its context");
+                break;
+            }
+            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            sampler.accept(token);
+            if model.is_eog_token(token) {
+                break;
+            }
+
+            pending.extend(
+                model
+                    .token_to_bytes(token, Special::Tokenize)
+                    .map_err(|e| llama_error("Decoding token", e))?,
+            );
+            let ready = utf8_prefix(&pending);
+            let piece = String::from_utf8_lossy(&pending[..ready]).into_owned();
+            pending.drain(..ready);
+
+            let start = generated.len();
+            generated.push_str(&piece);
+            if let Some(stop_at) = stops.iter().filter_map(|s| generated.find(s.as_str())).min() {
+                if stop_at > start {
+                    on_piece(&generated[start..stop_at]);
+                }
+                break;
+            }
+            if !piece.is_empty() && !on_piece(&piece) {
+                log::info!("Local generation stopped by receiver");
+                break;
+            }
+
+            batch.clear();
+            batch
+                .add(token, position, &[0], true)
+                .map_err(|e| llama_error("Building batch", e))?;
+            ctx.decode(&mut batch).map_err(|e| llama_error("Decoding", e))?;
+            position += 1;
+        }
+        Ok(())
+    }
+
+    /// Mean-pooled, L2-normalized embedding of `text`
+    pub fn embed(path: &Path, text: &str) -> Result<Vec<f64>, AppError> {
+        let model = load(path, None)?;
+        let params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(EMBEDDING_CTX))
+            .with_n_batch(EMBEDDING_CTX)
+            .with_embeddings(true);
+        let mut ctx = model
+            .new_context(backend()?, params)
+            .map_err(|e| llama_error("Creating context", e))?;
+
+        let mut tokens = model
+            .str_to_token(text, AddBos::Always)
+            .map_err(|e| llama_error("Tokenizing text", e))?;
+        tokens.truncate(EMBEDDING_CTX as usize);
+
+        let mut batch = LlamaBatch::new(EMBEDDING_CTX as usize, 1);
+        batch
+            .add_sequence(&tokens, 0, false)
+            .map_err(|e| llama_error("Building batch", e))?;
+        ctx.decode(&mut batch).map_err(|e| llama_error("Decoding text", e))?;
+
+        let embedding = ctx
+            .embeddings_seq_ith(0)
+            .map_err(|e| llama_error("Reading embedding", e))?;
+        let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt().max(f32::EPSILON);
+        Ok(embedding.iter().map(|v| (v / norm) as f64).collect())
+    }
+}
+
+#[cfg(not(feature = "embedded-llm"))]
+mod engine {
+    use std::path::Path;
+
+    use super::*;
+
+    fn unavailable() -> AppError {
+        AppError::Unsupported(
+            "This build of PrivatePDF doesn't include the embedded model runtime; use Ollama or an OpenAI-compatible server"
+                .to_string(),
+        )
+    }
+
+    pub fn generate(
+        _path: &Path,
+        _messages: &[ChatMessage],
+        _options: &ChatOptions,
+        _on_piece: impl FnMut(&str) -> bool,
+    ) -> Result<(), AppError> {
+        Err(unavailable())
+    }
+
+    pub fn embed(_path: &Path, _text: &str) -> Result<Vec<f64>, AppError> {
+        Err(unavailable())
+    }
+}
+
+/// Chat with a bundled GGUF model in-process (streaming)
+///
+/// Works without Ollama; chunks are emitted as `ollama_stream_chunk` like
+/// `ollama_chat_stream`, and `cancel_chat_stream` stops it the same way.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn local_chat_stream(
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    options: Option<GenerationOptions>,
+    request_id: Option<String>,
+    conversation_id: Option<String>,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    streams: tauri::State<'_, ChatStreams>,
+) -> Result<(), AppError> {
+    log::info!("Local streaming chat request: model={}, messages={}", model, messages.len());
+
+    let backend = LocalBackend::from_settings(&app_handle);
+    ollama_chat::run_chat_stream(
+        &backend,
+        &model,
+        messages,
+        ChatOptions {
+            temperature: temperature.unwrap_or(0.2),
+            max_tokens: max_tokens.unwrap_or(4096),
+            top_p: top_p.unwrap_or(0.9),
+            generation: options.unwrap_or_default(),
+            timeout: http::read_timeout(),
+        },
+        request_id,
+        conversation_id,
+        &window,
+        &app_handle,
+        &streams,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Embed text with a bundled GGUF embedding model in-process
+#[tauri::command]
+pub async fn local_embedding(model: String, text: String, app_handle: tauri::AppHandle) -> Result<Vec<f64>, AppError> {
+    log::info!("Local embedding request: model={}, text_len={}", model, text.len());
+
+    let embedding = LocalBackend::from_settings(&app_handle).embed(&model, &text).await?;
+
+    log::info!("Embedding generated: {} dimensions", embedding.len());
+    Ok(embedding)
+}
@@ -0,0 +1,123 @@
+use log::{LevelFilter, Metadata};
+use std::fs;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::error::AppError;
+use crate::settings;
+
+/// Log level used until settings are read, and when `log_level` is empty
+pub const DEFAULT_LOG_LEVEL: &str = "warn";
+/// Size at which the log file is rotated
+pub const MAX_FILE_BYTES: u128 = 5 * 1024 * 1024;
+/// Log files kept in the log directory, including the current one
+const MAX_LOG_FILES: usize = 5;
+
+/// Parsed `log_level` setting, consulted by the log plugin for every record
+static FILTER: RwLock<Option<LogFilter>> = RwLock::new(None);
+
+/// A default level plus per-module overrides, e.g. `info,ollama=debug,reqwest=warn`
+#[derive(Debug, Clone, PartialEq)]
+struct LogFilter {
+    default: LevelFilter,
+    /// Module name and level, longest name first so the most specific match wins
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    fn parse(directives: &str) -> Result<Self, String> {
+        let mut filter = LogFilter {
+            default: LevelFilter::from_str(DEFAULT_LOG_LEVEL).unwrap_or(LevelFilter::Warn),
+            modules: Vec::new(),
+        };
+        let level = |value: &str| {
+            LevelFilter::from_str(value.trim()).map_err(|_| format!("Invalid log level: {:?}", value.trim()))
+        };
+
+        for directive in directives.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, value)) if !module.trim().is_empty() => {
+                    filter.modules.push((module.trim().to_string(), level(value)?));
+                }
+                Some(_) => return Err(format!("Invalid log directive: {:?}", directive)),
+                None => filter.default = level(directive)?,
+            }
+        }
+        filter.modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Ok(filter)
+    }
+
+    /// Level for a record target such as `app_lib::ollama` or `reqwest::connect`
+    ///
+    /// A module matches any run of whole path segments, so `ollama` matches our
+    /// module and `reqwest` matches the crate and all its submodules.
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let target = format!("::{}::", target);
+        self.modules
+            .iter()
+            .find(|(module, _)| target.contains(&format!("::{}::", module)))
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+}
+
+/// Whether a record passes the configured levels; used as the log plugin's filter
+pub fn enabled(metadata: &Metadata) -> bool {
+    match FILTER.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(filter) => metadata.level() <= filter.level_for(metadata.target()),
+        None => metadata.level() <= LevelFilter::Warn,
+    }
+}
+
+/// Parse and apply a `log_level` setting at runtime
+pub fn apply(directives: &str) -> Result<(), String> {
+    let filter = LogFilter::parse(directives)?;
+    log::set_max_level(filter.max_level());
+    *FILTER.write().unwrap_or_else(|e| e.into_inner()) = Some(filter);
+    Ok(())
+}
+
+/// Delete the oldest rotated log files beyond `MAX_LOG_FILES`
+pub fn prune_rotated_logs(app_handle: &tauri::AppHandle) {
+    let Ok(dir) = crate::portable::app_log_dir(app_handle) else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+    let mut logs: Vec<(std::time::SystemTime, std::path::PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "log"))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    logs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    for (_, path) in logs.into_iter().skip(MAX_LOG_FILES) {
+        match fs::remove_file(&path) {
+            Ok(()) => log::info!("Removed old log file {}", path.display()),
+            Err(e) => log::warn!("Failed to remove old log file {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Change log levels, e.g. `info` or `warn,ollama=debug`
+///
+/// Takes effect immediately. With `persist` the value is also saved as the
+/// `log_level` setting so it survives a restart.
+#[tauri::command]
+pub async fn set_log_level(app_handle: tauri::AppHandle, level: String, persist: Option<bool>) -> Result<(), AppError> {
+    apply(&level).map_err(AppError::Parse)?;
+    log::warn!("Log level set to {:?}", level);
+
+    if persist.unwrap_or(false) {
+        let mut current = settings::read_settings(&app_handle);
+        current.log_level = level;
+        settings::save_settings(app_handle, current).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,417 @@
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::BufRead;
+use tauri::Manager;
+
+use crate::cli;
+use crate::rag;
+use crate::settings;
+use crate::summary::{self, SummaryStyle};
+use crate::vectorstore::VectorStore;
+use crate::workspace;
+
+/// Model Context Protocol revision the server speaks
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC request or notification; notifications have no id
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, PartialEq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// What to do with one line from the client
+#[derive(Debug)]
+enum Route {
+    /// Answered without touching the documents
+    Reply(Value, Result<Value, RpcError>),
+    /// A `tools/call` to run in the background
+    Call { id: Value, name: String, arguments: Value },
+    /// A notification; nothing is sent back
+    Ignore,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchArgs {
+    query: String,
+    document_id: Option<String>,
+    top_k: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RetrieveArgs {
+    document_id: String,
+    first_page: Option<u32>,
+    last_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SummarizeArgs {
+    document_id: String,
+    #[serde(default)]
+    style: SummaryStyle,
+}
+
+#[derive(Debug, Deserialize)]
+struct AskArgs {
+    document_id: String,
+    question: String,
+    top_k: Option<usize>,
+}
+
+/// The tools offered to clients, as listed by `tools/list`
+fn tools() -> Value {
+    json!([
+        {
+            "name": "list_documents",
+            "description": "List the documents indexed in PrivatePDF, with the id the other tools take.",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "search",
+            "description": "Find the passages most relevant to a query, in one document or in all of them.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "document_id": { "type": "string", "description": "Search only this document" },
+                    "top_k": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "retrieve",
+            "description": "Read the indexed text of a document, or of a range of its pages.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "document_id": { "type": "string" },
+                    "first_page": { "type": "integer", "minimum": 1 },
+                    "last_page": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["document_id"]
+            }
+        },
+        {
+            "name": "summarize",
+            "description": "Summarize a whole document with the local model.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "document_id": { "type": "string" },
+                    "style": { "type": "string", "enum": ["brief", "detailed", "bullets"] }
+                },
+                "required": ["document_id"]
+            }
+        },
+        {
+            "name": "ask",
+            "description": "Answer a question from a document with the local model, citing its pages.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "document_id": { "type": "string" },
+                    "question": { "type": "string" },
+                    "top_k": { "type": "integer", "minimum": 1 }
+                },
+                "required": ["document_id", "question"]
+            }
+        }
+    ])
+}
+
+fn is_tool(name: &str) -> bool {
+    tools()
+        .as_array()
+        .is_some_and(|tools| tools.iter().any(|tool| tool["name"] == name))
+}
+
+/// Decide how to answer one line read from the client
+fn route(line: &str) -> Route {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => return Route::Reply(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string()))),
+    };
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => return Route::Reply(id, Err(RpcError::new(INVALID_REQUEST, e.to_string()))),
+    };
+    let Some(id) = request.id else {
+        return Route::Ignore;
+    };
+
+    let result = match request.method.as_str() {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "privatepdf", "version": env!("CARGO_PKG_VERSION") }
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => match serde_json::from_value::<CallParams>(request.params) {
+            Ok(call) if is_tool(&call.name) => {
+                return Route::Call {
+                    id,
+                    name: call.name,
+                    arguments: call.arguments,
+                }
+            }
+            Ok(call) => Err(RpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", call.name))),
+            Err(e) => Err(RpcError::new(INVALID_PARAMS, e.to_string())),
+        },
+        method => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    };
+    Route::Reply(id, result)
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+    }
+}
+
+fn arguments<T: serde::de::DeserializeOwned>(arguments: Value) -> Result<T, String> {
+    // Clients may send no arguments at all for tools without required ones
+    let arguments = if arguments.is_null() { json!({}) } else { arguments };
+    serde_json::from_value(arguments).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+fn to_text(value: &impl serde::Serialize) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize the result: {}", e))
+}
+
+/// Run one of the `tools()` and return its text
+async fn run_tool(app_handle: &tauri::AppHandle, name: &str, args: Value) -> Result<String, String> {
+    let store = app_handle.state::<VectorStore>();
+    match name {
+        "list_documents" => to_text(&store.list()?),
+        "search" => {
+            let args: SearchArgs = arguments(args)?;
+            let top_k = args.top_k.unwrap_or(workspace::DEFAULT_TOP_K);
+            let hits = workspace::search_library(app_handle, &store, args.document_id.as_deref(), args.query, top_k).await?;
+            to_text(&hits)
+        }
+        "retrieve" => {
+            let args: RetrieveArgs = arguments(args)?;
+            if store.index_info(&args.document_id)?.is_none() {
+                return Err(format!("No document {}", args.document_id));
+            }
+            match (args.first_page, args.last_page) {
+                (None, None) => Ok(store.chunk_texts(&args.document_id)?.join("\n\n")),
+                (first, last) => {
+                    let first = first.unwrap_or(1);
+                    let last = last.unwrap_or(first);
+                    let chunks = store.page_chunks(&args.document_id, first, last)?;
+                    if chunks.is_empty() {
+                        return Err(format!("No indexed text on pages {}-{}", first, last));
+                    }
+                    Ok(chunks
+                        .iter()
+                        .map(|chunk| match chunk.page {
+                            Some(page) => format!("[p. {}]\n{}", page, chunk.text),
+                            None => chunk.text.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n\n"))
+                }
+            }
+        }
+        "summarize" => {
+            let args: SummarizeArgs = arguments(args)?;
+            let settings = settings::read_settings_for(app_handle, None, Some(&args.document_id));
+            let summary = summary::summarize(app_handle, &settings, &store, &args.document_id, args.style, |_, _, _| {}).await?;
+            to_text(&summary)
+        }
+        "ask" => {
+            let args: AskArgs = arguments(args)?;
+            let settings = settings::read_settings_for(app_handle, None, Some(&args.document_id));
+            let answer = rag::answer(app_handle, &settings, &store, &args.document_id, &args.question, args.top_k).await?;
+            to_text(&answer)
+        }
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+/// Answer a `tools/call`; failures of the tool itself are results the model can read
+async fn call(app_handle: tauri::AppHandle, id: Value, name: String, args: Value) -> Value {
+    log::info!("MCP tool call: {}", name);
+    let (text, is_error) = match run_tool(&app_handle, &name, args).await {
+        Ok(text) => (text, false),
+        Err(e) => {
+            log::warn!("MCP tool {} failed: {}", name, e);
+            (e, true)
+        }
+    };
+    response(id, Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })))
+}
+
+fn send(message: &Value) {
+    // stdout carries nothing but protocol messages; logs go to stderr
+    println!("{}", message);
+}
+
+/// Serve MCP over stdin and stdout until the client closes stdin; returns the exit code
+///
+/// Messages are newline-delimited JSON-RPC. Tool calls run concurrently, so a
+/// summary doesn't hold up a search sent after it.
+async fn serve(app_handle: &tauri::AppHandle) -> i32 {
+    let settings = settings::read_settings(app_handle);
+    if !settings.mcp_server {
+        eprintln!("The MCP server is turned off; enable it in PrivatePDF's settings first");
+        return 2;
+    }
+    // Listing and reading documents work without a model server
+    if let Err(e) = cli::ensure_server(app_handle, &settings).await {
+        log::warn!("{}", e);
+    }
+
+    // Blocking reads stay off the async runtime
+    let (lines_tx, mut lines) = tokio::sync::mpsc::unbounded_channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if lines_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    log::info!("MCP server ready on stdio");
+    let mut calls = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            line = lines.recv() => {
+                let Some(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match route(&line) {
+                    Route::Reply(id, result) => send(&response(id, result)),
+                    Route::Call { id, name, arguments } => calls.push(call(app_handle.clone(), id, name, arguments)),
+                    Route::Ignore => {}
+                }
+            }
+            Some(reply) = calls.next(), if !calls.is_empty() => send(&reply),
+        }
+    }
+    // Calls already sent still get their answer
+    while let Some(reply) = calls.next().await {
+        send(&reply);
+    }
+    log::info!("MCP client disconnected");
+    0
+}
+
+/// Serve MCP in the background and exit when the client disconnects
+pub fn start(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let code = serve(&app_handle).await;
+        app_handle.exit(code);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(line: &str) -> (Value, Result<Value, RpcError>) {
+        match route(line) {
+            Route::Reply(id, result) => (id, result),
+            other => panic!("expected a reply, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn initialize_announces_tools() {
+        let (id, result) = reply(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#);
+        assert_eq!(id, json!(1));
+        let result = result.unwrap();
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSION);
+        assert!(result["capabilities"]["tools"].is_object());
+    }
+
+    #[test]
+    fn every_tool_is_listed_with_a_schema() {
+        let (_, result) = reply(r#"{"jsonrpc":"2.0","id":"a","method":"tools/list"}"#);
+        let tools = result.unwrap()["tools"].as_array().unwrap().clone();
+        let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["list_documents", "search", "retrieve", "summarize", "ask"]);
+        assert!(tools.iter().all(|t| t["inputSchema"]["type"] == "object"));
+    }
+
+    #[test]
+    fn tool_calls_are_routed_to_the_tools() {
+        match route(r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"search","arguments":{"query":"revenue"}}}"#) {
+            Route::Call { id, name, arguments } => {
+                assert_eq!(id, json!(2));
+                assert_eq!(name, "search");
+                let args: SearchArgs = super::arguments(arguments).unwrap();
+                assert_eq!(args.query, "revenue");
+                assert!(args.document_id.is_none());
+            }
+            other => panic!("expected a call, got {:?}", other),
+        }
+        let (_, result) = reply(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"delete"}}"#);
+        assert_eq!(result.unwrap_err().code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn bad_messages_get_json_rpc_errors() {
+        let (id, result) = reply("{not json");
+        assert_eq!(id, Value::Null);
+        assert_eq!(result.unwrap_err().code, PARSE_ERROR);
+
+        let (id, result) = reply(r#"{"jsonrpc":"2.0","id":4,"method":"resources/list"}"#);
+        assert_eq!(id, json!(4));
+        assert_eq!(result.unwrap_err().code, METHOD_NOT_FOUND);
+
+        let (_, result) = reply(r#"{"jsonrpc":"2.0","id":5}"#);
+        assert_eq!(result.unwrap_err().code, INVALID_REQUEST);
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        assert!(matches!(route(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#), Route::Ignore));
+    }
+
+    #[test]
+    fn summary_style_defaults_when_missing() {
+        let args: SummarizeArgs = arguments(json!({ "document_id": "abc" })).unwrap();
+        assert!(matches!(args.style, SummaryStyle::Brief));
+    }
+}
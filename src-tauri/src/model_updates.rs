@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::context_window::ContextWindows;
+use crate::error::AppError;
+use crate::http::{self, RetryExt};
+use crate::ollama_models;
+use crate::settings;
+use crate::vectorstore::now_secs;
+
+/// Registry Ollama pulls unqualified model names from
+const REGISTRY: &str = "registry.ollama.ai";
+/// The manifest format Ollama stores; its SHA-256 is the digest `/api/tags` reports
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+/// Wait after startup before a scheduled check, so it doesn't compete with launch
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateStatus {
+    UpToDate,
+    UpdateAvailable,
+    /// Not on the Ollama registry: created locally, or pulled from another registry
+    NotInRegistry,
+    /// The registry couldn't be reached or answered with an error
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUpdate {
+    name: String,
+    status: UpdateStatus,
+    local_digest: Option<String>,
+    remote_digest: Option<String>,
+    error: Option<String>,
+}
+
+/// When the last scheduled check ran, kept next to settings.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckState {
+    checked_at: i64,
+}
+
+/// Registry path of a model name: `llama3.2` is `library/llama3.2:latest`
+///
+/// Returns None for models from other registries (`hf.co/...`), which can't be checked here.
+fn manifest_url(name: &str) -> Option<String> {
+    let name = name.strip_prefix(&format!("{}/", REGISTRY)).unwrap_or(name);
+    let (repo, tag) = name.rsplit_once(':').filter(|(_, tag)| !tag.contains('/')).unwrap_or((name, "latest"));
+    let repo = match repo.split('/').collect::<Vec<_>>()[..] {
+        [model] => format!("library/{}", model),
+        [namespace, model] if !namespace.contains('.') => format!("{}/{}", namespace, model),
+        _ => return None,
+    };
+    Some(format!("https://{}/v2/{}/manifests/{}", REGISTRY, repo, tag))
+}
+
+/// Digest of the model's current manifest on the registry, or None if it isn't there
+async fn remote_digest(name: &str) -> Result<Option<String>, AppError> {
+    let Some(url) = manifest_url(name) else {
+        return Ok(None);
+    };
+    let response = http::get(&url)?
+        .header(reqwest::header::ACCEPT, MANIFEST_MEDIA_TYPE)
+        .timeout(http::status_timeout())
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::request("Registry request", e))?;
+
+    if matches!(response.status(), reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::UNAUTHORIZED) {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(AppError::status("Registry request", response.status()));
+    }
+    let manifest = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::request("Registry request", e))?;
+    Ok(Some(format!("{:x}", Sha256::digest(&manifest))))
+}
+
+async fn check_model(name: String, local_digest: Option<String>) -> ModelUpdate {
+    let local = local_digest.as_deref().map(|d| d.trim_start_matches("sha256:").to_string());
+    let (status, remote_digest, error) = match remote_digest(&name).await {
+        Ok(None) => (UpdateStatus::NotInRegistry, None, None),
+        Ok(Some(remote)) => {
+            let status = match &local {
+                Some(local) if *local == remote => UpdateStatus::UpToDate,
+                Some(_) => UpdateStatus::UpdateAvailable,
+                None => UpdateStatus::Unknown,
+            };
+            (status, Some(remote), None)
+        }
+        Err(e) => {
+            log::warn!("Failed to check {} for updates: {}", name, e);
+            (UpdateStatus::Unknown, None, Some(e.to_string()))
+        }
+    };
+    ModelUpdate {
+        name,
+        status,
+        local_digest: local,
+        remote_digest,
+        error,
+    }
+}
+
+async fn check_all(app_handle: &tauri::AppHandle) -> Result<Vec<ModelUpdate>, AppError> {
+    let models = ollama_models::list_ollama_models(app_handle.clone()).await?;
+    let checks = models.into_iter().map(|m| check_model(m.name, m.digest));
+    let updates = futures::future::join_all(checks).await;
+
+    let available = updates.iter().filter(|u| u.status == UpdateStatus::UpdateAvailable).count();
+    log::info!("Checked {} models for updates: {} available", updates.len(), available);
+    Ok(updates)
+}
+
+fn state_path(app_handle: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+    Some(crate::portable::app_data_dir(app_handle).ok()?.join("model_update_check.json"))
+}
+
+/// Check installed models for updates every `model_update_check_days` days
+///
+/// Off by default since it contacts the Ollama registry. Emits
+/// `model_updates_available` with the models that have a newer version; pulling
+/// them is left to the user.
+pub fn check_in_background(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            let days = settings::read_settings(&app_handle).model_update_check_days;
+            if days == 0 {
+                return;
+            }
+            let interval = days as i64 * SECS_PER_DAY;
+            let path = state_path(&app_handle);
+            let last = path
+                .as_ref()
+                .and_then(|p| std::fs::read_to_string(p).ok())
+                .and_then(|json| serde_json::from_str::<CheckState>(&json).ok())
+                .unwrap_or_default();
+
+            let due_in = last.checked_at + interval - now_secs();
+            if due_in > 0 {
+                tokio::time::sleep(Duration::from_secs(due_in as u64)).await;
+                continue;
+            }
+
+            match check_all(&app_handle).await {
+                Ok(updates) => {
+                    let available: Vec<_> = updates
+                        .into_iter()
+                        .filter(|u| u.status == UpdateStatus::UpdateAvailable)
+                        .collect();
+                    if !available.is_empty() {
+                        app_handle.emit("model_updates_available", available).ok();
+                    }
+                }
+                // Ollama isn't running; try again at the next interval
+                Err(e) => log::warn!("Scheduled model update check failed: {}", e),
+            }
+            let state = CheckState { checked_at: now_secs() };
+            if let (Some(path), Ok(json)) = (path, serde_json::to_string(&state)) {
+                if let Err(e) = std::fs::write(&path, json) {
+                    log::warn!("Failed to save model update check time: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Compare installed models with the Ollama registry and report which have newer versions
+#[tauri::command]
+pub async fn check_model_updates(app_handle: tauri::AppHandle) -> Result<Vec<ModelUpdate>, AppError> {
+    log::info!("Checking installed models for updates");
+    check_all(&app_handle).await
+}
+
+/// Pull the latest version of an installed model
+///
+/// Only changed layers are downloaded. Progress is emitted as
+/// `model_download_progress`, like `download_ollama_model`.
+#[tauri::command]
+pub async fn update_model(
+    name: String,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    log::info!("Updating model {}", name);
+    app_handle.state::<ContextWindows>().forget(&name);
+    ollama_models::download_ollama_model(name, window, app_handle).await
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::AppError;
+
+/// Folder inside the vault used when none is given
+const DEFAULT_FOLDER: &str = "PrivatePDF";
+const BLOCK_START: &str = "<!-- privatepdf:start -->";
+const BLOCK_END: &str = "<!-- privatepdf:end -->";
+
+#[derive(Debug, Deserialize)]
+pub struct ObsidianNote {
+    pub title: String,
+    /// "summary", "qa" or "highlights"
+    pub kind: String,
+    /// Source document name, written as a [[wiki-link]]
+    pub document: Option<String>,
+    /// Markdown body of the note
+    pub body: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ObsidianExportSummary {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    /// Notes that exist but weren't written by PrivatePDF, left untouched
+    skipped: Vec<String>,
+    files: Vec<String>,
+    is_obsidian_vault: bool,
+}
+
+/// Strip characters Obsidian doesn't allow in note names
+fn note_file_name(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| match c {
+            '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => ' ',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    if cleaned.is_empty() {
+        "Untitled".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// A YAML double-quoted scalar; JSON string escaping is valid YAML, so colons,
+/// quotes and newlines in titles and tags can't break the frontmatter
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Collapse whitespace, newlines included, to single spaces
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn frontmatter(note: &ObsidianNote) -> String {
+    let mut tags = vec!["privatepdf".to_string(), format!("privatepdf/{}", note.kind)];
+    // Obsidian tags can't contain whitespace
+    tags.extend(note.tags.iter().map(|t| t.split_whitespace().collect::<Vec<_>>().join("-")));
+
+    let mut fm = String::from("---\n");
+    fm.push_str(&format!("title: {}\n", yaml_string(&single_line(&note.title))));
+    fm.push_str(&format!("type: {}\n", yaml_string(&note.kind)));
+    if let Some(document) = &note.document {
+        fm.push_str(&format!("source: {}\n", yaml_string(&format!("[[{}]]", note_file_name(document)))));
+    }
+    fm.push_str("tags:\n");
+    for tag in tags.iter().filter(|t| !t.is_empty()) {
+        fm.push_str(&format!("  - {}\n", yaml_string(tag)));
+    }
+    fm.push_str("---\n");
+    fm
+}
+
+/// The folder notes are written to, which must stay inside the vault
+fn note_folder(vault: &Path, folder: Option<&str>) -> Result<PathBuf, String> {
+    let folder = folder.map(str::trim).filter(|f| !f.is_empty()).unwrap_or(DEFAULT_FOLDER);
+    let relative = Path::new(folder);
+    // Rejects absolute paths, drive prefixes, "." and ".."
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("The note folder must be a folder inside the vault: {}", folder));
+    }
+    Ok(vault.join(relative))
+}
+
+/// The part of the note PrivatePDF owns; re-exports only replace this block
+fn managed_block(note: &ObsidianNote) -> String {
+    let mut block = String::from(BLOCK_START);
+    block.push('\n');
+    if let Some(document) = &note.document {
+        block.push_str(&format!("Source: [[{}]]\n\n", note_file_name(document)));
+    }
+    block.push_str(note.body.trim());
+    block.push('\n');
+    block.push_str(BLOCK_END);
+    block
+}
+
+/// Replace the managed block in an existing note, keeping the user's own edits around it
+fn replace_block(existing: &str, block: &str) -> Option<String> {
+    let start = existing.find(BLOCK_START)?;
+    let end = existing[start..].find(BLOCK_END)? + start + BLOCK_END.len();
+    Some(format!("{}{}{}", &existing[..start], block, &existing[end..]))
+}
+
+/// Write summaries, Q&A notes and highlights into an Obsidian vault
+///
+/// Notes are matched by file name. Re-exporting only rewrites the PrivatePDF block
+/// inside an existing note, so anything the user added outside it is kept.
+#[tauri::command]
+pub async fn export_to_obsidian(
+    vault_path: String,
+    folder: Option<String>,
+    notes: Vec<ObsidianNote>,
+) -> Result<ObsidianExportSummary, AppError> {
+    log::info!("Exporting {} notes to Obsidian vault: {}", notes.len(), vault_path);
+
+    let vault = Path::new(&vault_path);
+    if !vault.is_dir() {
+        return Err(AppError::Io(format!("Vault folder does not exist: {}", vault_path)));
+    }
+
+    let target = note_folder(vault, folder.as_deref())?;
+    fs::create_dir_all(&target).map_err(|e| format!("Failed to create note folder: {}", e))?;
+
+    let mut summary = ObsidianExportSummary {
+        is_obsidian_vault: vault.join(".obsidian").is_dir(),
+        ..Default::default()
+    };
+
+    for note in &notes {
+        let path = target.join(format!("{}.md", note_file_name(&note.title)));
+        let block = managed_block(note);
+
+        let content = if path.exists() {
+            let existing = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            match replace_block(&existing, &block) {
+                Some(updated) if updated == existing => {
+                    summary.unchanged += 1;
+                    continue;
+                }
+                Some(updated) => {
+                    summary.updated += 1;
+                    updated
+                }
+                None => {
+                    log::warn!("Skipping {}: not a PrivatePDF note", path.display());
+                    summary.skipped.push(path.display().to_string());
+                    continue;
+                }
+            }
+        } else {
+            summary.created += 1;
+            format!("{}\n# {}\n\n{}\n", frontmatter(note), single_line(&note.title), block)
+        };
+
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        summary.files.push(path.display().to_string());
+    }
+
+    log::info!(
+        "Obsidian export done: {} created, {} updated, {} unchanged, {} skipped",
+        summary.created,
+        summary.updated,
+        summary.unchanged,
+        summary.skipped.len()
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, tags: &[&str]) -> ObsidianNote {
+        ObsidianNote {
+            title: title.to_string(),
+            kind: "summary".to_string(),
+            document: Some("Report: 2024".to_string()),
+            body: "Body".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn frontmatter_quotes_values() {
+        let fm = frontmatter(&note("Q&A: \"costs\"\nand more", &["two words", "a: b"]));
+        assert!(fm.contains("title: \"Q&A: \\\"costs\\\" and more\"\n"), "{}", fm);
+        assert!(fm.contains("source: \"[[Report 2024]]\"\n"), "{}", fm);
+        assert!(fm.contains("  - \"two-words\"\n"), "{}", fm);
+        assert!(fm.contains("  - \"a:-b\"\n"), "{}", fm);
+        // Every line between the fences is a key or a list item
+        assert!(fm
+            .lines()
+            .filter(|l| *l != "---")
+            .all(|l| l.starts_with("  - ") || l.contains(": ") || l == "tags:"), "{}", fm);
+    }
+
+    #[test]
+    fn note_folder_stays_in_the_vault() {
+        let vault = Path::new("vault");
+        assert_eq!(note_folder(vault, None).unwrap(), vault.join(DEFAULT_FOLDER));
+        assert_eq!(note_folder(vault, Some("Papers/2024")).unwrap(), vault.join("Papers/2024"));
+        assert!(note_folder(vault, Some("../outside")).is_err());
+        assert!(note_folder(vault, Some("Papers/../../outside")).is_err());
+        assert!(note_folder(vault, Some("/etc")).is_err());
+        assert!(note_folder(vault, Some("./Papers")).is_err());
+    }
+
+    #[test]
+    fn managed_block_is_replaced_in_place() {
+        let existing = format!("# Title\n\nMy notes\n{}\nold\n{}\nMore notes\n", BLOCK_START, BLOCK_END);
+        let block = managed_block(&note("Title", &[]));
+        let updated = replace_block(&existing, &block).unwrap();
+        assert!(updated.starts_with("# Title\n\nMy notes\n"));
+        assert!(updated.ends_with("\nMore notes\n"));
+        assert!(updated.contains("Body"));
+        assert!(!updated.contains("old"));
+        assert!(replace_block("no block here", &block).is_none());
+    }
+}
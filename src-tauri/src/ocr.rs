@@ -0,0 +1,268 @@
+use serde::Serialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::language;
+use crate::pdf;
+use crate::progress::ProgressThrottle;
+use crate::secure_delete;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Render resolution; 300 DPI is what Tesseract is tuned for
+const RENDER_DPI: &str = "300";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrPage {
+    page_number: u32,
+    text: String,
+}
+
+fn tool_command(program: &Path) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// Find an external tool on PATH or in its usual install locations
+fn find_tool(name: &str) -> Option<PathBuf> {
+    let mut candidates = vec![PathBuf::from(name)];
+
+    #[cfg(target_os = "windows")]
+    {
+        let programfiles = std::env::var("PROGRAMFILES").unwrap_or_default();
+        let localappdata = std::env::var("LOCALAPPDATA").unwrap_or_default();
+        candidates.push(Path::new(&programfiles).join("Tesseract-OCR").join(format!("{}.exe", name)));
+        candidates.push(Path::new(&localappdata).join("Programs").join("Tesseract-OCR").join(format!("{}.exe", name)));
+        candidates.push(Path::new(&programfiles).join("poppler").join("Library").join("bin").join(format!("{}.exe", name)));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        for dir in ["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"] {
+            candidates.push(Path::new(dir).join(name));
+        }
+    }
+
+    candidates.into_iter().find(|candidate| {
+        tool_command(candidate)
+            .arg("-v")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Render one page to PNG with poppler's pdftoppm
+fn render_page(pdftoppm: &Path, pdf_path: &str, page: u32, out_prefix: &Path) -> Result<PathBuf, String> {
+    let page_arg = page.to_string();
+    let output = tool_command(pdftoppm)
+        .args(["-f", page_arg.as_str(), "-l", page_arg.as_str(), "-r", RENDER_DPI, "-png", "-singlefile"])
+        .arg(pdf_path)
+        .arg(out_prefix)
+        .output()
+        .map_err(|e| format!("Failed to run pdftoppm: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to render page {}: {}",
+            page,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(out_prefix.with_extension("png"))
+}
+
+/// Run Tesseract on an image and return the recognized text
+fn recognize(tesseract: &Path, image: &Path, languages: &str) -> Result<String, String> {
+    let output = tool_command(tesseract)
+        .arg(image)
+        .arg("stdout")
+        .args(["-l", languages])
+        .output()
+        .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Tesseract failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Tesseract's `-l` argument for the requested languages, English by default
+fn language_arg(languages: Option<Vec<String>>) -> Result<String, String> {
+    let languages = languages.filter(|l| !l.is_empty()).unwrap_or_else(|| vec!["eng".to_string()]);
+    if let Some(bad) = languages
+        .iter()
+        .find(|l| l.is_empty() || !l.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    {
+        return Err(format!("Invalid OCR language code: {}", bad));
+    }
+    Ok(languages.join("+"))
+}
+
+/// Language packs Tesseract has installed, e.g. "eng", "deu"
+fn installed_languages(tesseract: &Path) -> Vec<String> {
+    let output = match tool_command(tesseract).arg("--list-langs").output() {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    // The first line is a header naming the tessdata directory
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Tesseract languages for a document whose first page read as `sample` in English
+///
+/// Adds the detected language's pack, keeping English for the Latin text most
+/// documents also contain. Falls back to English when the pack isn't installed.
+fn detected_language_arg(tesseract: &Path, sample: &str) -> String {
+    let Some(detected) = language::detect(sample).filter(|l| l.reliable() && !l.is_english()) else {
+        return "eng".to_string();
+    };
+    let code = detected.tesseract_code();
+    if installed_languages(tesseract).iter().any(|l| l == code) {
+        log::info!("Detected {} text, using the {} language pack", detected.name(), code);
+        format!("{}+eng", code)
+    } else {
+        log::warn!(
+            "Detected {} text, but Tesseract's {} language pack isn't installed; reading it as English",
+            detected.name(),
+            code
+        );
+        "eng".to_string()
+    }
+}
+
+/// Render and recognize one page, deleting the rendered image afterwards
+async fn ocr_page(
+    pdftoppm: &Path,
+    tesseract: &Path,
+    pdf_path: &str,
+    page: u32,
+    out_prefix: PathBuf,
+    languages: &str,
+) -> Result<String, String> {
+    let (pdftoppm, tesseract, pdf_path, languages) =
+        (pdftoppm.to_path_buf(), tesseract.to_path_buf(), pdf_path.to_string(), languages.to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = render_page(&pdftoppm, &pdf_path, page, &out_prefix)?;
+        let result = recognize(&tesseract, &image, &languages);
+        if let Err(e) = secure_delete::secure_delete(&image) {
+            log::warn!("Failed to remove OCR page image: {}", e);
+        }
+        result
+    })
+    .await
+    .map_err(|e| format!("OCR task failed: {}", e))?
+}
+
+/// Recognize the text in a PNG or JPEG image, such as a pasted screenshot
+///
+/// The image is written to the app's temp directory for Tesseract and securely
+/// deleted afterwards. Blocking; call from `spawn_blocking`.
+pub(crate) fn recognize_image(
+    app_handle: &tauri::AppHandle,
+    image: &[u8],
+    languages: Option<Vec<String>>,
+) -> Result<String, String> {
+    let languages = language_arg(languages)?;
+    let extension = match image::guess_format(image) {
+        Ok(image::ImageFormat::Png) => "png",
+        Ok(image::ImageFormat::Jpeg) => "jpg",
+        _ => return Err("Unsupported image: expected PNG or JPEG".to_string()),
+    };
+    let tesseract = find_tool("tesseract").ok_or(
+        "Tesseract was not found. Install it from https://github.com/tesseract-ocr/tesseract to read text from images.",
+    )?;
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let path = secure_delete::temp_dir(app_handle)?.join(format!("ocr-image-{:x}.{}", nanos, extension));
+    std::fs::write(&path, image).map_err(|e| format!("Failed to write image: {}", e))?;
+    let result = recognize(&tesseract, &path, &languages);
+    if let Err(e) = secure_delete::secure_delete(&path) {
+        log::warn!("Failed to remove OCR image: {}", e);
+    }
+    result
+}
+
+/// OCR a scanned PDF page by page
+///
+/// Pages are rendered with poppler (`pdftoppm`) and recognized with Tesseract; both
+/// must be installed. Without `languages`, the first page is read in English and
+/// the document's language detected from it, so the matching language pack is
+/// used when it's installed. Emits `ocr_progress` events as pages complete.
+/// Rendered page images are securely deleted as soon as they've been read.
+#[tauri::command]
+pub async fn ocr_pdf(
+    app_handle: tauri::AppHandle,
+    path: String,
+    languages: Option<Vec<String>>,
+    window: tauri::Window,
+) -> Result<Vec<OcrPage>, AppError> {
+    let detect = languages.as_deref().unwrap_or_default().is_empty();
+    let mut languages = language_arg(languages)?;
+
+    let pdftoppm = find_tool("pdftoppm").ok_or_else(|| {
+        AppError::Unsupported("pdftoppm (poppler) was not found. Install poppler to OCR scanned PDFs.".to_string())
+    })?;
+    let tesseract = find_tool("tesseract").ok_or_else(|| {
+        AppError::Unsupported("Tesseract was not found. Install it from https://github.com/tesseract-ocr/tesseract to OCR scanned PDFs.".to_string())
+    })?;
+
+    let page_count = pdf::page_count(&path)? as u32;
+    let work_dir = secure_delete::temp_dir(&app_handle)?;
+    let out_prefix = |page: u32| work_dir.join(format!("ocr-{}-{}", std::process::id(), page));
+
+    // The first page read in English; kept when the document turns out to be English
+    let mut first_page = None;
+    if detect && page_count > 0 {
+        let text = ocr_page(&pdftoppm, &tesseract, &path, 1, out_prefix(1), &languages).await?;
+        let detected = detected_language_arg(&tesseract, &text);
+        if detected == languages {
+            first_page = Some(text);
+        }
+        languages = detected;
+    }
+    log::info!("Running OCR on {} ({} pages, languages: {})", path, page_count, languages);
+
+    let mut throttle = ProgressThrottle::new();
+    let mut pages = Vec::with_capacity(page_count as usize);
+
+    for page in 1..=page_count {
+        let text = match first_page.take().filter(|_| page == 1) {
+            Some(text) => text,
+            None => ocr_page(&pdftoppm, &tesseract, &path, page, out_prefix(page), &languages).await?,
+        };
+
+        pages.push(OcrPage { page_number: page, text });
+
+        let percent = page as f64 / page_count as f64 * 100.0;
+        if throttle.should_emit(percent, false) {
+            window.emit_to(window.label(), "ocr_progress", json!({
+                "path": path,
+                "page": page,
+                "total": page_count,
+                "percent": percent
+            })).ok();
+        }
+    }
+
+    log::info!("OCR completed for {} pages", pages.len());
+    Ok(pages)
+}
@@ -0,0 +1,306 @@
+use serde_json::json;
+use std::path::Path;
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::progress::ProgressThrottle;
+
+/// File type bits of a Unix mode, and the value marking a symlink
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Resolve a ZIP entry name under `root`, rejecting anything that could escape it
+///
+/// `enclosed_name` already drops `..` and absolute paths, but we check the
+/// components again so a future zip crate change can't reintroduce zip-slip.
+fn safe_entry_path(root: &Path, entry: &zip::read::ZipFile) -> Result<std::path::PathBuf, AppError> {
+    use std::path::Component;
+
+    let relative = entry
+        .enclosed_name()
+        .ok_or_else(|| AppError::Parse(format!("Unsafe path in Ollama archive: {}", entry.name())))?;
+    if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(AppError::Parse(format!("Unsafe path in Ollama archive: {}", entry.name())));
+    }
+    let path = root.join(relative);
+    if !path.starts_with(root) {
+        return Err(AppError::Parse(format!("Unsafe path in Ollama archive: {}", entry.name())));
+    }
+    Ok(path)
+}
+
+/// Extract every entry of a ZIP under `root`, keeping Unix permission bits
+fn extract_zip(zip_path: &Path, root: &Path, window: &tauri::Window) -> Result<(), AppError> {
+    let zip_file = std::fs::File::open(zip_path)
+        .map_err(|e| AppError::Io(format!("Failed to open ZIP file: {}", e)))?;
+    let mut archive = zip::ZipArchive::new(zip_file)
+        .map_err(|e| AppError::Parse(format!("Failed to read ZIP archive: {}", e)))?;
+
+    let total_files = archive.len();
+    log::info!("Extracting {} files...", total_files);
+    let mut throttle = ProgressThrottle::new();
+
+    for i in 0..total_files {
+        let mut file = archive.by_index(i)
+            .map_err(|e| AppError::Parse(format!("Failed to access ZIP entry: {}", e)))?;
+        let outpath = safe_entry_path(root, &file)?;
+
+        if file.unix_mode().is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+            return Err(AppError::Parse(format!("Symlink in Ollama archive: {}", file.name())));
+        }
+
+        if file.is_dir() {
+            std::fs::create_dir_all(&outpath)
+                .map_err(|e| AppError::Io(format!("Failed to create directory: {}", e)))?;
+        } else {
+            if let Some(p) = outpath.parent() {
+                std::fs::create_dir_all(p)
+                    .map_err(|e| AppError::Io(format!("Failed to create parent directory: {}", e)))?;
+            }
+            let mut outfile = std::fs::File::create(&outpath)
+                .map_err(|e| AppError::Io(format!("Failed to create output file: {}", e)))?;
+            std::io::copy(&mut file, &mut outfile)
+                .map_err(|e| AppError::Io(format!("Failed to extract file: {}", e)))?;
+        }
+
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode & 0o777))
+                .map_err(|e| AppError::Io(format!("Failed to set file permissions: {}", e)))?;
+        }
+
+        // Emit extraction progress (throttled)
+        let percent = ((i + 1) as f64 / total_files as f64) * 100.0;
+        if throttle.should_emit(percent, i == total_files - 1) {
+            window.emit("ollama_extraction_progress", json!({
+                "current": i + 1,
+                "total": total_files,
+                "percent": percent
+            })).ok();
+        }
+    }
+
+    log::info!("Extraction completed");
+    Ok(())
+}
+
+/// Resolve `path` lexically (without touching the disk), or None if it climbs above its start
+fn normalize_relative(path: &Path) -> Option<std::path::PathBuf> {
+    use std::path::Component;
+
+    let mut normalized = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/// Extract a gzipped tarball under `root`
+///
+/// The Linux release ships its GPU libraries as relative symlinks, so links are
+/// allowed as long as they resolve inside `root`. `unpack_in` refuses entry
+/// paths that escape it and applies the archived permission bits.
+fn extract_tarball(tarball: &Path, root: &Path, window: &tauri::Window) -> Result<(), AppError> {
+    let open = || -> Result<tar::Archive<flate2::read::GzDecoder<std::fs::File>>, AppError> {
+        let file = std::fs::File::open(tarball)
+            .map_err(|e| AppError::Io(format!("Failed to open tarball: {}", e)))?;
+        Ok(tar::Archive::new(flate2::read::GzDecoder::new(file)))
+    };
+
+    // Count entries first so progress can be reported as a percentage
+    let total_files = open()?
+        .entries()
+        .map_err(|e| AppError::Parse(format!("Failed to read tarball: {}", e)))?
+        .count();
+    log::info!("Extracting {} files...", total_files);
+
+    let mut archive = open()?;
+    let entries = archive
+        .entries()
+        .map_err(|e| AppError::Parse(format!("Failed to read tarball: {}", e)))?;
+    let mut throttle = ProgressThrottle::new();
+
+    for (i, entry) in entries.enumerate() {
+        let mut entry = entry.map_err(|e| AppError::Parse(format!("Failed to access tarball entry: {}", e)))?;
+        let path = entry
+            .path()
+            .map_err(|e| AppError::Parse(format!("Invalid path in Ollama archive: {}", e)))?
+            .into_owned();
+        let relative = normalize_relative(&path)
+            .ok_or_else(|| AppError::Parse(format!("Unsafe path in Ollama archive: {}", path.display())))?;
+
+        let kind = entry.header().entry_type();
+        if kind.is_symlink() || kind.is_hard_link() {
+            let target = entry
+                .link_name()
+                .map_err(|e| AppError::Parse(format!("Invalid link in Ollama archive: {}", e)))?
+                .ok_or_else(|| AppError::Parse(format!("Link without target in Ollama archive: {}", path.display())))?;
+            // Hard link targets are relative to the archive root, symlinks to the link's directory
+            let base = if kind.is_symlink() {
+                relative.parent().unwrap_or(Path::new("")).to_path_buf()
+            } else {
+                std::path::PathBuf::new()
+            };
+            if normalize_relative(&base.join(&target)).is_none() {
+                return Err(AppError::Parse(format!(
+                    "Link escapes the install directory in Ollama archive: {} -> {}",
+                    path.display(),
+                    target.display()
+                )));
+            }
+        }
+
+        let unpacked = entry
+            .unpack_in(root)
+            .map_err(|e| AppError::Io(format!("Failed to extract file: {}", e)))?;
+        if !unpacked {
+            return Err(AppError::Parse(format!("Unsafe path in Ollama archive: {}", path.display())));
+        }
+
+        let percent = ((i + 1) as f64 / total_files.max(1) as f64) * 100.0;
+        if throttle.should_emit(percent, i + 1 == total_files) {
+            window.emit("ollama_extraction_progress", json!({
+                "current": i + 1,
+                "total": total_files,
+                "percent": percent
+            })).ok();
+        }
+    }
+
+    log::info!("Extraction completed");
+    Ok(())
+}
+
+/// Check the extracted server binary exists and make sure it can be run
+fn make_executable(binary: &Path) -> Result<(), AppError> {
+    if !binary.is_file() {
+        return Err(AppError::Io(format!("Extraction failed: {} not found", binary.display())));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(binary, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| AppError::Io(format!("Failed to make Ollama executable: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Install the contents of a ZIP or tarball to `install_path` all at once
+///
+/// Files go to a sibling staging directory first. Only when extraction succeeded
+/// and `binary` exists (and is executable) is the old install renamed aside and the staging directory
+/// renamed into place, so a failed or interrupted install leaves the previous
+/// version untouched rather than a mix of old and new files.
+pub(crate) fn install_archive_atomically(
+    archive_path: &Path,
+    install_path: &Path,
+    binary: &str,
+    window: &tauri::Window,
+) -> Result<(), AppError> {
+    let parent = install_path
+        .parent()
+        .ok_or_else(|| AppError::Io(format!("Invalid install path: {}", install_path.display())))?;
+    let dir_name = install_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "ollama".to_string());
+    let staging = parent.join(format!("{}.staging", dir_name));
+    let backup = parent.join(format!("{}.old", dir_name));
+
+    // Leftovers from an install that was interrupted
+    for stale in [&staging, &backup] {
+        if stale.exists() {
+            std::fs::remove_dir_all(stale)
+                .map_err(|e| AppError::Io(format!("Failed to remove {}: {}", stale.display(), e)))?;
+        }
+    }
+    std::fs::create_dir_all(&staging)
+        .map_err(|e| AppError::Io(format!("Failed to create staging directory: {}", e)))?;
+
+    let is_zip = archive_path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    let extracted = if is_zip {
+        extract_zip(archive_path, &staging, window)
+    } else {
+        extract_tarball(archive_path, &staging, window)
+    }
+    .and_then(|_| make_executable(&staging.join(binary)));
+    if let Err(e) = extracted {
+        if let Err(cleanup) = std::fs::remove_dir_all(&staging) {
+            log::warn!("Failed to remove staging directory: {}", cleanup);
+        }
+        return Err(e);
+    }
+
+    let had_previous = install_path.exists();
+    if had_previous {
+        std::fs::rename(install_path, &backup).map_err(|e| {
+            AppError::Io(format!(
+                "Failed to replace the existing Ollama install (is Ollama still running?): {}",
+                e
+            ))
+        })?;
+    }
+    if let Err(e) = std::fs::rename(&staging, install_path) {
+        if had_previous {
+            if let Err(restore) = std::fs::rename(&backup, install_path) {
+                log::error!("Failed to restore previous Ollama install: {}", restore);
+            }
+        }
+        return Err(AppError::Io(format!("Failed to move Ollama into place: {}", e)));
+    }
+    if had_previous {
+        if let Err(e) = std::fs::remove_dir_all(&backup) {
+            log::warn!("Failed to remove previous Ollama install: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// In-memory archive with one empty file per name
+    fn archive(names: &[&str]) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for name in names {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(b"x").unwrap();
+        }
+        zip::ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    fn entry_path(root: &Path, name: &str) -> Result<std::path::PathBuf, AppError> {
+        let mut archive = archive(&[name]);
+        let entry = archive.by_index(0).unwrap();
+        safe_entry_path(root, &entry)
+    }
+
+    #[test]
+    fn entries_resolve_under_root() {
+        let root = Path::new("/tmp/staging");
+        assert_eq!(entry_path(root, "bin/ollama").unwrap(), root.join("bin/ollama"));
+        assert_eq!(entry_path(root, "./lib/libggml.so").unwrap(), root.join("lib/libggml.so"));
+    }
+
+    #[test]
+    fn escaping_entries_are_rejected() {
+        let root = Path::new("/tmp/staging");
+        for name in ["../evil", "bin/../../evil", "/etc/passwd"] {
+            assert!(entry_path(root, name).is_err(), "{} was accepted", name);
+        }
+    }
+}
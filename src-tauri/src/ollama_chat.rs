@@ -0,0 +1,421 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::backend::{self, ChatOptions, ChatUsage, GenerationOptions, LlmBackend};
+use crate::context_window;
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::http;
+use crate::ollama::{ChatMessage, StreamChunk};
+use crate::privacy::{PiiMasker, StreamRestorer};
+use crate::prompts;
+use crate::scheduler::{self, Lane};
+use crate::settings;
+
+/// Chat with Ollama (non-streaming) - Windows only
+///
+/// With `template_id`, the prompt template's system prompt is added and the last
+/// user message is rendered through it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ollama_chat(
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    options: Option<GenerationOptions>,
+    template_id: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, AppError> {
+    chat_in(Lane::Interactive, model, messages, temperature, max_tokens, top_p, options, template_id, app_handle).await
+}
+
+/// `ollama_chat` for backend features, queued in the given scheduler lane
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn chat_in(
+    lane: Lane,
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    options: Option<GenerationOptions>,
+    template_id: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, AppError> {
+    log::info!("Ollama chat request: model={}, messages={}", model, messages.len());
+
+    let messages = match template_id {
+        Some(id) => prompts::get_template(&app_handle, &id)?.apply(messages, prompts::default_variables()),
+        None => messages,
+    };
+
+    let mut masker = PiiMasker::new();
+    let messages = apply_privacy_filter(&app_handle, &mut masker, messages);
+
+    let backend = backend::from_settings(&app_handle);
+    let settings = settings::read_settings(&app_handle);
+    let mut options = ChatOptions {
+        temperature: temperature.unwrap_or(0.2),
+        max_tokens: max_tokens.unwrap_or(4096),
+        top_p: top_p.unwrap_or(0.9),
+        generation: options.unwrap_or_default().or(&settings.generation),
+        timeout: http::read_timeout(),
+    };
+    // Same window as streamed chats, so summaries and flashcards see the whole prompt
+    if options.generation.num_ctx.is_none() {
+        options.generation.num_ctx = Some(context_window::for_model(&app_handle, &settings, &model).await.num_ctx);
+    }
+    let permit = scheduler::acquire(&app_handle, lane, None).await;
+    let reply = backend.chat(&model, &messages, &options).await?;
+    drop(permit);
+
+    log::info!("Chat response received from {}: {} chars", backend.name(), reply.len());
+    Ok(masker.restore(&reply))
+}
+
+/// Mask PII in outgoing messages when the privacy filter is enabled in settings
+fn apply_privacy_filter(
+    app_handle: &tauri::AppHandle,
+    masker: &mut PiiMasker,
+    messages: Vec<ChatMessage>,
+) -> Vec<ChatMessage> {
+    if !settings::read_settings(app_handle).privacy_filter {
+        return messages;
+    }
+
+    let messages: Vec<ChatMessage> = messages
+        .into_iter()
+        .map(|m| ChatMessage {
+            content: masker.mask(&m.content),
+            ..m
+        })
+        .collect();
+
+    if masker.masked_count() > 0 {
+        log::info!("Privacy filter masked {} value(s) before inference", masker.masked_count());
+    }
+    messages
+}
+
+/// Generate embedding - Windows only
+///
+/// Queued behind chat answers, so embedding batches from the frontend don't slow them down.
+#[tauri::command]
+pub async fn ollama_embedding(
+    model: String,
+    text: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<f64>, AppError> {
+    embed_in(Lane::Embedding, model, text, &app_handle).await
+}
+
+/// `ollama_embedding` for backend features, queued in the given scheduler lane
+pub(crate) async fn embed_in(
+    lane: Lane,
+    model: String,
+    text: String,
+    app_handle: &tauri::AppHandle,
+) -> Result<Vec<f64>, AppError> {
+    log::info!("Ollama embedding request: model={}, text_len={}", model, text.len());
+
+    let _permit = scheduler::acquire(app_handle, lane, None).await;
+    let embedding = backend::from_settings(app_handle).embed(&model, &text).await?;
+
+    log::info!("Embedding generated: {} dimensions", embedding.len());
+    Ok(embedding)
+}
+
+/// Payload of `chat_stats`, sent when a streamed answer finishes
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatStats {
+    pub request_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub model: String,
+    pub backend: &'static str,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// Generation speed, from the server's own timing when it reports one
+    pub tokens_per_second: Option<f64>,
+    pub prompt_tokens_per_second: Option<f64>,
+    /// Time until the first piece of the answer arrived
+    pub first_token_ms: Option<u64>,
+    /// Time from sending the request to the last chunk
+    pub total_ms: u64,
+}
+
+impl ChatStats {
+    fn new(
+        backend: &'static str,
+        model: &str,
+        request_id: Option<&str>,
+        usage: ChatUsage,
+        first_token: Option<std::time::Duration>,
+        total: std::time::Duration,
+    ) -> Self {
+        let rate = |tokens: Option<u64>, time: Option<std::time::Duration>| {
+            let secs = time?.as_secs_f64();
+            let tokens = tokens? as f64;
+            (secs > 0.0).then(|| tokens / secs)
+        };
+        // Without server timings, generation is timed from the first token to the last
+        let generation_time = usage.eval.or_else(|| first_token.map(|first| total.saturating_sub(first)));
+        Self {
+            request_id: request_id.map(String::from),
+            conversation_id: None,
+            model: model.to_string(),
+            backend,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            tokens_per_second: rate(usage.completion_tokens, generation_time),
+            prompt_tokens_per_second: rate(usage.prompt_tokens, usage.prompt_eval),
+            first_token_ms: first_token.map(|d| d.as_millis() as u64),
+            total_ms: total.as_millis() as u64,
+        }
+    }
+}
+
+/// In-flight streaming chats, keyed by the request id the frontend passed in
+#[derive(Default)]
+pub struct ChatStreams {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl ChatStreams {
+    fn register(&self, request_id: &str) -> Result<CancellationToken, AppError> {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        if tokens.contains_key(request_id) {
+            return Err(AppError::Other(format!("A chat stream with id {} is already running", request_id)));
+        }
+        let token = CancellationToken::new();
+        tokens.insert(request_id.to_string(), token.clone());
+        Ok(token)
+    }
+
+    fn remove(&self, request_id: &str) {
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(request_id);
+    }
+
+    fn cancel(&self, request_id: &str) -> bool {
+        match self.tokens.lock().unwrap_or_else(|e| e.into_inner()).remove(request_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Chat with Ollama (streaming) - Windows only
+/// Returns chunks as they arrive for better UX
+/// Pass a `request_id` to tell concurrent streams apart (it is echoed in every
+/// `StreamChunk`) and to stop the generation with `cancel_chat_stream`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ollama_chat_stream(
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    options: Option<GenerationOptions>,
+    request_id: Option<String>,
+    conversation_id: Option<String>,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    streams: tauri::State<'_, ChatStreams>,
+) -> Result<(), AppError> {
+    stream_answer(
+        model,
+        messages,
+        temperature,
+        max_tokens,
+        top_p,
+        options,
+        request_id,
+        conversation_id,
+        &window,
+        &app_handle,
+        &streams,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// `ollama_chat_stream` for callers that need the answer afterwards; None if it was cancelled
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn stream_answer(
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    options: Option<GenerationOptions>,
+    request_id: Option<String>,
+    conversation_id: Option<String>,
+    window: &tauri::Window,
+    app_handle: &tauri::AppHandle,
+    streams: &ChatStreams,
+) -> Result<Option<String>, AppError> {
+    log::info!("Ollama streaming chat request: model={}, messages={}", model, messages.len());
+
+    let backend = backend::from_settings(app_handle);
+    run_chat_stream(
+        backend.as_ref(),
+        &model,
+        messages,
+        ChatOptions {
+            temperature: temperature.unwrap_or(0.2),
+            max_tokens: max_tokens.unwrap_or(4096),
+            top_p: top_p.unwrap_or(0.9),
+            generation: options.unwrap_or_default(),
+            timeout: http::read_timeout(),
+        },
+        request_id,
+        conversation_id,
+        window,
+        app_handle,
+        streams,
+    )
+    .await
+}
+
+/// Privacy filter, settings defaults, cancellation and chunk events around a streaming chat
+///
+/// Shared by `ollama_chat_stream` and the embedded model's `local_chat_stream`;
+/// `options.generation` holds the request's overrides, settings fill in the rest.
+/// A finished answer emits `chat_stats` and, with a `conversation_id`, is added to
+/// that conversation's per-model totals. Returns the answer as shown to the user,
+/// or None if the stream was cancelled.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_chat_stream(
+    backend: &dyn LlmBackend,
+    model: &str,
+    messages: Vec<ChatMessage>,
+    mut options: ChatOptions,
+    request_id: Option<String>,
+    conversation_id: Option<String>,
+    window: &tauri::Window,
+    app_handle: &tauri::AppHandle,
+    streams: &ChatStreams,
+) -> Result<Option<String>, AppError> {
+    let mut masker = PiiMasker::new();
+    let messages = apply_privacy_filter(app_handle, &mut masker, messages);
+
+    let settings = settings::read_settings(app_handle);
+    options.generation = options.generation.or(&settings.generation);
+    // Document chats need a larger window than Ollama's default, sized to what the model and machine can take
+    if options.generation.num_ctx.is_none() {
+        options.generation.num_ctx = Some(context_window::for_model(app_handle, &settings, model).await.num_ctx);
+    }
+
+    let cancel_token = match &request_id {
+        Some(id) => streams.register(id)?,
+        None => CancellationToken::new(),
+    };
+
+    // Dropping the stream future on cancel also drops the HTTP connection,
+    // which makes the server stop generating
+    let result = tokio::select! {
+        result = async {
+            let _permit = scheduler::acquire(app_handle, Lane::Interactive, request_id.as_deref()).await;
+            stream_chat(backend, model, &messages, &options, request_id.as_deref(), window, &masker).await
+        } => result.map(Some),
+        _ = cancel_token.cancelled() => {
+            log::info!("Streaming chat cancelled by user");
+            window.emit_to(window.label(), "ollama_stream_chunk", StreamChunk {
+                request_id: request_id.clone(),
+                content: String::new(),
+                done: true,
+            }).ok();
+            Ok(None)
+        }
+    };
+
+    if let Some(id) = &request_id {
+        streams.remove(id);
+    }
+
+    let Some((mut stats, answer)) = result? else {
+        return Ok(None);
+    };
+    log::info!(
+        "Chat stats: {:?} prompt tokens, {:?} completion tokens, {:.1} tokens/s, {} ms",
+        stats.prompt_tokens,
+        stats.completion_tokens,
+        stats.tokens_per_second.unwrap_or(0.0),
+        stats.total_ms
+    );
+    if let Some(id) = &conversation_id {
+        if let Err(e) = app_handle.state::<ConversationStore>().record_stats(id, &stats) {
+            log::warn!("{}", e);
+        }
+    }
+    stats.conversation_id = conversation_id;
+    window.emit_to(window.label(), "chat_stats", stats).ok();
+    Ok(Some(answer))
+}
+
+/// Send a streaming chat request and forward each chunk to the window, returning
+/// the stats and the full (restored) answer
+async fn stream_chat(
+    backend: &dyn LlmBackend,
+    model: &str,
+    messages: &[ChatMessage],
+    options: &ChatOptions,
+    request_id: Option<&str>,
+    window: &tauri::Window,
+    masker: &PiiMasker,
+) -> Result<(ChatStats, String), AppError> {
+    log::info!("Streaming response from {}...", backend.name());
+
+    // Placeholders can be split across chunks, so restoring goes through a small buffer
+    let mut restorer = (masker.masked_count() > 0).then(|| StreamRestorer::new(masker));
+    let started = std::time::Instant::now();
+    let mut first_token = None;
+    let mut answer = String::new();
+
+    let usage = backend
+        .chat_stream(model, messages, options, &mut |content, done| {
+            if first_token.is_none() && !content.is_empty() {
+                first_token = Some(started.elapsed());
+            }
+            let content = match restorer.as_mut() {
+                Some(restorer) if done => restorer.push(content) + &restorer.finish(),
+                Some(restorer) => restorer.push(content),
+                None => content.to_string(),
+            };
+            answer.push_str(&content);
+
+            // Emit chunk to frontend
+            window.emit_to(window.label(), "ollama_stream_chunk", StreamChunk {
+                request_id: request_id.map(String::from),
+                content,
+                done,
+            }).ok();
+        })
+        .await?;
+
+    log::info!("Streaming completed successfully");
+    let stats = ChatStats::new(backend.name(), model, request_id, usage, first_token, started.elapsed());
+    Ok((stats, answer))
+}
+
+/// Stop a streaming chat started with the given request id
+#[tauri::command]
+pub async fn cancel_chat_stream(
+    request_id: String,
+    streams: tauri::State<'_, ChatStreams>,
+) -> Result<bool, AppError> {
+    let cancelled = streams.cancel(&request_id);
+    log::info!("Cancel chat stream {}: {}", request_id, if cancelled { "cancelled" } else { "not running" });
+    Ok(cancelled)
+}
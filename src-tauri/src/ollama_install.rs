@@ -0,0 +1,489 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::AppError;
+use crate::http;
+use crate::ollama_archive;
+use crate::progress::ProgressThrottle;
+
+/// Ollama downloads in progress, keyed by download id, so each can be cancelled on its own
+#[derive(Default)]
+pub struct OllamaDownloads {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl OllamaDownloads {
+    fn register(&self, download_id: &str) -> Result<CancellationToken, AppError> {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        if tokens.contains_key(download_id) {
+            return Err(AppError::Other(format!("A download with id {} is already running", download_id)));
+        }
+        let token = CancellationToken::new();
+        tokens.insert(download_id.to_string(), token.clone());
+        Ok(token)
+    }
+
+    fn remove(&self, download_id: &str) {
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(download_id);
+    }
+
+    fn cancel(&self, download_id: &str) -> bool {
+        match self.tokens.lock().unwrap_or_else(|e| e.into_inner()).remove(download_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Sidecar of a `.part` file, recording what the partial download belongs to
+#[derive(Debug, Serialize, Deserialize)]
+struct PartialDownload {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    total: u64,
+}
+
+/// Download `url` to `dest`, resuming from `<dest>.part` when possible
+///
+/// The partial file and its ETag survive cancellation, errors and app restarts.
+/// A later call sends a Range request guarded by If-Range, so the server either
+/// continues where the file stopped (206) or sends the whole file again if it
+/// changed in between (200). A range the server rejects (416) means the saved
+/// part is unusable, so it's discarded and the download starts over. Returns the
+/// final file size.
+async fn download_resumable(
+    url: &str,
+    dest: &Path,
+    download_id: &str,
+    window: &tauri::Window,
+    cancel: &CancellationToken,
+) -> Result<u64, AppError> {
+    use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+    use reqwest::StatusCode;
+    use std::io::Write;
+
+    let part_path = std::path::PathBuf::from(format!("{}.part", dest.display()));
+    let meta_path = std::path::PathBuf::from(format!("{}.part.json", dest.display()));
+
+    let saved = std::fs::read_to_string(&meta_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<PartialDownload>(&json).ok())
+        .filter(|meta| meta.url == url);
+    let existing = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    let validator = saved
+        .as_ref()
+        .and_then(|meta| meta.etag.clone().or_else(|| meta.last_modified.clone()));
+
+    // Without a validator we can't tell whether the bytes on disk are still valid
+    let resume_from = match &validator {
+        Some(_) if existing > 0 => existing,
+        _ => 0,
+    };
+
+    let mut request = http::get(url)?.timeout(std::time::Duration::from_secs(600)); // 10 minutes for large download
+    if let Some(validator) = validator.as_ref().filter(|_| resume_from > 0) {
+        log::info!("Resuming download at {} bytes", resume_from);
+        request = request
+            .header(RANGE, format!("bytes={}-", resume_from))
+            .header(IF_RANGE, validator.as_str());
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::request("Download request", e))?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && resume_from > 0 {
+        // The part file already holds the whole archive
+        if saved.as_ref().is_some_and(|meta| meta.total == existing) {
+            log::info!("Partial download is already complete");
+            std::fs::rename(&part_path, dest).map_err(|e| AppError::Io(format!("Failed to finalize download: {}", e)))?;
+            let _ = std::fs::remove_file(&meta_path);
+            return Ok(existing);
+        }
+        // Otherwise every retry would send the same range and fail the same way
+        log::warn!("Server rejected the range of the partial download; starting over");
+        let _ = std::fs::remove_file(&part_path);
+        let _ = std::fs::remove_file(&meta_path);
+        return Box::pin(download_resumable(url, dest, download_id, window, cancel)).await;
+    }
+
+    if !response.status().is_success() {
+        return Err(AppError::status("Download", response.status()));
+    }
+
+    let resumed = resume_from > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    let offset = if resumed { resume_from } else { 0 };
+    let total_size = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit('/').next())
+        .and_then(|v| v.parse::<u64>().ok())
+        .or_else(|| response.content_length().map(|len| len + offset))
+        .unwrap_or(0);
+    log::info!(
+        "Download size: {} bytes ({:.2} MB){}",
+        total_size,
+        total_size as f64 / 1_048_576.0,
+        if resumed { ", resumed" } else { "" }
+    );
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let meta = PartialDownload {
+        url: url.to_string(),
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        total: total_size,
+    };
+    let meta_json = serde_json::to_string(&meta).map_err(|e| AppError::Parse(format!("Failed to serialize download state: {}", e)))?;
+    std::fs::write(&meta_path, meta_json).map_err(|e| AppError::Io(format!("Failed to save download state: {}", e)))?;
+
+    let mut file = if resumed {
+        std::fs::OpenOptions::new().append(true).open(&part_path)
+    } else {
+        std::fs::File::create(&part_path)
+    }
+    .map_err(|e| AppError::Io(format!("Failed to create temp file: {}", e)))?;
+
+    let mut downloaded = offset;
+    let mut throttle = ProgressThrottle::new();
+    let mut stream = response.bytes_stream();
+    loop {
+        let chunk_result = tokio::select! {
+            _ = cancel.cancelled() => {
+                file.flush().ok();
+                log::info!("Download cancelled at {} / {} bytes", downloaded, total_size);
+                return Err(AppError::Cancelled("Download cancelled".to_string()));
+            }
+            next = stream.next() => match next {
+                Some(chunk_result) => chunk_result,
+                None => break,
+            },
+        };
+        let chunk = chunk_result.map_err(|e| AppError::request("Download stream", e))?;
+
+        file.write_all(&chunk)
+            .map_err(|e| AppError::Io(format!("Failed to write to temp file: {}", e)))?;
+
+        downloaded += chunk.len() as u64;
+
+        let percent = if total_size > 0 {
+            (downloaded as f64 / total_size as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        // Emit progress event (throttled)
+        if throttle.should_emit(percent, downloaded == total_size) {
+            window.emit("ollama_download_progress", json!({
+                "download_id": download_id,
+                "downloaded": downloaded,
+                "total": total_size,
+                "percent": percent,
+                "resumed_from": offset
+            })).ok();
+
+            log::info!("Download progress: {:.1}% ({} / {} bytes)", percent, downloaded, total_size);
+        }
+    }
+
+    file.flush().map_err(|e| AppError::Io(format!("Failed to write to temp file: {}", e)))?;
+    drop(file);
+
+    if total_size > 0 && downloaded != total_size {
+        return Err(AppError::Network(format!("Download incomplete: {} of {} bytes", downloaded, total_size)));
+    }
+
+    std::fs::rename(&part_path, dest).map_err(|e| AppError::Io(format!("Failed to finalize download: {}", e)))?;
+    let _ = std::fs::remove_file(&meta_path);
+    Ok(downloaded)
+}
+
+/// Checksums published with every Ollama release (`<sha256>  ./<asset>` per line)
+const OLLAMA_CHECKSUMS_URL: &str = "https://github.com/ollama/ollama/releases/latest/download/sha256sum.txt";
+
+/// Published SHA256 of the release asset at `url`
+async fn expected_sha256(url: &str) -> Result<String, AppError> {
+    let asset = url.rsplit('/').next().unwrap_or(url);
+    let response = http::get(OLLAMA_CHECKSUMS_URL)?
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| AppError::request("Fetching Ollama checksums", e))?;
+    if !response.status().is_success() {
+        return Err(AppError::status("Fetching Ollama checksums", response.status()));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| AppError::request("Reading Ollama checksums", e))?;
+
+    checksum_for(&body, asset).ok_or_else(|| AppError::Parse(format!("No published checksum for {}", asset)))
+}
+
+/// SHA256 listed for `asset` in a `sha256sum` style listing
+fn checksum_for(listing: &str, asset: &str) -> Option<String> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some((parts.next()?, parts.next()?))
+        })
+        .find(|(_, name)| name.trim_start_matches("./").trim_start_matches('*') == asset)
+        .map(|(hash, _)| hash.to_ascii_lowercase())
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// SHA256 of a file's contents as a lowercase hex string
+fn sha256_file(path: &Path) -> Result<String, AppError> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path).map_err(|e| AppError::Io(format!("Failed to open ZIP file: {}", e)))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| AppError::Io(format!("Failed to read ZIP file: {}", e)))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Cancel the Ollama download started with the given download id
+///
+/// The partial file is kept, so the next download resumes where this one stopped.
+#[tauri::command]
+pub async fn cancel_ollama_download(
+    download_id: String,
+    downloads: tauri::State<'_, OllamaDownloads>,
+) -> Result<bool, AppError> {
+    let cancelled = downloads.cancel(&download_id);
+    log::info!("Cancel Ollama download {}: {}", download_id, if cancelled { "cancelled" } else { "not running" });
+    Ok(cancelled)
+}
+
+/// Where release assets are downloaded from
+const OLLAMA_RELEASE_URL: &str = "https://github.com/ollama/ollama/releases/latest/download";
+
+/// Server binary inside the managed install directory
+#[cfg(target_os = "windows")]
+const MANAGED_BINARY: &str = "ollama.exe";
+#[cfg(target_os = "linux")]
+const MANAGED_BINARY: &str = "bin/ollama";
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+const MANAGED_BINARY: &str = "ollama";
+
+/// Release asset for this platform
+fn release_asset(is_amd_gpu: bool) -> Result<&'static str, AppError> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("windows", "x86_64") if is_amd_gpu => Ok("ollama-windows-amd64-rocm.zip"),
+        ("windows", "x86_64") => Ok("ollama-windows-amd64.zip"),
+        // ROCm libraries ship as a separate add-on archive; the base build still runs on the CPU
+        ("linux", "x86_64") => Ok("ollama-linux-amd64.tgz"),
+        ("linux", "aarch64") => Ok("ollama-linux-arm64.tgz"),
+        ("macos", _) => Ok("ollama-darwin.tgz"),
+        (os, arch) => Err(AppError::Unsupported(format!(
+            "Managed Ollama installation isn't available for {} ({})",
+            os, arch
+        ))),
+    }
+}
+
+/// Base directory for PrivatePDF's own Ollama install and its download
+///
+/// `%LOCALAPPDATA%\PrivatePDF` on Windows, `~/.local/share/privatepdf` (or
+/// `$XDG_DATA_HOME/privatepdf`) on Linux and `~/Library/Application Support/PrivatePDF`
+/// on macOS. In portable mode it's the `data/` folder beside the executable.
+fn managed_base_dir() -> Option<std::path::PathBuf> {
+    if let Some(dir) = crate::portable::data_dir() {
+        return Some(dir.to_path_buf());
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("LOCALAPPDATA").map(|dir| Path::new(&dir).join("PrivatePDF"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        std::env::var_os("HOME").map(|home| Path::new(&home).join("Library").join("Application Support").join("PrivatePDF"))
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(std::path::PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+            .map(|dir| dir.join("privatepdf"))
+    }
+}
+
+/// Directory Ollama is installed to by `download_ollama_zip`
+pub fn managed_install_dir() -> Option<std::path::PathBuf> {
+    managed_base_dir().map(|dir| dir.join("ollama"))
+}
+
+/// Where the release archive is downloaded before extraction (kept as `.part` while resuming)
+pub fn managed_download_path() -> Option<std::path::PathBuf> {
+    let name = if cfg!(target_os = "windows") { "ollama_temp.zip" } else { "ollama_temp.tgz" };
+    managed_base_dir().map(|dir| dir.join(name))
+}
+
+/// The managed install's server binary, if it has been installed
+pub fn managed_ollama_binary() -> Option<std::path::PathBuf> {
+    managed_install_dir()
+        .map(|dir| dir.join(MANAGED_BINARY))
+        .filter(|path| path.is_file())
+}
+
+/// Download and install Ollama into the PrivatePDF-managed directory
+///
+/// Uses the official release ZIP on Windows (the ROCm build when `is_amd_gpu`)
+/// and the tarball on Linux and macOS. `start_ollama_service` prefers this
+/// install over a system-wide one. Progress events carry `download_id`, which
+/// `cancel_ollama_download` takes; one is generated when it isn't given.
+#[tauri::command]
+pub async fn download_ollama_zip(
+    is_amd_gpu: bool,
+    download_id: Option<String>,
+    window: tauri::Window,
+    downloads: tauri::State<'_, OllamaDownloads>,
+) -> Result<String, AppError> {
+    log::info!("Starting managed Ollama installation (AMD GPU: {})", is_amd_gpu);
+
+    let download_id = download_id.unwrap_or_else(|| {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        format!("download_{:x}", nanos)
+    });
+
+    // 1. Determine download URL for this platform and GPU
+    let url = &format!("{}/{}", OLLAMA_RELEASE_URL, release_asset(is_amd_gpu)?);
+
+    // Fetch the published checksum first, so we never extract an archive we can't verify
+    let expected_sha256 = expected_sha256(url).await?;
+    log::info!("Expected SHA256: {}", expected_sha256);
+
+    log::info!("Downloading from: {}", url);
+    window.emit("ollama_download_status", json!({"download_id": download_id, "status": "downloading", "message": "Starting download..."})).ok();
+
+    // 2. Get installation path
+    let install_path = managed_install_dir()
+        .ok_or_else(|| AppError::Io("Failed to determine the Ollama install directory".to_string()))?;
+    let temp_zip_path = managed_download_path()
+        .ok_or_else(|| AppError::Io("Failed to determine the Ollama download path".to_string()))?;
+
+    log::info!("Will install to: {}", install_path.display());
+    log::info!("Temp archive path: {}", temp_zip_path.display());
+
+    // 3. Create parent directory if needed
+    if let Some(parent) = temp_zip_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| AppError::Io(format!("Failed to create temp directory: {}", e)))?;
+    }
+
+    // 4. Download with progress events, resuming a previous partial download
+    let cancel = downloads.register(&download_id)?;
+    let result = download_resumable(url, &temp_zip_path, &download_id, &window, &cancel).await;
+    downloads.remove(&download_id);
+
+    let downloaded = match result {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let status = if cancel.is_cancelled() { "cancelled" } else { "error" };
+            window.emit("ollama_download_status", json!({"download_id": download_id, "status": status, "message": e.message()})).ok();
+            return Err(e);
+        }
+    };
+
+    log::info!("Download completed: {} bytes", downloaded);
+    window.emit("ollama_download_status", json!({"download_id": download_id, "status": "verifying", "message": "Verifying checksum..."})).ok();
+
+    let zip_to_hash = temp_zip_path.clone();
+    let actual_sha256 = tauri::async_runtime::spawn_blocking(move || sha256_file(&zip_to_hash))
+        .await
+        .map_err(|e| AppError::Other(format!("Checksum task failed: {}", e)))??;
+    if !actual_sha256.eq_ignore_ascii_case(&expected_sha256) {
+        log::error!("Checksum mismatch: expected {}, got {}", expected_sha256, actual_sha256);
+        // Don't resume from or retry with a corrupted archive
+        if let Err(e) = crate::secure_delete::secure_delete(&temp_zip_path) {
+            log::warn!("Failed to remove temp ZIP: {}", e);
+        }
+        let message = "Downloaded Ollama archive failed checksum verification; it may be corrupted or tampered with. Please try again.";
+        window.emit("ollama_download_status", json!({"download_id": download_id, "status": "error", "message": message})).ok();
+        return Err(AppError::ChecksumMismatch(format!(
+            "Checksum mismatch for Ollama ZIP: expected {}, got {}",
+            expected_sha256, actual_sha256
+        )));
+    }
+    log::info!("Checksum verified");
+
+    window.emit("ollama_download_status", json!({"download_id": download_id, "status": "extracting", "message": "Extracting files..."})).ok();
+
+    // 5. Extract to a staging directory and swap it into place
+    let (zip_path, target, extract_window) = (temp_zip_path.clone(), install_path.clone(), window.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        ollama_archive::install_archive_atomically(&zip_path, &target, MANAGED_BINARY, &extract_window)
+    })
+    .await
+    .map_err(|e| AppError::Other(format!("Extraction task failed: {}", e)))??;
+
+    // 6. Clean up temp ZIP file
+    if let Err(e) = crate::secure_delete::secure_delete(&temp_zip_path) {
+        log::warn!("Failed to remove temp ZIP: {}", e);
+    }
+
+    log::info!("Ollama successfully installed to: {}", install_path.display());
+    window.emit("ollama_download_status", json!({"download_id": download_id, "status": "completed", "message": "Installation complete!"})).ok();
+
+    Ok(format!("Installed to: {}", install_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_is_found_by_asset_name() {
+        let hash = "A".repeat(64);
+        let listing = format!(
+            "{}  ./ollama-linux-amd64.tgz\n{}  ./ollama-windows-amd64.zip\n",
+            "b".repeat(64),
+            hash
+        );
+        assert_eq!(checksum_for(&listing, "ollama-windows-amd64.zip"), Some("a".repeat(64)));
+        assert_eq!(checksum_for(&listing, "ollama-darwin.zip"), None);
+    }
+
+    #[test]
+    fn checksum_accepts_binary_marker_and_rejects_bad_hashes() {
+        let hash = "0123456789abcdef".repeat(4);
+        assert_eq!(checksum_for(&format!("{} *Ollama-darwin.zip", hash), "Ollama-darwin.zip"), Some(hash));
+        assert_eq!(checksum_for("deadbeef  ./Ollama-darwin.zip", "Ollama-darwin.zip"), None);
+        assert_eq!(checksum_for(&format!("{}  ./Ollama-darwin.zip", "z".repeat(64)), "Ollama-darwin.zip"), None);
+        assert_eq!(checksum_for("", "Ollama-darwin.zip"), None);
+    }
+}
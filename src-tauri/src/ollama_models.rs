@@ -0,0 +1,317 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::borrow::Cow;
+use tauri::{Emitter, Manager};
+
+use crate::backend;
+use crate::context_window::ContextWindows;
+use crate::error::AppError;
+use crate::http::{self, RetryExt};
+use crate::ollama;
+use crate::progress::ProgressThrottle;
+use crate::settings;
+
+/// One line of the /api/pull progress stream
+#[derive(Debug, Deserialize)]
+struct PullLine<'a> {
+    #[serde(borrow)]
+    status: Option<Cow<'a, str>>,
+    total: Option<u64>,
+    completed: Option<u64>,
+    #[serde(borrow)]
+    error: Option<Cow<'a, str>>,
+}
+
+/// Download/pull a model from Ollama with streaming progress
+/// Used for Windows where WebView2 blocks fetch to localhost
+#[tauri::command]
+pub async fn download_ollama_model(
+    model_name: String,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    log::warn!("Starting download for model: {}", model_name);
+    let base_url = ollama::ollama_url(&app_handle);
+
+    // Call Ollama pull API with streaming enabled
+    let response = http::post(&format!("{}/api/pull", base_url))?
+        .json(&serde_json::json!({
+            "name": model_name,
+            "stream": true  // Enable streaming for progress updates
+        }))
+        .timeout(std::time::Duration::from_secs(1800)) // 30 minute timeout for large models
+        .send()
+        .await
+        .map_err(|e| AppError::ollama_request("Model download", e))?;
+
+    if !response.status().is_success() {
+        let error = AppError::ollama_status("Model download", &model_name, response.status());
+        log::error!("{}", error);
+        return Err(error);
+    }
+
+    // Stream the response and emit progress events
+    let mut stream = response.bytes_stream();
+    let mut buffer = ollama::NdjsonBuffer::default();
+    let mut throttle = ProgressThrottle::new();
+    let mut last_status = String::new();
+
+    while let Some(chunk_result) = stream.next().await {
+        let chunk = chunk_result.map_err(|e| AppError::ollama_request("Model download stream", e))?;
+
+        // Process complete JSON lines (newline-delimited JSON)
+        buffer.feed(&chunk, |line| {
+            // Parse JSON line and emit progress
+            let data = match serde_json::from_slice::<PullLine>(line) {
+                Ok(data) => data,
+                Err(_) => return Ok(()),
+            };
+            let total = data.total.unwrap_or(0);
+            let completed = data.completed.unwrap_or(0);
+
+            // Calculate percentage
+            let percent = if total > 0 {
+                (completed as f64 / total as f64) * 100.0
+            } else {
+                0.0
+            };
+
+            // Emit progress event for frontend (always on status change, otherwise throttled)
+            let status = data.status.as_deref().unwrap_or("");
+            let status_changed = status != last_status;
+            if status_changed {
+                last_status = status.to_string();
+            }
+            if throttle.should_emit(percent, status_changed) {
+                window.emit("model_download_progress", json!({
+                    "model": model_name,
+                    "status": status,
+                    "total": total,
+                    "completed": completed,
+                    "percent": percent
+                })).ok();
+            }
+
+            // Check for error in response
+            if let Some(error) = data.error {
+                log::error!("Ollama pull error: {}", error);
+                return Err(AppError::ollama_error(&error));
+            }
+            Ok(())
+        })?;
+    }
+
+    log::warn!("Successfully downloaded model: {}", model_name);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<TagsModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsModel {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified_at: Option<String>,
+    #[serde(default)]
+    digest: Option<String>,
+    #[serde(default)]
+    details: ModelDetails,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelDetails {
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+    /// e.g. "8.0B"
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    /// e.g. "Q4_K_M"
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
+
+/// An installed model as shown in the model manager
+#[derive(Debug, Serialize)]
+pub struct InstalledModel {
+    pub name: String,
+    size_bytes: u64,
+    modified_at: Option<String>,
+    pub digest: Option<String>,
+    details: ModelDetails,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelInfo {
+    name: String,
+    pub details: ModelDetails,
+    /// Context length reported by the model architecture, if known
+    pub context_length: Option<u64>,
+    license: Option<String>,
+    template: Option<String>,
+    parameters: Option<String>,
+}
+
+/// List installed models with size, parameter count and quantization
+#[tauri::command]
+pub async fn list_ollama_models(app_handle: tauri::AppHandle) -> Result<Vec<InstalledModel>, AppError> {
+    log::info!("Listing installed Ollama models");
+
+    let response = http::get(&format!("{}/api/tags", ollama::ollama_url(&app_handle)))?
+        .timeout(http::status_timeout())
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::ollama_request("Listing models", e))?;
+
+    if !response.status().is_success() {
+        return Err(AppError::status("Listing models", response.status()));
+    }
+
+    let data: TagsResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Parse(format!("Failed to parse model list: {}", e)))?;
+
+    Ok(data
+        .models
+        .into_iter()
+        .map(|m| InstalledModel {
+            name: m.name,
+            size_bytes: m.size,
+            modified_at: m.modified_at,
+            digest: m.digest,
+            details: m.details,
+        })
+        .collect())
+}
+
+/// Delete an installed model and free its disk space
+#[tauri::command]
+pub async fn delete_ollama_model(name: String, app_handle: tauri::AppHandle) -> Result<(), AppError> {
+    log::info!("Deleting Ollama model: {}", name);
+
+    let response = http::delete(&format!("{}/api/delete", ollama::ollama_url(&app_handle)))?
+        .json(&json!({ "model": name, "name": name }))
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| AppError::ollama_request("Deleting model", e))?;
+
+    if !response.status().is_success() {
+        return Err(AppError::ollama_status("Deleting model", &name, response.status()));
+    }
+
+    app_handle.state::<ContextWindows>().forget(&name);
+    log::info!("Deleted model: {}", name);
+    Ok(())
+}
+
+/// Show details for one model (family, parameters, quantization, context length, license)
+#[tauri::command]
+pub async fn show_model_info(name: String, app_handle: tauri::AppHandle) -> Result<ModelInfo, AppError> {
+    log::info!("Fetching model info: {}", name);
+    fetch_model_info(&app_handle, name).await
+}
+
+/// `/api/show` for one model
+pub(crate) async fn fetch_model_info(app_handle: &tauri::AppHandle, name: String) -> Result<ModelInfo, AppError> {
+    let response = http::post(&format!("{}/api/show", ollama::ollama_url(app_handle)))?
+        .json(&json!({ "model": name, "name": name }))
+        .timeout(http::status_timeout())
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::ollama_request("Fetching model info", e))?;
+
+    if !response.status().is_success() {
+        return Err(AppError::ollama_status("Fetching model info", &name, response.status()));
+    }
+
+    let data: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| AppError::Parse(format!("Failed to parse model info: {}", e)))?;
+
+    let details: ModelDetails = serde_json::from_value(data["details"].clone()).unwrap_or_default();
+
+    // model_info keys are prefixed with the architecture, e.g. "llama.context_length"
+    let context_length = data["model_info"].as_object().and_then(|info| {
+        info.iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+    });
+
+    let text = |key: &str| data[key].as_str().map(String::from);
+
+    Ok(ModelInfo {
+        name,
+        details,
+        context_length,
+        license: text("license"),
+        template: text("template"),
+        parameters: text("parameters"),
+    })
+}
+
+/// Send an empty generate request, which loads or unloads a model without generating
+async fn set_model_loaded(app_handle: &tauri::AppHandle, model: &str, keep_alive: serde_json::Value) -> Result<(), AppError> {
+    if settings::read_settings(app_handle).llm_backend != backend::BackendKind::Ollama {
+        return Err(AppError::Unsupported(
+            "Loading and unloading models is only supported with Ollama".to_string(),
+        ));
+    }
+
+    let response = http::post(&format!("{}/api/generate", ollama::ollama_url(app_handle)))?
+        .json(&json!({ "model": model, "keep_alive": keep_alive }))
+        // Loading a large model from disk into VRAM can take a minute or more
+        .timeout(std::time::Duration::from_secs(300))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::ollama_request("Loading model", e))?;
+
+    if !response.status().is_success() {
+        return Err(AppError::ollama_status("Loading model", model, response.status()));
+    }
+    Ok(())
+}
+
+/// Load a model into memory ahead of the first question
+///
+/// Without this the first question pays the 20-60 s load time. The model stays
+/// loaded for `keep_alive` (e.g. "30m", "-1" for forever), defaulting to the
+/// keep-alive in settings.
+#[tauri::command]
+pub async fn preload_model(
+    model: Option<String>,
+    keep_alive: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    let settings = settings::read_settings(&app_handle);
+    let model = model.unwrap_or(settings.ollama_model);
+    let keep_alive = keep_alive
+        .or(settings.generation.keep_alive)
+        .unwrap_or_else(|| ollama::DEFAULT_KEEP_ALIVE.to_string());
+    log::info!("Preloading model {} (keep_alive {})", model, keep_alive);
+
+    let started = std::time::Instant::now();
+    set_model_loaded(&app_handle, &model, backend::keep_alive_value(&keep_alive)).await?;
+
+    log::info!("Model {} loaded in {:.1}s", model, started.elapsed().as_secs_f32());
+    Ok(())
+}
+
+/// Unload a model right away to free RAM/VRAM
+#[tauri::command]
+pub async fn unload_model(model: String, app_handle: tauri::AppHandle) -> Result<(), AppError> {
+    log::info!("Unloading model {}", model);
+    set_model_loaded(&app_handle, &model, json!(0)).await?;
+    log::info!("Model {} unloaded", model);
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::backend::{ChatOptions, ChatStreamMessage, ChatUsage, ChunkSink, LlmBackend};
+use crate::error::AppError;
+use crate::http::{self, RetryExt};
+use crate::ollama::{ChatMessage, NdjsonBuffer};
+
+/// Client for servers implementing the OpenAI chat completions and embeddings API
+pub struct OpenAiCompatibleBackend {
+    /// Base URL including the version prefix, e.g. http://127.0.0.1:1234/v1
+    pub(crate) base_url: String,
+    pub(crate) api_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    choices: Vec<CompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChoice {
+    message: ChatMessage,
+}
+
+/// One `data:` event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+struct CompletionChunk<'a> {
+    #[serde(borrow, default)]
+    choices: Vec<CompletionChunkChoice<'a>>,
+    /// Sent on the last event by servers that support `stream_options.include_usage`
+    usage: Option<CompletionUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionUsage {
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChunkChoice<'a> {
+    #[serde(borrow)]
+    delta: Option<ChatStreamMessage<'a>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbedding {
+    embedding: Vec<f64>,
+}
+
+impl OpenAiCompatibleBackend {
+    fn post(&self, path: &str) -> Result<reqwest::RequestBuilder, AppError> {
+        let request = http::post(&format!("{}{}", self.base_url, path))?;
+        Ok(match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        })
+    }
+
+    fn chat_body(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions, stream: bool) -> serde_json::Value {
+        let mut body = json!({
+            "model": model,
+            "messages": messages.iter().map(openai_message).collect::<Vec<_>>(),
+            "stream": stream,
+            "temperature": options.temperature,
+            "top_p": options.top_p,
+            "max_tokens": options.max_tokens,
+        });
+        if let Some(seed) = options.generation.seed {
+            body["seed"] = json!(seed);
+        }
+        if !options.generation.stop.is_empty() {
+            body["stop"] = json!(options.generation.stop);
+        }
+        if stream {
+            // Token counts for `chat_stats`; servers without usage reporting ignore it
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+        body
+    }
+}
+
+/// A chat message in OpenAI format; images become `image_url` parts with data URLs
+fn openai_message(message: &ChatMessage) -> serde_json::Value {
+    if message.images.is_empty() {
+        return json!({ "role": message.role, "content": message.content });
+    }
+    let mut parts = vec![json!({ "type": "text", "text": message.content })];
+    for image in &message.images {
+        // Base64 of a JPEG starts with the encoded FF D8 FF marker
+        let mime = if image.starts_with("/9j/") { "image/jpeg" } else { "image/png" };
+        parts.push(json!({
+            "type": "image_url",
+            "image_url": { "url": format!("data:{};base64,{}", mime, image) },
+        }));
+    }
+    json!({ "role": message.role, "content": parts })
+}
+
+/// Turn an HTTP error status into an error, including the server's error text if any
+async fn error_message(what: &str, model: &str, response: reqwest::Response) -> AppError {
+    let status = response.status();
+    let detail = response
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|body| body["error"]["message"].as_str().map(String::from));
+    let message = match detail {
+        Some(detail) => format!("{} failed: HTTP {} ({})", what, status, detail),
+        None => format!("{} failed: HTTP {}", what, status),
+    };
+    if status == reqwest::StatusCode::NOT_FOUND && message.contains("model") {
+        return AppError::ModelNotFound(format!("Model not found: {} ({})", model, message));
+    }
+    AppError::Http {
+        status: status.as_u16(),
+        message,
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiCompatibleBackend {
+    fn name(&self) -> &'static str {
+        "openai_compatible"
+    }
+
+    async fn chat(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<String, AppError> {
+        let response = self
+            .post("/chat/completions")?
+            .json(&self.chat_body(model, messages, options, false))
+            .timeout(options.timeout)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::request("Chat request", e))?;
+
+        if !response.status().is_success() {
+            return Err(error_message("Chat", model, response).await);
+        }
+
+        let data: CompletionResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Parse(format!("Failed to parse response: {}", e)))?;
+        data.choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| AppError::Parse("Chat response contained no choices".to_string()))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_chunk: &mut ChunkSink<'_>,
+    ) -> Result<ChatUsage, AppError> {
+        let response = self
+            .post("/chat/completions")?
+            .json(&self.chat_body(model, messages, options, true))
+            .timeout(options.timeout)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::request("Chat request", e))?;
+
+        if !response.status().is_success() {
+            return Err(error_message("Chat", model, response).await);
+        }
+
+        // Server-sent events: one "data: {json}" line per delta, ending with "data: [DONE]"
+        let mut stream = response.bytes_stream();
+        let mut buffer = NdjsonBuffer::default();
+        let mut finished = false;
+        let mut usage = ChatUsage::default();
+
+        while let Some(chunk_result) = stream.next().await {
+            let chunk = chunk_result.map_err(|e| AppError::request("Chat stream", e))?;
+
+            buffer.feed::<_, AppError>(&chunk, |line| {
+                let Some(data) = std::str::from_utf8(line).ok().and_then(|l| l.strip_prefix("data:")) else {
+                    return Ok(());
+                };
+                let data = data.trim();
+                if data == "[DONE]" {
+                    finished = true;
+                    return Ok(());
+                }
+                match serde_json::from_str::<CompletionChunk>(data) {
+                    Ok(event) => {
+                        if let Some(reported) = event.usage {
+                            usage.prompt_tokens = reported.prompt_tokens;
+                            usage.completion_tokens = reported.completion_tokens;
+                        }
+                        let content = event
+                            .choices
+                            .into_iter()
+                            .next()
+                            .and_then(|choice| choice.delta)
+                            .and_then(|delta| delta.content);
+                        if let Some(content) = content.filter(|c| !c.is_empty()) {
+                            on_chunk(&content, false);
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to parse stream event: {}", e);
+                    }
+                }
+                Ok(())
+            })?;
+
+            if finished {
+                break;
+            }
+        }
+
+        on_chunk("", true);
+        Ok(usage)
+    }
+
+    async fn embed(&self, model: &str, text: &str) -> Result<Vec<f64>, AppError> {
+        let response = self
+            .post("/embeddings")?
+            .json(&json!({
+                "model": model,
+                "input": text,
+            }))
+            .timeout(http::read_timeout())
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::request("Embedding request", e))?;
+
+        if !response.status().is_success() {
+            return Err(error_message("Embedding", model, response).await);
+        }
+
+        let data: OpenAiEmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Parse(format!("Failed to parse response: {}", e)))?;
+        data.data
+            .into_iter()
+            .next()
+            .map(|e| e.embedding)
+            .ok_or_else(|| AppError::Parse("Embedding response contained no data".to_string()))
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::AppError;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Oldest pandoc with the reader set and `--wrap=none` we rely on
+const MIN_MAJOR_VERSION: u32 = 2;
+
+/// File extensions we hand to pandoc, with the matching pandoc reader
+const INPUT_FORMATS: &[(&str, &str)] = &[
+    ("odt", "odt"),
+    ("rtf", "rtf"),
+    ("tex", "latex"),
+    ("latex", "latex"),
+    ("org", "org"),
+    ("rst", "rst"),
+    ("textile", "textile"),
+    ("wiki", "mediawiki"),
+    ("fb2", "fb2"),
+    ("opml", "opml"),
+    ("dbk", "docbook"),
+    ("ipynb", "ipynb"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct PandocStatus {
+    available: bool,
+    path: Option<String>,
+    version: Option<String>,
+    /// Extensions that can be converted with the detected pandoc
+    supported_extensions: Vec<String>,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertedDocument {
+    /// Converted document as Markdown (keeps headings and lists for chunking)
+    text: String,
+    input_format: String,
+}
+
+fn pandoc_command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// Locations to try, in order: PATH first, then common install directories
+fn candidate_paths() -> Vec<String> {
+    let mut paths = vec!["pandoc".to_string()];
+
+    #[cfg(target_os = "windows")]
+    {
+        let localappdata = std::env::var("LOCALAPPDATA").unwrap_or_default();
+        let programfiles = std::env::var("PROGRAMFILES").unwrap_or_default();
+        paths.push(format!(r"{}\Pandoc\pandoc.exe", localappdata));
+        paths.push(format!(r"{}\Pandoc\pandoc.exe", programfiles));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = std::env::var("HOME").unwrap_or_default();
+        paths.push("/opt/homebrew/bin/pandoc".to_string());
+        paths.push("/usr/local/bin/pandoc".to_string());
+        paths.push("/usr/bin/pandoc".to_string());
+        paths.push(format!("{}/.local/bin/pandoc", home));
+    }
+
+    paths
+}
+
+/// Run `pandoc --version` and return the version string if it's usable
+fn probe(program: &str) -> Option<String> {
+    let output = pandoc_command(program).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // First line looks like "pandoc 3.1.11" (or "pandoc.exe 3.1.11" on Windows)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next()?.split_whitespace().nth(1)?.to_string();
+    let major: u32 = version.split('.').next()?.parse().ok()?;
+
+    if major < MIN_MAJOR_VERSION {
+        log::warn!("pandoc {} at {} is too old (need {}.x or newer)", version, program, MIN_MAJOR_VERSION);
+        return None;
+    }
+    Some(version)
+}
+
+/// Find a working pandoc binary
+fn find_pandoc() -> Option<(String, String)> {
+    candidate_paths().into_iter().find_map(|path| {
+        if path != "pandoc" && !Path::new(&path).exists() {
+            return None;
+        }
+        probe(&path).map(|version| (path, version))
+    })
+}
+
+/// Report whether pandoc is installed and which formats it unlocks
+#[tauri::command]
+pub async fn get_pandoc_status() -> Result<PandocStatus, AppError> {
+    let found = tauri::async_runtime::spawn_blocking(find_pandoc)
+        .await
+        .map_err(|e| format!("Pandoc task failed: {}", e))?;
+    Ok(match found {
+        Some((path, version)) => {
+            log::info!("Found pandoc {} at {}", version, path);
+            PandocStatus {
+                available: true,
+                path: Some(path),
+                version: Some(version),
+                supported_extensions: INPUT_FORMATS.iter().map(|(ext, _)| ext.to_string()).collect(),
+                message: "Pandoc is available for ODT, RTF, LaTeX and other formats.".to_string(),
+            }
+        }
+        None => PandocStatus {
+            available: false,
+            path: None,
+            version: None,
+            supported_extensions: vec![],
+            message: format!(
+                "Pandoc {}.0 or newer was not found. Install it from https://pandoc.org/installing.html to import ODT, RTF, LaTeX and similar formats.",
+                MIN_MAJOR_VERSION
+            ),
+        },
+    })
+}
+
+/// Convert a document to Markdown text with the user's pandoc install
+#[tauri::command]
+pub async fn convert_with_pandoc(path: String) -> Result<ConvertedDocument, AppError> {
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let input_format = INPUT_FORMATS
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, format)| format.to_string())
+        .ok_or_else(|| format!("Pandoc conversion is not supported for .{} files", extension))?;
+
+    log::info!("Converting {} with pandoc ({})", path, input_format);
+    let format = input_format.clone();
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        let (pandoc, _) = find_pandoc().ok_or("Pandoc is not installed")?;
+        pandoc_command(&pandoc)
+            .args(["--from", format.as_str(), "--to", "gfm", "--wrap=none"])
+            // A file named like an option ("--lua-filter=...") must stay a file name
+            .arg("--")
+            .arg(&path)
+            .output()
+            .map_err(|e| format!("Failed to run pandoc: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Pandoc task failed: {}", e))??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Other(format!("Pandoc failed to convert the file: {}", stderr.trim())));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    log::info!("Pandoc conversion produced {} chars", text.len());
+    Ok(ConvertedDocument { text, input_format })
+}
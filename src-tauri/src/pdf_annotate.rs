@@ -0,0 +1,331 @@
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::pdf;
+use crate::pdf_layout::{self, TextRun};
+
+/// Words matched at the start of a snippet to find where it sits on the page
+const ANCHOR_WORDS: usize = 8;
+/// Shorter anchor tried when the full one isn't found (hyphenation, ligatures)
+const MIN_ANCHOR_WORDS: usize = 4;
+/// Size of a sticky-note icon, in points
+const NOTE_SIZE: f32 = 20.0;
+/// Highlight opacity; the appearance also uses the multiply blend mode so text stays readable
+const HIGHLIGHT_OPACITY: f32 = 0.4;
+/// Author shown by PDF viewers for the annotations we add
+const AUTHOR: &str = "PrivatePDF";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// Highlight the cited text, with the comment (if any) as its popup note
+    #[default]
+    Highlight,
+    /// A sticky note beside the cited text
+    Comment,
+}
+
+/// One annotation to write, typically built from a RAG citation
+#[derive(Debug, Clone, Deserialize)]
+pub struct Annotation {
+    /// 1-based page number
+    page: u32,
+    #[serde(default)]
+    kind: AnnotationKind,
+    /// Text to locate on the page, e.g. the citation snippet
+    #[serde(default)]
+    text: Option<String>,
+    /// Note shown when the annotation is opened, e.g. the question it answers
+    #[serde(default)]
+    comment: Option<String>,
+    /// Explicit area [x0, y0, x1, y1] in PDF points, used when `text` isn't given or isn't found
+    #[serde(default)]
+    rect: Option<[f32; 4]>,
+    /// RGB components from 0 to 1; yellow by default
+    #[serde(default)]
+    color: Option<[f32; 3]>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnotatedPdf {
+    output_path: String,
+    /// Annotations written to the copy
+    added: usize,
+    /// Indexes (into the request) of annotations whose text wasn't found; they were
+    /// written as notes at the top of the page instead, or skipped when on a missing page
+    unlocated: Vec<usize>,
+}
+
+/// Lowercased alphanumeric words, so snippets match page text despite punctuation and "…"
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Bounding box [x0, y0, x1, y1] of a line of runs, from its descenders to its ascenders
+fn line_box(line: &[TextRun]) -> [f32; 4] {
+    let x0 = line.iter().map(|r| r.x).fold(f32::MAX, f32::min);
+    let x1 = line.iter().map(|r| r.x_end).fold(f32::MIN, f32::max);
+    let y = line.iter().map(|r| r.y).fold(f32::MAX, f32::min);
+    let size = line.iter().map(|r| r.size).fold(0.0, f32::max);
+    [x0, y - size * 0.25, x1, y + size * 0.9]
+}
+
+/// Boxes of the page lines covered by `text`, or None if it can't be found
+///
+/// Glyph widths are estimated (see `pdf_layout`), so boxes span whole lines rather
+/// than the exact characters of the snippet.
+fn locate(lines: &[Vec<TextRun>], text: &str) -> Option<Vec<[f32; 4]>> {
+    let target = words(text);
+    if target.is_empty() {
+        return None;
+    }
+
+    // Every word on the page with the line it's on
+    let page: Vec<(usize, String)> = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            let text = line.iter().map(|r| r.text.as_str()).collect::<Vec<_>>().join(" ");
+            words(&text).into_iter().map(move |w| (i, w))
+        })
+        .collect();
+
+    let start = [ANCHOR_WORDS, MIN_ANCHOR_WORDS].iter().find_map(|&n| {
+        let anchor = &target[..n.min(target.len())];
+        page.windows(anchor.len())
+            .position(|window| window.iter().map(|(_, w)| w).eq(anchor.iter()))
+    })?;
+    let end = (start + target.len()).min(page.len()) - 1;
+
+    let (first, last) = (page[start].0, page[end].0);
+    Some(lines[first..=last].iter().map(|line| line_box(line)).collect())
+}
+
+/// Page size from its (possibly inherited) MediaBox, defaulting to US Letter
+fn media_box(doc: &Document, page_id: ObjectId) -> [f32; 4] {
+    let mut current = doc.get_dictionary(page_id).ok();
+    while let Some(dict) = current {
+        if let Ok(values) = dict.get(b"MediaBox").and_then(|o| o.as_array()) {
+            let values: Vec<f32> = values.iter().filter_map(|v| v.as_float().ok()).collect();
+            if let [x0, y0, x1, y1] = values[..] {
+                return [x0, y0, x1, y1];
+            }
+        }
+        current = dict
+            .get(b"Parent")
+            .and_then(|o| o.as_reference())
+            .and_then(|id| doc.get_dictionary(id))
+            .ok();
+    }
+    [0.0, 0.0, 612.0, 792.0]
+}
+
+/// A PDF text string; UTF-16 with a byte order mark when it isn't plain ASCII
+fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::string_literal(text);
+    }
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+fn numbers(values: &[f32]) -> Object {
+    Object::Array(values.iter().map(|&v| Object::Real(v)).collect())
+}
+
+/// Union of line boxes
+fn bounds(boxes: &[[f32; 4]]) -> [f32; 4] {
+    boxes.iter().fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |acc, b| {
+        [acc[0].min(b[0]), acc[1].min(b[1]), acc[2].max(b[2]), acc[3].max(b[3])]
+    })
+}
+
+/// Appearance stream drawing the highlight, for viewers that don't generate one
+fn highlight_appearance(doc: &mut Document, rect: [f32; 4], boxes: &[[f32; 4]], color: [f32; 3]) -> ObjectId {
+    let mut content = format!("/GS0 gs {} {} {} rg\n", color[0], color[1], color[2]);
+    for b in boxes {
+        content.push_str(&format!("{} {} {} {} re f\n", b[0], b[1], b[2] - b[0], b[3] - b[1]));
+    }
+    let dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Form",
+        "BBox" => numbers(&rect),
+        "Resources" => dictionary! {
+            "ExtGState" => dictionary! {
+                "GS0" => dictionary! {
+                    "Type" => "ExtGState",
+                    "BM" => "Multiply",
+                    "ca" => Object::Real(HIGHLIGHT_OPACITY),
+                    "CA" => Object::Real(HIGHLIGHT_OPACITY),
+                },
+            },
+        },
+    };
+    doc.add_object(Stream::new(dict, content.into_bytes()))
+}
+
+fn highlight(doc: &mut Document, boxes: &[[f32; 4]], color: [f32; 3], comment: Option<&str>) -> Dictionary {
+    let rect = bounds(boxes);
+    // Each quad is upper-left, upper-right, lower-left, lower-right
+    let quads: Vec<f32> = boxes
+        .iter()
+        .flat_map(|b| [b[0], b[3], b[2], b[3], b[0], b[1], b[2], b[1]])
+        .collect();
+    let appearance = highlight_appearance(doc, rect, boxes, color);
+
+    let mut annot = dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Highlight",
+        "Rect" => numbers(&rect),
+        "QuadPoints" => numbers(&quads),
+        "C" => numbers(&color),
+        "CA" => Object::Real(HIGHLIGHT_OPACITY),
+        "T" => text_string(AUTHOR),
+        // Print
+        "F" => 4,
+        "AP" => dictionary! { "N" => appearance },
+    };
+    if let Some(comment) = comment {
+        annot.set("Contents", text_string(comment));
+    }
+    annot
+}
+
+/// Sticky note with its top-left corner at (x, y), kept inside the page
+fn note(page: [f32; 4], x: f32, y: f32, color: [f32; 3], comment: &str) -> Dictionary {
+    let x = x.clamp(page[0], page[2] - NOTE_SIZE);
+    let y = y.clamp(page[1] + NOTE_SIZE, page[3]);
+    dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Text",
+        "Rect" => numbers(&[x, y - NOTE_SIZE, x + NOTE_SIZE, y]),
+        "Name" => "Comment",
+        "C" => numbers(&color),
+        "T" => text_string(AUTHOR),
+        "Contents" => text_string(comment),
+        "F" => 4,
+    }
+}
+
+/// Append an annotation to the page's /Annots, which may be inline or a reference
+fn attach(doc: &mut Document, page_id: ObjectId, annot: Dictionary) -> Result<(), String> {
+    let annot_id = doc.add_object(annot);
+    let existing = doc
+        .get_dictionary(page_id)
+        .map_err(|e| format!("Failed to read page: {}", e))?
+        .get(b"Annots")
+        .ok()
+        .cloned();
+
+    match existing {
+        Some(Object::Reference(id)) => {
+            doc.get_object_mut(id)
+                .and_then(Object::as_array_mut)
+                .map_err(|e| format!("Failed to read page annotations: {}", e))?
+                .push(Object::Reference(annot_id));
+        }
+        other => {
+            let mut annots = match other {
+                Some(Object::Array(items)) => items,
+                _ => Vec::new(),
+            };
+            annots.push(Object::Reference(annot_id));
+            doc.get_dictionary_mut(page_id)
+                .map_err(|e| format!("Failed to read page: {}", e))?
+                .set("Annots", annots);
+        }
+    }
+    Ok(())
+}
+
+fn annotate(path: &str, annotations: &[Annotation], output_path: &str) -> Result<AnnotatedPdf, String> {
+    let mut doc = pdf::load_document(path)?;
+    let pages = doc.get_pages();
+    let mut added = 0;
+    let mut unlocated = Vec::new();
+
+    for (index, annotation) in annotations.iter().enumerate() {
+        let Some(&page_id) = pages.get(&annotation.page) else {
+            log::warn!("Skipping annotation {}: page {} doesn't exist", index, annotation.page);
+            unlocated.push(index);
+            continue;
+        };
+        let color = annotation.color.unwrap_or([1.0, 0.9, 0.0]);
+        let comment = annotation.comment.as_deref().filter(|c| !c.trim().is_empty());
+
+        let located = match annotation.text.as_deref() {
+            Some(text) => match pdf_layout::page_layout(&doc, page_id) {
+                Ok((runs, _)) => locate(&pdf_layout::text_lines(runs), text),
+                Err(e) => {
+                    log::warn!("Failed to read text on page {}: {}", annotation.page, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        if annotation.text.is_some() && located.is_none() {
+            unlocated.push(index);
+        }
+        let boxes = located.or_else(|| annotation.rect.map(|rect| vec![rect]));
+        let page = media_box(&doc, page_id);
+
+        let annot = match (annotation.kind, boxes) {
+            (AnnotationKind::Highlight, Some(boxes)) => highlight(&mut doc, &boxes, color, comment),
+            (AnnotationKind::Comment, Some(boxes)) => {
+                let [_, _, x1, y1] = bounds(&boxes);
+                note(page, x1 + 4.0, y1, color, comment.unwrap_or_default())
+            }
+            // Nowhere to put it: leave the note at the top of the page so it isn't lost
+            (_, None) => {
+                let text = comment.or(annotation.text.as_deref()).unwrap_or_default();
+                note(page, page[0] + 8.0, page[3] - 8.0, color, text)
+            }
+        };
+        attach(&mut doc, page_id, annot)?;
+        added += 1;
+    }
+
+    doc.save(output_path)
+        .map_err(|e| format!("Failed to write annotated PDF: {}", e))?;
+
+    Ok(AnnotatedPdf {
+        output_path: output_path.to_string(),
+        added,
+        unlocated,
+    })
+}
+
+/// Write highlight and comment annotations onto a copy of a PDF
+///
+/// Each annotation is placed on the text it cites (e.g. a RAG citation snippet), so
+/// the saved copy shows where an answer came from. The original file is never modified.
+#[tauri::command]
+pub async fn annotate_pdf(
+    path: String,
+    annotations: Vec<Annotation>,
+    output_path: String,
+) -> Result<AnnotatedPdf, AppError> {
+    log::info!("Annotating {} with {} annotations", path, annotations.len());
+
+    if Path::new(&path) == Path::new(&output_path) {
+        return Err(AppError::Other("The annotated copy must be saved to a different file".to_string()));
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || annotate(&path, &annotations, &output_path))
+        .await
+        .map_err(|e| format!("Annotation task failed: {}", e))??;
+
+    log::info!(
+        "Annotated PDF written to {} ({} added, {} not located)",
+        result.output_path,
+        result.added,
+        result.unlocated.len()
+    );
+    Ok(result)
+}
@@ -0,0 +1,130 @@
+use base64::Engine;
+use image::{DynamicImage, ImageBuffer, ImageFormat, Luma, Rgb};
+use lopdf::Document;
+use serde::Serialize;
+use std::io::Cursor;
+
+use crate::error::AppError;
+use crate::pdf;
+
+/// Images smaller than this on either side are bullets, rules or masks, not figures
+const MIN_IMAGE_SIZE: u32 = 32;
+
+#[derive(Debug, Serialize)]
+pub struct PdfImage {
+    /// 1-based page number
+    page: u32,
+    /// Position among the page's images
+    index: usize,
+    width: u32,
+    height: u32,
+    /// PNG, ready to pass in a chat message's `images`
+    png_base64: String,
+}
+
+/// Convert raw 8-bit samples to an image, inferring the color model from the size
+fn raw_image(width: u32, height: u32, data: &[u8]) -> Option<DynamicImage> {
+    let pixels = width as usize * height as usize;
+    if pixels == 0 {
+        return None;
+    }
+    match data.len() / pixels {
+        1 => ImageBuffer::<Luma<u8>, _>::from_raw(width, height, data[..pixels].to_vec()).map(DynamicImage::ImageLuma8),
+        3 => ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, data[..pixels * 3].to_vec()).map(DynamicImage::ImageRgb8),
+        4 => {
+            // DeviceCMYK
+            let rgb = data[..pixels * 4]
+                .chunks_exact(4)
+                .flat_map(|p| {
+                    let k = 255 - p[3] as u16;
+                    [0, 1, 2].map(|i| ((255 - p[i] as u16) * k / 255) as u8)
+                })
+                .collect();
+            ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, rgb).map(DynamicImage::ImageRgb8)
+        }
+        _ => None,
+    }
+}
+
+/// Decode one image XObject; None for formats we can't convert (JPEG 2000, CCITT, JBIG2, < 8 bits)
+fn decode_image(doc: &Document, image: &lopdf::xobject::PdfImage) -> Result<Option<DynamicImage>, String> {
+    let filters = image.filters.clone().unwrap_or_default();
+    let width = image.width as u32;
+    let height = image.height as u32;
+
+    if filters.iter().any(|f| f == "DCTDecode") {
+        return image::load_from_memory_with_format(image.content, ImageFormat::Jpeg)
+            .map(Some)
+            .map_err(|e| format!("Failed to decode JPEG image: {}", e));
+    }
+    if filters.iter().any(|f| f != "FlateDecode") || image.bits_per_component.unwrap_or(8) != 8 {
+        log::info!("Skipping image {:?} with filters {:?}", image.id, filters);
+        return Ok(None);
+    }
+
+    let data = if filters.is_empty() {
+        image.content.to_vec()
+    } else {
+        doc.get_object(image.id)
+            .and_then(|o| o.as_stream())
+            .map_err(|e| format!("Failed to read image: {}", e))?
+            .decompressed_content()
+            .map_err(|e| format!("Failed to decompress image: {}", e))?
+    };
+    Ok(raw_image(width, height, &data))
+}
+
+fn to_png_base64(image: &DynamicImage) -> Result<String, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+/// Extract the images embedded in one page of a PDF as base64 PNG
+///
+/// Meant for figures and diagrams to send to a vision model; tiny images and
+/// formats that can't be decoded are skipped.
+#[tauri::command]
+pub async fn extract_pdf_images(path: String, page: u32) -> Result<Vec<PdfImage>, AppError> {
+    log::info!("Extracting images from page {} of {}", page, path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let doc = pdf::load_document(&path)?;
+        let page_id = *doc
+            .get_pages()
+            .get(&page)
+            .ok_or_else(|| format!("Page {} does not exist", page))?;
+        let images = doc
+            .get_page_images(page_id)
+            .map_err(|e| format!("Failed to read page images: {}", e))?;
+
+        let mut extracted = Vec::new();
+        for image in &images {
+            if image.width < MIN_IMAGE_SIZE as i64 || image.height < MIN_IMAGE_SIZE as i64 {
+                continue;
+            }
+            let decoded = match decode_image(&doc, image) {
+                Ok(Some(decoded)) => decoded,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    continue;
+                }
+            };
+            extracted.push(PdfImage {
+                page,
+                index: extracted.len(),
+                width: decoded.width(),
+                height: decoded.height(),
+                png_base64: to_png_base64(&decoded)?,
+            });
+        }
+
+        log::info!("Extracted {} of {} images", extracted.len(), images.len());
+        Ok(extracted)
+    })
+    .await
+    .map_err(|e| format!("Image extraction task failed: {}", e))?
+}
@@ -0,0 +1,317 @@
+use lopdf::{Document, Object, ObjectId};
+
+/// Lines closer than this (in points) are treated as the same rule
+pub(crate) const RULE_TOLERANCE: f32 = 2.0;
+/// Filled rectangles thinner than this are drawn rules, not cell backgrounds
+const THIN_RECT: f32 = 2.0;
+/// Approximate glyph width as a fraction of the font size; widths aren't read
+/// from the font, so cell extents are estimates
+pub(crate) const GLYPH_WIDTH: f32 = 0.5;
+
+/// Affine transform [a b c d e f] in PDF's row-vector convention
+#[derive(Debug, Clone, Copy)]
+struct Matrix([f32; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn translate(x: f32, y: f32) -> Matrix {
+        Matrix([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    /// `self` applied first, then `other`
+    fn then(self, other: Matrix) -> Matrix {
+        let [a, b, c, d, e, f] = self.0;
+        let [oa, ob, oc, od, oe, of] = other.0;
+        Matrix([
+            a * oa + b * oc,
+            a * ob + b * od,
+            c * oa + d * oc,
+            c * ob + d * od,
+            e * oa + f * oc + oe,
+            e * ob + f * od + of,
+        ])
+    }
+
+    fn apply(self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// Length of a unit vertical vector after the transform (the effective font size)
+    fn vertical_scale(self) -> f32 {
+        let [_, _, c, d, _, _] = self.0;
+        (c * c + d * d).sqrt()
+    }
+}
+
+/// A run of text at its baseline origin, in page coordinates (y grows upwards)
+#[derive(Debug, Clone)]
+pub(crate) struct TextRun {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) x_end: f32,
+    pub(crate) size: f32,
+    pub(crate) text: String,
+}
+
+/// An axis-aligned line segment in page coordinates
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Segment {
+    pub(crate) x0: f32,
+    pub(crate) y0: f32,
+    pub(crate) x1: f32,
+    pub(crate) y1: f32,
+}
+
+impl Segment {
+    pub(crate) fn new(x0: f32, y0: f32, x1: f32, y1: f32) -> Segment {
+        Segment {
+            x0: x0.min(x1),
+            y0: y0.min(y1),
+            x1: x0.max(x1),
+            y1: y0.max(y1),
+        }
+    }
+
+    pub(crate) fn is_horizontal(&self) -> bool {
+        self.y1 - self.y0 <= RULE_TOLERANCE && self.x1 - self.x0 > RULE_TOLERANCE
+    }
+
+    pub(crate) fn is_vertical(&self) -> bool {
+        self.x1 - self.x0 <= RULE_TOLERANCE && self.y1 - self.y0 > RULE_TOLERANCE
+    }
+
+    pub(crate) fn touches(&self, other: &Segment) -> bool {
+        self.x0 <= other.x1 + RULE_TOLERANCE
+            && other.x0 <= self.x1 + RULE_TOLERANCE
+            && self.y0 <= other.y1 + RULE_TOLERANCE
+            && other.y0 <= self.y1 + RULE_TOLERANCE
+    }
+}
+
+/// Decode a PDF string operand
+///
+/// Font encodings aren't resolved: UTF-16 strings (with BOM) are decoded and
+/// everything else is read as Latin-1, which covers the simple fonts most
+/// generated tables use. Control characters are dropped.
+fn decode_pdf_string(bytes: &[u8]) -> String {
+    let text = if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    };
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+fn number(operands: &[Object], i: usize) -> f32 {
+    operands.get(i).and_then(|o| o.as_float().ok()).unwrap_or(0.0)
+}
+
+/// Text state while walking a content stream
+struct TextState {
+    ctm: Matrix,
+    tm: Matrix,
+    tlm: Matrix,
+    size: f32,
+    leading: f32,
+    char_spacing: f32,
+    h_scale: f32,
+}
+
+impl TextState {
+    fn next_line(&mut self, tx: f32, ty: f32) {
+        self.tlm = Matrix::translate(tx, ty).then(self.tlm);
+        self.tm = self.tlm;
+    }
+
+    /// Record `text` at the current position and advance past it
+    fn show(&mut self, text: &str, runs: &mut Vec<TextRun>) {
+        let chars = text.chars().count() as f32;
+        let width = chars * (self.size * GLYPH_WIDTH + self.char_spacing) * self.h_scale;
+        let trm = self.tm.then(self.ctm);
+        let (x, y) = trm.apply(0.0, 0.0);
+        let (x_end, _) = trm.apply(width, 0.0);
+        if !text.trim().is_empty() {
+            runs.push(TextRun {
+                x,
+                y,
+                x_end,
+                size: (self.size * trm.vertical_scale()).max(1.0),
+                text: text.to_string(),
+            });
+        }
+        self.advance(width);
+    }
+
+    fn advance(&mut self, width: f32) {
+        self.tm = Matrix::translate(width, 0.0).then(self.tm);
+    }
+}
+
+/// Text runs and ruling lines drawn on one page
+pub(crate) fn page_layout(doc: &Document, page_id: ObjectId) -> Result<(Vec<TextRun>, Vec<Segment>), String> {
+    let content = doc
+        .get_and_decode_page_content(page_id)
+        .map_err(|e| format!("Failed to read page content: {}", e))?;
+
+    let mut runs = Vec::new();
+    let mut segments = Vec::new();
+    let mut stack = Vec::new();
+    let mut state = TextState {
+        ctm: Matrix::IDENTITY,
+        tm: Matrix::IDENTITY,
+        tlm: Matrix::IDENTITY,
+        size: 10.0,
+        leading: 0.0,
+        char_spacing: 0.0,
+        h_scale: 1.0,
+    };
+    // Path under construction: segments with whether they come from a thin rectangle
+    let mut path: Vec<(Segment, bool)> = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut subpath_start = (0.0, 0.0);
+
+    for op in &content.operations {
+        let operands = &op.operands;
+        match op.operator.as_str() {
+            "q" => stack.push(state.ctm),
+            "Q" => state.ctm = stack.pop().unwrap_or(Matrix::IDENTITY),
+            "cm" => {
+                let m = Matrix([0, 1, 2, 3, 4, 5].map(|i| number(operands, i)));
+                state.ctm = m.then(state.ctm);
+            }
+            "m" => {
+                current = state.ctm.apply(number(operands, 0), number(operands, 1));
+                subpath_start = current;
+            }
+            "l" => {
+                let next = state.ctm.apply(number(operands, 0), number(operands, 1));
+                path.push((Segment::new(current.0, current.1, next.0, next.1), false));
+                current = next;
+            }
+            "h" => {
+                path.push((Segment::new(current.0, current.1, subpath_start.0, subpath_start.1), false));
+                current = subpath_start;
+            }
+            "re" => {
+                let (x, y, w, h) = (number(operands, 0), number(operands, 1), number(operands, 2), number(operands, 3));
+                let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)].map(|(px, py)| state.ctm.apply(px, py));
+                let xs = corners.iter().map(|c| c.0);
+                let ys = corners.iter().map(|c| c.1);
+                let (x0, x1) = (xs.clone().fold(f32::MAX, f32::min), xs.fold(f32::MIN, f32::max));
+                let (y0, y1) = (ys.clone().fold(f32::MAX, f32::min), ys.fold(f32::MIN, f32::max));
+                if y1 - y0 <= THIN_RECT {
+                    let mid = (y0 + y1) / 2.0;
+                    path.push((Segment::new(x0, mid, x1, mid), true));
+                } else if x1 - x0 <= THIN_RECT {
+                    let mid = (x0 + x1) / 2.0;
+                    path.push((Segment::new(mid, y0, mid, y1), true));
+                } else {
+                    for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 0)] {
+                        path.push((Segment::new(corners[a].0, corners[a].1, corners[b].0, corners[b].1), false));
+                    }
+                }
+                current = corners[0];
+                subpath_start = corners[0];
+            }
+            // Stroked paths are rules, whatever their shape
+            "S" | "s" | "B" | "B*" | "b" | "b*" => segments.extend(path.drain(..).map(|(s, _)| s)),
+            // Filled shapes are rules only when thin; larger ones are cell shading
+            "f" | "F" | "f*" => segments.extend(path.drain(..).filter(|(_, thin)| *thin).map(|(s, _)| s)),
+            "n" => path.clear(),
+            "BT" => {
+                state.tm = Matrix::IDENTITY;
+                state.tlm = Matrix::IDENTITY;
+            }
+            "Tf" => state.size = number(operands, 1),
+            "TL" => state.leading = number(operands, 0),
+            "Tc" => state.char_spacing = number(operands, 0),
+            "Tz" => state.h_scale = number(operands, 0) / 100.0,
+            "Td" => state.next_line(number(operands, 0), number(operands, 1)),
+            "TD" => {
+                state.leading = -number(operands, 1);
+                state.next_line(number(operands, 0), number(operands, 1));
+            }
+            "Tm" => {
+                state.tlm = Matrix([0, 1, 2, 3, 4, 5].map(|i| number(operands, i)));
+                state.tm = state.tlm;
+            }
+            "T*" => state.next_line(0.0, -state.leading),
+            "Tj" | "'" | "\"" => {
+                if op.operator != "Tj" {
+                    state.next_line(0.0, -state.leading);
+                }
+                if let Some(Object::String(bytes, _)) = operands.last() {
+                    state.show(&decode_pdf_string(bytes), &mut runs);
+                }
+            }
+            "TJ" => {
+                let Some(Object::Array(items)) = operands.first() else {
+                    continue;
+                };
+                let mut text = String::new();
+                for item in items {
+                    match item {
+                        Object::String(bytes, _) => text.push_str(&decode_pdf_string(bytes)),
+                        other => {
+                            let shift = -other.as_float().unwrap_or(0.0) / 1000.0;
+                            if shift > 2.0 {
+                                // A gap of more than two ems inside one TJ separates columns
+                                state.show(&text, &mut runs);
+                                text.clear();
+                                state.advance(shift * state.size * state.h_scale);
+                            } else if shift > 0.25 && !text.is_empty() && !text.ends_with(' ') {
+                                text.push(' ');
+                            }
+                        }
+                    }
+                }
+                state.show(&text, &mut runs);
+            }
+            _ => {}
+        }
+    }
+
+    Ok((runs, segments))
+}
+
+/// Group runs into lines, top to bottom, each sorted left to right
+pub(crate) fn text_lines(mut runs: Vec<TextRun>) -> Vec<Vec<TextRun>> {
+    runs.sort_by(|a, b| b.y.partial_cmp(&a.y).unwrap_or(std::cmp::Ordering::Equal));
+    let mut lines: Vec<Vec<TextRun>> = Vec::new();
+    for run in runs {
+        match lines.last_mut() {
+            Some(line) if (line[0].y - run.y).abs() <= line[0].size.max(run.size) * 0.5 => line.push(run),
+            _ => lines.push(vec![run]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_compose_in_pdf_order() {
+        let scale = Matrix([2.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        let m = scale.then(Matrix::translate(10.0, 20.0));
+        assert_eq!(m.apply(1.0, 1.0), (12.0, 22.0));
+        assert_eq!(Matrix::IDENTITY.then(m).apply(0.0, 0.0), (10.0, 20.0));
+        assert_eq!(m.vertical_scale(), 2.0);
+    }
+
+    #[test]
+    fn strings_decode_as_utf16_or_latin1() {
+        assert_eq!(decode_pdf_string(&[0xFE, 0xFF, 0x00, 0x4E, 0x20, 0xAC]), "N€");
+        assert_eq!(decode_pdf_string(b"Caf\xe9\x07"), "Café");
+    }
+}
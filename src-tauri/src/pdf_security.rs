@@ -0,0 +1,439 @@
+use lopdf::{Dictionary, Document, Object};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Emitter;
+
+use crate::error::AppError;
+
+/// PDF names that can run code, launch programs or carry payloads
+const SUSPICIOUS_NAMES: &[(&str, &str)] = &[
+    ("JavaScript", "Embedded JavaScript"),
+    ("JS", "JavaScript action"),
+    ("Launch", "Launch action (can start external programs)"),
+    ("EmbeddedFile", "Embedded file attachment"),
+    ("OpenAction", "Action that runs when the document is opened"),
+    ("AA", "Automatic action triggered by page or form events"),
+    ("RichMedia", "Rich media content (Flash)"),
+    ("XFA", "XFA form (can contain scripts)"),
+    ("SubmitForm", "Form submission to a remote address"),
+    ("ImportData", "Import of external data"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfFinding {
+    keyword: String,
+    description: String,
+    count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfScanReport {
+    path: String,
+    suspicious: bool,
+    findings: Vec<PdfFinding>,
+    /// Names of attachments that look like executables or scripts
+    executable_attachments: Vec<String>,
+    /// Suspicious names written with #xx escapes, a common way to hide them from scanners
+    obfuscated_names: usize,
+    /// The file packs objects into compressed object streams; they're unpacked and inspected
+    has_object_streams: bool,
+    /// False when the file couldn't be parsed, in which case it's reported as suspicious
+    inspected: bool,
+}
+
+impl PdfScanReport {
+    pub fn suspicious(&self) -> bool {
+        self.suspicious
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SanitizedPdf {
+    output_path: String,
+    neutralized: usize,
+}
+
+/// A `/Name` token in the raw file
+struct NameToken {
+    decoded: String,
+    escaped: bool,
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace()
+        || matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%' | 0)
+}
+
+fn hex_value(pair: &[u8]) -> Option<u8> {
+    std::str::from_utf8(pair).ok().and_then(|s| u8::from_str_radix(s, 16).ok())
+}
+
+/// Find every name token, decoding #xx escapes
+fn name_tokens(data: &[u8]) -> Vec<NameToken> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        if data[i] != b'/' {
+            i += 1;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        while end < data.len() && !is_delimiter(data[end]) {
+            end += 1;
+        }
+
+        let raw = &data[start..end];
+        let mut decoded = String::with_capacity(raw.len());
+        let mut escaped = false;
+        let mut j = 0;
+        while j < raw.len() {
+            if raw[j] == b'#' && j + 3 <= raw.len() {
+                if let Some(value) = hex_value(&raw[j + 1..j + 3]) {
+                    decoded.push(value as char);
+                    escaped = true;
+                    j += 3;
+                    continue;
+                }
+            }
+            decoded.push(raw[j] as char);
+            j += 1;
+        }
+
+        if !decoded.is_empty() {
+            tokens.push(NameToken { decoded, escaped });
+        }
+        i = end.max(i + 1);
+    }
+
+    tokens
+}
+
+fn is_suspicious(name: &str) -> bool {
+    SUSPICIOUS_NAMES.iter().any(|(keyword, _)| *keyword == name)
+}
+
+/// Attachment extensions that run as programs or scripts
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "scr", "bat", "cmd", "com", "vbs", "vbe", "js", "jse", "jar", "ps1", "msi", "dll", "hta", "lnk",
+];
+
+/// Actions that run code, start programs or send data; `sanitize_pdf` empties them
+const ACTIVE_ACTIONS: &[&[u8]] = &[b"JavaScript", b"Launch", b"SubmitForm", b"ImportData"];
+
+/// Entries `sanitize_pdf` removes from every dictionary
+const REMOVED_KEYS: &[&[u8]] = &[
+    b"JS",
+    b"JavaScript",
+    b"OpenAction",
+    b"AA",
+    b"Launch",
+    b"EmbeddedFile",
+    b"EmbeddedFiles",
+    b"EF",
+    b"RichMedia",
+    b"XFA",
+];
+
+/// A PDF text string: UTF-16BE with a byte order mark, or PDFDocEncoding (close enough to Latin-1 for file names)
+fn text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        None => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn is_executable_name(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXECUTABLE_EXTENSIONS.iter().any(|x| x.eq_ignore_ascii_case(e)))
+}
+
+/// What the object walk found
+#[derive(Default)]
+struct Inspection {
+    counts: HashMap<&'static str, usize>,
+    executable_attachments: BTreeSet<String>,
+}
+
+impl Inspection {
+    fn count(&mut self, name: &[u8]) {
+        if let Some((keyword, _)) = SUSPICIOUS_NAMES.iter().find(|(keyword, _)| keyword.as_bytes() == name) {
+            *self.counts.entry(*keyword).or_default() += 1;
+        }
+    }
+
+    /// Look at an object and everything nested in it
+    fn visit(&mut self, object: &Object) {
+        match object {
+            Object::Dictionary(dict) => self.visit_dict(dict),
+            Object::Stream(stream) => self.visit_dict(&stream.dict),
+            Object::Array(items) => items.iter().for_each(|item| self.visit(item)),
+            Object::Name(name) => self.count(name),
+            _ => {}
+        }
+    }
+
+    fn visit_dict(&mut self, dict: &Dictionary) {
+        // File specifications name attachments in /F and /UF
+        let is_filespec = dict.has(b"EF") || matches!(dict.get(b"Type"), Ok(Object::Name(n)) if n == b"Filespec");
+        for (key, value) in dict.iter() {
+            self.count(key);
+            match value {
+                Object::String(bytes, _) if is_filespec && (key == b"F" || key == b"UF") => {
+                    let name = text_string(bytes);
+                    if is_executable_name(&name) {
+                        self.executable_attachments.insert(name);
+                    }
+                }
+                value => self.visit(value),
+            }
+        }
+    }
+}
+
+/// Walk every object of a parsed document, including those unpacked from object streams
+fn inspect(doc: &Document) -> Inspection {
+    let mut inspection = Inspection::default();
+    for object in doc.objects.values() {
+        inspection.visit(object);
+    }
+    inspection
+}
+
+fn scan_bytes(path: &Path, data: &[u8]) -> PdfScanReport {
+    let tokens = name_tokens(data);
+    // lopdf decodes #xx escapes, so obfuscation is only visible in the raw bytes
+    let obfuscated_names = tokens
+        .iter()
+        .filter(|t| t.escaped && is_suspicious(&t.decoded))
+        .count();
+    let has_object_streams = tokens.iter().any(|t| t.decoded == "ObjStm");
+
+    let inspection = match Document::load_mem(data) {
+        Ok(doc) => Some(inspect(&doc)),
+        Err(e) => {
+            log::warn!("Failed to parse {} for scanning: {}", path.display(), e);
+            None
+        }
+    };
+    let inspected = inspection.is_some();
+    let inspection = inspection.unwrap_or_default();
+
+    let findings: Vec<PdfFinding> = SUSPICIOUS_NAMES
+        .iter()
+        .filter_map(|(keyword, description)| {
+            let count = inspection.counts.get(keyword).copied().unwrap_or(0);
+            (count > 0).then(|| PdfFinding {
+                keyword: format!("/{}", keyword),
+                description: description.to_string(),
+                count,
+            })
+        })
+        .collect();
+    let executable_attachments: Vec<String> = inspection.executable_attachments.into_iter().collect();
+
+    PdfScanReport {
+        path: path.display().to_string(),
+        // A file that couldn't be parsed can't be vouched for
+        suspicious: !inspected || obfuscated_names > 0 || !findings.is_empty() || !executable_attachments.is_empty(),
+        findings,
+        executable_attachments,
+        obfuscated_names,
+        has_object_streams,
+        inspected,
+    }
+}
+
+/// Empty active actions and remove active-content entries from an object and
+/// everything nested in it; returns how many were neutralized
+fn neutralize(object: &mut Object) -> usize {
+    match object {
+        Object::Dictionary(dict) => neutralize_dict(dict),
+        Object::Stream(stream) => neutralize_dict(&mut stream.dict),
+        Object::Array(items) => items.iter_mut().map(neutralize).sum(),
+        _ => 0,
+    }
+}
+
+fn neutralize_dict(dict: &mut Dictionary) -> usize {
+    if matches!(dict.get(b"S"), Ok(Object::Name(action)) if ACTIVE_ACTIONS.contains(&action.as_slice())) {
+        // An empty dictionary where an action was expected does nothing
+        *dict = Dictionary::new();
+        return 1;
+    }
+    let mut neutralized = 0;
+    for key in REMOVED_KEYS {
+        if dict.remove(key).is_some() {
+            neutralized += 1;
+        }
+    }
+    for (_, value) in dict.iter_mut() {
+        neutralized += neutralize(value);
+    }
+    neutralized
+}
+
+/// Scan a PDF before it's indexed
+pub(crate) fn scan_file(path: &Path) -> Result<PdfScanReport, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read PDF: {}", e))?;
+    Ok(scan_bytes(path, &data))
+}
+
+/// Scan a PDF for JavaScript, launch actions and the like before it's read,
+/// sending the report as `pdf_active_content` when something is found
+///
+/// Text extraction never runs the active content, so indexing goes on; the user
+/// can make a clean copy with `sanitize_pdf`.
+pub(crate) async fn warn_active_content(app_handle: &tauri::AppHandle, path: &Path) {
+    if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("pdf")) {
+        return;
+    }
+    let source = path.to_path_buf();
+    match tauri::async_runtime::spawn_blocking(move || scan_file(&source)).await {
+        Ok(Ok(report)) if report.suspicious() => {
+            log::warn!("{} contains active content", path.display());
+            app_handle.emit("pdf_active_content", report).ok();
+        }
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => log::warn!("Failed to scan {}: {}", path.display(), e),
+        Err(e) => log::warn!("Scanning task failed: {}", e),
+    }
+}
+
+/// Scan a PDF for JavaScript, launch actions, embedded executables and auto-run actions
+#[tauri::command]
+pub async fn scan_pdf(path: String) -> Result<PdfScanReport, AppError> {
+    log::info!("Scanning PDF for active content: {}", path);
+
+    let report = scan_file(Path::new(&path))?;
+
+    if report.suspicious {
+        log::warn!(
+            "PDF {} contains active content: {:?}",
+            path,
+            report.findings.iter().map(|f| f.keyword.as_str()).collect::<Vec<_>>()
+        );
+    }
+    Ok(report)
+}
+
+/// Write a working copy of the PDF with active content removed
+///
+/// The document is parsed (including compressed object streams), JavaScript,
+/// launch and form-submission actions are emptied, and auto-run actions, scripts
+/// and embedded files are removed from every dictionary; stream data is left as
+/// it is. The original file is never modified. The copy goes to the app temp directory
+/// unless `output_path` is given.
+#[tauri::command]
+pub async fn sanitize_pdf(
+    app_handle: tauri::AppHandle,
+    path: String,
+    output_path: Option<String>,
+) -> Result<SanitizedPdf, AppError> {
+    log::info!("Sanitizing PDF: {}", path);
+
+    let mut doc = Document::load(&path).map_err(|e| format!("Failed to parse PDF: {}", e))?;
+    let neutralized: usize = doc.objects.values_mut().map(neutralize).sum();
+
+    let output = match output_path {
+        Some(p) => PathBuf::from(p),
+        None => {
+            let stem = Path::new(&path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("document");
+            crate::secure_delete::temp_dir(&app_handle)?.join(format!("{}.sanitized.pdf", stem))
+        }
+    };
+
+    doc.save(&output)
+        .map_err(|e| format!("Failed to write sanitized PDF: {}", e))?;
+
+    log::info!("Sanitized PDF written to {} ({} entries neutralized)", output.display(), neutralized);
+    Ok(SanitizedPdf {
+        output_path: output.display().to_string(),
+        neutralized,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, StringFormat};
+
+    /// A one-page document that runs JavaScript when opened
+    fn scripted_pdf() -> Document {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let action_id = doc.add_object(dictionary! {
+            "S" => "JavaScript",
+            "JS" => Object::String(b"app.alert(1)".to_vec(), StringFormat::Literal),
+        });
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id, "OpenAction" => action_id });
+        doc.trailer.set("Root", catalog_id);
+        doc
+    }
+
+    fn to_bytes(doc: &mut Document) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn finds_open_action_javascript() {
+        let report = scan_bytes(Path::new("test.pdf"), &to_bytes(&mut scripted_pdf()));
+        assert!(report.inspected);
+        assert!(report.suspicious);
+        let keywords: Vec<&str> = report.findings.iter().map(|f| f.keyword.as_str()).collect();
+        assert!(keywords.contains(&"/OpenAction"));
+        assert!(keywords.contains(&"/JavaScript"));
+    }
+
+    #[test]
+    fn neutralized_document_is_clean() {
+        let mut doc = scripted_pdf();
+        let neutralized: usize = doc.objects.values_mut().map(neutralize).sum();
+        assert!(neutralized >= 2);
+        let report = scan_bytes(Path::new("test.pdf"), &to_bytes(&mut doc));
+        assert!(!report.suspicious, "{:?}", report.findings);
+    }
+
+    #[test]
+    fn unparsable_file_is_suspicious() {
+        let report = scan_bytes(Path::new("test.pdf"), b"%PDF-1.7 not really");
+        assert!(!report.inspected);
+        assert!(report.suspicious);
+    }
+
+    #[test]
+    fn flags_executable_attachment_names() {
+        assert!(is_executable_name("invoice.pdf.exe"));
+        assert!(is_executable_name("run.PS1"));
+        assert!(!is_executable_name("notes.txt"));
+        assert_eq!(text_string(&[0xFE, 0xFF, 0, b'a', 0, b'.', 0, b'j', 0, b's']), "a.js");
+    }
+
+    #[test]
+    fn counts_escaped_names() {
+        let tokens = name_tokens(b"<< /J#61vaScript (x) /OpenAction 1 0 R >>");
+        assert!(tokens.iter().any(|t| t.escaped && t.decoded == "JavaScript"));
+        assert!(tokens.iter().any(|t| !t.escaped && t.decoded == "OpenAction"));
+    }
+}
@@ -0,0 +1,399 @@
+use lopdf::{Document, ObjectId};
+use serde::Serialize;
+
+use crate::documents::markdown_table;
+use crate::error::AppError;
+use crate::pdf;
+use crate::pdf_layout::{page_layout, text_lines, Segment, TextRun, RULE_TOLERANCE};
+
+/// Rows needed before whitespace-aligned text counts as a table
+const MIN_ALIGNED_ROWS: usize = 3;
+/// Average cell length above which aligned blocks are prose columns, not tables
+const MAX_AVERAGE_CELL_CHARS: usize = 40;
+#[derive(Debug, Serialize)]
+pub struct PdfTable {
+    /// 1-based page number
+    page: u32,
+    /// "ruled" (drawn grid lines) or "aligned" (whitespace-separated columns)
+    kind: &'static str,
+    rows: Vec<Vec<String>>,
+    csv: String,
+    /// Ready to paste into a prompt
+    markdown: String,
+}
+
+/// A cell of whitespace-separated text within a line
+#[derive(Debug, Clone)]
+struct Cell {
+    x0: f32,
+    x1: f32,
+    y: f32,
+    size: f32,
+    text: String,
+}
+
+/// Merge the runs of a line into cells split at gaps wider than about 1.5 ems
+fn line_cells(line: &[TextRun]) -> Vec<Cell> {
+    let mut cells: Vec<Cell> = Vec::new();
+    for run in line {
+        match cells.last_mut() {
+            Some(cell) if run.x - cell.x1 <= run.size * 1.5 => {
+                if run.x - cell.x1 > run.size * 0.15 && !cell.text.ends_with(' ') {
+                    cell.text.push(' ');
+                }
+                cell.text.push_str(&run.text);
+                cell.x1 = cell.x1.max(run.x_end);
+            }
+            _ => cells.push(Cell {
+                x0: run.x,
+                x1: run.x_end,
+                y: run.y,
+                size: run.size,
+                text: run.text.clone(),
+            }),
+        }
+    }
+    for cell in &mut cells {
+        cell.text = cell.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    cells
+}
+
+/// Cells line up when their left edges, right edges or centers match
+fn aligned(a: &Cell, b: &Cell) -> bool {
+    let tolerance = a.size.max(b.size) * 2.0;
+    (a.x0 - b.x0).abs() <= tolerance
+        || (a.x1 - b.x1).abs() <= tolerance
+        || ((a.x0 + a.x1) / 2.0 - (b.x0 + b.x1) / 2.0).abs() <= tolerance
+}
+
+/// Tables made of consecutive lines with the same number of aligned columns
+fn aligned_tables(lines: &[Vec<TextRun>]) -> Vec<Vec<Vec<String>>> {
+    let rows: Vec<Vec<Cell>> = lines.iter().map(|line| line_cells(line)).collect();
+    let mut tables = Vec::new();
+    let mut block: Vec<&Vec<Cell>> = Vec::new();
+
+    let mut flush = |block: &mut Vec<&Vec<Cell>>| {
+        let cells = block.iter().flat_map(|row| row.iter());
+        let (count, chars) = cells.fold((0, 0), |(n, c), cell| (n + 1, c + cell.text.chars().count()));
+        if block.len() >= MIN_ALIGNED_ROWS && count > 0 && chars / count <= MAX_AVERAGE_CELL_CHARS {
+            tables.push(
+                block
+                    .iter()
+                    .map(|row| row.iter().map(|cell| cell.text.clone()).collect())
+                    .collect(),
+            );
+        }
+        block.clear();
+    };
+
+    for row in &rows {
+        let continues = match block.last() {
+            Some(previous) => {
+                row.len() == previous.len()
+                    && (previous[0].y - row[0].y) <= previous[0].size * 3.0
+                    && row.iter().zip(previous.iter()).all(|(a, b)| aligned(a, b))
+            }
+            None => false,
+        };
+        if !continues {
+            flush(&mut block);
+        }
+        if row.len() >= 2 {
+            block.push(row);
+        }
+    }
+    flush(&mut block);
+    tables
+}
+
+/// Sorted positions with values closer than the tolerance merged
+fn distinct(mut values: Vec<f32>) -> Vec<f32> {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mut out: Vec<f32> = Vec::new();
+    for value in values {
+        match out.last() {
+            Some(last) if value - last <= RULE_TOLERANCE => {}
+            _ => out.push(value),
+        }
+    }
+    out
+}
+
+/// Groups of rules that touch each other, each a candidate table grid
+fn rule_groups(segments: &[Segment]) -> Vec<Vec<Segment>> {
+    let rules: Vec<Segment> = segments
+        .iter()
+        .copied()
+        .filter(|s| s.is_horizontal() || s.is_vertical())
+        .collect();
+    let mut parent: Vec<usize> = (0..rules.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+    for i in 0..rules.len() {
+        for j in i + 1..rules.len() {
+            if rules[i].touches(&rules[j]) {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut groups: std::collections::HashMap<usize, Vec<Segment>> = std::collections::HashMap::new();
+    for (i, rule) in rules.iter().enumerate() {
+        groups.entry(find(&mut parent, i)).or_default().push(*rule);
+    }
+    groups.into_values().collect()
+}
+
+/// Fill a grid of ruled cells with the runs inside it; used runs are removed from `runs`
+fn ruled_table(rules: &[Segment], runs: &mut Vec<TextRun>) -> Option<Vec<Vec<String>>> {
+    let xs = distinct(rules.iter().filter(|s| s.is_vertical()).map(|s| s.x0).collect());
+    let ys = distinct(rules.iter().filter(|s| s.is_horizontal()).map(|s| s.y0).collect());
+    if xs.len() < 3 || ys.len() < 2 {
+        return None;
+    }
+    let (left, right) = (xs[0], xs[xs.len() - 1]);
+    let (bottom, top) = (ys[0], ys[ys.len() - 1]);
+
+    let (inside, outside): (Vec<TextRun>, Vec<TextRun>) = runs.drain(..).partition(|run| {
+        let y = run.y + run.size * 0.3;
+        run.x >= left - RULE_TOLERANCE && run.x <= right && y >= bottom && y <= top
+    });
+    *runs = outside;
+    if inside.is_empty() {
+        return None;
+    }
+
+    // Without inner horizontal rules, each text line is a row
+    let row_bounds: Vec<f32> = if ys.len() >= 3 {
+        ys.iter().rev().copied().collect()
+    } else {
+        let lines = text_lines(inside.clone());
+        let mut bounds = vec![top];
+        for pair in lines.windows(2) {
+            bounds.push((pair[0][0].y + pair[1][0].y) / 2.0);
+        }
+        bounds.push(bottom);
+        bounds
+    };
+
+    let columns = xs.len() - 1;
+    let mut grid = vec![vec![String::new(); columns]; row_bounds.len() - 1];
+    for line in text_lines(inside) {
+        for run in line {
+            let y = run.y + run.size * 0.3;
+            let row = row_bounds.windows(2).position(|b| y <= b[0] && y >= b[1]);
+            let column = xs.windows(2).position(|b| run.x + 1.0 >= b[0] && run.x + 1.0 <= b[1]);
+            if let (Some(row), Some(column)) = (row, column) {
+                let cell = &mut grid[row][column];
+                if !cell.is_empty() {
+                    cell.push(' ');
+                }
+                cell.push_str(run.text.trim());
+            }
+        }
+    }
+
+    // Double rules and decorative frames leave empty rows and columns behind
+    grid.retain(|row| row.iter().any(|cell| !cell.is_empty()));
+    let keep: Vec<bool> = (0..columns)
+        .map(|c| grid.iter().any(|row| !row[c].is_empty()))
+        .collect();
+    for row in &mut grid {
+        let mut c = 0;
+        row.retain(|_| {
+            c += 1;
+            keep[c - 1]
+        });
+    }
+    (grid.len() >= 2 && grid[0].len() >= 2).then_some(grid)
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn to_csv(rows: &[Vec<String>]) -> String {
+    rows.iter()
+        .map(|row| row.iter().map(|cell| csv_field(cell)).collect::<Vec<_>>().join(","))
+        .map(|line| line + "\n")
+        .collect()
+}
+
+fn page_tables(doc: &Document, page: u32, page_id: ObjectId) -> Result<Vec<PdfTable>, String> {
+    let (mut runs, segments) = page_layout(doc, page_id)?;
+
+    let mut tables: Vec<(&'static str, Vec<Vec<String>>)> = Vec::new();
+    for group in rule_groups(&segments) {
+        if let Some(rows) = ruled_table(&group, &mut runs) {
+            tables.push(("ruled", rows));
+        }
+    }
+    // Text inside ruled tables was consumed above, so it isn't detected twice
+    for rows in aligned_tables(&text_lines(runs)) {
+        tables.push(("aligned", rows));
+    }
+
+    Ok(tables
+        .into_iter()
+        .map(|(kind, rows)| PdfTable {
+            page,
+            kind,
+            csv: to_csv(&rows),
+            markdown: markdown_table(&rows),
+            rows,
+        })
+        .collect())
+}
+
+/// Detect tables in a PDF and return them as rows, CSV and Markdown
+///
+/// Finds tables drawn with ruling lines and tables laid out as whitespace-aligned
+/// columns. `pages` (1-based) limits the search; all pages are scanned otherwise.
+/// Markdown output can go straight into a prompt, where the model reads it far
+/// better than the flattened page text.
+#[tauri::command]
+pub async fn extract_pdf_tables(path: String, pages: Option<Vec<u32>>) -> Result<Vec<PdfTable>, AppError> {
+    log::info!("Extracting tables from {} (pages {:?})", path, pages);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let doc = pdf::load_document(&path)?;
+        let mut tables = Vec::new();
+        for (page, page_id) in doc.get_pages() {
+            if pages.as_ref().is_some_and(|wanted| !wanted.contains(&page)) {
+                continue;
+            }
+            match page_tables(&doc, page, page_id) {
+                Ok(found) => tables.extend(found),
+                Err(e) => log::warn!("Failed to read tables on page {}: {}", page, e),
+            }
+        }
+
+        log::info!("Found {} tables", tables.len());
+        Ok(tables)
+    })
+    .await
+    .map_err(|e| format!("Table extraction task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_layout::GLYPH_WIDTH;
+
+    const SIZE: f32 = 10.0;
+
+    fn run(x: f32, y: f32, text: &str) -> TextRun {
+        TextRun {
+            x,
+            y,
+            x_end: x + text.chars().count() as f32 * SIZE * GLYPH_WIDTH,
+            size: SIZE,
+            text: text.to_string(),
+        }
+    }
+
+    /// Runs for a row of cells starting at the given x positions
+    fn row(y: f32, cells: &[(f32, &str)]) -> Vec<TextRun> {
+        cells.iter().map(|&(x, text)| run(x, y, text)).collect()
+    }
+
+    #[test]
+    fn runs_group_into_lines_top_down() {
+        let lines = text_lines(vec![run(50.0, 100.0, "b"), run(10.0, 200.0, "top"), run(10.0, 102.0, "a")]);
+        let texts: Vec<Vec<&str>> = lines.iter().map(|l| l.iter().map(|r| r.text.as_str()).collect()).collect();
+        assert_eq!(texts, [vec!["top"], vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn wide_gaps_split_cells() {
+        // "Net" and "sales" are a word apart; "1,204" is far to the right
+        let cells = line_cells(&row(100.0, &[(10.0, "Net"), (29.0, "sales"), (200.0, "1,204")]));
+        let texts: Vec<&str> = cells.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["Net sales", "1,204"]);
+    }
+
+    #[test]
+    fn aligned_columns_form_a_table() {
+        let lines = vec![
+            row(700.0, &[(50.0, "Region"), (200.0, "Q1"), (300.0, "Q2")]),
+            row(686.0, &[(50.0, "North"), (200.0, "120"), (300.0, "135")]),
+            row(672.0, &[(50.0, "South"), (200.0, "98"), (300.0, "110")]),
+            // Prose after a gap isn't part of it
+            row(600.0, &[(50.0, "Figures are unaudited.")]),
+        ];
+        let tables = aligned_tables(&lines);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0][0], ["Region", "Q1", "Q2"]);
+        assert_eq!(tables[0][2], ["South", "98", "110"]);
+    }
+
+    #[test]
+    fn short_or_wordy_blocks_are_not_tables() {
+        let two_rows = vec![
+            row(700.0, &[(50.0, "Region"), (200.0, "Q1")]),
+            row(686.0, &[(50.0, "North"), (200.0, "120")]),
+        ];
+        assert!(aligned_tables(&two_rows).is_empty());
+
+        let sentence = "a column of running text that is far too long to be a table cell";
+        let columns: Vec<Vec<TextRun>> = (0..4)
+            .map(|i| row(700.0 - i as f32 * 14.0, &[(50.0, sentence), (400.0, sentence)]))
+            .collect();
+        assert!(aligned_tables(&columns).is_empty());
+    }
+
+    /// A grid with the given column and row rule positions
+    fn grid(xs: &[f32], ys: &[f32]) -> Vec<Segment> {
+        let (left, right) = (xs[0], xs[xs.len() - 1]);
+        let (bottom, top) = (ys[0], ys[ys.len() - 1]);
+        let vertical = xs.iter().map(|&x| Segment::new(x, bottom, x, top));
+        let horizontal = ys.iter().map(|&y| Segment::new(left, y, right, y));
+        vertical.chain(horizontal).collect()
+    }
+
+    #[test]
+    fn separate_grids_are_separate_groups() {
+        let mut segments = grid(&[50.0, 150.0, 250.0], &[600.0, 620.0, 640.0]);
+        segments.extend(grid(&[50.0, 150.0, 250.0], &[300.0, 320.0, 340.0]));
+        // A filled box is neither a horizontal nor a vertical rule
+        segments.push(Segment::new(0.0, 0.0, 40.0, 40.0));
+        let mut sizes: Vec<usize> = rule_groups(&segments).iter().map(Vec::len).collect();
+        sizes.sort();
+        assert_eq!(sizes, [6, 6]);
+    }
+
+    #[test]
+    fn ruled_cells_take_the_text_inside_them() {
+        let rules = grid(&[50.0, 150.0, 250.0], &[600.0, 620.0, 640.0]);
+        let mut runs = row(626.0, &[(55.0, "Item"), (155.0, "Cost")]);
+        runs.extend(row(606.0, &[(55.0, "Paper"), (155.0, "4.50")]));
+        runs.push(run(55.0, 500.0, "Below the table"));
+
+        let table = ruled_table(&rules, &mut runs).unwrap();
+        assert_eq!(table, [vec!["Item", "Cost"], vec!["Paper", "4.50"]]);
+        // Text outside the grid is left for the aligned pass
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].text, "Below the table");
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        let rows = vec![
+            vec!["Name".to_string(), "Note".to_string()],
+            vec!["A, Inc.".to_string(), "said \"hi\"".to_string()],
+        ];
+        assert_eq!(to_csv(&rows), "Name,Note\n\"A, Inc.\",\"said \"\"hi\"\"\"\n");
+    }
+}
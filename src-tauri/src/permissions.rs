@@ -0,0 +1,125 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// The capability file compiled into this build, so the report reflects what actually shipped
+const DEFAULT_CAPABILITY: &str = include_str!("../capabilities/default.json");
+
+/// Plugins registered in `lib.rs` (keep in sync with the builder there)
+const REGISTERED_PLUGINS: &[(&str, &str)] = &[
+    ("fs", "Read files you open and write exports"),
+    ("http", "HTTP requests from the UI (restricted to the Ollama API on localhost)"),
+    ("dialog", "Native open/save/confirm dialogs"),
+    ("shell", "Open links in the browser and run the local Ollama binary"),
+    ("process", "Restart/exit the app"),
+    ("log", "Write logs to the app log directory"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct PluginInfo {
+    name: String,
+    purpose: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionReport {
+    plugins: Vec<PluginInfo>,
+    /// Every permission granted by the capability file
+    permissions: Vec<String>,
+    filesystem_scopes: Vec<String>,
+    shell_capabilities: Vec<String>,
+    /// URLs the UI may fetch through the http plugin
+    ui_network_endpoints: Vec<String>,
+    /// Hosts the Rust backend may contact (enforced by the HTTP guard)
+    backend_network_hosts: Vec<String>,
+    content_security_policy: Option<String>,
+    /// Plain-language summary for display
+    summary: Vec<String>,
+}
+
+/// Introspect plugins, capabilities and network endpoints enabled in this build
+#[tauri::command]
+pub fn get_permission_report(app_handle: tauri::AppHandle) -> Result<PermissionReport, AppError> {
+    let capability: Value = serde_json::from_str(DEFAULT_CAPABILITY)
+        .map_err(|e| format!("Failed to parse capability file: {}", e))?;
+
+    let mut permissions = Vec::new();
+    let mut ui_network_endpoints = Vec::new();
+
+    for entry in capability["permissions"].as_array().into_iter().flatten() {
+        match entry {
+            Value::String(id) => add_unique(&mut permissions, id),
+            Value::Object(obj) => {
+                let id = obj.get("identifier").and_then(|v| v.as_str()).unwrap_or("unknown");
+                add_unique(&mut permissions, id);
+                for allow in obj.get("allow").and_then(|v| v.as_array()).into_iter().flatten() {
+                    if let Some(url) = allow.get("url").and_then(|v| v.as_str()) {
+                        ui_network_endpoints.push(url.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let filesystem_scopes: Vec<String> = permissions
+        .iter()
+        .filter(|p| p.starts_with("fs:"))
+        .cloned()
+        .collect();
+    let shell_capabilities: Vec<String> = permissions
+        .iter()
+        .filter(|p| p.starts_with("shell:"))
+        .cloned()
+        .collect();
+    let backend_network_hosts: Vec<String> = crate::http::LOCAL_HOSTS
+        .iter()
+        .chain(crate::http::DOWNLOAD_HOSTS)
+        .map(|h| h.to_string())
+        .collect();
+    let content_security_policy = app_handle
+        .config()
+        .app
+        .security
+        .csp
+        .as_ref()
+        .and_then(|csp| serde_json::to_string(csp).ok());
+
+    let summary = vec![
+        format!("{} plugins registered: {}", REGISTERED_PLUGINS.len(),
+            REGISTERED_PLUGINS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")),
+        format!("Filesystem access: {}", if filesystem_scopes.is_empty() { "none".to_string() } else { filesystem_scopes.join(", ") }),
+        format!("Shell access: {}", if shell_capabilities.is_empty() { "none".to_string() } else { shell_capabilities.join(", ") }),
+        format!("The UI may only fetch: {}", ui_network_endpoints.join(", ")),
+        format!("The backend only contacts localhost for AI; {} are used solely when you install Ollama from the app",
+            crate::http::DOWNLOAD_HOSTS.join(", ")),
+        match &content_security_policy {
+            Some(_) => "A Content Security Policy is active".to_string(),
+            None => "No Content Security Policy is set".to_string(),
+        },
+    ];
+
+    Ok(PermissionReport {
+        plugins: REGISTERED_PLUGINS
+            .iter()
+            .map(|(name, purpose)| PluginInfo {
+                name: name.to_string(),
+                purpose: purpose.to_string(),
+            })
+            .collect(),
+        permissions,
+        filesystem_scopes,
+        shell_capabilities,
+        ui_network_endpoints,
+        backend_network_hosts,
+        content_security_policy,
+        summary,
+    })
+}
+
+fn add_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|v| v == value) {
+        list.push(value.to_string());
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::Manager;
+
+use crate::error::AppError;
+
+/// File next to the executable that turns portable mode on
+const FLAG_FILE: &str = "portable.flag";
+/// Command-line switch that turns portable mode on
+const FLAG_ARG: &str = "--portable";
+
+static DATA_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// `data/` beside the executable in portable mode, None otherwise
+///
+/// Portable mode keeps settings, indexes, logs and the managed Ollama install
+/// with the app, so it can run from a USB stick without writing to the user's
+/// profile. Decided once per launch.
+pub fn data_dir() -> Option<&'static Path> {
+    DATA_DIR
+        .get_or_init(|| {
+            let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+            let enabled = exe_dir.join(FLAG_FILE).is_file() || std::env::args().skip(1).any(|arg| arg == FLAG_ARG);
+            enabled.then(|| exe_dir.join("data"))
+        })
+        .as_deref()
+}
+
+/// Settings and indexes: `data/` in portable mode, the OS app data directory otherwise
+pub fn app_data_dir(app_handle: &tauri::AppHandle) -> tauri::Result<PathBuf> {
+    match data_dir() {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => app_handle.path().app_data_dir(),
+    }
+}
+
+/// Temporary files: `data/cache` in portable mode
+pub fn app_cache_dir(app_handle: &tauri::AppHandle) -> tauri::Result<PathBuf> {
+    match data_dir() {
+        Some(dir) => Ok(dir.join("cache")),
+        None => app_handle.path().app_cache_dir(),
+    }
+}
+
+/// Log files: `data/logs` in portable mode
+pub fn app_log_dir(app_handle: &tauri::AppHandle) -> tauri::Result<PathBuf> {
+    match data_dir() {
+        Some(dir) => Ok(dir.join("logs")),
+        None => app_handle.path().app_log_dir(),
+    }
+}
+
+/// Where the log plugin writes; chosen before the app handle exists
+pub fn log_target() -> tauri_plugin_log::TargetKind {
+    match data_dir() {
+        Some(dir) => tauri_plugin_log::TargetKind::Folder {
+            path: dir.join("logs"),
+            file_name: None,
+        },
+        None => tauri_plugin_log::TargetKind::LogDir { file_name: None },
+    }
+}
+
+/// Models pulled by the Ollama server the app starts; None leaves Ollama's default
+pub fn ollama_models_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("ollama_models"))
+}
+
+/// Point the webview's own storage into `data/` before any window is created
+///
+/// Only WebView2 (Windows) can be redirected this way; an explicitly set
+/// folder is left alone.
+pub fn prepare() {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("Failed to create portable data directory {}: {}", dir.display(), e);
+    }
+    #[cfg(target_os = "windows")]
+    if std::env::var_os("WEBVIEW2_USER_DATA_FOLDER").is_none() {
+        std::env::set_var("WEBVIEW2_USER_DATA_FOLDER", dir.join("webview"));
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PortableMode {
+    enabled: bool,
+    /// Where settings and indexes are stored in either mode
+    data_dir: Option<String>,
+}
+
+/// Whether the app runs in portable mode and where its data lives
+#[tauri::command]
+pub async fn get_portable_mode(app_handle: tauri::AppHandle) -> Result<PortableMode, AppError> {
+    Ok(PortableMode {
+        enabled: data_dir().is_some(),
+        data_dir: app_data_dir(&app_handle).ok().map(|dir| dir.display().to_string()),
+    })
+}
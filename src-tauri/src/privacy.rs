@@ -0,0 +1,237 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Longest placeholder we may have to hold back while streaming, e.g. "[PHONE_123]"
+const MAX_PLACEHOLDER_LEN: usize = 24;
+
+/// Fewest digits in a phone number; shorter matches are references, not numbers
+const MIN_PHONE_DIGITS: usize = 7;
+
+/// PII patterns, checked in order (IBAN and SSN before the looser phone pattern)
+fn patterns() -> &'static [(&'static str, Regex)] {
+    static PATTERNS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            ("EMAIL", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+            ("IBAN", r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,4})?\b"),
+            ("SSN", r"\b\d{3}-\d{2}-\d{4}\b"),
+            // International (+44 20 7946 0958), area code in parentheses ((555) 123-4567)
+            // or 555-123-4567; bare number pairs like "2019-2020" are left alone
+            (
+                "PHONE",
+                r"(?:\+\d{1,3}[ .-]?(?:\(\d{1,4}\)[ .-]?)?\d{1,4}(?:[ .-]?\d{2,4}){1,5}|\(\d{2,4}\)[ .-]?\d{3,4}[ .-]?\d{3,4}|\b\d{3}[.-]\d{3}[.-]\d{4})\b",
+            ),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("invalid PII pattern")))
+        .collect()
+    })
+}
+
+/// Replaces PII with reversible placeholders like `[EMAIL_1]`
+///
+/// Detection is regex only (emails, phone numbers, US SSNs, IBANs). There is no
+/// named-entity recognition, so names, addresses and other free-form PII are
+/// not detected and reach the model unmasked. The same value always maps to the same placeholder,
+/// so the model can still reason about "the same email" across messages.
+#[derive(Default)]
+pub struct PiiMasker {
+    /// placeholder -> original value
+    originals: HashMap<String, String>,
+    /// original value -> placeholder
+    placeholders: HashMap<String, String>,
+    counters: HashMap<&'static str, usize>,
+    /// Put before the kind in placeholders, e.g. `REDACTED_` for `[REDACTED_EMAIL_1]`
+    prefix: &'static str,
+}
+
+impl PiiMasker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A masker for text that is stored masked, such as chunks of sensitive documents
+    ///
+    /// Its placeholders (`[REDACTED_EMAIL_1]`) can't be mistaken for the ones a chat
+    /// masker restores, so a stored placeholder is never replaced with another value.
+    pub fn redacting() -> Self {
+        Self {
+            prefix: "REDACTED_",
+            ..Self::default()
+        }
+    }
+
+    /// Mask every PII match in `text`
+    pub fn mask(&mut self, text: &str) -> String {
+        let mut masked = text.to_string();
+        for (kind, regex) in patterns() {
+            masked = regex
+                .replace_all(&masked, |caps: &regex::Captures| {
+                    let value = &caps[0];
+                    if *kind == "PHONE" && value.chars().filter(char::is_ascii_digit).count() < MIN_PHONE_DIGITS {
+                        return value.to_string();
+                    }
+                    self.placeholder_for(kind, value)
+                })
+                .into_owned();
+        }
+        masked
+    }
+
+    /// Put the original values back in place of placeholders
+    pub fn restore(&self, text: &str) -> String {
+        if self.originals.is_empty() {
+            return text.to_string();
+        }
+        let mut restored = text.to_string();
+        for (placeholder, original) in &self.originals {
+            restored = restored.replace(placeholder.as_str(), original);
+        }
+        restored
+    }
+
+    /// Number of distinct values that were masked
+    pub fn masked_count(&self) -> usize {
+        self.originals.len()
+    }
+
+    fn placeholder_for(&mut self, kind: &'static str, value: &str) -> String {
+        if let Some(existing) = self.placeholders.get(value) {
+            return existing.clone();
+        }
+        let counter = self.counters.entry(kind).or_insert(0);
+        *counter += 1;
+        let placeholder = format!("[{}{}_{}]", self.prefix, kind, counter);
+        self.originals.insert(placeholder.clone(), value.to_string());
+        self.placeholders.insert(value.to_string(), placeholder.clone());
+        placeholder
+    }
+}
+
+/// Restores placeholders in a streamed answer where a placeholder can be split across chunks
+pub struct StreamRestorer<'a> {
+    masker: &'a PiiMasker,
+    pending: String,
+}
+
+impl<'a> StreamRestorer<'a> {
+    pub fn new(masker: &'a PiiMasker) -> Self {
+        Self {
+            masker,
+            pending: String::new(),
+        }
+    }
+
+    /// Add a streamed chunk, returning the text that is safe to display now
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.pending.push_str(chunk);
+
+        // Hold back a trailing "[..." that may still become a placeholder
+        let hold_from = match self.pending.rfind('[') {
+            Some(idx)
+                if !self.pending[idx..].contains(']')
+                    && self.pending.len() - idx < MAX_PLACEHOLDER_LEN =>
+            {
+                idx
+            }
+            _ => self.pending.len(),
+        };
+
+        let ready: String = self.pending.drain(..hold_from).collect();
+        self.masker.restore(&ready)
+    }
+
+    /// Flush whatever is still held back at the end of the stream
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        self.masker.restore(&rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_and_restores() {
+        let mut masker = PiiMasker::new();
+        let text = "Mail jane.doe@example.com or call +1 555 123 4567. SSN 123-45-6789, IBAN DE89 3704 0044 0532 0130 00.";
+        let masked = masker.mask(text);
+        assert!(!masked.contains("jane.doe@example.com"));
+        assert!(!masked.contains("555 123 4567"));
+        assert!(!masked.contains("123-45-6789"));
+        assert!(!masked.contains("DE89"));
+        assert!(masked.contains("[EMAIL_1]"));
+        assert!(masked.contains("[SSN_1]"));
+        assert!(masked.contains("[IBAN_1]"));
+        assert_eq!(masker.masked_count(), 4);
+        assert_eq!(masker.restore(&masked), text);
+    }
+
+    #[test]
+    fn same_value_same_placeholder() {
+        let mut masker = PiiMasker::new();
+        let first = masker.mask("a@example.com");
+        let second = masker.mask("again: a@example.com, and b@example.com");
+        assert_eq!(first, "[EMAIL_1]");
+        assert_eq!(second, "again: [EMAIL_1], and [EMAIL_2]");
+    }
+
+    #[test]
+    fn redacted_placeholders_survive_chat_restore() {
+        let mut stored = PiiMasker::redacting();
+        let chunk = stored.mask("Contact a@example.com");
+        assert_eq!(chunk, "Contact [REDACTED_EMAIL_1]");
+
+        let mut chat = PiiMasker::new();
+        let prompt = chat.mask(&format!("{} or b@example.com", chunk));
+        assert_eq!(prompt, "Contact [REDACTED_EMAIL_1] or [EMAIL_1]");
+        assert_eq!(chat.restore(&prompt), "Contact [REDACTED_EMAIL_1] or b@example.com");
+    }
+
+    #[test]
+    fn phone_formats() {
+        let mut masker = PiiMasker::new();
+        for phone in ["+44 20 7946 0958", "(555) 123-4567", "555-123-4567", "+49 (30) 1234567"] {
+            let masked = masker.mask(&format!("Call {} today", phone));
+            assert!(!masked.contains(phone), "{} wasn't masked: {}", phone, masked);
+        }
+    }
+
+    #[test]
+    fn leaves_ordinary_numbers_alone() {
+        let mut masker = PiiMasker::new();
+        for text in [
+            "Revenue grew 2019-2020",
+            "between 1200 1500 units",
+            "see section 4.2.1",
+            "ISBN 978-3-16-148410-0",
+            "call +1 555",
+        ] {
+            assert_eq!(masker.mask(text), text);
+        }
+        assert_eq!(masker.masked_count(), 0);
+    }
+
+    #[test]
+    fn stream_restorer_holds_back_split_placeholders() {
+        let mut masker = PiiMasker::new();
+        masker.mask("a@example.com");
+        let mut restorer = StreamRestorer::new(&masker);
+        assert_eq!(restorer.push("Write to [EMA"), "Write to ");
+        assert_eq!(restorer.push("IL_1"), "");
+        assert_eq!(restorer.push("] now"), "a@example.com now");
+        assert_eq!(restorer.finish(), "");
+    }
+
+    #[test]
+    fn stream_restorer_releases_brackets_that_are_not_placeholders() {
+        let masker = PiiMasker::new();
+        let mut restorer = StreamRestorer::new(&masker);
+        assert_eq!(restorer.push("see [1"), "see ");
+        assert_eq!(restorer.push("] and"), "[1] and");
+        assert_eq!(restorer.push(" [unfinished"), " ");
+        assert_eq!(restorer.finish(), "[unfinished");
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+/// Minimum time between two progress events
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+/// Percent change that triggers an event regardless of timing
+const MIN_PERCENT_DELTA: f64 = 0.5;
+
+/// Rate limiter for progress events sent to the frontend
+///
+/// Download, extraction and model pull loops can report thousands of updates per
+/// second on fast connections, which floods the IPC channel. Every progress
+/// emitter should go through one of these instead of emitting on every chunk.
+#[derive(Default)]
+pub struct ProgressThrottle {
+    last_emit: Option<Instant>,
+    last_percent: f64,
+}
+
+impl ProgressThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if an event for `percent` should be emitted now
+    ///
+    /// The first update, reaching 100% and `force`d updates (e.g. a status change)
+    /// always pass. Otherwise an update passes if the percentage moved by at least
+    /// 0.5 points, or if it moved at all and 100ms have passed since the last event.
+    pub fn should_emit(&mut self, percent: f64, force: bool) -> bool {
+        let now = Instant::now();
+        let delta = (percent - self.last_percent).abs();
+
+        let emit = match self.last_emit {
+            None => true,
+            Some(_) if force => true,
+            Some(_) if percent >= 100.0 && self.last_percent < 100.0 => true,
+            Some(_) if delta >= MIN_PERCENT_DELTA => true,
+            Some(last) => delta > 0.0 && now.duration_since(last) >= MIN_INTERVAL,
+        };
+
+        if emit {
+            self.last_emit = Some(now);
+            self.last_percent = percent;
+        }
+        emit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_update_and_completion_always_pass() {
+        let mut throttle = ProgressThrottle::new();
+        assert!(throttle.should_emit(0.0, false));
+        assert!(!throttle.should_emit(0.1, false));
+        assert!(throttle.should_emit(100.0, false));
+        assert!(!throttle.should_emit(100.0, false));
+    }
+
+    #[test]
+    fn small_steps_wait_for_the_interval() {
+        let mut throttle = ProgressThrottle::new();
+        assert!(throttle.should_emit(10.0, false));
+        assert!(!throttle.should_emit(10.2, false));
+        assert!(throttle.should_emit(10.5, false));
+        std::thread::sleep(MIN_INTERVAL);
+        assert!(throttle.should_emit(10.6, false));
+        std::thread::sleep(MIN_INTERVAL);
+        assert!(!throttle.should_emit(10.6, false));
+    }
+
+    #[test]
+    fn forced_updates_pass() {
+        let mut throttle = ProgressThrottle::new();
+        assert!(throttle.should_emit(50.0, false));
+        assert!(throttle.should_emit(50.0, true));
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::AppError;
+use crate::ollama::ChatMessage;
+use crate::vectorstore::now_secs;
+
+/// Serializes read-modify-write cycles on prompts.json
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A named prompt preset
+///
+/// `template` is wrapped around the user's message; `{{input}}` is replaced
+/// with the message, `{{document}}` with the document name (RAG only) and
+/// `{{date}}` with today's date. Unknown variables are left as written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    /// Generated from the name when saving a new template
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    pub template: String,
+    /// Shipped with the app; can be edited but not deleted
+    #[serde(default)]
+    pub builtin: bool,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn builtin(id: &str, name: &str, system_prompt: &str, template: &str) -> PromptTemplate {
+    PromptTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        system_prompt: Some(system_prompt.to_string()),
+        template: template.to_string(),
+        builtin: true,
+        updated_at: 0,
+    }
+}
+
+fn builtins() -> Vec<PromptTemplate> {
+    vec![
+        builtin(
+            "summarize",
+            "Summarize",
+            "You write concise, faithful summaries. Keep every key fact, figure and conclusion.",
+            "Summarize the following:\n\n{{input}}",
+        ),
+        builtin(
+            "eli5",
+            "Explain like I'm five",
+            "You explain things simply, as if to a five-year-old: short sentences, everyday words and familiar examples.",
+            "Explain this like I'm five:\n\n{{input}}",
+        ),
+        builtin(
+            "action-items",
+            "Extract action items",
+            "You extract action items. Reply with a checklist only, one item per line, with the owner and due date when they are mentioned.",
+            "List every action item in the following:\n\n{{input}}",
+        ),
+    ]
+}
+
+impl PromptTemplate {
+    /// Fill `{{name}}` placeholders from `variables`
+    pub fn render(&self, variables: &HashMap<&str, String>) -> String {
+        let mut text = self.template.clone();
+        for (name, value) in variables {
+            text = text.replace(&format!("{{{{{}}}}}", name), value);
+        }
+        text
+    }
+
+    /// Apply the template to a chat: its system prompt goes first and the last
+    /// user message is rendered through it as `{{input}}`
+    pub fn apply(&self, mut messages: Vec<ChatMessage>, mut variables: HashMap<&str, String>) -> Vec<ChatMessage> {
+        if let Some(last) = messages.iter_mut().rev().find(|m| m.role == "user") {
+            variables.insert("input", last.content.clone());
+            last.content = self.render(&variables);
+        }
+        if let Some(system_prompt) = self.system_prompt.as_ref().filter(|p| !p.trim().is_empty()) {
+            messages.insert(
+                0,
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: system_prompt.clone(),
+                    images: Vec::new(),
+                },
+            );
+        }
+        messages
+    }
+}
+
+/// Variables every template can use
+pub fn default_variables() -> HashMap<&'static str, String> {
+    let days = now_secs() / 86_400;
+    HashMap::from([("date", civil_date(days))])
+}
+
+/// YYYY-MM-DD for a day count since 1970-01-01 (Howard Hinnant's civil_from_days)
+fn civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn prompts_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::portable::app_data_dir(app_handle)
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("prompts.json"))
+}
+
+/// Templates saved by the user, including edited built-ins
+fn read_saved(app_handle: &tauri::AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    let path = prompts_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read prompt templates: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse prompt templates: {}", e))
+}
+
+fn write_saved(app_handle: &tauri::AppHandle, templates: &[PromptTemplate]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(templates)
+        .map_err(|e| format!("Failed to serialize prompt templates: {}", e))?;
+    fs::write(prompts_path(app_handle)?, json).map_err(|e| format!("Failed to write prompt templates: {}", e))
+}
+
+/// Built-ins (or their edited versions) followed by the user's templates
+fn all_templates(app_handle: &tauri::AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    let saved = read_saved(app_handle)?;
+    let mut templates: Vec<PromptTemplate> = builtins()
+        .into_iter()
+        .map(|b| saved.iter().find(|s| s.id == b.id).cloned().unwrap_or(b))
+        .collect();
+    templates.extend(saved.into_iter().filter(|s| !s.builtin));
+    Ok(templates)
+}
+
+/// Look up a template by id for `rag_query` and `ollama_chat`
+pub fn get_template(app_handle: &tauri::AppHandle, id: &str) -> Result<PromptTemplate, String> {
+    all_templates(app_handle)?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Prompt template not found: {}", id))
+}
+
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+/// List built-in and saved prompt templates
+#[tauri::command]
+pub async fn list_prompt_templates(app_handle: tauri::AppHandle) -> Result<Vec<PromptTemplate>, AppError> {
+    log::info!("Listing prompt templates");
+    Ok(all_templates(&app_handle)?)
+}
+
+/// Create a template (empty id) or update an existing one
+#[tauri::command]
+pub async fn save_prompt_template(
+    app_handle: tauri::AppHandle,
+    mut template: PromptTemplate,
+) -> Result<PromptTemplate, AppError> {
+    log::info!("Saving prompt template: {}", template.name);
+
+    if template.name.trim().is_empty() {
+        return Err(AppError::Other("Template name is empty".to_string()));
+    }
+    if template.template.trim().is_empty() {
+        return Err(AppError::Other("Template text is empty".to_string()));
+    }
+
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut saved = read_saved(&app_handle)?;
+
+    if template.id.is_empty() {
+        template.id = format!("{}-{}", slug(&template.name), now_secs());
+    }
+    template.builtin = builtins().iter().any(|b| b.id == template.id);
+    template.updated_at = now_secs();
+
+    match saved.iter_mut().find(|s| s.id == template.id) {
+        Some(existing) => *existing = template.clone(),
+        None => saved.push(template.clone()),
+    }
+    write_saved(&app_handle, &saved)?;
+    Ok(template)
+}
+
+/// Delete a saved template; deleting an edited built-in restores the original
+#[tauri::command]
+pub async fn delete_prompt_template(app_handle: tauri::AppHandle, id: String) -> Result<(), AppError> {
+    log::info!("Deleting prompt template: {}", id);
+
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut saved = read_saved(&app_handle)?;
+    let before = saved.len();
+    saved.retain(|s| s.id != id);
+
+    if saved.len() == before {
+        if builtins().iter().any(|b| b.id == id) {
+            return Err(AppError::Unsupported("Built-in templates can't be deleted".to_string()));
+        }
+        return Err(AppError::Other(format!("Prompt template not found: {}", id)));
+    }
+    Ok(write_saved(&app_handle, &saved)?)
+}
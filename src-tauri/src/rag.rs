@@ -0,0 +1,474 @@
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::chat_context;
+use crate::context_window;
+use crate::conversations::ConversationStore;
+use crate::embedding_model;
+use crate::error::AppError;
+use crate::grounding;
+use crate::injection;
+use crate::language;
+use crate::ollama::ChatMessage;
+use crate::ollama_chat::{self, ChatStreams};
+use crate::prompts;
+use crate::retrieval_trace::{self, RetrievalTrace};
+use crate::scheduler::Lane;
+use crate::settings::{self, AppSettings};
+use crate::vectorstore::{now_secs, SearchHit, VectorStore};
+
+const SYSTEM_PROMPT: &str = "You answer questions about a document using only the numbered excerpts provided. \
+Cite the excerpts you use as [1], [2], ... If the excerpts don't contain the answer, say so instead of guessing.";
+
+/// Characters of chunk text shown in a citation
+const CITATION_SNIPPET_CHARS: usize = 200;
+
+/// Where an excerpt came from, so the UI can show "source: p. 42" and jump there
+#[derive(Debug, Serialize)]
+pub struct Citation {
+    /// Excerpt number the answer cites it by ([1] is 1)
+    number: usize,
+    document_id: String,
+    document: Option<String>,
+    /// 1-based page in the viewer, when the indexer recorded it
+    page: Option<u32>,
+    /// Character offsets of the chunk in the page text
+    start: Option<usize>,
+    end: Option<usize>,
+    snippet: String,
+    score: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RagResult {
+    /// Chunks the answer was grounded on, in excerpt order ([1] is the first)
+    sources: Vec<SearchHit>,
+    /// One per source, in the same order
+    citations: Vec<Citation>,
+    /// Standalone question used for retrieval, when a follow-up was rewritten
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rewritten_query: Option<String>,
+    /// Share of the answer's claims found in the sources, in strict grounding mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grounding_score: Option<f32>,
+    /// Claims of the answer no source supports
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unsupported_claims: Vec<String>,
+    /// The streamed answer failed the grounding check and shouldn't be shown as an answer
+    refused: bool,
+}
+
+impl RagResult {
+    /// Sources and citations of an answer that wasn't checked against them
+    pub(crate) fn ungrounded(citations: Vec<Citation>, sources: Vec<SearchHit>) -> Self {
+        RagResult {
+            sources,
+            citations,
+            rewritten_query: None,
+            grounding_score: None,
+            unsupported_claims: Vec::new(),
+            refused: false,
+        }
+    }
+}
+
+/// Start of the chunk text on one line, cut at a word boundary
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= CITATION_SNIPPET_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(CITATION_SNIPPET_CHARS).collect();
+    let cut = match cut.rfind(' ') {
+        Some(i) if i > CITATION_SNIPPET_CHARS / 2 => &cut[..i],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut)
+}
+
+pub(crate) fn citations(document_id: &str, document: Option<&str>, hits: &[SearchHit]) -> Vec<Citation> {
+    hits.iter()
+        .enumerate()
+        .map(|(i, hit)| Citation {
+            number: i + 1,
+            document_id: document_id.to_string(),
+            document: document.map(str::to_string),
+            page: hit.page,
+            start: hit.start,
+            end: hit.end,
+            snippet: snippet(&hit.text),
+            score: hit.score,
+        })
+        .collect()
+}
+
+/// Build the user prompt with the retrieved chunks as numbered excerpts
+///
+/// `texts` are the chunks as screened by `injection::screen`; numbering follows
+/// `hits` so citations still match the sources when an excerpt is left out.
+pub(crate) fn build_prompt(question: &str, hits: &[SearchHit], texts: &[Option<String>]) -> String {
+    let mut prompt = String::from("Excerpts:\n\n");
+    for (i, (hit, text)) in hits.iter().zip(texts).enumerate() {
+        if let Some(text) = text {
+            prompt.push_str(&injection::excerpt(i + 1, hit.page, text));
+        }
+    }
+    prompt.push_str(&format!("Question: {}", question.trim()));
+    prompt
+}
+
+/// Arguments of `rag_query`
+#[derive(Debug, Deserialize)]
+pub struct RagRequest {
+    document_id: String,
+    question: String,
+    /// Excerpts to retrieve; sized to the model's context window when unset
+    top_k: Option<usize>,
+    /// For `cancel_chat_stream`
+    request_id: Option<String>,
+    /// Prompt template the question is rendered with
+    template_id: Option<String>,
+    /// Conversation the question continues, for query rewriting and pasted context
+    conversation_id: Option<String>,
+    /// Workspace the question was asked from, whose settings apply
+    workspace_id: Option<String>,
+    /// The assistant message the answer is saved as, to keep its trace
+    message_id: Option<String>,
+}
+
+/// Answer a question about a document end to end on the Rust side
+///
+/// Embeds the question, retrieves the closest chunks from the document's index
+/// (vector and keyword search combined), and streams the answer as
+/// `ollama_stream_chunk` events. Only the retrieved sources and their page
+/// citations are returned over IPC, not the assembled prompt.
+///
+/// With a `conversation_id` and the `rewrite_queries` setting on, a follow-up
+/// like "what about the second one?" is first rewritten into a standalone query
+/// from the chat history, and that query is used for retrieval.
+///
+/// Settings overridden for the document, or for `workspace_id` when asked from
+/// a workspace, take the place of the app-wide ones.
+///
+/// With `strict_grounding` on, the model is told to answer only from the
+/// excerpts, and the finished answer is checked against them: the result
+/// carries a `grounding_score`, the claims no excerpt supports, and `refused`
+/// when too few of them are supported.
+///
+/// Retrieved excerpts are delimited and stripped of instruction-like phrases
+/// before they go into the prompt; suspected injections emit `injection_warning`.
+///
+/// With the assistant message's `message_id`, the chunks, prompt and options
+/// used are kept for `get_retrieval_trace`.
+#[tauri::command]
+pub async fn rag_query(
+    request: RagRequest,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    streams: tauri::State<'_, ChatStreams>,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<RagResult, AppError> {
+    let RagRequest {
+        document_id,
+        question,
+        top_k,
+        request_id,
+        template_id,
+        conversation_id,
+        workspace_id,
+        message_id,
+    } = request;
+    log::info!("RAG query on {}: {} chars", document_id, question.len());
+
+    if question.trim().is_empty() {
+        return Err(AppError::Other("Question is empty".to_string()));
+    }
+
+    let settings = settings::read_settings_for(&app_handle, workspace_id.as_deref(), Some(&document_id));
+    let rewritten_query = match conversation_id.as_deref().filter(|_| settings.rewrite_queries) {
+        Some(conversation_id) => {
+            let history = conversations.get(conversation_id)?.map(|c| c.messages).unwrap_or_default();
+            chat_context::rewrite_query(&app_handle, &settings, &history, &question).await
+        }
+        None => None,
+    };
+    let search_query = rewritten_query.clone().unwrap_or_else(|| question.clone());
+    if rewritten_query.is_some() {
+        log::info!("Rewrote follow-up question for retrieval ({} chars)", search_query.len());
+    }
+
+    let hits = retrieve(&app_handle, &settings, &store, &document_id, &search_query, top_k).await?;
+    let screened = injection::screen(&app_handle, &settings, &document_id, &hits).await;
+    if let Some(warning) = &screened.warning {
+        window.emit_to(window.label(), "injection_warning", warning).ok();
+    }
+
+    let document = store.index_info(&document_id)?;
+    let document_name = document.as_ref().map(|d| d.name().to_string());
+
+    // A template rewrites the question and adds its system prompt after the citation rules
+    let mut system_prompt = answer_prompt(&settings, &store, &document_id, &question);
+    let mut prompt_question = question.clone();
+    if let Some(template_id) = template_id {
+        let template = prompts::get_template(&app_handle, &template_id)?;
+        let mut variables = prompts::default_variables();
+        variables.insert("input", question.clone());
+        variables.insert("document", document_name.clone().unwrap_or_default());
+        prompt_question = template.render(&variables);
+        if let Some(extra) = template.system_prompt.filter(|p| !p.trim().is_empty()) {
+            system_prompt = format!("{}\n\n{}", system_prompt, extra);
+        }
+    }
+
+    let mut messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: system_prompt,
+        images: Vec::new(),
+    }];
+    if let Some(conversation_id) = &conversation_id {
+        messages.extend(context_message(&conversations, conversation_id)?);
+    }
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: build_prompt(&prompt_question, &hits, &screened.texts),
+        images: Vec::new(),
+    });
+    let trace = message_id.map(|message_id| RetrievalTrace {
+        message_id,
+        conversation_id: conversation_id.clone(),
+        document_id: document_id.clone(),
+        query: search_query.clone(),
+        chunks: hits.clone(),
+        excluded_chunks: screened.excluded(&hits),
+        messages: messages.clone(),
+        model: settings.ollama_model.clone(),
+        temperature: settings.temperature,
+        top_p: settings.top_p,
+        generation: settings.generation.clone(),
+        created_at: now_secs(),
+    });
+
+    let answer = ollama_chat::stream_answer(
+        settings.ollama_model.clone(),
+        messages,
+        Some(settings.temperature),
+        None,
+        Some(settings.top_p),
+        Some(settings.generation.clone()),
+        request_id,
+        conversation_id,
+        &window,
+        &app_handle,
+        &streams,
+    )
+    .await?;
+    if let Some(trace) = trace.filter(|_| answer.is_some()) {
+        retrieval_trace::record_or_warn(&conversations, &trace);
+    }
+
+    let grounding = match answer.filter(|_| settings.strict_grounding) {
+        Some(answer) => Some(grounding::check(&app_handle, &settings, &store, &document_id, &answer, &hits).await),
+        None => None,
+    };
+    let refused = grounding
+        .as_ref()
+        .is_some_and(|g| g.score < grounding::MIN_GROUNDING_SCORE);
+    if refused {
+        log::warn!("Answer refused: too few of its claims are in the retrieved excerpts");
+    }
+
+    let citations = citations(&document_id, document_name.as_deref(), &hits);
+    Ok(RagResult {
+        sources: hits,
+        citations,
+        rewritten_query,
+        grounding_score: grounding.as_ref().map(|g| g.score),
+        unsupported_claims: grounding.map(|g| g.unsupported).unwrap_or_default(),
+        refused,
+    })
+}
+
+/// Closest chunks of the document to `query`, by vector and keyword search combined
+pub(crate) async fn retrieve(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    store: &VectorStore,
+    document_id: &str,
+    query: &str,
+    top_k: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    let embedding_model = embedding_model::from_settings(settings);
+    if let Some(info) = store.index_info(document_id)? {
+        info.check_model(&embedding_model)?;
+    }
+    let embedding = ollama_chat::embed_in(Lane::Interactive, embedding_model, query.to_string(), app_handle).await?;
+    let vector: Vec<f32> = embedding.iter().map(|&v| v as f32).collect();
+    // Without an explicit count, retrieve as many chunks as the model's context has room for
+    let top_k = match top_k {
+        Some(top_k) => top_k,
+        None => context_window::for_model(app_handle, settings, &settings.ollama_model).await.retrieval_top_k,
+    };
+    let hits = store.hybrid_search(document_id, &vector, query, top_k)?;
+
+    if hits.is_empty() {
+        return Err(format!("No indexed content found for document {}", document_id));
+    }
+    log::info!("Retrieved {} chunks (best score {:.3})", hits.len(), hits[0].score);
+    Ok(hits)
+}
+
+/// System prompt for document questions: citation and excerpt rules, which
+/// language to answer in, the user's instructions, and the verification rules
+/// in strict grounding mode
+pub(crate) fn answer_prompt(settings: &AppSettings, store: &VectorStore, document_id: &str, question: &str) -> String {
+    let mut rules = format!("{}\n\n{}", SYSTEM_PROMPT, injection::EXCERPT_RULES);
+    let document_language = language::document_language(store, document_id);
+    if let Some(instruction) = language::answer_instruction(question, document_language.as_ref()) {
+        rules = format!("{}\n\n{}", rules, instruction);
+    }
+    let prompt = with_instructions(&rules, settings);
+    if settings.strict_grounding {
+        format!("{}\n\n{}", prompt, grounding::GROUNDING_PROMPT)
+    } else {
+        prompt
+    }
+}
+
+/// An answer produced without a window, e.g. for the command line
+#[derive(Debug, Serialize)]
+pub struct Answer {
+    pub answer: String,
+    citations: Vec<Citation>,
+    /// Set in strict grounding mode, as in `RagResult`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grounding_score: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unsupported_claims: Vec<String>,
+    pub refused: bool,
+}
+
+impl Answer {
+    /// Pages of the excerpts the answer was given, in order and without repeats
+    pub fn pages(&self) -> Vec<u32> {
+        let mut pages: Vec<u32> = self.citations.iter().filter_map(|c| c.page).collect();
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
+}
+
+/// `rag_query` in one non-streaming request, with no conversation or template
+pub(crate) async fn answer(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    store: &VectorStore,
+    document_id: &str,
+    question: &str,
+    top_k: Option<usize>,
+) -> Result<Answer, String> {
+    let hits = retrieve(app_handle, settings, store, document_id, question, top_k).await?;
+    // Without a window the warning is only logged
+    let screened = injection::screen(app_handle, settings, document_id, &hits).await;
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: answer_prompt(settings, store, document_id, question),
+            images: Vec::new(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: build_prompt(question, &hits, &screened.texts),
+            images: Vec::new(),
+        },
+    ];
+    let answer = ollama_chat::chat_in(
+        Lane::Interactive,
+        settings.ollama_model.clone(),
+        messages,
+        Some(settings.temperature),
+        None,
+        Some(settings.top_p),
+        Some(settings.generation.clone()),
+        None,
+        app_handle.clone(),
+    )
+    .await?;
+
+    let grounding = if settings.strict_grounding {
+        Some(grounding::check(app_handle, settings, store, document_id, &answer, &hits).await)
+    } else {
+        None
+    };
+    let document_name = store.index_info(document_id)?.map(|d| d.name().to_string());
+    Ok(Answer {
+        citations: citations(document_id, document_name.as_deref(), &hits),
+        refused: grounding
+            .as_ref()
+            .is_some_and(|g| g.score < grounding::MIN_GROUNDING_SCORE),
+        grounding_score: grounding.as_ref().map(|g| g.score),
+        unsupported_claims: grounding.map(|g| g.unsupported).unwrap_or_default(),
+        answer: answer.trim().to_string(),
+    })
+}
+
+/// Text the user pasted into the conversation, as a system message
+pub(crate) fn context_message(conversations: &ConversationStore, conversation_id: &str) -> Result<Option<ChatMessage>, String> {
+    let documents = conversations.context_documents(conversation_id)?;
+    if documents.is_empty() {
+        return Ok(None);
+    }
+    let mut content = String::from("The user added the following to this conversation:\n");
+    for document in documents {
+        let kind = if document.kind == "screenshot" { "Text from a screenshot" } else { "Pasted text" };
+        content.push_str(&format!("\n{}: {}\n\"\"\"\n{}\n\"\"\"\n", kind, document.title, document.content));
+    }
+    Ok(Some(ChatMessage {
+        role: "system".to_string(),
+        content,
+        images: Vec::new(),
+    }))
+}
+
+/// A built-in system prompt followed by the user's `system_prompt` setting, if any
+pub(crate) fn with_instructions(prompt: &str, settings: &AppSettings) -> String {
+    match settings.system_prompt.trim() {
+        "" => prompt.to_string(),
+        extra => format!("{}\n\n{}", prompt, extra),
+    }
+}
+
+/// One non-streaming completion with the configured model, queued in `lane`
+pub(crate) async fn complete(
+    lane: Lane,
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    instruction: &str,
+    text: &str,
+) -> Result<String, String> {
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: instruction.to_string(),
+            images: Vec::new(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: text.to_string(),
+            images: Vec::new(),
+        },
+    ];
+    let reply = ollama_chat::chat_in(
+        lane,
+        settings.ollama_model.clone(),
+        messages,
+        Some(settings.temperature),
+        None,
+        Some(settings.top_p),
+        None,
+        None,
+        app_handle.clone(),
+    )
+    .await?;
+    Ok(reply.trim().to_string())
+}
@@ -0,0 +1,165 @@
+use serde::Deserialize;
+use tauri::Emitter;
+
+use crate::conversations::{AnswerCandidate, ConversationStore};
+use crate::error::AppError;
+use crate::injection;
+use crate::ollama::ChatMessage;
+use crate::ollama_chat::{self, ChatStreams};
+use crate::rag;
+use crate::retrieval_trace::{self, RetrievalTrace};
+use crate::settings;
+use crate::vectorstore::{now_secs, VectorStore};
+
+/// Lowest temperature for a regenerated answer, so it doesn't repeat the last one
+const MIN_REGENERATE_TEMPERATURE: f32 = 0.7;
+
+/// Sampling for `regenerate_answer`; unset fields are picked to differ from the last answer
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RegenerateOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    /// A random seed when unset
+    pub seed: Option<i64>,
+}
+
+/// Chunk ids of the sources saved with a message, in excerpt order
+fn source_chunk_ids(sources: &[serde_json::Value]) -> Vec<String> {
+    sources
+        .iter()
+        .filter_map(|source| source.get("chunk_id")?.as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+fn random_seed() -> i64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    (nanos % i32::MAX as u128) as i64
+}
+
+/// Answer a question again from the excerpts the earlier answer was given
+///
+/// The question is the user message before `message_id`, and the excerpts are
+/// the chunks saved as that message's sources, read back by id; nothing is
+/// retrieved again. The new answer streams as `ollama_stream_chunk` events with
+/// another seed and, unless set in `options`, a temperature of at least
+/// `MIN_REGENERATE_TEMPERATURE`. The original answer and every regenerated one
+/// are kept as candidates; returns all of them, the new one last. The frontend
+/// saves the conversation with whichever the user picks. The message's retrieval
+/// trace is replaced with the new answer's.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn regenerate_answer(
+    conversation_id: String,
+    message_id: String,
+    options: Option<RegenerateOptions>,
+    request_id: Option<String>,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    streams: tauri::State<'_, ChatStreams>,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<Vec<AnswerCandidate>, AppError> {
+    log::info!("Regenerating message {} in {}", message_id, conversation_id);
+
+    let conversation = conversations
+        .get(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let document_id = conversation
+        .document_id
+        .clone()
+        .ok_or_else(|| AppError::Unsupported("Only answers about a document can be regenerated".to_string()))?;
+    let position = conversation
+        .messages
+        .iter()
+        .position(|m| m.id == message_id && m.role == "assistant")
+        .ok_or_else(|| format!("Answer not found: {}", message_id))?;
+    let original = &conversation.messages[position];
+    let question = conversation.messages[..position]
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .map(|m| m.content.clone())
+        .ok_or_else(|| AppError::Other("No question found before the answer".to_string()))?;
+
+    let chunk_ids = source_chunk_ids(&original.sources);
+    let hits = store.chunks(&document_id, &chunk_ids)?;
+    if hits.is_empty() {
+        return Err(AppError::Other("The answer's sources are no longer in the index; ask the question again".to_string()));
+    }
+    if hits.len() < chunk_ids.len() {
+        log::warn!("{} of the answer's sources are no longer in the index", chunk_ids.len() - hits.len());
+    }
+
+    let settings = settings::read_settings_for(&app_handle, None, Some(&document_id));
+    let options = options.unwrap_or_default();
+    let temperature = options
+        .temperature
+        .unwrap_or_else(|| settings.temperature.max(MIN_REGENERATE_TEMPERATURE));
+    let seed = options.seed.unwrap_or_else(random_seed);
+    let generation = crate::backend::GenerationOptions {
+        seed: Some(seed),
+        ..settings.generation.clone()
+    };
+
+    let screened = injection::screen(&app_handle, &settings, &document_id, &hits).await;
+    if let Some(warning) = &screened.warning {
+        window.emit_to(window.label(), "injection_warning", warning).ok();
+    }
+    let mut messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: rag::answer_prompt(&settings, &store, &document_id, &question),
+        images: Vec::new(),
+    }];
+    messages.extend(rag::context_message(&conversations, &conversation_id)?);
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: rag::build_prompt(&question, &hits, &screened.texts),
+        images: Vec::new(),
+    });
+    let top_p = options.top_p.unwrap_or(settings.top_p);
+    let trace = RetrievalTrace {
+        message_id: message_id.clone(),
+        conversation_id: Some(conversation_id.clone()),
+        document_id: document_id.clone(),
+        query: question.clone(),
+        chunks: hits.clone(),
+        excluded_chunks: screened.excluded(&hits),
+        messages: messages.clone(),
+        model: settings.ollama_model.clone(),
+        temperature,
+        top_p,
+        generation: generation.clone(),
+        created_at: now_secs(),
+    };
+
+    let answer = ollama_chat::stream_answer(
+        settings.ollama_model.clone(),
+        messages,
+        Some(temperature),
+        None,
+        Some(top_p),
+        Some(generation),
+        request_id,
+        Some(conversation_id.clone()),
+        &window,
+        &app_handle,
+        &streams,
+    )
+    .await?;
+
+    let Some(answer) = answer else {
+        log::info!("Regeneration cancelled");
+        return Ok(conversations.candidates(&conversation_id, &message_id)?);
+    };
+    if conversations.candidates(&conversation_id, &message_id)?.is_empty() {
+        conversations.add_candidate(&conversation_id, &message_id, &original.content, None, None)?;
+    }
+    conversations.add_candidate(&conversation_id, &message_id, answer.trim(), Some(temperature), Some(seed))?;
+    retrieval_trace::record_or_warn(&conversations, &trace);
+    Ok(conversations.candidates(&conversation_id, &message_id)?)
+}
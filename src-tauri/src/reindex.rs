@@ -0,0 +1,204 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::Emitter;
+
+use crate::conversations::ConversationStore;
+use crate::document_sources::DocumentSource;
+use crate::embedding_cache;
+use crate::embedding_model;
+use crate::error::AppError;
+use crate::ingest::{self, IndexingProgress};
+use crate::pdf_security;
+use crate::progress::ProgressThrottle;
+use crate::settings;
+use crate::vectorstore::VectorStore;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexStatus {
+    /// The file matches what was indexed; nothing was done
+    Unchanged,
+    Reindexed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReindexReport {
+    status: ReindexStatus,
+    /// Id of the document after re-indexing: the hash of the new content
+    pub(crate) document_id: String,
+    previous_id: String,
+    chunks: usize,
+    /// Chunks whose text was unchanged and kept their embedding
+    reused: usize,
+    /// Chunks sent to the embedding model
+    embedded: usize,
+    /// Chunks of deleted indexes removed from the store
+    orphans_removed: usize,
+}
+
+/// Point workspaces and conversations at a document's new id
+fn move_references(
+    store: &VectorStore,
+    conversations: &ConversationStore,
+    from: &str,
+    to: &str,
+) -> Result<(), String> {
+    store
+        .conn()
+        .execute(
+            "UPDATE OR IGNORE workspace_documents SET index_id = ?2 WHERE index_id = ?1",
+            params![from, to],
+        )
+        .map_err(|e| format!("Failed to update workspaces: {}", e))?;
+    store
+        .conn()
+        .execute("UPDATE library_documents SET hash = ?2 WHERE hash = ?1", params![from, to])
+        .map_err(|e| format!("Failed to update library: {}", e))?;
+    conversations
+        .conn()
+        .execute(
+            "UPDATE conversations SET document_id = ?2 WHERE document_id = ?1",
+            params![from, to],
+        )
+        .map_err(|e| format!("Failed to update conversations: {}", e))?;
+    Ok(())
+}
+
+/// Re-index a document whose file changed on disk
+///
+/// The file recorded for the document is checked by modification time, then by
+/// content hash; if either matches nothing is done unless `force` is set. Chunks
+/// whose text didn't change keep their embeddings, so only edited passages are
+/// sent to the embedding model. The new content gets its hash as document id,
+/// workspaces, library entries and conversations are moved to it, the old index
+/// is deleted, and chunks left behind by deleted indexes are garbage-collected.
+///
+/// `sensitive` switches the document to or from PII-masked indexing (see
+/// `ingest::mask_pages`), which always re-indexes it; unset keeps its current mode.
+#[tauri::command]
+pub async fn reindex_document(
+    document_id: String,
+    force: Option<bool>,
+    sensitive: Option<bool>,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<ReindexReport, AppError> {
+    let info = store.index_info(&document_id)?;
+    let was_masked = info.as_ref().is_some_and(|i| i.is_masked());
+    let masked = sensitive.unwrap_or(was_masked);
+    let force = force.unwrap_or(false) || masked != was_masked;
+    log::info!("Re-indexing {} (force: {}, masked: {})", document_id, force, masked);
+
+    let source = store
+        .source(&document_id)?
+        .ok_or_else(|| format!("No source file is recorded for document {}", document_id))?;
+    let path = PathBuf::from(&source.path);
+    if !path.is_file() {
+        return Err(AppError::Io(format!("{} no longer exists", source.path)));
+    }
+    let unchanged = |orphans_removed| ReindexReport {
+        status: ReindexStatus::Unchanged,
+        document_id: document_id.clone(),
+        previous_id: document_id.clone(),
+        chunks: info.as_ref().map(|i| i.chunk_count()).unwrap_or(0),
+        reused: 0,
+        embedded: 0,
+        orphans_removed,
+    };
+
+    let mtime = ingest::file_mtime(&path);
+    if !force && mtime.is_some() && mtime == source.mtime {
+        log::info!("{} is unchanged (same modification time)", source.path);
+        return Ok(unchanged(store.remove_orphans()?));
+    }
+
+    let hash_path = source.path.clone();
+    let hash = tauri::async_runtime::spawn_blocking(move || embedding_cache::hash_file(&hash_path))
+        .await
+        .map_err(|e| format!("Hashing task failed: {}", e))??;
+    if !force && hash == source.hash {
+        log::info!("{} is unchanged (same content)", source.path);
+        store.set_source(&DocumentSource { mtime, ..source })?;
+        return Ok(unchanged(store.remove_orphans()?));
+    }
+
+    pdf_security::warn_active_content(&app_handle, &path).await;
+    let read_path = source.path.clone();
+    let pages = tauri::async_runtime::spawn_blocking(move || ingest::read_pages(&read_path))
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))??;
+    let chunks = if masked { ingest::chunk_pages(&ingest::mask_pages(&pages)) } else { ingest::chunk_pages(&pages) };
+    let name = info.as_ref().map(|i| i.name().to_string()).unwrap_or_else(|| ingest::file_name(&path));
+    if chunks.is_empty() {
+        return Err(AppError::Other(format!("No text found in {}; scanned PDFs need OCR first", name)));
+    }
+
+    // Vectors are only reusable if they came from the model used now
+    let model = embedding_model::from_settings(&settings::read_settings(&app_handle));
+    let same_model = info
+        .as_ref()
+        .is_some_and(|i| i.model().unwrap_or(embedding_model::DEFAULT) == model);
+    let known = if same_model {
+        store.chunk_vectors(&document_id)?
+    } else {
+        HashMap::new()
+    };
+    let mut throttle = ProgressThrottle::new();
+    let embedded = ingest::embed_chunks(&app_handle, &model, &hash, &name, &chunks, &known, |percent, last| {
+        if throttle.should_emit(percent, last) {
+            app_handle.emit("indexing_progress", IndexingProgress {
+                document_id: Some(hash.clone()),
+                path: source.path.clone(),
+                name: name.clone(),
+                stage: "embedding",
+                percent,
+                queued: 0,
+            }).ok();
+        }
+    })
+    .await?;
+
+    if hash == document_id {
+        // Forced re-index of unchanged content (e.g. after switching models): replace the chunks in place
+        store.reset(&document_id, embedded.dimension, &model)?;
+    }
+    store.create(&hash, &name, embedded.dimension, Some(&model))?;
+    store.promote(&embedded.staging, &hash)?;
+    store.set_masked(&hash, masked)?;
+    ingest::record_language(&app_handle, &store, &hash, &pages, &model);
+    store.set_source(&DocumentSource {
+        index_id: hash.clone(),
+        path: source.path.clone(),
+        mtime,
+        hash: hash.clone(),
+    })?;
+    if hash != document_id {
+        move_references(&store, &conversations, &document_id, &hash)?;
+        store
+            .conn()
+            .execute("DELETE FROM indexes WHERE id = ?1", params![document_id])
+            .map_err(|e| format!("Failed to delete old index: {}", e))?;
+    }
+    let orphans_removed = store.remove_orphans()?;
+
+    log::info!(
+        "Re-indexed {} as {}: {} chunks, {} reused, {} embedded",
+        name,
+        hash,
+        embedded.chunks,
+        embedded.reused,
+        embedded.chunks - embedded.reused
+    );
+    Ok(ReindexReport {
+        status: ReindexStatus::Reindexed,
+        document_id: hash,
+        previous_id: document_id.clone(),
+        chunks: embedded.chunks,
+        reused: embedded.reused,
+        embedded: embedded.chunks - embedded.reused,
+        orphans_removed,
+    })
+}
@@ -0,0 +1,165 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::vectorstore::VectorStore;
+
+/// Files last listed on each remote source, and the version of each that was imported
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS remote_files (
+        source_id TEXT NOT NULL REFERENCES remote_sources(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        size INTEGER,
+        modified TEXT,
+        version TEXT NOT NULL,
+        imported_version TEXT,
+        local_path TEXT,
+        PRIMARY KEY (source_id, path)
+    );
+";
+
+/// A supported document found when listing a source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Relative to the source, with `/` separators
+    pub path: String,
+    pub size: Option<i64>,
+    pub modified: Option<String>,
+    pub etag: Option<String>,
+}
+
+impl Entry {
+    /// Changes whenever the file does; the ETag where the server sends one
+    fn version(&self) -> String {
+        self.etag
+            .clone()
+            .unwrap_or_else(|| format!("{}:{}", self.size.unwrap_or(-1), self.modified.as_deref().unwrap_or("")))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteFile {
+    /// Relative to the source, with `/` separators
+    pub path: String,
+    size: Option<i64>,
+    modified: Option<String>,
+    imported: bool,
+    /// Imported, and changed on the source since
+    pub changed: bool,
+    /// Where the imported copy is indexed from
+    local_path: Option<String>,
+}
+
+/// Create the remote file table; after `remote_sources`, which it references
+pub fn init(store: &VectorStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize remote files: {}", e))
+}
+
+/// Save the listing of a source, forgetting files that went away
+pub fn record(store: &VectorStore, source_id: &str, entries: &[Entry]) -> Result<(), String> {
+    let mut conn = store.conn();
+    let tx = conn.transaction().map_err(|e| format!("Failed to save remote files: {}", e))?;
+    let listed: HashSet<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+    let known: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT path FROM remote_files WHERE source_id = ?1")
+            .map_err(|e| format!("Failed to save remote files: {}", e))?;
+        let rows = stmt
+            .query_map(params![source_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to save remote files: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to save remote files: {}", e))?;
+        rows
+    };
+    for path in known.iter().filter(|p| !listed.contains(p.as_str())) {
+        tx.execute("DELETE FROM remote_files WHERE source_id = ?1 AND path = ?2", params![source_id, path])
+            .map_err(|e| format!("Failed to save remote files: {}", e))?;
+    }
+    for entry in entries {
+        tx.execute(
+            "INSERT INTO remote_files (source_id, path, size, modified, version) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (source_id, path) DO UPDATE SET size = ?3, modified = ?4, version = ?5",
+            params![source_id, entry.path, entry.size, entry.modified, entry.version()],
+        )
+        .map_err(|e| format!("Failed to save remote files: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to save remote files: {}", e))
+}
+
+/// The files of a source as last listed
+pub fn list(store: &VectorStore, source_id: &str) -> Result<Vec<RemoteFile>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, size, modified, imported_version IS NOT NULL,
+                    imported_version IS NOT NULL AND imported_version != version, local_path
+             FROM remote_files WHERE source_id = ?1 ORDER BY path",
+        )
+        .map_err(|e| format!("Failed to read remote files: {}", e))?;
+    let files = stmt
+        .query_map(params![source_id], |row| {
+            Ok(RemoteFile {
+                path: row.get(0)?,
+                size: row.get(1)?,
+                modified: row.get(2)?,
+                imported: row.get(3)?,
+                changed: row.get(4)?,
+                local_path: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read remote files: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read remote files: {}", e))?;
+    Ok(files)
+}
+
+/// Remember that the listed version of a file was imported to `local_path`
+pub fn mark_imported(store: &VectorStore, source_id: &str, path: &str, local_path: &str) -> Result<(), String> {
+    store
+        .conn()
+        .execute(
+            "UPDATE remote_files SET imported_version = version, local_path = ?3 WHERE source_id = ?1 AND path = ?2",
+            params![source_id, path, local_path],
+        )
+        .map(|_| ())
+        .map_err(|e| format!("Failed to record import of {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn entry(path: &str, etag: &str) -> Entry {
+        Entry {
+            path: path.to_string(),
+            size: Some(10),
+            modified: None,
+            etag: Some(etag.to_string()),
+        }
+    }
+
+    #[test]
+    fn changed_files_are_flagged_after_import() {
+        let store = VectorStore::open(Path::new(":memory:")).unwrap();
+        store
+            .conn()
+            .execute_batch("CREATE TABLE remote_sources (id TEXT PRIMARY KEY); INSERT INTO remote_sources VALUES ('rs');")
+            .unwrap();
+        init(&store).unwrap();
+
+        record(&store, "rs", &[entry("a.pdf", "1"), entry("b.pdf", "1")]).unwrap();
+        mark_imported(&store, "rs", "a.pdf", "/mnt/nas/a.pdf").unwrap();
+        record(&store, "rs", &[entry("a.pdf", "2"), entry("c.pdf", "1")]).unwrap();
+
+        let files = list(&store, "rs").unwrap();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.pdf", "c.pdf"]);
+        assert!(files[0].imported && files[0].changed);
+        assert_eq!(files[0].local_path.as_deref(), Some("/mnt/nas/a.pdf"));
+        assert!(!files[1].imported && !files[1].changed);
+    }
+}
@@ -0,0 +1,456 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::http;
+use crate::ingest::{self, IngestQueue};
+use crate::reindex;
+use crate::remote_files::{self, Entry, RemoteFile};
+use crate::settings;
+use crate::vectorstore::{now_secs, VectorStore};
+use crate::webdav::{self, Credentials};
+
+/// Remote sources live in `vectors.db`; the files listed on them are in `remote_files`
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS remote_sources (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        location TEXT NOT NULL,
+        username TEXT,
+        name TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        checked_at INTEGER
+    );
+";
+
+/// Wait after startup before the first check, so it doesn't compete with launch
+const STARTUP_DELAY: Duration = Duration::from_secs(90);
+/// How often sources are checked for changed files while the app runs
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Folders nested deeper than this below a source aren't listed
+const MAX_DEPTH: usize = 8;
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// A WebDAV collection, e.g. on a NAS
+    Webdav,
+    /// A folder of a network share mounted by the OS
+    Share,
+}
+
+impl SourceKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            SourceKind::Webdav => "webdav",
+            SourceKind::Share => "share",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteSource {
+    id: String,
+    kind: SourceKind,
+    /// Collection URL for WebDAV, folder path for a share
+    location: String,
+    username: Option<String>,
+    name: String,
+    /// When the files were last listed, in seconds since the epoch
+    checked_at: Option<i64>,
+}
+
+/// Create the remote source tables in the vector store database and allow their hosts
+pub fn init(store: &VectorStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize remote sources: {}", e))?;
+    remote_files::init(store)?;
+    allow_hosts(store)
+}
+
+fn password_secret(source_id: &str) -> String {
+    format!("remote_source_{}", source_id)
+}
+
+fn new_source_id() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    format!("rs_{:x}", nanos)
+}
+
+fn read_source(row: &rusqlite::Row) -> rusqlite::Result<RemoteSource> {
+    let kind: String = row.get(1)?;
+    Ok(RemoteSource {
+        id: row.get(0)?,
+        kind: if kind == "webdav" { SourceKind::Webdav } else { SourceKind::Share },
+        location: row.get(2)?,
+        username: row.get(3)?,
+        name: row.get(4)?,
+        checked_at: row.get(5)?,
+    })
+}
+
+fn sources(store: &VectorStore) -> Result<Vec<RemoteSource>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare("SELECT id, kind, location, username, name, checked_at FROM remote_sources ORDER BY created_at")
+        .map_err(|e| format!("Failed to read remote sources: {}", e))?;
+    let sources = stmt
+        .query_map([], read_source)
+        .map_err(|e| format!("Failed to read remote sources: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read remote sources: {}", e))?;
+    Ok(sources)
+}
+
+fn source(store: &VectorStore, source_id: &str) -> Result<RemoteSource, String> {
+    store
+        .conn()
+        .query_row(
+            "SELECT id, kind, location, username, name, checked_at FROM remote_sources WHERE id = ?1",
+            params![source_id],
+            read_source,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read remote source: {}", e))?
+        .ok_or_else(|| format!("Remote source not found: {}", source_id))
+}
+
+/// Let the network guard through to the WebDAV servers of the registered sources
+fn allow_hosts(store: &VectorStore) -> Result<(), String> {
+    let hosts = sources(store)?
+        .iter()
+        .filter(|s| s.kind == SourceKind::Webdav)
+        .filter_map(|s| reqwest::Url::parse(&s.location).ok()?.host_str().map(str::to_string))
+        .collect();
+    http::set_remote_hosts(hosts);
+    Ok(())
+}
+
+/// A relative path that stays inside the folder it's joined to
+fn safe_relative(path: &str) -> Option<PathBuf> {
+    let relative = PathBuf::from(path);
+    let normal = relative.components().all(|c| matches!(c, Component::Normal(_)));
+    (normal && !path.is_empty()).then_some(relative)
+}
+
+fn is_candidate(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    !name.starts_with('.') && !name.starts_with("~$") && ingest::is_supported(Path::new(name))
+}
+
+/// Supported documents in a mounted share, up to `MAX_DEPTH` folders down
+fn list_share(root: &Path) -> Result<Vec<Entry>, String> {
+    if !root.is_dir() {
+        return Err(format!("{} isn't reachable; is the share mounted?", root.display()));
+    }
+    let mut entries = Vec::new();
+    let mut folders = vec![(root.to_path_buf(), String::new(), 0)];
+    while let Some((folder, prefix, depth)) = folders.pop() {
+        let Ok(items) = std::fs::read_dir(&folder) else {
+            log::warn!("Skipping unreadable folder {}", folder.display());
+            continue;
+        };
+        for item in items.flatten() {
+            let name = item.file_name().to_string_lossy().into_owned();
+            let path = format!("{}{}", prefix, name);
+            let Ok(metadata) = item.metadata() else { continue };
+            if metadata.is_dir() {
+                if depth < MAX_DEPTH && !name.starts_with('.') {
+                    folders.push((item.path(), format!("{}/", path), depth + 1));
+                }
+            } else if is_candidate(&path) {
+                let modified = metadata.modified().ok().and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+                entries.push(Entry {
+                    path,
+                    size: Some(metadata.len() as i64),
+                    modified: modified.map(|m| m.as_secs().to_string()),
+                    etag: None,
+                });
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+fn credentials(source: &RemoteSource) -> Result<Option<Credentials>, String> {
+    let Some(username) = &source.username else {
+        return Ok(None);
+    };
+    Ok(Some(Credentials {
+        username: username.clone(),
+        password: settings::get_secret(&password_secret(&source.id))?,
+    }))
+}
+
+async fn list_webdav(source: &RemoteSource) -> Result<Vec<Entry>, String> {
+    let base = webdav::collection_url(&source.location)?;
+    let keep = |path: &str| is_candidate(path) && safe_relative(path).is_some();
+    let files = webdav::list(&base, credentials(source)?.as_ref(), MAX_DEPTH, keep).await?;
+    Ok(files
+        .into_iter()
+        .map(|f| Entry {
+            path: f.path,
+            size: f.size,
+            modified: f.modified,
+            etag: f.etag,
+        })
+        .collect())
+}
+
+/// Save the listing of a source and when it was taken, and return its files
+fn record_listing(store: &VectorStore, source_id: &str, entries: &[Entry]) -> Result<Vec<RemoteFile>, String> {
+    remote_files::record(store, source_id, entries)?;
+    store
+        .conn()
+        .execute("UPDATE remote_sources SET checked_at = ?2 WHERE id = ?1", params![source_id, now_secs()])
+        .map_err(|e| format!("Failed to save remote files: {}", e))?;
+    remote_files::list(store, source_id)
+}
+
+/// List a source now and save what's on it
+async fn refresh(app_handle: &tauri::AppHandle, source: &RemoteSource) -> Result<Vec<RemoteFile>, String> {
+    let entries = match source.kind {
+        SourceKind::Webdav => list_webdav(source).await?,
+        SourceKind::Share => {
+            let root = PathBuf::from(&source.location);
+            tauri::async_runtime::spawn_blocking(move || list_share(&root))
+                .await
+                .map_err(|e| format!("Listing task failed: {}", e))??
+        }
+    };
+    log::info!("{} has {} documents", source.name, entries.len());
+    record_listing(&app_handle.state::<VectorStore>(), &source.id, &entries)
+}
+
+/// Copy a WebDAV file to the app data folder
+async fn download(app_handle: &tauri::AppHandle, source: &RemoteSource, path: &str) -> Result<PathBuf, String> {
+    let relative = safe_relative(path).ok_or_else(|| format!("Invalid remote path: {}", path))?;
+    let local = crate::portable::app_data_dir(app_handle)
+        .map_err(|e| e.to_string())?
+        .join("remote")
+        .join(&source.id)
+        .join(relative);
+    if let Some(parent) = local.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let url = webdav::file_url(&webdav::collection_url(&source.location)?, path)?;
+    webdav::download(&url, credentials(source)?.as_ref(), &local).await?;
+    Ok(local)
+}
+
+/// Fetch a listed file and index it, re-indexing the earlier copy when it changed
+async fn import(
+    app_handle: &tauri::AppHandle,
+    source: &RemoteSource,
+    file: &RemoteFile,
+    sensitive: Option<bool>,
+) -> Result<String, String> {
+    let local = match source.kind {
+        SourceKind::Webdav => download(app_handle, source, &file.path).await?,
+        SourceKind::Share => {
+            let relative = safe_relative(&file.path).ok_or_else(|| format!("Invalid remote path: {}", file.path))?;
+            Path::new(&source.location).join(relative)
+        }
+    };
+    let local = local.display().to_string();
+
+    let store = app_handle.state::<VectorStore>();
+    match store.source_by_path(&local)? {
+        Some(indexed) => {
+            let report = reindex::reindex_document(
+                indexed.index_id,
+                None,
+                sensitive,
+                app_handle.clone(),
+                app_handle.state(),
+                app_handle.state(),
+            )
+            .await?;
+            log::info!("Updated {} from {}: {:?}", file.path, source.name, report);
+        }
+        None => app_handle
+            .state::<IngestQueue>()
+            .enqueue(PathBuf::from(&local), sensitive.unwrap_or(false))?,
+    }
+    remote_files::mark_imported(&store, &source.id, &file.path, &local)?;
+    Ok(local)
+}
+
+/// List every source and re-import the imported files that changed on it
+async fn check_all(app_handle: &tauri::AppHandle) -> Result<usize, String> {
+    let offline = settings::read_settings(app_handle).offline_mode;
+    let mut updated = 0;
+    for source in sources(&app_handle.state::<VectorStore>())? {
+        // The guard would refuse it anyway; skipping keeps the blocked request log clean
+        if offline && source.kind == SourceKind::Webdav {
+            continue;
+        }
+        let files = match refresh(app_handle, &source).await {
+            Ok(files) => files,
+            Err(e) => {
+                log::warn!("Failed to check {}: {}", source.name, e);
+                continue;
+            }
+        };
+        for file in files.iter().filter(|f| f.changed) {
+            match import(app_handle, &source, file, None).await {
+                Ok(_) => updated += 1,
+                Err(e) => log::warn!("Failed to update {} from {}: {}", file.path, source.name, e),
+            }
+        }
+    }
+    Ok(updated)
+}
+
+/// Check the remote sources for changed files every half hour
+///
+/// Imported files that changed are fetched again and re-indexed incrementally.
+/// Emits `remote_files_updated` with the number of updated files.
+pub fn check_in_background(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            match check_all(&app_handle).await {
+                Ok(0) => {}
+                Ok(updated) => {
+                    app_handle.emit("remote_files_updated", updated).ok();
+                }
+                Err(e) => log::warn!("Remote source check failed: {}", e),
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Register a WebDAV collection or a mounted network share to import documents from
+///
+/// The WebDAV password is kept in the OS keychain. Offline mode still blocks
+/// WebDAV sources.
+#[tauri::command]
+pub async fn add_remote_source(
+    kind: SourceKind,
+    location: String,
+    name: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<RemoteSource, AppError> {
+    let location = location.trim().to_string();
+    match kind {
+        SourceKind::Webdav => {
+            webdav::collection_url(&location)?;
+        }
+        SourceKind::Share if !Path::new(&location).is_absolute() || !Path::new(&location).is_dir() => {
+            return Err(AppError::Other(format!("{} is not a folder", location)));
+        }
+        SourceKind::Share => {}
+    }
+    let id = new_source_id();
+    let username = username.filter(|u| !u.is_empty() && kind == SourceKind::Webdav);
+    if let (Some(_), Some(password)) = (&username, password.filter(|p| !p.is_empty())) {
+        settings::set_secret(&password_secret(&id), &password)?;
+    }
+    let name = name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| location.clone());
+    log::info!("Adding remote source {} ({})", name, kind.as_str());
+
+    store
+        .conn()
+        .execute(
+            "INSERT INTO remote_sources (id, kind, location, username, name, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, kind.as_str(), location, username, name, now_secs()],
+        )
+        .map_err(|e| AppError::Other(format!("Failed to save remote source: {}", e)))?;
+    allow_hosts(&store)?;
+    Ok(source(&store, &id)?)
+}
+
+#[tauri::command]
+pub async fn list_remote_sources(store: tauri::State<'_, VectorStore>) -> Result<Vec<RemoteSource>, AppError> {
+    Ok(sources(&store)?)
+}
+
+/// Forget a source; documents imported from it stay indexed
+#[tauri::command]
+pub async fn remove_remote_source(source_id: String, store: tauri::State<'_, VectorStore>) -> Result<bool, AppError> {
+    log::info!("Removing remote source {}", source_id);
+    let has_password = source(&store, &source_id).is_ok_and(|s| s.username.is_some());
+    let removed = store
+        .conn()
+        .execute("DELETE FROM remote_sources WHERE id = ?1", params![source_id])
+        .map_err(|e| AppError::Other(format!("Failed to remove remote source: {}", e)))?;
+    if has_password {
+        if let Err(e) = settings::set_secret(&password_secret(&source_id), "") {
+            log::warn!("Failed to remove the password of {}: {}", source_id, e);
+        }
+    }
+    allow_hosts(&store)?;
+    Ok(removed > 0)
+}
+
+/// List the documents on a source now, marking the imported ones that changed
+#[tauri::command]
+pub async fn list_remote_files(
+    source_id: String,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<Vec<RemoteFile>, AppError> {
+    let source = source(&store, &source_id)?;
+    Ok(refresh(&app_handle, &source).await?)
+}
+
+/// Import files listed on a source; changed files that were imported before are re-indexed
+///
+/// Returns the local paths that were queued or updated.
+#[tauri::command]
+pub async fn import_remote_files(
+    source_id: String,
+    paths: Vec<String>,
+    sensitive: Option<bool>,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<Vec<String>, AppError> {
+    let source = source(&store, &source_id)?;
+    let files = remote_files::list(&store, &source_id)?;
+    log::info!("Importing {} files from {}", paths.len(), source.name);
+    let mut imported = Vec::new();
+    for path in &paths {
+        let file = files
+            .iter()
+            .find(|f| &f.path == path)
+            .ok_or_else(|| AppError::Other(format!("{} isn't listed on {}; list the source first", path, source.name)))?;
+        imported.push(import(&app_handle, &source, file, sensitive).await?);
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_paths_cant_leave_the_download_folder() {
+        assert!(safe_relative("reports/q3.pdf").is_some());
+        assert!(safe_relative("../q3.pdf").is_none());
+        assert!(safe_relative("reports/../../q3.pdf").is_none());
+        assert!(safe_relative("/etc/q3.pdf").is_none());
+        assert!(safe_relative("").is_none());
+    }
+
+    #[test]
+    fn shares_are_listed_recursively() {
+        let dir = std::env::temp_dir().join(format!("privatepdf-test-remote-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("2026")).unwrap();
+        for name in ["a.pdf", "2026/b.pdf", "notes.xyz", ".hidden.pdf"] {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+        let paths: Vec<String> = list_share(&dir).unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, ["2026/b.pdf", "a.pdf"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,145 @@
+use futures::StreamExt;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::ollama::ChatMessage;
+use crate::ollama_chat;
+use crate::settings;
+use crate::vectorstore::SearchHit;
+
+/// Cosine hits handed to the reranker
+pub const CANDIDATES: usize = 50;
+/// Passages scored per model call; keeps each prompt well inside a 4k context
+const BATCH_SIZE: usize = 10;
+/// Batches scored at the same time
+const PARALLELISM: usize = 3;
+/// Longest passage excerpt shown to the model
+const MAX_PASSAGE_CHARS: usize = 600;
+
+const SYSTEM_PROMPT: &str = "You rate how well passages answer a search query. For every numbered passage, \
+reply with one line `<number>: <score>`, where score is 0 (unrelated) to 10 (directly answers the query). \
+Reply with the scores only.";
+
+fn score_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?im)^\W*(?:passage\s*)?(\d+)\W*[:=\-]\s*(\d+(?:\.\d+)?)").expect("invalid score pattern")
+    })
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_PASSAGE_CHARS {
+        format!("{}…", text.chars().take(MAX_PASSAGE_CHARS).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// Parse `<number>: <score>` lines into scores by passage number (1-based)
+fn parse_scores(reply: &str) -> HashMap<usize, f32> {
+    score_pattern()
+        .captures_iter(reply)
+        .filter_map(|c| Some((c[1].parse().ok()?, c[2].parse::<f32>().ok()?.clamp(0.0, 10.0))))
+        .collect()
+}
+
+/// Score one batch of passages with the chat model
+async fn score_batch(
+    app_handle: &tauri::AppHandle,
+    model: &str,
+    query: &str,
+    batch: &[SearchHit],
+) -> Result<Vec<Option<f32>>, String> {
+    let mut prompt = format!("Query: {}\n\n", query.trim());
+    for (i, hit) in batch.iter().enumerate() {
+        prompt.push_str(&format!("[{}] {}\n\n", i + 1, excerpt(&hit.text)));
+    }
+
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: SYSTEM_PROMPT.to_string(),
+            images: Vec::new(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+            images: Vec::new(),
+        },
+    ];
+    let reply = ollama_chat::ollama_chat(model.to_string(), messages, Some(0.0), Some(256), None, None, None, app_handle.clone()).await?;
+
+    let scores = parse_scores(&reply);
+    Ok((1..=batch.len()).map(|n| scores.get(&n).copied()).collect())
+}
+
+/// Reorder cosine hits by model-judged relevance to `query` and keep `top_k`
+///
+/// Uses the configured chat model as a prompt-based cross-encoder: it sees the
+/// query and each passage together, which orders long technical chunks much
+/// better than embedding similarity alone. Passages the model didn't score, or
+/// whose batch failed, keep their cosine order behind the scored ones; ties are
+/// broken by cosine score.
+pub async fn rerank(app_handle: &tauri::AppHandle, query: &str, hits: Vec<SearchHit>, top_k: usize) -> Vec<SearchHit> {
+    let model = settings::read_settings(app_handle).ollama_model;
+    log::info!("Reranking {} hits with {}", hits.len(), model);
+
+    // Collected first: a lazily mapped stream of borrowing futures isn't provably `Send`
+    let scoring: Vec<_> = hits
+        .chunks(BATCH_SIZE)
+        .map(|batch| {
+            let model = &model;
+            async move {
+                score_batch(app_handle, model, query, batch).await.unwrap_or_else(|e| {
+                    log::warn!("Reranking batch failed, keeping cosine order: {}", e);
+                    vec![None; batch.len()]
+                })
+            }
+        })
+        .collect();
+    let batches: Vec<Vec<Option<f32>>> = futures::stream::iter(scoring).buffered(PARALLELISM).collect().await;
+
+    let mut scored: Vec<(Option<f32>, SearchHit)> = batches.into_iter().flatten().zip(hits).collect();
+    log::info!(
+        "Reranker scored {} of {} hits",
+        scored.iter().filter(|(s, _)| s.is_some()).count(),
+        scored.len()
+    );
+
+    scored.sort_by(|(sa, a), (sb, b)| {
+        sb.unwrap_or(-1.0)
+            .partial_cmp(&sa.unwrap_or(-1.0))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    scored.into_iter().take(top_k).map(|(_, hit)| hit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_are_read_from_loose_replies() {
+        let scores = parse_scores("1: 8\nPassage 2 = 3.5\n  [3] - 10\n**4**: 12\n5: n/a\nSure, here you go.");
+        assert_eq!(scores.get(&1), Some(&8.0));
+        assert_eq!(scores.get(&2), Some(&3.5));
+        assert_eq!(scores.get(&3), Some(&10.0));
+        // Out of range scores are clamped
+        assert_eq!(scores.get(&4), Some(&10.0));
+        assert_eq!(scores.get(&5), None);
+        assert_eq!(scores.len(), 4);
+        assert!(parse_scores("").is_empty());
+    }
+
+    #[test]
+    fn long_passages_are_shortened() {
+        assert_eq!(excerpt("  two\n\nwords "), "two words");
+        let long = "é".repeat(MAX_PASSAGE_CHARS + 5);
+        let short = excerpt(&long);
+        assert_eq!(short.chars().count(), MAX_PASSAGE_CHARS + 1);
+        assert!(short.ends_with('…'));
+    }
+}
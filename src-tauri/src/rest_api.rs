@@ -0,0 +1,308 @@
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use hyper::body::HttpBody;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, HeaderMap, Method, Request, Response, Server, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::sync::Mutex;
+use tauri::Manager;
+use tokio::sync::oneshot;
+
+use crate::error::AppError;
+use crate::ingest::IngestQueue;
+use crate::rag;
+use crate::settings;
+use crate::vectorstore::VectorStore;
+use crate::workspace;
+
+pub const DEFAULT_PORT: u16 = 7437;
+/// Keychain entry holding the token clients authenticate with
+const TOKEN_SECRET: &str = "rest_api_token";
+const TOKEN_BYTES: usize = 32;
+/// Larger request bodies are refused; requests carry paths and questions only
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// The local REST API; off unless turned on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestApiSettings {
+    pub enabled: bool,
+    /// Port on 127.0.0.1 the API listens on
+    pub port: u16,
+}
+
+impl Default for RestApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+struct Running {
+    port: u16,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// The running server, if any
+#[derive(Default)]
+pub struct RestApi {
+    running: Mutex<Option<Running>>,
+}
+
+impl RestApi {
+    /// Start, stop or move the server to match `settings`
+    pub fn apply(&self, app_handle: &tauri::AppHandle, settings: &RestApiSettings) {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let wanted = if settings.enabled { Some(settings.port) } else { None };
+        if running.as_ref().map(|r| r.port) == wanted {
+            return;
+        }
+        if let Some(previous) = running.take() {
+            previous.shutdown.send(()).ok();
+            log::info!("Stopped the REST API on port {}", previous.port);
+        }
+        if let Some(port) = wanted {
+            match start(app_handle.clone(), port) {
+                Ok(started) => *running = Some(started),
+                Err(e) => log::warn!("REST API not started: {}", e),
+            }
+        }
+    }
+}
+
+/// The API token from the keychain, generated on first use
+///
+/// The token is never written to settings.json; without a keychain the API stays off.
+fn token() -> Result<String, AppError> {
+    if let Some(token) = settings::get_secret(TOKEN_SECRET)? {
+        return Ok(token);
+    }
+    new_token()
+}
+
+fn new_token() -> Result<String, AppError> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    settings::set_secret(TOKEN_SECRET, &token)?;
+    Ok(token)
+}
+
+/// Compare without stopping at the first differing byte, so timing doesn't leak the token
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Whether the request carries `Authorization: Bearer <token>`
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| same_token(given.trim(), token))
+}
+
+fn start(app_handle: tauri::AppHandle, port: u16) -> Result<Running, String> {
+    token().map_err(|e| format!("no keychain for its token: {}", e))?;
+    // Loopback only; the API is for scripts on this machine
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = TcpListener::bind(address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(async move {
+        let make_service = make_service_fn(move |_| {
+            let app_handle = app_handle.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(app_handle.clone(), request))) }
+        });
+        let server = match Server::from_tcp(listener) {
+            Ok(server) => server,
+            Err(e) => {
+                log::warn!("REST API not started: {}", e);
+                return;
+            }
+        };
+        log::info!("REST API listening on http://{}", address);
+        let stopped = async {
+            stopped.await.ok();
+        };
+        if let Err(e) = server.serve(make_service).with_graceful_shutdown(stopped).await {
+            log::warn!("REST API stopped: {}", e);
+        }
+    });
+    Ok(Running { port, shutdown })
+}
+
+/// A failed request: the status and the message sent back as `{"error": ...}`
+struct ApiError(StatusCode, String);
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+fn json_response(status: StatusCode, body: &impl Serialize) -> Response<Body> {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap_or_default()
+}
+
+fn to_json(value: &impl Serialize) -> Result<serde_json::Value, ApiError> {
+    serde_json::to_value(value).map_err(|e| ApiError::from(format!("Failed to serialize the response: {}", e)))
+}
+
+async fn read_json<T: DeserializeOwned>(mut body: Body) -> Result<T, ApiError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+        if bytes.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(ApiError(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large".to_string()));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    serde_json::from_slice(&bytes).map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))
+}
+
+#[derive(Debug, Deserialize)]
+struct IngestRequest {
+    paths: Vec<String>,
+    #[serde(default)]
+    sensitive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchRequest {
+    query: String,
+    document_id: Option<String>,
+    top_k: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AskRequest {
+    document_id: String,
+    question: String,
+    top_k: Option<usize>,
+}
+
+fn require_document(store: &VectorStore, document_id: &str) -> Result<(), ApiError> {
+    match store.index_info(document_id)? {
+        Some(_) => Ok(()),
+        None => Err(ApiError(StatusCode::NOT_FOUND, format!("No document {}", document_id))),
+    }
+}
+
+async fn route(app_handle: &tauri::AppHandle, request: Request<Body>) -> Result<serde_json::Value, ApiError> {
+    let store = app_handle.state::<VectorStore>();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    match (method, path.as_str()) {
+        (Method::GET, "/documents") => to_json(&store.list()?),
+        (Method::POST, "/ingest") => {
+            let body: IngestRequest = read_json(request.into_body()).await?;
+            let queued = app_handle.state::<IngestQueue>().enqueue_all(body.paths, body.sensitive)?;
+            Ok(json!({ "queued": queued }))
+        }
+        (Method::POST, "/search") => {
+            let body: SearchRequest = read_json(request.into_body()).await?;
+            if let Some(document_id) = &body.document_id {
+                require_document(&store, document_id)?;
+            }
+            let top_k = body.top_k.unwrap_or(workspace::DEFAULT_TOP_K);
+            let hits = workspace::search_library(app_handle, &store, body.document_id.as_deref(), body.query, top_k).await?;
+            to_json(&hits)
+        }
+        (Method::POST, "/ask") => {
+            let body: AskRequest = read_json(request.into_body()).await?;
+            require_document(&store, &body.document_id)?;
+            let settings = settings::read_settings_for(app_handle, None, Some(&body.document_id));
+            let answer = rag::answer(app_handle, &settings, &store, &body.document_id, &body.question, body.top_k).await?;
+            to_json(&answer)
+        }
+        (_, "/documents" | "/ingest" | "/search" | "/ask") => {
+            Err(ApiError(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed".to_string()))
+        }
+        (_, path) => Err(ApiError(StatusCode::NOT_FOUND, format!("No endpoint {}", path))),
+    }
+}
+
+async fn handle(app_handle: tauri::AppHandle, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    // Read for every request, so a reset token takes effect at once
+    let token = match token() {
+        Ok(token) => token,
+        Err(e) => return Ok(json_response(StatusCode::SERVICE_UNAVAILABLE, &json!({ "error": e.to_string() }))),
+    };
+    if !authorized(request.headers(), &token) {
+        log::warn!("REST API request without a valid token: {} {}", request.method(), request.uri().path());
+        return Ok(json_response(StatusCode::UNAUTHORIZED, &json!({ "error": "Missing or wrong API token" })));
+    }
+
+    log::info!("REST API: {} {}", request.method(), request.uri().path());
+    Ok(match route(&app_handle, request).await {
+        Ok(body) => json_response(StatusCode::OK, &body),
+        Err(ApiError(status, message)) => json_response(status, &json!({ "error": message })),
+    })
+}
+
+/// The token scripts send as `Authorization: Bearer <token>`, generated on first use
+#[tauri::command]
+pub async fn get_rest_api_token() -> Result<String, AppError> {
+    token()
+}
+
+/// Replace the API token; scripts using the old one are refused from now on
+#[tauri::command]
+pub async fn reset_rest_api_token() -> Result<String, AppError> {
+    log::info!("Resetting the REST API token");
+    new_token()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(authorization).unwrap());
+        headers
+    }
+
+    #[test]
+    fn only_the_bearer_token_is_accepted() {
+        let token = "0123456789abcdef";
+        assert!(authorized(&headers("Bearer 0123456789abcdef"), token));
+        assert!(!authorized(&headers("Bearer 0123456789abcdee"), token));
+        assert!(!authorized(&headers("Bearer 0123456789abcde"), token));
+        assert!(!authorized(&headers("Basic 0123456789abcdef"), token));
+        assert!(!authorized(&HeaderMap::new(), token));
+    }
+
+    #[test]
+    fn the_api_is_off_by_default() {
+        let settings: RestApiSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, RestApiSettings::default());
+        assert!(!settings.enabled);
+        assert_eq!(settings.port, DEFAULT_PORT);
+    }
+
+    #[test]
+    fn request_bodies_have_defaults() {
+        let ingest: IngestRequest = serde_json::from_str(r#"{"paths":["/a.pdf"]}"#).unwrap();
+        assert!(!ingest.sensitive);
+        let search: SearchRequest = serde_json::from_str(r#"{"query":"revenue"}"#).unwrap();
+        assert!(search.document_id.is_none() && search.top_k.is_none());
+    }
+}
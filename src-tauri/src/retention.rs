@@ -0,0 +1,284 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::settings;
+use crate::vectorstore::{now_secs, VectorStore};
+
+/// Wait after startup before the first purge, so it doesn't compete with launch
+const STARTUP_DELAY: Duration = Duration::from_secs(120);
+/// How often the policies are applied while the app runs
+const PURGE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// How long chats, document indexes and logs are kept; 0 keeps them forever
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Delete conversations not updated for this many days
+    pub chat_days: u64,
+    /// Drop the index of library documents not opened for this many days.
+    /// Pinned documents and documents in a workspace are kept; the library
+    /// entry stays, so the document can be indexed again when it's opened.
+    pub index_days: u64,
+    /// Delete retrieval traces and log files older than this many days
+    pub log_days: u64,
+}
+
+impl RetentionSettings {
+    fn is_off(&self) -> bool {
+        self.chat_days == 0 && self.index_days == 0 && self.log_days == 0
+    }
+}
+
+/// What a purge removed; the payload of `retention_purged`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionReport {
+    conversations: usize,
+    indexes: usize,
+    traces: usize,
+    log_files: usize,
+}
+
+impl RetentionReport {
+    fn is_empty(&self) -> bool {
+        self.conversations + self.indexes + self.traces + self.log_files == 0
+    }
+}
+
+fn cutoff_secs(days: u64) -> i64 {
+    now_secs() - days as i64 * SECS_PER_DAY
+}
+
+/// Delete conversations last updated before `cutoff_ms`, with their stats, context and traces
+fn purge_conversations(conn: &Connection, cutoff_ms: i64) -> Result<usize, String> {
+    let stale = "SELECT id FROM conversations WHERE updated_at < ?1";
+    for table in ["conversation_stats", "context_documents", "retrieval_traces"] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE conversation_id IN ({})", table, stale),
+            params![cutoff_ms],
+        )
+        .map_err(|e| format!("Failed to purge old conversations: {}", e))?;
+    }
+    // Messages, memory and answer candidates cascade
+    conn.execute("DELETE FROM conversations WHERE updated_at < ?1", params![cutoff_ms])
+        .map_err(|e| format!("Failed to purge old conversations: {}", e))
+}
+
+/// Delete retrieval traces created before `cutoff`
+fn purge_traces(conn: &Connection, cutoff: i64) -> Result<usize, String> {
+    conn.execute("DELETE FROM retrieval_traces WHERE created_at < ?1", params![cutoff])
+        .map_err(|e| format!("Failed to purge old retrieval traces: {}", e))
+}
+
+/// Drop the indexes of library documents last opened before `cutoff`
+///
+/// A document opened from several paths counts as opened at the latest of
+/// them, and is kept if any of its entries is pinned.
+fn purge_indexes(store: &VectorStore, cutoff: i64) -> Result<usize, String> {
+    let stale: Vec<String> = {
+        let conn = store.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT l.hash FROM library_documents l
+                 JOIN indexes i ON i.id = l.hash
+                 WHERE l.hash NOT IN (SELECT index_id FROM workspace_documents)
+                 GROUP BY l.hash
+                 HAVING MAX(l.last_opened) < ?1 AND MAX(l.pinned) = 0",
+            )
+            .map_err(|e| format!("Failed to find unused indexes: {}", e))?;
+        let rows = stmt
+            .query_map(params![cutoff], |row| row.get(0))
+            .map_err(|e| format!("Failed to find unused indexes: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to find unused indexes: {}", e))?;
+        rows
+    };
+    for index_id in &stale {
+        log::info!("Dropping index {} of a document not opened recently", index_id);
+        store.delete(index_id)?;
+    }
+    Ok(stale.len())
+}
+
+/// Delete `.log` files in `dir` last written before `cutoff`, never the newest one
+fn purge_logs(dir: &Path, cutoff: SystemTime) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut logs: Vec<(SystemTime, std::path::PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "log"))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    logs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    let mut removed = 0;
+    for (_, path) in logs.into_iter().skip(1).filter(|(modified, _)| *modified < cutoff) {
+        match fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Failed to remove old log file {}: {}", path.display(), e),
+        }
+    }
+    removed
+}
+
+/// Apply the retention policies once
+fn purge(app_handle: &tauri::AppHandle, policy: &RetentionSettings) -> Result<RetentionReport, String> {
+    let mut report = RetentionReport::default();
+
+    if policy.chat_days > 0 || policy.log_days > 0 {
+        let store = app_handle.state::<ConversationStore>();
+        let conn = store.conn();
+        if policy.chat_days > 0 {
+            report.conversations = purge_conversations(&conn, cutoff_secs(policy.chat_days) * 1000)?;
+        }
+        if policy.log_days > 0 {
+            report.traces = purge_traces(&conn, cutoff_secs(policy.log_days))?;
+        }
+    }
+    if policy.index_days > 0 {
+        report.indexes = purge_indexes(&app_handle.state::<VectorStore>(), cutoff_secs(policy.index_days))?;
+    }
+    if policy.log_days > 0 {
+        if let Ok(dir) = crate::portable::app_log_dir(app_handle) {
+            let max_age = Duration::from_secs(policy.log_days * SECS_PER_DAY as u64);
+            let cutoff = SystemTime::now().checked_sub(max_age).unwrap_or(SystemTime::UNIX_EPOCH);
+            report.log_files = purge_logs(&dir, cutoff);
+        }
+    }
+
+    if !report.is_empty() {
+        log::info!("Retention purge removed {:?}", report);
+    }
+    Ok(report)
+}
+
+async fn purge_blocking(app_handle: &tauri::AppHandle, policy: RetentionSettings) -> Result<RetentionReport, String> {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || purge(&handle, &policy))
+        .await
+        .map_err(|e| format!("Retention purge failed: {}", e))?
+}
+
+/// Apply the `retention` settings every few hours
+///
+/// Settings are read again before each run, so turning a policy on takes effect
+/// without a restart. Emits `retention_purged` when anything was removed.
+pub fn enforce_in_background(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            let policy = settings::read_settings(&app_handle).retention;
+            if !policy.is_off() {
+                match purge_blocking(&app_handle, policy).await {
+                    Ok(report) if !report.is_empty() => {
+                        app_handle.emit("retention_purged", report).ok();
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Scheduled retention purge failed: {}", e),
+                }
+            }
+            tokio::time::sleep(PURGE_INTERVAL).await;
+        }
+    });
+}
+
+/// Apply the retention settings now instead of waiting for the next scheduled purge
+#[tauri::command]
+pub async fn apply_retention(app_handle: tauri::AppHandle) -> Result<RetentionReport, AppError> {
+    let policy = settings::read_settings(&app_handle).retention;
+    log::info!("Applying retention policy {:?}", policy);
+    Ok(purge_blocking(&app_handle, policy).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{library, retrieval_trace, workspace};
+
+    fn vectors() -> VectorStore {
+        let store = VectorStore::open(Path::new(":memory:")).unwrap();
+        workspace::init(&store).unwrap();
+        library::init(&store).unwrap();
+        store
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn old_conversations_are_purged_with_their_side_tables() {
+        let store = ConversationStore::open(Path::new(":memory:")).unwrap();
+        retrieval_trace::init(&store).unwrap();
+        let conn = store.conn();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, title, created_at, updated_at) VALUES ('old', 'a', 0, 1000), ('new', 'b', 0, 9000);
+             INSERT INTO messages (conversation_id, position, id, role, content, timestamp)
+                 VALUES ('old', 0, 'm0', 'user', 'hi', 0), ('new', 0, 'm1', 'user', 'hi', 0);
+             INSERT INTO context_documents (id, conversation_id, kind, title, content, created_at)
+                 VALUES ('c0', 'old', 'text', 't', 'x', 0);
+             INSERT INTO retrieval_traces (message_id, conversation_id, trace, created_at)
+                 VALUES ('m0', 'old', '{}', 100), ('m1', 'new', '{}', 100);",
+        )
+        .unwrap();
+
+        assert_eq!(purge_conversations(&conn, 5000).unwrap(), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM conversations"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM messages"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM context_documents"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM retrieval_traces WHERE conversation_id = 'new'"), 1);
+
+        assert_eq!(purge_traces(&conn, 200).unwrap(), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM retrieval_traces"), 0);
+    }
+
+    #[test]
+    fn only_unused_unpinned_indexes_are_dropped() {
+        let store = vectors();
+        store
+            .conn()
+            .execute_batch(
+                "INSERT INTO indexes (id, name, dimension, created_at)
+                     VALUES ('stale', 's', 2, 0), ('pinned', 'p', 2, 0), ('shared', 'w', 2, 0), ('moved', 'm', 2, 0);
+                 INSERT INTO library_documents (path, hash, title, last_opened, pinned) VALUES
+                     ('/a.pdf', 'stale', 'a', 10, 0),
+                     ('/b.pdf', 'pinned', 'b', 10, 1),
+                     ('/c.pdf', 'shared', 'c', 10, 0),
+                     ('/d.pdf', 'moved', 'd', 10, 0),
+                     ('/e.pdf', 'moved', 'd', 5000, 0);
+                 INSERT INTO workspaces (id, name, created_at) VALUES ('ws', 'ws', 0);
+                 INSERT INTO workspace_documents (workspace_id, index_id, name, added_at) VALUES ('ws', 'shared', 'c', 0);",
+            )
+            .unwrap();
+
+        assert_eq!(purge_indexes(&store, 1000).unwrap(), 1);
+        let conn = store.conn();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM indexes WHERE id = 'stale'"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM indexes"), 3);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM library_documents"), 5);
+    }
+
+    #[test]
+    fn the_newest_log_file_is_kept() {
+        let dir = std::env::temp_dir().join(format!("privatepdf-test-retention-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a.log", "b.log", "notes.txt"] {
+            fs::write(dir.join(name), "x").unwrap();
+        }
+
+        let future = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(purge_logs(&dir, future), 1);
+        assert!(dir.join("notes.txt").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
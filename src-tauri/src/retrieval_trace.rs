@@ -0,0 +1,97 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::GenerationOptions;
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::ollama::ChatMessage;
+use crate::vectorstore::SearchHit;
+
+/// Traces live in `conversations.db`; like the chat stats they can be written
+/// before the frontend saves the conversation, so there's no foreign key
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS retrieval_traces (
+        message_id TEXT PRIMARY KEY,
+        conversation_id TEXT,
+        trace TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_retrieval_traces_conversation ON retrieval_traces(conversation_id);
+";
+
+/// Everything that went into one answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievalTrace {
+    pub message_id: String,
+    pub conversation_id: Option<String>,
+    pub document_id: String,
+    /// Text the chunks were retrieved for: the question, or its standalone rewrite
+    pub query: String,
+    /// Chunks in excerpt order with the scores they were retrieved with
+    pub chunks: Vec<SearchHit>,
+    /// Chunks that were retrieved but left out of the prompt as suspected injection
+    #[serde(default)]
+    pub excluded_chunks: Vec<String>,
+    /// The messages sent to the model, system prompt first
+    pub messages: Vec<ChatMessage>,
+    pub model: String,
+    pub temperature: f32,
+    pub top_p: f32,
+    pub generation: GenerationOptions,
+    /// Seconds since the epoch
+    pub created_at: i64,
+}
+
+/// Create the trace table in the conversation store database
+pub fn init(store: &ConversationStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize retrieval traces: {}", e))
+}
+
+/// Save the trace of an answer, replacing an earlier one for the same message
+/// (a regenerated answer, say)
+fn record(store: &ConversationStore, trace: &RetrievalTrace) -> Result<(), String> {
+    let json = serde_json::to_string(trace).map_err(|e| format!("Failed to serialize retrieval trace: {}", e))?;
+    store
+        .conn()
+        .execute(
+            "INSERT OR REPLACE INTO retrieval_traces (message_id, conversation_id, trace, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![trace.message_id, trace.conversation_id, json, trace.created_at],
+        )
+        .map_err(|e| format!("Failed to save retrieval trace: {}", e))?;
+    Ok(())
+}
+
+/// `record`, logging failures; a missing trace shouldn't fail the answer
+pub fn record_or_warn(store: &ConversationStore, trace: &RetrievalTrace) {
+    if let Err(e) = record(store, trace) {
+        log::warn!("{}", e);
+    }
+}
+
+/// The chunks, scores, prompt and generation options behind an answer
+///
+/// Traces are kept for answers from `rag_query`, `query_selection` and
+/// `regenerate_answer` when they're given the assistant message's id. Returns
+/// null for messages without one.
+#[tauri::command]
+pub async fn get_retrieval_trace(
+    message_id: String,
+    store: tauri::State<'_, ConversationStore>,
+) -> Result<Option<RetrievalTrace>, AppError> {
+    let json: Option<String> = store
+        .conn()
+        .query_row(
+            "SELECT trace FROM retrieval_traces WHERE message_id = ?1",
+            params![message_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read retrieval trace: {}", e))?;
+    Ok(json.map(|json| serde_json::from_str(&json).map_err(|e| format!("Failed to read retrieval trace: {}", e)))
+        .transpose()?)
+}
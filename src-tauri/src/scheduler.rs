@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio::sync::oneshot;
+
+/// Default number of requests sent to the model server at once
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 1;
+
+/// Priority of a model request; earlier lanes go first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lane {
+    /// Chat answers and the query embedding, rewrite and rerank steps of a question
+    Interactive,
+    /// Chunk embeddings while indexing
+    Embedding,
+    /// Summaries, suggested questions, flashcards
+    Background,
+}
+
+struct Waiter {
+    /// Arrival order within a lane
+    seq: u64,
+    lane: Lane,
+    request_id: Option<String>,
+    /// Receives its permit, so a permit sent to a request cancelled in the
+    /// meantime is dropped with the channel and frees the slot again
+    wake: oneshot::Sender<Permit>,
+}
+
+struct Queue {
+    in_flight: usize,
+    max_in_flight: usize,
+    next_seq: u64,
+    waiting: Vec<Waiter>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedRequest {
+    request_id: String,
+    lane: Lane,
+    /// 1 is next to run
+    position: usize,
+}
+
+/// Payload of `request_queue_changed` and `get_request_queue`
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    in_flight: usize,
+    max_in_flight: usize,
+    interactive_waiting: usize,
+    embedding_waiting: usize,
+    background_waiting: usize,
+    /// Waiting requests that have a request id, in the order they'll run
+    queued: Vec<QueuedRequest>,
+}
+
+/// Limits how many requests reach the model server at once
+///
+/// Ollama slows to a crawl when an embedding batch runs alongside a chat stream,
+/// so requests wait here for a slot and the highest-priority lane is served first.
+pub struct RequestScheduler {
+    queue: Mutex<Queue>,
+}
+
+impl Default for RequestScheduler {
+    fn default() -> Self {
+        Self {
+            queue: Mutex::new(Queue {
+                in_flight: 0,
+                max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+                next_seq: 0,
+                waiting: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl Queue {
+    /// Hand free slots to waiters in priority order
+    fn dispatch(&mut self, app_handle: &tauri::AppHandle) {
+        while self.in_flight < self.max_in_flight && !self.waiting.is_empty() {
+            let next = (0..self.waiting.len())
+                .min_by_key(|&i| (self.waiting[i].lane, self.waiting[i].seq))
+                .unwrap_or(0);
+            let waiter = self.waiting.remove(next);
+            self.in_flight += 1;
+            let permit = Permit {
+                app_handle: app_handle.clone(),
+            };
+            // The request was cancelled while queued. Its permit never ran, so it's
+            // forgotten rather than dropped (which would take this lock again).
+            if let Err(permit) = waiter.wake.send(permit) {
+                std::mem::forget(permit);
+                self.in_flight -= 1;
+            }
+        }
+    }
+
+    fn status(&self) -> QueueStatus {
+        let mut order: Vec<&Waiter> = self.waiting.iter().collect();
+        order.sort_by_key(|w| (w.lane, w.seq));
+        let waiting = |lane| self.waiting.iter().filter(|w| w.lane == lane).count();
+        QueueStatus {
+            in_flight: self.in_flight,
+            max_in_flight: self.max_in_flight,
+            interactive_waiting: waiting(Lane::Interactive),
+            embedding_waiting: waiting(Lane::Embedding),
+            background_waiting: waiting(Lane::Background),
+            queued: order
+                .iter()
+                .enumerate()
+                .filter_map(|(i, w)| {
+                    Some(QueuedRequest {
+                        request_id: w.request_id.clone()?,
+                        lane: w.lane,
+                        position: i + 1,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RequestScheduler {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Apply the `max_concurrent_requests` setting
+    pub fn set_max_in_flight(&self, app_handle: &tauri::AppHandle, max: usize) {
+        let mut queue = self.lock();
+        queue.max_in_flight = max.max(1);
+        queue.dispatch(app_handle);
+    }
+}
+
+/// A slot for one request; the next waiting request runs when it's dropped
+pub struct Permit {
+    app_handle: tauri::AppHandle,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let scheduler = self.app_handle.state::<RequestScheduler>();
+        let status = {
+            let mut queue = scheduler.lock();
+            queue.in_flight = queue.in_flight.saturating_sub(1);
+            let had_waiters = !queue.waiting.is_empty();
+            queue.dispatch(&self.app_handle);
+            had_waiters.then(|| queue.status())
+        };
+        if let Some(status) = status {
+            self.app_handle.emit("request_queue_changed", status).ok();
+        }
+    }
+}
+
+/// Wait for a slot to send a request to the model server
+///
+/// Returns at once when a slot is free and nothing of the same or higher priority
+/// is waiting. Queued requests emit `request_queue_changed` so the UI can show
+/// their position; pass the frontend's `request_id` to have it listed there.
+/// Dropping the future (e.g. a cancelled chat) gives up the place in the queue.
+pub async fn acquire(app_handle: &tauri::AppHandle, lane: Lane, request_id: Option<&str>) -> Permit {
+    let scheduler = app_handle.state::<RequestScheduler>();
+    let (receiver, status) = {
+        let mut queue = scheduler.lock();
+        let ahead = queue.waiting.iter().any(|w| w.lane <= lane);
+        if queue.in_flight < queue.max_in_flight && !ahead {
+            queue.in_flight += 1;
+            return Permit {
+                app_handle: app_handle.clone(),
+            };
+        }
+        let (wake, receiver) = oneshot::channel();
+        let seq = queue.next_seq;
+        queue.next_seq += 1;
+        queue.waiting.push(Waiter {
+            seq,
+            lane,
+            request_id: request_id.map(String::from),
+            wake,
+        });
+        (receiver, queue.status())
+    };
+
+    log::debug!("{:?} request queued behind {} in flight", lane, status.in_flight);
+    app_handle.emit("request_queue_changed", status).ok();
+    match receiver.await {
+        Ok(permit) => permit,
+        // Waiters are only removed by sending them a permit, so this can't happen;
+        // run anyway rather than hang
+        Err(_) => {
+            scheduler.lock().in_flight += 1;
+            Permit {
+                app_handle: app_handle.clone(),
+            }
+        }
+    }
+}
+
+/// Requests in flight and waiting for the model server
+#[tauri::command]
+pub fn get_request_queue(scheduler: tauri::State<'_, RequestScheduler>) -> QueueStatus {
+    scheduler.lock().status()
+}
@@ -0,0 +1,199 @@
+use serde::Serialize;
+use std::fs;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+
+/// Size of the zero buffer used when overwriting files
+const OVERWRITE_BLOCK: usize = 1024 * 1024;
+
+/// Shown with every purge report so users don't over-trust the overwrite
+const SSD_NOTE: &str = "Files are overwritten with zeros before deletion. This is best-effort: \
+SSDs (wear levelling) and copy-on-write filesystems (APFS, Btrfs, ZFS) may keep old copies of the \
+data in blocks the app cannot reach. Full-disk encryption is the only reliable protection there.";
+
+#[derive(Debug, Default, Serialize)]
+pub struct SecureDeleteReport {
+    files_deleted: usize,
+    bytes_overwritten: u64,
+    failures: Vec<String>,
+    note: String,
+}
+
+/// Remove a symlink itself, never what it points to
+fn unlink(path: &Path) -> Result<u64, String> {
+    // Windows removes symlinks to directories with remove_dir
+    fs::remove_file(path)
+        .or_else(|_| fs::remove_dir(path))
+        .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    Ok(0)
+}
+
+/// Overwrite a file with zeros, flush it to disk, then remove it
+///
+/// Returns the number of bytes overwritten. A symlink is only unlinked; the file
+/// it points to is left alone.
+pub fn secure_delete(path: &Path) -> Result<u64, String> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if metadata.file_type().is_symlink() {
+        return unlink(path);
+    }
+    let len = metadata.len();
+
+    {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {} for overwrite: {}", path.display(), e))?;
+        file.seek(SeekFrom::Start(0))
+            .map_err(|e| format!("Failed to seek {}: {}", path.display(), e))?;
+
+        let zeros = vec![0u8; OVERWRITE_BLOCK];
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = remaining.min(OVERWRITE_BLOCK as u64) as usize;
+            file.write_all(&zeros[..n])
+                .map_err(|e| format!("Failed to overwrite {}: {}", path.display(), e))?;
+            remaining -= n as u64;
+        }
+        file.sync_all()
+            .map_err(|e| format!("Failed to flush {}: {}", path.display(), e))?;
+    }
+
+    fs::remove_file(path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    Ok(len)
+}
+
+/// Securely delete every file under `dir` and then remove the directory tree
+fn secure_delete_dir(dir: &Path, report: &mut SecureDeleteReport) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.failures.push(format!("Failed to read {}: {}", dir.display(), e));
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // file_type() doesn't follow symlinks, so a link to a directory outside
+        // the temp folder is unlinked rather than descended into
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => {
+                if let Err(e) = unlink(&path) {
+                    log::warn!("{}", e);
+                    report.failures.push(e);
+                }
+            }
+            Ok(file_type) if file_type.is_dir() => secure_delete_dir(&path, report),
+            Ok(_) => record(report, secure_delete(&path)),
+            Err(e) => report.failures.push(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    if let Err(e) = fs::remove_dir(dir) {
+        report.failures.push(format!("Failed to remove {}: {}", dir.display(), e));
+    }
+}
+
+fn record(report: &mut SecureDeleteReport, result: Result<u64, String>) {
+    match result {
+        Ok(bytes) => {
+            report.files_deleted += 1;
+            report.bytes_overwritten += bytes;
+        }
+        Err(e) => {
+            log::warn!("{}", e);
+            report.failures.push(e);
+        }
+    }
+}
+
+/// Directory for temporary artifacts (OCR rasterizations, export intermediates)
+///
+/// Everything written here is removed by `purge_temp_data`.
+pub fn temp_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::portable::app_cache_dir(app_handle)
+        .map_err(|e| format!("Failed to get app cache directory: {}", e))?
+        .join("tmp");
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Securely delete all temporary data the app has left on disk
+#[tauri::command]
+pub async fn purge_temp_data(app_handle: tauri::AppHandle) -> Result<SecureDeleteReport, AppError> {
+    log::info!("Purging temporary data...");
+
+    let mut report = SecureDeleteReport {
+        note: SSD_NOTE.to_string(),
+        ..Default::default()
+    };
+
+    // Leftover Ollama installer archive from an interrupted install
+    if let Some(download) = crate::ollama_install::managed_download_path() {
+        // Includes an interrupted download kept for resuming
+        for suffix in ["", ".part", ".part.json"] {
+            let temp_archive = PathBuf::from(format!("{}{}", download.display(), suffix));
+            if temp_archive.exists() {
+                record(&mut report, secure_delete(&temp_archive));
+            }
+        }
+    }
+
+    let dir = temp_dir(&app_handle)?;
+    secure_delete_dir(&dir, &mut report);
+
+    log::info!(
+        "Temp data purged: {} files, {} bytes overwritten, {} failures",
+        report.files_deleted,
+        report.bytes_overwritten,
+        report.failures.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("privatepdf-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn overwrites_and_removes_a_tree() {
+        let dir = scratch_dir("tree");
+        fs::create_dir(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.png"), b"page image").unwrap();
+        fs::write(dir.join("nested").join("b.txt"), b"text").unwrap();
+
+        let mut report = SecureDeleteReport::default();
+        secure_delete_dir(&dir, &mut report);
+        assert_eq!(report.files_deleted, 2);
+        assert_eq!(report.bytes_overwritten, 14);
+        assert!(report.failures.is_empty(), "{:?}", report.failures);
+        assert!(!dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn leaves_symlink_targets_alone() {
+        let outside = scratch_dir("outside");
+        fs::write(outside.join("keep.txt"), b"not ours").unwrap();
+        let dir = scratch_dir("links");
+        std::os::unix::fs::symlink(&outside, dir.join("linked-dir")).unwrap();
+        std::os::unix::fs::symlink(outside.join("keep.txt"), dir.join("linked-file")).unwrap();
+
+        let mut report = SecureDeleteReport::default();
+        secure_delete_dir(&dir, &mut report);
+        assert!(!dir.exists());
+        assert_eq!(fs::read(outside.join("keep.txt")).unwrap(), b"not ours");
+        fs::remove_dir_all(&outside).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use serde::Deserialize;
+use tauri::Emitter;
+
+use crate::conversations::ConversationStore;
+use crate::error::AppError;
+use crate::injection;
+use crate::language;
+use crate::ollama::ChatMessage;
+use crate::ollama_chat::{self, ChatStreams};
+use crate::privacy::PiiMasker;
+use crate::rag::{self, RagResult};
+use crate::retrieval_trace::{self, RetrievalTrace};
+use crate::settings;
+use crate::vectorstore::{now_secs, SearchHit, VectorStore};
+
+const SELECTION_PROMPT: &str = "You answer questions about a passage the user selected in a document. Focus on \
+the selected passage; the numbered surrounding text is only there for context and can be cited as [1], [2], ... \
+Don't bring in information from outside the passage and its surroundings.";
+
+/// Chunks kept on each side of the one matching a selection
+const SELECTION_NEIGHBOURS: usize = 2;
+/// Longer selections are cut so the prompt still fits a small context
+const MAX_SELECTION_CHARS: usize = 4000;
+
+/// Words of `text`, lowercased, for matching a selection against chunks
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Share of the selection's words that appear in `chunk`, 0..1
+fn overlap(selection: &[String], chunk: &str) -> f32 {
+    if selection.is_empty() {
+        return 0.0;
+    }
+    let chunk_words: std::collections::HashSet<String> = words(chunk).into_iter().collect();
+    selection.iter().filter(|w| chunk_words.contains(*w)).count() as f32 / selection.len() as f32
+}
+
+/// The chunks around a selection: the best-matching chunk on `page` and its neighbours
+///
+/// Chunks from the adjacent pages are considered too, since a selection near a
+/// page break can continue there. Each hit is scored by word overlap with the selection.
+fn selection_context(store: &VectorStore, document_id: &str, page: u32, selection: &str) -> Result<Vec<SearchHit>, String> {
+    let mut chunks = store.page_chunks(document_id, page.saturating_sub(1), page + 1)?;
+    let selection_words = words(selection);
+    for chunk in &mut chunks {
+        chunk.score = overlap(&selection_words, &chunk.text);
+    }
+
+    let Some(anchor) = chunks
+        .iter()
+        .enumerate()
+        // Prefer the selected page when adjacent pages match as well
+        .max_by(|(_, a), (_, b)| {
+            (a.score, a.page == Some(page))
+                .partial_cmp(&(b.score, b.page == Some(page)))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|(i, _)| i)
+    else {
+        return Ok(Vec::new());
+    };
+
+    let first = anchor.saturating_sub(SELECTION_NEIGHBOURS);
+    let last = (anchor + SELECTION_NEIGHBOURS).min(chunks.len() - 1);
+    Ok(chunks.drain(first..=last).collect())
+}
+
+/// Arguments of `query_selection`
+#[derive(Debug, Deserialize)]
+pub struct SelectionRequest {
+    document_id: String,
+    /// 1-based page the selection is on
+    page: u32,
+    text_selection: String,
+    /// "Explain this passage." when empty
+    #[serde(default)]
+    question: String,
+    /// For `cancel_chat_stream`
+    request_id: Option<String>,
+    /// The assistant message the answer is saved as, to keep its trace
+    message_id: Option<String>,
+}
+
+/// Answer a question about a passage the user selected in the viewer
+///
+/// Unlike `rag_query` nothing is retrieved from elsewhere in the document: the
+/// prompt holds the selection itself plus the chunks just before and after it,
+/// so "explain this paragraph" stays about that paragraph. Streams the answer as
+/// `ollama_stream_chunk` events and returns the surrounding chunks as sources.
+/// With a `message_id` the answer's trace is kept, as in `rag_query`.
+#[tauri::command]
+pub async fn query_selection(
+    request: SelectionRequest,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    streams: tauri::State<'_, ChatStreams>,
+    conversations: tauri::State<'_, ConversationStore>,
+) -> Result<RagResult, AppError> {
+    let SelectionRequest {
+        document_id,
+        page,
+        text_selection,
+        question,
+        request_id,
+        message_id,
+    } = request;
+    log::info!(
+        "Selection query on {} p. {}: {} chars selected, {} chars asked",
+        document_id,
+        page,
+        text_selection.len(),
+        question.len()
+    );
+
+    let selection = text_selection.trim();
+    if selection.is_empty() {
+        return Err(AppError::Other("Selection is empty".to_string()));
+    }
+    let selection: String = selection.chars().take(MAX_SELECTION_CHARS).collect();
+    // The viewer shows the original text, but a sensitive document only reaches the model masked
+    let selection = match store.index_info(&document_id)? {
+        Some(info) if info.is_masked() => PiiMasker::redacting().mask(&selection),
+        _ => selection,
+    };
+    let question = if question.trim().is_empty() {
+        "Explain this passage.".to_string()
+    } else {
+        question
+    };
+
+    let hits = selection_context(&store, &document_id, page, &selection)?;
+    log::info!("Using {} chunks around the selection", hits.len());
+
+    let settings = settings::read_settings_for(&app_handle, None, Some(&document_id));
+    let screened = injection::screen(&app_handle, &settings, &document_id, &hits).await;
+    if let Some(warning) = &screened.warning {
+        window.emit_to(window.label(), "injection_warning", warning).ok();
+    }
+    let mut prompt = String::new();
+    if !hits.is_empty() {
+        prompt.push_str("Surrounding text:\n\n");
+        for (i, (hit, text)) in hits.iter().zip(&screened.texts).enumerate() {
+            if let Some(text) = text {
+                prompt.push_str(&injection::excerpt(i + 1, hit.page, text));
+            }
+        }
+    }
+    // The selection is what the user asked about, so it's delimited but not stripped
+    prompt.push_str(&format!("Selected passage (p. {}):\n\"\"\"\n{}\n\"\"\"\n\n", page, selection));
+    prompt.push_str(&format!("Question: {}", question.trim()));
+
+    let mut selection_rules = format!("{}\n\n{}", SELECTION_PROMPT, injection::EXCERPT_RULES);
+    let selection_language = language::detect(&selection).filter(|l| l.reliable());
+    if let Some(instruction) = language::answer_instruction(&question, selection_language.as_ref()) {
+        selection_rules = format!("{}\n\n{}", selection_rules, instruction);
+    }
+
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: rag::with_instructions(&selection_rules, &settings),
+            images: Vec::new(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+            images: Vec::new(),
+        },
+    ];
+
+    let trace = message_id.map(|message_id| RetrievalTrace {
+        message_id,
+        conversation_id: None,
+        document_id: document_id.clone(),
+        query: selection.clone(),
+        chunks: hits.clone(),
+        excluded_chunks: screened.excluded(&hits),
+        messages: messages.clone(),
+        model: settings.ollama_model.clone(),
+        temperature: settings.temperature,
+        top_p: settings.top_p,
+        generation: settings.generation.clone(),
+        created_at: now_secs(),
+    });
+
+    let answer = ollama_chat::stream_answer(
+        settings.ollama_model,
+        messages,
+        Some(settings.temperature),
+        None,
+        Some(settings.top_p),
+        Some(settings.generation),
+        request_id,
+        None,
+        &window,
+        &app_handle,
+        &streams,
+    )
+    .await?;
+    if let Some(trace) = trace.filter(|_| answer.is_some()) {
+        retrieval_trace::record_or_warn(&conversations, &trace);
+    }
+
+    let document_name = store.index_info(&document_id)?.map(|d| d.name().to_string());
+    Ok(RagResult::ungrounded(rag::citations(&document_id, document_name.as_deref(), &hits), hits))
+}
@@ -1,15 +1,99 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tauri::Manager;
 
+use crate::error::AppError;
+use crate::workspace_settings::merge_overrides;
+
+/// Service name secrets are stored under in the OS keychain
+const KEYRING_SERVICE: &str = "com.privatepdf.desktop";
+/// Keychain entry holding `AppSettings::openai_api_key`
+const OPENAI_API_KEY: &str = "openai_api_key";
+/// Keychain entry holding `NetworkSettings::proxy.password`
+const PROXY_PASSWORD: &str = "proxy_password";
+
+/// Secrets already read from the keychain; `read_settings` runs on every request
+/// and some keychains prompt or block on each access
+static SECRET_CACHE: Mutex<Option<HashMap<String, Option<String>>>> = Mutex::new(None);
+
+/// Settings as last read or saved, secrets included; a single chat request reads
+/// them several times, so settings.json is only parsed again after a save
+static SETTINGS_CACHE: Mutex<Option<AppSettings>> = Mutex::new(None);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct AppSettings {
+    /// "light", "dark", or "system" to follow the OS
     pub theme: String,
     pub ollama_model: String,
+    /// Model that embeds document chunks and questions; indexes remember which one built them
+    pub embedding_model: String,
     pub temperature: f32,
     pub top_p: f32,
+    /// Mask emails, phone numbers, SSNs and IBANs before prompts reach the model
+    pub privacy_filter: bool,
+    /// Host running the Ollama API (a LAN machine is allowed)
+    pub ollama_host: String,
+    pub ollama_port: u16,
+    /// Server used for chat and embeddings
+    pub llm_backend: crate::backend::BackendKind,
+    /// Base URL of an OpenAI-compatible server, including the /v1 prefix
+    pub openai_base_url: String,
+    /// Sent as a bearer token when set; most local servers don't need one.
+    /// Kept in the OS keychain, never written to settings.json.
+    pub openai_api_key: String,
+    /// Directory searched for GGUF models by the embedded backend; empty uses
+    /// `models/` in the app data directory
+    pub local_model_dir: String,
+    /// Stop the Ollama server PrivatePDF started when the window closes;
+    /// servers started outside the app are never stopped
+    pub stop_on_exit: bool,
+    /// Default generation options; a request can override any of them.
+    /// `keep_alive` also sets how long `preload_model` keeps a model loaded.
+    pub generation: crate::backend::GenerationOptions,
+    /// Timeouts and retries for requests to the model server
+    pub network: crate::http::NetworkSettings,
+    /// Default log level with optional per-module overrides, e.g. `info,ollama=debug`
+    pub log_level: String,
+    /// Look for a new PrivatePDF release at startup; off unless the user opts in
+    pub auto_update_check: bool,
+    /// Rewrite follow-up questions into standalone queries from the chat history before retrieval
+    pub rewrite_queries: bool,
+    /// Seconds between background pings of the Ollama API; 0 turns the health monitor off
+    pub health_check_interval_secs: u64,
+    /// Voice for reading answers aloud: a Piper voice in `voices/` in the app data
+    /// directory, or an OS voice name; empty uses the OS default voice
+    pub tts_voice: String,
+    /// Whisper model for dictated questions: a ggml `.bin` path or a name like
+    /// `base.en` in `whisper/` in the app data directory; empty uses `base`
+    pub stt_model: String,
+    /// Days between background checks of installed models against the Ollama
+    /// registry; 0 (the default) only checks when asked
+    pub model_update_check_days: u64,
+    /// Requests sent to the model server at once; the rest wait, chat answers first
+    pub max_concurrent_requests: usize,
+    /// Extra instructions added to the system prompt of document chats
+    pub system_prompt: String,
+    /// Answer only from the retrieved excerpts, and refuse answers whose claims
+    /// can't be found in them
+    pub strict_grounding: bool,
+    /// Model that checks retrieved excerpts for prompt injection before they're
+    /// used; empty only strips instruction-like phrases
+    pub injection_classifier_model: String,
+    /// Refuse every request to a host other than the Ollama server: downloads,
+    /// update and model update checks are blocked and logged
+    pub offline_mode: bool,
+    /// How long chats, indexes of unused documents, and logs are kept
+    pub retention: crate::retention::RetentionSettings,
+    /// Let other apps reach the indexed documents through `privatepdf --mcp`,
+    /// the Model Context Protocol server
+    pub mcp_server: bool,
+    /// Localhost REST API for scripts, authenticated with a token from the keychain
+    pub rest_api: crate::rest_api::RestApiSettings,
 }
 
 impl Default for AppSettings {
@@ -17,83 +101,343 @@ impl Default for AppSettings {
         Self {
             theme: "dark".to_string(),
             ollama_model: "gemma3:1b-it-q4_K_M".to_string(),
+            embedding_model: crate::embedding_model::DEFAULT.to_string(),
             temperature: 0.2,
             top_p: 0.7,
+            privacy_filter: false,
+            ollama_host: "127.0.0.1".to_string(),
+            ollama_port: crate::ollama::DEFAULT_PORT,
+            llm_backend: crate::backend::BackendKind::Ollama,
+            openai_base_url: "http://127.0.0.1:1234/v1".to_string(),
+            openai_api_key: String::new(),
+            local_model_dir: String::new(),
+            stop_on_exit: true,
+            generation: crate::backend::GenerationOptions {
+                repeat_penalty: Some(1.1),
+                repeat_last_n: Some(64),
+                // Keeps the model loaded between questions instead of Ollama's 5 minutes
+                keep_alive: Some(crate::ollama::DEFAULT_KEEP_ALIVE.to_string()),
+                ..Default::default()
+            },
+            network: crate::http::NetworkSettings::default(),
+            log_level: crate::logging::DEFAULT_LOG_LEVEL.to_string(),
+            auto_update_check: false,
+            rewrite_queries: true,
+            health_check_interval_secs: 10,
+            tts_voice: String::new(),
+            stt_model: String::new(),
+            model_update_check_days: 0,
+            max_concurrent_requests: crate::scheduler::DEFAULT_MAX_IN_FLIGHT,
+            system_prompt: String::new(),
+            strict_grounding: false,
+            injection_classifier_model: String::new(),
+            offline_mode: false,
+            retention: crate::retention::RetentionSettings::default(),
+            mcp_server: false,
+            rest_api: crate::rest_api::RestApiSettings::default(),
         }
     }
 }
 
 /// Get the path to the settings file
-fn get_settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+pub(crate) fn get_settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let app_data_dir = crate::portable::app_data_dir(app_handle)
+        .map_err(|e| AppError::Io(format!("Failed to get app data directory: {}", e)))?;
 
     // Ensure directory exists
     if !app_data_dir.exists() {
         fs::create_dir_all(&app_data_dir)
-            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+            .map_err(|e| AppError::Io(format!("Failed to create app data directory: {}", e)))?;
     }
 
     Ok(app_data_dir.join("settings.json"))
 }
 
+/// Copy of a settings file from before its last save
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.bak", path.display()))
+}
+
+/// Replace a JSON file so a crash leaves either the old contents or the new ones
+///
+/// The new contents go to a temp file that is flushed to disk and renamed over
+/// the original. The file being replaced becomes the one rolling backup, unless
+/// it's already corrupt and would push out a good backup.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<(), AppError> {
+    let temp = PathBuf::from(format!("{}.tmp", path.display()));
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        fs::remove_file(&temp).ok();
+        return Err(AppError::Io(format!("Failed to write {}: {}", path.display(), e)));
+    }
+
+    let current_is_valid = fs::read_to_string(path)
+        .ok()
+        .is_some_and(|json| serde_json::from_str::<serde_json::Value>(&json).is_ok());
+    if current_is_valid {
+        if let Err(e) = fs::copy(path, backup_path(path)) {
+            log::warn!("Failed to back up {}: {}", path.display(), e);
+        }
+    }
+
+    fs::rename(&temp, path).map_err(|e| {
+        fs::remove_file(&temp).ok();
+        AppError::Io(format!("Failed to replace {}: {}", path.display(), e))
+    })
+}
+
+/// Parse a settings file; None if it doesn't exist
+fn parse_settings_file(path: &Path) -> Option<Result<AppSettings, String>> {
+    match fs::read_to_string(path) {
+        Ok(json) => Some(serde_json::from_str(&json).map_err(|e| e.to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => Some(Err(e.to_string())),
+    }
+}
+
+/// Settings from `settings.json`, recovering from a corrupt file
+///
+/// A file that can't be read is moved aside to `settings.json.corrupt` and the
+/// backup from the previous save is restored in its place; without a usable
+/// backup the defaults apply. Either way later reads don't hit the same error.
+fn read_settings_file(path: &Path) -> AppSettings {
+    let error = match parse_settings_file(path) {
+        None => return AppSettings::default(),
+        Some(Ok(settings)) => return settings,
+        Some(Err(e)) => e,
+    };
+    log::error!("Settings file is corrupt ({}), recovering", error);
+    if let Err(e) = fs::rename(path, PathBuf::from(format!("{}.corrupt", path.display()))) {
+        log::warn!("Failed to move the corrupt settings file aside: {}", e);
+    }
+
+    let backup = backup_path(path);
+    match parse_settings_file(&backup) {
+        Some(Ok(settings)) => {
+            match fs::copy(&backup, path) {
+                Ok(_) => log::warn!("Restored settings from {}", backup.display()),
+                Err(e) => log::warn!("Using settings from {}, failed to restore it: {}", backup.display(), e),
+            }
+            settings
+        }
+        _ => {
+            log::warn!("No usable settings backup, using defaults");
+            AppSettings::default()
+        }
+    }
+}
+
+fn validate_secret_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(AppError::Other(format!("Invalid secret name: {:?}", name)));
+    }
+    Ok(())
+}
+
+fn keyring_entry(name: &str) -> Result<keyring::Entry, AppError> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+        .map_err(|e| AppError::Unsupported(format!("OS keychain is not available: {}", e)))
+}
+
+/// Read a secret from the OS keychain (Credential Manager, Keychain or Secret Service)
+pub fn get_secret(name: &str) -> Result<Option<String>, AppError> {
+    let mut cache = SECRET_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(value) = cache.as_ref().and_then(|c| c.get(name)) {
+        return Ok(value.clone());
+    }
+
+    let value = match keyring_entry(name)?.get_password() {
+        Ok(value) => Some(value),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => return Err(AppError::Other(format!("Failed to read secret {}: {}", name, e))),
+    };
+    cache.get_or_insert_with(HashMap::new).insert(name.to_string(), value.clone());
+    Ok(value)
+}
+
+/// Store a secret in the OS keychain; an empty value deletes it
+pub fn set_secret(name: &str, value: &str) -> Result<(), AppError> {
+    let entry = keyring_entry(name)?;
+    if value.is_empty() {
+        match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(AppError::Other(format!("Failed to delete secret {}: {}", name, e))),
+        }
+    } else {
+        entry
+            .set_password(value)
+            .map_err(|e| AppError::Other(format!("Failed to store secret {}: {}", name, e)))?;
+    }
+
+    let value = Some(value.to_string()).filter(|v| !v.is_empty());
+    SECRET_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(name.to_string(), value);
+    // Cached settings carry the old secret; `read_settings` locks the settings
+    // cache before the secret cache, so this one must already be released
+    *SETTINGS_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    Ok(())
+}
+
+/// Fill secret fields from the keychain
+///
+/// A key still present in settings.json (saved before secrets moved to the
+/// keychain) is kept as is and moved on the next save.
+fn with_secrets(mut settings: AppSettings) -> AppSettings {
+    if settings.openai_api_key.is_empty() {
+        match get_secret(OPENAI_API_KEY) {
+            Ok(key) => settings.openai_api_key = key.unwrap_or_default(),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    if settings.network.proxy.password.is_empty() {
+        match get_secret(PROXY_PASSWORD) {
+            Ok(password) => settings.network.proxy.password = password.unwrap_or_default(),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    settings
+}
+
+/// Read settings for backend use, falling back to defaults if missing or unreadable
+///
+/// Served from memory after the first read; `save_settings` refreshes the copy.
+pub fn read_settings(app_handle: &tauri::AppHandle) -> AppSettings {
+    let mut cache = SETTINGS_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(settings) = cache.as_ref() {
+        return settings.clone();
+    }
+
+    let path = match get_settings_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("{}, using default settings", e);
+            return AppSettings::default();
+        }
+    };
+    let settings = with_secrets(read_settings_file(&path));
+    *cache = Some(settings.clone());
+    settings
+}
+
+/// `read_settings` with a workspace's and document's overrides applied
+pub fn read_settings_for(
+    app_handle: &tauri::AppHandle,
+    workspace_id: Option<&str>,
+    document_id: Option<&str>,
+) -> AppSettings {
+    merge_overrides(app_handle, read_settings(app_handle), workspace_id, document_id)
+}
+
 /// Save app settings to disk
 #[tauri::command]
 pub async fn save_settings(
     app_handle: tauri::AppHandle,
-    settings: AppSettings,
-) -> Result<(), String> {
+    mut settings: AppSettings,
+) -> Result<(), AppError> {
     log::info!("Saving app settings...");
 
     let path = get_settings_path(&app_handle)?;
+    // Applied below with the proxy password, which isn't written to the file
+    let network = settings.network.clone();
+
+    // Without a keychain (e.g. Linux with no Secret Service) the key stays in the file
+    match set_secret(OPENAI_API_KEY, &settings.openai_api_key) {
+        Ok(()) => settings.openai_api_key.clear(),
+        Err(e) => log::warn!("{}; API key will be saved in settings.json", e),
+    }
+    match set_secret(PROXY_PASSWORD, &settings.network.proxy.password) {
+        Ok(()) => settings.network.proxy.password.clear(),
+        Err(e) => log::warn!("{}; proxy password will be saved in settings.json", e),
+    }
 
     let json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("Failed to serialize settings: {}", e)))?;
 
-    fs::write(&path, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+    write_atomic(&path, &json)?;
+    // Secrets were cleared from `settings` for the file; the next read fills them in
+    *SETTINGS_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
+    crate::http::configure(&network);
+    crate::http::set_offline(settings.offline_mode);
+    crate::ollama::configure(&settings);
+    crate::theme::update(&app_handle, &settings.theme);
+    if let Some(api) = app_handle.try_state::<crate::rest_api::RestApi>() {
+        api.apply(&app_handle, &settings.rest_api);
+    }
+    if let Some(scheduler) = app_handle.try_state::<crate::scheduler::RequestScheduler>() {
+        scheduler.set_max_in_flight(&app_handle, settings.max_concurrent_requests);
+    }
+    // Detect the selected model's context window now rather than on the first question
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let settings = read_settings(&handle);
+        crate::context_window::for_model(&handle, &settings, &settings.ollama_model).await;
+    });
+    if let Err(e) = crate::logging::apply(&settings.log_level) {
+        log::warn!("{}", e);
+    }
     log::info!("Settings saved successfully to: {:?}", path);
     Ok(())
 }
 
 /// Load app settings from disk
+///
+/// With a `workspace_id` or `document_id`, returns the settings in effect there,
+/// with that workspace's and document's overrides applied. Don't pass those to
+/// `save_settings`; overrides are saved with `save_workspace_settings`.
 #[tauri::command]
-pub async fn load_settings(app_handle: tauri::AppHandle) -> Result<AppSettings, String> {
+pub async fn load_settings(
+    workspace_id: Option<String>,
+    document_id: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<AppSettings, AppError> {
     log::info!("Loading app settings...");
+    let merge = |settings| merge_overrides(&app_handle, settings, workspace_id.as_deref(), document_id.as_deref());
 
     let path = get_settings_path(&app_handle)?;
 
     if !path.exists() {
         log::info!("No settings file found, returning defaults");
-        return Ok(AppSettings::default());
     }
-
-    let json = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read settings file: {}", e))?;
-
-    let settings: AppSettings = serde_json::from_str(&json)
-        .map_err(|e| format!("Failed to parse settings: {}", e))?;
+    // A corrupt file falls back to the backup, then to defaults, rather than failing
+    let settings = merge(read_settings(&app_handle));
 
     log::info!("Settings loaded successfully");
+    crate::startup::mark(&app_handle, "settings_loaded");
     Ok(settings)
 }
 
 /// Reset settings to defaults
 #[tauri::command]
-pub async fn reset_settings(app_handle: tauri::AppHandle) -> Result<AppSettings, String> {
+pub async fn reset_settings(app_handle: tauri::AppHandle) -> Result<AppSettings, AppError> {
     log::info!("Resetting settings to defaults...");
 
-    let path = get_settings_path(&app_handle)?;
-
-    // Delete existing settings file if it exists
-    if path.exists() {
-        fs::remove_file(&path).map_err(|e| format!("Failed to delete settings file: {}", e))?;
-    }
-
+    // Saving over the current file keeps it as the backup, so a reset can be undone by hand
     let defaults = AppSettings::default();
     save_settings(app_handle, defaults.clone()).await?;
 
     Ok(defaults)
 }
+
+/// Store a secret in the OS keychain instead of settings.json; an empty value deletes it
+#[tauri::command]
+pub async fn save_secret(name: String, value: String) -> Result<(), AppError> {
+    log::info!("Saving secret {}", name);
+    validate_secret_name(&name)?;
+    set_secret(&name, &value)
+}
+
+/// Read a secret from the OS keychain, or null if it was never saved
+#[tauri::command]
+pub async fn load_secret(name: String) -> Result<Option<String>, AppError> {
+    log::info!("Loading secret {}", name);
+    validate_secret_name(&name)?;
+    get_secret(&name)
+}
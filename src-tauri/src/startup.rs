@@ -0,0 +1,101 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::Manager;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupPhase {
+    name: String,
+    /// Milliseconds since `run()` was entered
+    at_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StartupReport {
+    phases: Vec<StartupPhase>,
+    total_ms: u64,
+}
+
+/// Startup milestones, kept in Tauri state so they can be queried after launch
+pub struct StartupTimings {
+    started: Instant,
+    phases: Mutex<Vec<StartupPhase>>,
+}
+
+impl StartupTimings {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record a milestone (only the first occurrence of each name is kept)
+    pub fn mark(&self, name: &str) {
+        let at_ms = self.started.elapsed().as_millis() as u64;
+        let mut phases = self.phases.lock().unwrap_or_else(|e| e.into_inner());
+        if phases.iter().any(|p| p.name == name) {
+            return;
+        }
+        log::info!("Startup: {} at {} ms", name, at_ms);
+        phases.push(StartupPhase {
+            name: name.to_string(),
+            at_ms,
+        });
+    }
+}
+
+impl Default for StartupTimings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Record a milestone from anywhere that has an app handle
+pub fn mark(app_handle: &tauri::AppHandle, name: &str) {
+    if let Some(timings) = app_handle.try_state::<StartupTimings>() {
+        timings.mark(name);
+    }
+}
+
+/// Work that isn't needed to show the window, run once the window exists
+pub fn run_deferred_init(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Building the HTTP client loads TLS roots, which is slow on cold HDDs
+        let _ = crate::http::client();
+        mark(&app_handle, "http_client_ready");
+        // Watched folders are scanned for new files, and older stores get their keyword index
+        let handle = app_handle.clone();
+        let stores = tauri::async_runtime::spawn_blocking(move || {
+            crate::folder_watch::resume(&handle);
+            handle.state::<crate::vectorstore::VectorStore>().build_keyword_index()
+        })
+        .await;
+        match stores {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log::warn!("{}", e),
+            Err(e) => log::warn!("Deferred store setup failed: {}", e),
+        }
+        mark(&app_handle, "stores_ready");
+        crate::updates::check_in_background(app_handle.clone());
+        crate::model_updates::check_in_background(app_handle.clone());
+        crate::retention::enforce_in_background(app_handle.clone());
+        crate::remote_sources::check_in_background(app_handle.clone());
+        let rest_api = crate::settings::read_settings(&app_handle).rest_api;
+        app_handle.state::<crate::rest_api::RestApi>().apply(&app_handle, &rest_api);
+        mark(&app_handle, "deferred_init_complete");
+    });
+}
+
+/// Get startup phase timings (plugin init, window, settings load, first status check)
+#[tauri::command]
+pub fn get_startup_timings(state: tauri::State<'_, StartupTimings>) -> StartupReport {
+    let phases = state
+        .phases
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let total_ms = phases.iter().map(|p| p.at_ms).max().unwrap_or(0);
+
+    StartupReport { phases, total_ms }
+}
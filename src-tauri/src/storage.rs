@@ -0,0 +1,227 @@
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Open a store database: vectors.db, conversations.db or embedding_cache.db
+///
+/// Built with the `encrypted-storage` feature, the stores are SQLCipher databases
+/// keyed with a random key kept in the OS keychain, so a copied data directory
+/// can't be read without the user's login. A database still in plain text is
+/// encrypted the first time it's opened. Without a keychain, plain databases
+/// stay plain rather than locking the user out of their library.
+pub fn open(path: &Path) -> Result<Connection, String> {
+    #[cfg(feature = "encrypted-storage")]
+    if path != Path::new(":memory:") {
+        return match encrypted::key() {
+            Ok(key) => encrypted::open(path, &key),
+            Err(e) if !encrypted::is_encrypted(path) => {
+                log::warn!("Keeping {} unencrypted: {}", path.display(), e);
+                Connection::open(path).map_err(|e| e.to_string())
+            }
+            Err(e) => Err(format!("{} is encrypted but its key can't be read: {}", path.display(), e)),
+        };
+    }
+    Connection::open(path).map_err(|e| e.to_string())
+}
+
+/// Attach an unencrypted database file to `conn` as `name`
+///
+/// SQLCipher would otherwise assume it uses the key of the main database.
+pub fn attach_plain(conn: &Connection, path: &Path, name: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS {} KEY ''", name),
+        params![path.to_string_lossy()],
+    )?;
+    Ok(())
+}
+
+/// Copy a consistent, unencrypted snapshot of a live store database to `dest`
+///
+/// Bundles carry their own passphrase encryption, and their databases have to
+/// open on machines whose keychain holds a different key.
+#[cfg(not(feature = "encrypted-storage"))]
+pub fn snapshot(conn: &Connection, dest: &Path) -> Result<(), String> {
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])
+        .map(|_| ())
+        .map_err(|e| format!("Failed to snapshot database: {}", e))
+}
+
+/// Copy a consistent, unencrypted snapshot of a live store database to `dest`
+///
+/// Bundles carry their own passphrase encryption, and their databases have to
+/// open on machines whose keychain holds a different key. `VACUUM INTO` would
+/// keep the store's key, so the rows are exported to a plain database instead.
+#[cfg(feature = "encrypted-storage")]
+pub fn snapshot(conn: &Connection, dest: &Path) -> Result<(), String> {
+    attach_plain(conn, dest, "snapshot").map_err(|e| format!("Failed to snapshot database: {}", e))?;
+    let exported = conn.query_row("SELECT sqlcipher_export('snapshot')", [], |_| Ok(()));
+    if let Err(e) = conn.execute("DETACH DATABASE snapshot", []) {
+        log::warn!("Failed to detach snapshot: {}", e);
+    }
+    exported.map_err(|e| format!("Failed to snapshot database: {}", e))
+}
+
+#[cfg(feature = "encrypted-storage")]
+mod encrypted {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    use chacha20poly1305::aead::OsRng;
+    use rusqlite::{params, Connection};
+    use std::fs;
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+
+    use crate::settings;
+
+    /// Keychain entry holding the key of the store databases
+    const DATABASE_KEY: &str = "database_key";
+    const KEY_BYTES: usize = 32;
+    /// First bytes of every unencrypted SQLite database
+    const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+    /// The store key from the keychain, generated on first use
+    pub fn key() -> Result<String, String> {
+        if let Some(key) = settings::get_secret(DATABASE_KEY)? {
+            return Ok(key);
+        }
+        let mut bytes = [0u8; KEY_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        let key: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        settings::set_secret(DATABASE_KEY, &key)?;
+        log::info!("Generated a database key and stored it in the OS keychain");
+        Ok(key)
+    }
+
+    fn header(path: &Path) -> Option<[u8; 16]> {
+        let mut header = [0u8; 16];
+        fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+        Some(header)
+    }
+
+    /// Whether `path` holds a database that isn't plain SQLite; a missing or empty file isn't
+    pub fn is_encrypted(path: &Path) -> bool {
+        header(path).is_some_and(|h| &h != PLAIN_HEADER)
+    }
+
+    fn sibling(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    /// Finish an encryption that was interrupted between its renames
+    fn recover(path: &Path) -> Result<(), String> {
+        let encrypting = sibling(path, ".encrypting");
+        let plain = sibling(path, ".plain");
+        if !path.exists() && plain.exists() && encrypting.exists() {
+            fs::rename(&encrypting, path).map_err(|e| format!("Failed to finish encrypting {}: {}", path.display(), e))?;
+        }
+        if path.exists() && plain.exists() {
+            crate::secure_delete::secure_delete(&plain)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite a plain database as an encrypted one, overwriting the plain copy
+    pub fn encrypt_in_place(path: &Path, key: &str) -> Result<(), String> {
+        log::info!("Encrypting {}", path.display());
+        let encrypting = sibling(path, ".encrypting");
+        let plain = sibling(path, ".plain");
+        let _ = fs::remove_file(&encrypting);
+        {
+            let conn = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            conn.execute(
+                "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+                params![encrypting.to_string_lossy(), format!("x'{}'", key)],
+            )
+            .and_then(|_| conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(())))
+            .and_then(|_| conn.execute("DETACH DATABASE encrypted", []))
+            .map_err(|e| format!("Failed to encrypt {}: {}", path.display(), e))?;
+        }
+        // The plain file is moved aside before it's overwritten, so a crash leaves one complete copy
+        fs::rename(path, &plain).map_err(|e| format!("Failed to encrypt {}: {}", path.display(), e))?;
+        fs::rename(&encrypting, path).map_err(|e| format!("Failed to encrypt {}: {}", path.display(), e))?;
+        crate::secure_delete::secure_delete(&plain)?;
+        Ok(())
+    }
+
+    pub fn open(path: &Path, key: &str) -> Result<Connection, String> {
+        recover(path)?;
+        if header(path).is_some_and(|h| &h == PLAIN_HEADER) {
+            encrypt_in_place(path, key)?;
+        }
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(&format!("PRAGMA key = \"x'{}'\";", key))
+            .map_err(|e| format!("Failed to unlock {}: {}", path.display(), e))?;
+        // A wrong key only shows on the first read
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+            .map_err(|_| format!("{} is encrypted with a different key than the keychain holds", path.display()))?;
+        Ok(conn)
+    }
+}
+
+#[cfg(all(test, feature = "encrypted-storage"))]
+mod tests {
+    use super::encrypted;
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("privatepdf-test-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn plain_database(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE notes (body TEXT);
+             CREATE VIRTUAL TABLE notes_fts USING fts5(body);
+             INSERT INTO notes VALUES ('quarterly revenue');
+             INSERT INTO notes_fts VALUES ('quarterly revenue');",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn plain_databases_are_encrypted_on_open() {
+        let dir = scratch_dir("encrypt");
+        let path = dir.join("store.db");
+        plain_database(&path);
+
+        let conn = encrypted::open(&path, KEY).unwrap();
+        let body: String = conn.query_row("SELECT body FROM notes", [], |row| row.get(0)).unwrap();
+        assert_eq!(body, "quarterly revenue");
+        drop(conn);
+
+        assert!(encrypted::is_encrypted(&path));
+        assert!(!fs::read(&path).unwrap().windows(9).any(|w| w == b"quarterly"));
+        assert!(!dir.join("store.db.plain").exists());
+        let wrong = "ff".repeat(32);
+        assert!(encrypted::open(&path, &wrong).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn snapshots_are_plain_and_keep_full_text_tables() {
+        let dir = scratch_dir("snapshot");
+        let path = dir.join("store.db");
+        plain_database(&path);
+        let conn = encrypted::open(&path, KEY).unwrap();
+
+        let copy = dir.join("copy.db");
+        snapshot(&conn, &copy).unwrap();
+        let plain = Connection::open(&copy).unwrap();
+        let hits: i64 = plain
+            .query_row("SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH 'revenue'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(hits, 1);
+
+        attach_plain(&conn, &copy, "bundle").unwrap();
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM bundle.notes", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,319 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::Emitter;
+
+use crate::error::AppError;
+use crate::settings;
+
+/// Whisper models expect 16 kHz mono audio
+const SAMPLE_RATE: u32 = 16000;
+/// Model used when the `stt_model` setting is empty
+const DEFAULT_MODEL: &str = "base";
+/// Longest recording accepted; a question rarely takes more than a minute
+const MAX_AUDIO_SECS: u32 = 300;
+
+/// Result of `transcribe_audio`; partial text is sent as `transcription_partial` first
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcription {
+    request_id: String,
+    text: String,
+    /// Language the model heard, e.g. "en"
+    language: Option<String>,
+    /// Length of the recording
+    duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TranscriptionPartial {
+    request_id: String,
+    /// Everything transcribed so far
+    text: String,
+}
+
+/// Decode a WAV file into 16 kHz mono samples in -1..1
+///
+/// Takes 8, 16, 24 and 32-bit PCM and 32-bit float, at any sample rate and
+/// channel count, which covers what browsers and OS recorders produce.
+fn decode_wav(bytes: &[u8]) -> Result<Vec<f32>, AppError> {
+    let invalid = |why: &str| AppError::Parse(format!("Invalid WAV audio: {}", why));
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes([bytes[offset + 4], bytes[offset + 5], bytes[offset + 6], bytes[offset + 7]]) as usize;
+        let start = offset + 8;
+        // Recorders that stream the file leave the data size unset
+        let end = start.saturating_add(size).min(bytes.len());
+        match id {
+            b"fmt " if end - start >= 16 => {
+                let chunk = &bytes[start..end];
+                let mut tag = u16::from_le_bytes([chunk[0], chunk[1]]);
+                // WAVE_FORMAT_EXTENSIBLE keeps the real format in its sub-format GUID
+                if tag == 0xFFFE && chunk.len() >= 26 {
+                    tag = u16::from_le_bytes([chunk[24], chunk[25]]);
+                }
+                let channels = u16::from_le_bytes([chunk[2], chunk[3]]);
+                let rate = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+                let bits = u16::from_le_bytes([chunk[14], chunk[15]]);
+                format = Some((tag, channels, rate, bits));
+            }
+            b"data" => data = Some(&bytes[start..end]),
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        offset = start.saturating_add(size).saturating_add(size & 1);
+    }
+
+    let (tag, channels, rate, bits) = format.ok_or_else(|| invalid("no format chunk"))?;
+    let data = data.ok_or_else(|| invalid("no audio data"))?;
+    if channels == 0 || rate == 0 {
+        return Err(invalid("no channels"));
+    }
+    let width = usize::from(bits / 8);
+    let sample = |b: &[u8]| -> Option<f32> {
+        match (tag, bits) {
+            (1, 8) => Some((f32::from(b[0]) - 128.0) / 128.0),
+            (1, 16) => Some(f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0),
+            (1, 24) => Some((i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0),
+            (1, 32) => Some(i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0),
+            (3, 32) => Some(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            _ => None,
+        }
+    };
+    if width == 0 || sample(&[0; 4]).is_none() {
+        return Err(AppError::Unsupported(format!(
+            "Unsupported WAV encoding (format {}, {} bits); record 16-bit PCM",
+            tag, bits
+        )));
+    }
+
+    // Mix down to mono
+    let frame = width * usize::from(channels);
+    let mono: Vec<f32> = data
+        .chunks_exact(frame)
+        .map(|frame| frame.chunks_exact(width).filter_map(&sample).sum::<f32>() / f32::from(channels))
+        .collect();
+    if mono.len() as u64 > u64::from(rate) * u64::from(MAX_AUDIO_SECS) {
+        return Err(AppError::Other(format!(
+            "The recording is longer than {} minutes",
+            MAX_AUDIO_SECS / 60
+        )));
+    }
+    Ok(resample(&mono, rate))
+}
+
+/// Linear resampling to `SAMPLE_RATE`; plenty for speech recognition
+fn resample(samples: &[f32], rate: u32) -> Vec<f32> {
+    if rate == SAMPLE_RATE || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let step = f64::from(rate) / f64::from(SAMPLE_RATE);
+    let len = (samples.len() as f64 / step) as usize;
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            samples[index] * (1.0 - fraction) + next * fraction
+        })
+        .collect()
+}
+
+/// Resolve the `stt_model` setting to a ggml Whisper model file
+///
+/// A path to a `.bin` file is used as is; a name like `base.en` is looked up as
+/// `ggml-base.en.bin` in `whisper/` in the app data directory.
+fn find_model(app_handle: &tauri::AppHandle, model: &str) -> Result<PathBuf, AppError> {
+    let model = match model.trim() {
+        "" => DEFAULT_MODEL,
+        model => model,
+    };
+    let path = Path::new(model);
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("bin")) && path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    let dir = crate::portable::app_data_dir(app_handle)
+        .map_err(|e| AppError::Io(format!("Failed to get app data directory: {}", e)))?
+        .join("whisper");
+    let path = dir.join(format!("ggml-{}.bin", model));
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(AppError::ModelNotFound(format!(
+            "Speech model not found: {} (looked for {}); download ggml-{}.bin from the whisper.cpp models",
+            model,
+            path.display(),
+            model
+        )))
+    }
+}
+
+#[cfg(feature = "speech-to-text")]
+mod engine {
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use whisper_rs::{FullParams, SamplingStrategy, SegmentCallbackData, WhisperContext, WhisperContextParameters};
+
+    use super::*;
+
+    /// The last model used, kept loaded for the next question
+    static LOADED: Mutex<Option<(PathBuf, Arc<WhisperContext>)>> = Mutex::new(None);
+
+    fn whisper_error(what: &str, e: impl std::fmt::Display) -> AppError {
+        AppError::Other(format!("{} failed: {}", what, e))
+    }
+
+    fn load(path: &Path) -> Result<Arc<WhisperContext>, AppError> {
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, context)) = loaded.as_ref().filter(|(p, _)| p == path) {
+            return Ok(context.clone());
+        }
+
+        log::info!("Loading speech model {}", path.display());
+        let context = Arc::new(
+            WhisperContext::new_with_params(&path.to_string_lossy(), WhisperContextParameters::default())
+                .map_err(|e| AppError::Other(format!("Failed to load speech model {}: {}", path.display(), e)))?,
+        );
+        *loaded = Some((path.to_path_buf(), context.clone()));
+        Ok(context)
+    }
+
+    /// Transcribe 16 kHz mono samples, calling `on_segment` as each segment is decoded
+    ///
+    /// Returns the text and the detected (or requested) language.
+    pub fn transcribe(
+        path: &Path,
+        samples: &[f32],
+        language: Option<&str>,
+        on_segment: impl FnMut(&str) + 'static,
+    ) -> Result<(String, Option<String>), AppError> {
+        let context = load(path)?;
+        let mut state = context
+            .create_state()
+            .map_err(|e| whisper_error("Creating speech state", e))?;
+
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(8);
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(threads as i32);
+        params.set_language(Some(language.unwrap_or("auto")));
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        let mut on_segment = on_segment;
+        params.set_segment_callback_safe(move |segment: SegmentCallbackData| on_segment(&segment.text));
+
+        state
+            .full(params, samples)
+            .map_err(|e| whisper_error("Transcribing audio", e))?;
+
+        let segments = state
+            .full_n_segments()
+            .map_err(|e| whisper_error("Reading transcript", e))?;
+        let mut text = String::new();
+        for i in 0..segments {
+            let segment = state
+                .full_get_segment_text(i)
+                .map_err(|e| whisper_error("Reading transcript", e))?;
+            text.push_str(&segment);
+        }
+        let language = state
+            .full_lang_id_from_state()
+            .ok()
+            .and_then(whisper_rs::get_lang_str)
+            .map(str::to_string)
+            .or_else(|| language.map(str::to_string));
+        Ok((text.trim().to_string(), language))
+    }
+}
+
+#[cfg(not(feature = "speech-to-text"))]
+mod engine {
+    use std::path::Path;
+
+    use super::*;
+
+    pub fn transcribe(
+        _path: &Path,
+        _samples: &[f32],
+        _language: Option<&str>,
+        _on_segment: impl FnMut(&str) + 'static,
+    ) -> Result<(String, Option<String>), AppError> {
+        Err(AppError::Unsupported(
+            "This build of PrivatePDF doesn't include speech recognition".to_string(),
+        ))
+    }
+}
+
+/// Transcribe a dictated question with a local Whisper model
+///
+/// `wav_bytes` is a WAV recording; `language` is a code like "en", or None to
+/// detect it. Nothing leaves the machine: the model in the `stt_model` setting
+/// runs in-process. The transcript so far is sent to the calling window as
+/// `transcription_partial` events while the audio is decoded. Speech
+/// recognition is only compiled in with the `speech-to-text` feature.
+#[tauri::command]
+pub async fn transcribe_audio(
+    wav_bytes: Vec<u8>,
+    language: Option<String>,
+    request_id: Option<String>,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+) -> Result<Transcription, AppError> {
+    log::info!("Transcription request: {} bytes", wav_bytes.len());
+
+    let request_id = request_id.unwrap_or_else(|| {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        format!("stt_{:x}", nanos)
+    });
+    let samples = decode_wav(&wav_bytes)?;
+    if samples.is_empty() {
+        return Err(AppError::Other("The recording is empty".to_string()));
+    }
+    let duration_ms = samples.len() as u64 * 1000 / u64::from(SAMPLE_RATE);
+    let model = find_model(&app_handle, &settings::read_settings(&app_handle).stt_model)?;
+    let language = language.filter(|l| !l.trim().is_empty() && l != "auto");
+
+    let partial_window = window.clone();
+    let partial_id = request_id.clone();
+    let mut partial = String::new();
+    let started = std::time::Instant::now();
+    let (text, language) = tauri::async_runtime::spawn_blocking(move || {
+        engine::transcribe(&model, &samples, language.as_deref(), move |segment| {
+            partial.push_str(segment);
+            let _ = partial_window.emit_to(
+                partial_window.label(),
+                "transcription_partial",
+                TranscriptionPartial {
+                    request_id: partial_id.clone(),
+                    text: partial.trim().to_string(),
+                },
+            );
+        })
+    })
+    .await
+    .map_err(|e| AppError::Other(format!("Transcription task failed: {}", e)))??;
+
+    log::info!(
+        "Transcribed {} ms of audio in {} ms ({} chars)",
+        duration_ms,
+        started.elapsed().as_millis(),
+        text.len()
+    );
+    Ok(Transcription {
+        request_id,
+        text,
+        language,
+        duration_ms,
+    })
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::ollama::ChatMessage;
+use crate::ollama_chat;
+use crate::scheduler::Lane;
+use crate::settings;
+use crate::vectorstore::VectorStore;
+
+/// Questions generated when no count is given, and the most a request can ask for
+const DEFAULT_COUNT: usize = 4;
+const MAX_COUNT: usize = 10;
+/// Chunks sampled from the document, spread evenly from start to end
+const SAMPLE_CHUNKS: usize = 8;
+/// Sampled chunks are cut to this many characters to keep the prompt short
+const MAX_CHUNK_CHARS: usize = 800;
+
+/// Questions generated per document, so the empty chat can show them without waiting
+static CACHE: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+struct SuggestedQuestions {
+    document_id: String,
+    questions: Vec<String>,
+}
+
+/// Chunks spread evenly across the document, so questions cover more than its opening
+fn sample_chunks(chunks: &[String]) -> Vec<&str> {
+    let step = chunks.len().div_ceil(SAMPLE_CHUNKS).max(1);
+    chunks
+        .iter()
+        .step_by(step)
+        .map(|text| {
+            let text = text.trim();
+            match text.char_indices().nth(MAX_CHUNK_CHARS) {
+                Some((end, _)) => &text[..end],
+                None => text,
+            }
+        })
+        .collect()
+}
+
+fn build_prompt(samples: &[&str], count: usize) -> String {
+    let mut prompt = format!(
+        "Here are excerpts from a document. Suggest {} questions a reader could ask about it.\n\
+         Each question must be answerable from the document, short, and self-contained.\n\
+         Reply with JSON only, in the form {{\"questions\": [\"...\"]}}.\n\n",
+        count
+    );
+    for sample in samples {
+        prompt.push_str(&format!("---\n{}\n\n", sample));
+    }
+    prompt
+}
+
+/// Pull the question list out of a model reply that may include extra prose or code fences
+fn parse_questions(reply: &str) -> Result<Vec<String>, String> {
+    let start = reply.find('{').ok_or("Model reply contained no JSON")?;
+    let end = reply.rfind('}').ok_or("Model reply contained no JSON")?;
+
+    #[derive(Deserialize)]
+    struct Questions {
+        questions: Vec<String>,
+    }
+
+    serde_json::from_str::<Questions>(&reply[start..=end])
+        .map(|q| q.questions)
+        .map_err(|e| format!("Failed to parse suggested questions: {}", e))
+}
+
+async fn generate(app_handle: &tauri::AppHandle, document_id: &str, count: usize) -> Result<Vec<String>, String> {
+    let chunks = app_handle.state::<VectorStore>().chunk_texts(document_id)?;
+    if chunks.is_empty() {
+        return Err(format!("Document {} has no indexed text", document_id));
+    }
+    let samples = sample_chunks(&chunks);
+    log::info!("Generating {} questions for {} from {} chunks", count, document_id, samples.len());
+
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You suggest starter questions for a document. Only ask about what the excerpts cover.".to_string(),
+            images: Vec::new(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: build_prompt(&samples, count),
+            images: Vec::new(),
+        },
+    ];
+    let model = settings::read_settings(app_handle).ollama_model;
+    let reply = ollama_chat::chat_in(Lane::Background, model, messages, Some(0.5), Some(1024), None, None, None, app_handle.clone()).await?;
+
+    let mut questions: Vec<String> = Vec::new();
+    for question in parse_questions(&reply)? {
+        let question = question.trim().to_string();
+        if !question.is_empty() && !questions.contains(&question) {
+            questions.push(question);
+        }
+    }
+    questions.truncate(count);
+    if questions.is_empty() {
+        return Err("Model suggested no questions".to_string());
+    }
+
+    CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(document_id.to_string(), questions.clone());
+    Ok(questions)
+}
+
+/// Generate suggestions in the background after a document is indexed
+///
+/// Emits `suggested_questions` with `{document_id, questions}` when done; failures
+/// are only logged since suggestions are optional.
+pub fn generate_in_background(app_handle: tauri::AppHandle, document_id: String) {
+    tauri::async_runtime::spawn(async move {
+        match generate(&app_handle, &document_id, DEFAULT_COUNT).await {
+            Ok(questions) => {
+                app_handle
+                    .emit("suggested_questions", SuggestedQuestions { document_id, questions })
+                    .ok();
+            }
+            Err(e) => log::warn!("Failed to suggest questions for {}: {}", document_id, e),
+        }
+    });
+}
+
+/// Starter questions for a document, shown on an empty chat
+///
+/// Returns the questions generated after indexing when there are enough of them,
+/// otherwise asks the chat model using excerpts sampled across the document.
+#[tauri::command]
+pub async fn generate_suggested_questions(
+    app_handle: tauri::AppHandle,
+    document_id: String,
+    count: Option<usize>,
+) -> Result<Vec<String>, AppError> {
+    let count = count.unwrap_or(DEFAULT_COUNT).clamp(1, MAX_COUNT);
+
+    let cached = CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|cache| cache.get(&document_id).cloned());
+    if let Some(questions) = cached.filter(|q| q.len() >= count) {
+        return Ok(questions.into_iter().take(count).collect());
+    }
+
+    Ok(generate(&app_handle, &document_id, count).await?)
+}
@@ -0,0 +1,179 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::Emitter;
+
+use crate::chunking::{self, ChunkStrategy};
+use crate::error::AppError;
+use crate::progress::ProgressThrottle;
+use crate::rag::complete;
+use crate::scheduler::Lane;
+use crate::settings::{self, AppSettings};
+use crate::vectorstore::VectorStore;
+
+/// Characters of document text per map step (roughly 1.5k tokens), small enough
+/// to leave room for the prompt and reply in a 4k context
+const SUMMARY_CHUNK_CHARS: usize = 6000;
+/// Chunk summaries requested from the model at the same time
+const SUMMARY_PARALLELISM: usize = 3;
+
+const MAP_PROMPT: &str = "Summarize the following part of a longer document. Keep every key fact, figure, \
+name and conclusion; leave out repetition and filler. Reply with the summary only.";
+
+const COMBINE_PROMPT: &str = "The following are summaries of consecutive parts of one document. Merge them \
+into a single summary that keeps every key fact, in document order. Reply with the summary only.";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryStyle {
+    /// One or two paragraphs
+    #[default]
+    Brief,
+    /// Section by section, with the important details
+    Detailed,
+    /// Key points as a bulleted list
+    Bullets,
+}
+
+impl SummaryStyle {
+    fn prompt(self) -> &'static str {
+        match self {
+            SummaryStyle::Brief => "The following are summaries of consecutive parts of one document. Write a \
+concise summary of the whole document in one or two paragraphs.",
+            SummaryStyle::Detailed => "The following are summaries of consecutive parts of one document. Write a \
+detailed summary of the whole document, following its structure and keeping the important details, figures \
+and conclusions.",
+            SummaryStyle::Bullets => "The following are summaries of consecutive parts of one document. Summarize \
+the whole document as a Markdown bulleted list of its key points, most important first.",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DocumentSummary {
+    summary: String,
+    /// Parts the document was split into for the map step
+    chunks: usize,
+}
+
+/// Merge partial summaries until they fit in one prompt
+///
+/// Consecutive summaries are grouped up to `SUMMARY_CHUNK_CHARS` and each group is
+/// merged by the model; repeated until a single pass can take them all.
+async fn reduce(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    mut partials: Vec<String>,
+) -> Result<String, String> {
+    while partials.len() > 1 && partials.iter().map(|p| p.len() + 2).sum::<usize>() > SUMMARY_CHUNK_CHARS {
+        let mut groups: Vec<String> = Vec::new();
+        let mut current = String::new();
+        for partial in &partials {
+            if !current.is_empty() && current.len() + partial.len() + 2 > SUMMARY_CHUNK_CHARS {
+                groups.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(partial);
+        }
+        groups.push(current);
+
+        // Every group holds a single summary; merging can't shrink them any further
+        if groups.len() == partials.len() {
+            break;
+        }
+
+        log::info!("Merging {} partial summaries into {}", partials.len(), groups.len());
+        let merges: Vec<_> = groups
+            .iter()
+            .map(|group| complete(Lane::Background, app_handle, settings, COMBINE_PROMPT, group))
+            .collect();
+        partials = futures::stream::iter(merges)
+            .buffered(SUMMARY_PARALLELISM)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(partials.join("\n\n"))
+}
+
+/// Summarize a whole indexed document with map-reduce
+///
+/// The document text is split into context-sized parts, each part is summarized
+/// (a few at a time), and the partial summaries are merged into one summary in
+/// the requested style. `on_progress` gets the stage, `map` or `reduce`, with
+/// the parts completed so far and the total.
+pub(crate) async fn summarize(
+    app_handle: &tauri::AppHandle,
+    settings: &AppSettings,
+    store: &VectorStore,
+    document_id: &str,
+    style: SummaryStyle,
+    mut on_progress: impl FnMut(&str, usize, usize),
+) -> Result<DocumentSummary, String> {
+    let text = store.chunk_texts(document_id)?.join("\n\n");
+    if text.trim().is_empty() {
+        return Err(format!("No indexed content found for document {}", document_id));
+    }
+
+    let parts: Vec<String> = chunking::chunk(&text, ChunkStrategy::Recursive, SUMMARY_CHUNK_CHARS, 0)
+        .iter()
+        .map(|c| c.text().to_string())
+        .collect();
+    let total = parts.len();
+    log::info!("Split document into {} parts", total);
+
+    let mut partials = Vec::with_capacity(total);
+    // `buffered` keeps the results in document order
+    // Collected first: a lazily mapped stream of borrowing futures isn't provably `Send`
+    let maps: Vec<_> = parts
+        .iter()
+        .map(|part| complete(Lane::Background, app_handle, settings, MAP_PROMPT, part))
+        .collect();
+    let mut summaries = futures::stream::iter(maps).buffered(SUMMARY_PARALLELISM);
+    while let Some(result) = summaries.next().await {
+        let partial = result.map_err(|e| format!("Failed to summarize part {} of {}: {}", partials.len() + 1, total, e))?;
+        partials.push(partial);
+        on_progress("map", partials.len(), total);
+    }
+
+    on_progress("reduce", total, total);
+    let combined = reduce(app_handle, settings, partials).await?;
+    let summary = complete(Lane::Background, app_handle, settings, style.prompt(), &combined).await?;
+
+    log::info!("Summary of {} ready: {} chars from {} parts", document_id, summary.len(), total);
+    Ok(DocumentSummary { summary, chunks: total })
+}
+
+/// Summarize a whole indexed document; see `summarize`
+///
+/// Emits `summary_progress` as parts complete.
+#[tauri::command]
+pub async fn summarize_document(
+    document_id: String,
+    style: Option<SummaryStyle>,
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+) -> Result<DocumentSummary, AppError> {
+    let style = style.unwrap_or_default();
+    log::info!("Summarizing document {} ({:?})", document_id, style);
+
+    let settings = settings::read_settings(&app_handle);
+    let mut throttle = ProgressThrottle::new();
+    Ok(summarize(&app_handle, &settings, &store, &document_id, style, |stage, completed, total| {
+        let percent = (completed as f64 / total as f64) * 100.0;
+        if stage == "reduce" || throttle.should_emit(percent, false) {
+            window.emit_to(window.label(), "summary_progress", json!({
+                "document_id": document_id,
+                "stage": stage,
+                "completed": completed,
+                "total": total,
+                "percent": percent
+            })).ok();
+        }
+    })
+    .await?)
+}
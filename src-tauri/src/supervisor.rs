@@ -0,0 +1,358 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::backend::BackendKind;
+use crate::error::AppError;
+use crate::{http, ollama, settings};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+#[cfg(target_os = "windows")]
+const DETACHED_PROCESS: u32 = 0x00000008;
+
+/// How often the supervisor checks whether the server is still alive
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Consecutive restarts before giving up
+const MAX_RESTARTS: u32 = 5;
+/// Longest wait between two restarts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A server that stays up this long resets the restart counter
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// Lines of server stderr kept for diagnostics
+const SERVER_LOG_LINES: usize = 500;
+
+/// Tail of the stderr of every server we spawned, oldest first
+static SERVER_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Recent stderr lines of the servers PrivatePDF started (empty if it started none)
+pub fn server_log() -> Vec<String> {
+    SERVER_LOG.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+}
+
+fn capture_log_line(line: &str) {
+    let mut log = SERVER_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if log.len() == SERVER_LOG_LINES {
+        log.pop_front();
+    }
+    log.push_back(line.to_string());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// No server started by PrivatePDF
+    Stopped,
+    Running,
+    /// The server exited unexpectedly and a restart is scheduled
+    Crashed,
+    /// The server kept crashing and the supervisor gave up
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaHealth {
+    state: HealthState,
+    pid: Option<u32>,
+    /// Restarts since the server was last stable
+    restarts: u32,
+    message: Option<String>,
+    /// The API answered the last health check, whoever started the server
+    running: bool,
+    /// Round trip of the last successful health check
+    latency_ms: Option<u64>,
+}
+
+impl OllamaHealth {
+    pub fn state(&self) -> HealthState {
+        self.state
+    }
+}
+
+struct Inner {
+    child: Option<Child>,
+    binary: Option<PathBuf>,
+    /// OLLAMA_HOST the server was started with, reused for restarts
+    bind: String,
+    state: HealthState,
+    restarts: u32,
+    started_at: Option<Instant>,
+    /// Bumped on every start/stop so an old monitor task knows to exit
+    generation: u64,
+    /// Result of the last health check
+    reachable: bool,
+    latency_ms: Option<u64>,
+}
+
+/// Owns the `ollama serve` process started by PrivatePDF
+///
+/// Tracks the PID, forwards the server's stderr to the app log, detects crashes
+/// and restarts the server with exponential backoff. Every state change is
+/// emitted to the frontend as `ollama_health_changed`.
+pub struct OllamaSupervisor {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for OllamaSupervisor {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                child: None,
+                binary: None,
+                bind: String::new(),
+                state: HealthState::Stopped,
+                restarts: 0,
+                started_at: None,
+                generation: 0,
+                reachable: false,
+                latency_ms: None,
+            })),
+        }
+    }
+}
+
+fn lock(inner: &Mutex<Inner>) -> MutexGuard<'_, Inner> {
+    inner.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn health(inner: &Inner, message: Option<String>) -> OllamaHealth {
+    OllamaHealth {
+        state: inner.state,
+        pid: inner.child.as_ref().map(|c| c.id()),
+        restarts: inner.restarts,
+        message,
+        running: inner.reachable,
+        latency_ms: inner.latency_ms,
+    }
+}
+
+fn emit_health(app_handle: &tauri::AppHandle, health: OllamaHealth) {
+    app_handle.emit("ollama_health_changed", health).ok();
+}
+
+/// Spawn `<binary> serve` listening on `bind`, with stderr piped into the log
+///
+/// The address is only set in the child's environment; this process's own
+/// environment is never changed.
+fn spawn_server(binary: &Path, bind: &str) -> Result<Child, String> {
+    let mut cmd = Command::new(binary);
+    cmd.arg("serve")
+        .env("OLLAMA_HOST", bind)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    // Portable installs keep pulled models with the app unless the user chose a folder
+    if let Some(models) = crate::portable::ollama_models_dir() {
+        if std::env::var_os("OLLAMA_MODELS").is_none() {
+            cmd.env("OLLAMA_MODELS", models);
+        }
+    }
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW | DETACHED_PROCESS);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", binary.display(), e))?;
+
+    if let Some(stderr) = child.stderr.take() {
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                capture_log_line(&line);
+                if line.contains("level=ERROR") || line.contains("panic") {
+                    log::warn!("ollama: {}", line);
+                } else {
+                    log::debug!("ollama: {}", line);
+                }
+            }
+        });
+    }
+    Ok(child)
+}
+
+impl OllamaSupervisor {
+    /// Start `binary serve` on `bind` under supervision, or return the PID if it's already running
+    pub fn start(&self, app_handle: &tauri::AppHandle, binary: &Path, bind: &str) -> Result<u32, String> {
+        let mut inner = lock(&self.inner);
+        if let Some(child) = inner.child.as_mut() {
+            if matches!(child.try_wait(), Ok(None)) {
+                return Ok(child.id());
+            }
+        }
+
+        let child = spawn_server(binary, bind)?;
+        let pid = child.id();
+        inner.child = Some(child);
+        inner.binary = Some(binary.to_path_buf());
+        inner.bind = bind.to_string();
+        inner.state = HealthState::Running;
+        inner.restarts = 0;
+        inner.started_at = Some(Instant::now());
+        inner.generation += 1;
+        let generation = inner.generation;
+        emit_health(app_handle, health(&inner, None));
+        drop(inner);
+
+        log::info!("Supervising Ollama server (PID {})", pid);
+        tauri::async_runtime::spawn(monitor(self.inner.clone(), app_handle.clone(), generation));
+        Ok(pid)
+    }
+
+    /// Stop the supervised server without triggering a restart
+    ///
+    /// Returns true if a managed process was running.
+    pub fn stop(&self, app_handle: &tauri::AppHandle) -> bool {
+        let mut inner = lock(&self.inner);
+        inner.generation += 1;
+        inner.state = HealthState::Stopped;
+        inner.started_at = None;
+
+        let was_running = match inner.child.take() {
+            Some(mut child) => {
+                let pid = child.id();
+                if let Err(e) = child.kill() {
+                    log::warn!("Failed to kill Ollama server (PID {}): {}", pid, e);
+                }
+                let _ = child.wait();
+                log::info!("Stopped supervised Ollama server (PID {})", pid);
+                true
+            }
+            None => false,
+        };
+        emit_health(app_handle, health(&inner, None));
+        was_running
+    }
+
+    pub fn health(&self) -> OllamaHealth {
+        health(&lock(&self.inner), None)
+    }
+}
+
+/// Watch the server process and restart it with backoff when it exits unexpectedly
+async fn monitor(inner: Arc<Mutex<Inner>>, app_handle: tauri::AppHandle, generation: u64) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let backoff = {
+            let mut guard = lock(&inner);
+            if guard.generation != generation {
+                return;
+            }
+            let reason = match guard.child.as_mut().map(|c| c.try_wait()) {
+                Some(Ok(None)) => continue,
+                Some(Err(e)) => {
+                    log::warn!("Failed to check Ollama server status: {}", e);
+                    continue;
+                }
+                Some(Ok(Some(status))) => format!("exited unexpectedly ({})", status),
+                // The previous restart attempt failed to spawn
+                None => "could not be restarted".to_string(),
+            };
+
+            guard.child = None;
+            if guard.started_at.is_some_and(|t| t.elapsed() >= STABLE_AFTER) {
+                guard.restarts = 0;
+            }
+
+            if guard.restarts >= MAX_RESTARTS {
+                guard.state = HealthState::Failed;
+                let message = format!("Ollama {}, giving up after {} restarts", reason, MAX_RESTARTS);
+                log::error!("{}", message);
+                emit_health(&app_handle, health(&guard, Some(message)));
+                return;
+            }
+
+            guard.state = HealthState::Crashed;
+            let backoff = Duration::from_secs(1 << guard.restarts).min(MAX_BACKOFF);
+            let message = format!("Ollama {}, restarting in {}s", reason, backoff.as_secs());
+            log::warn!("{}", message);
+            emit_health(&app_handle, health(&guard, Some(message)));
+            backoff
+        };
+
+        tokio::time::sleep(backoff).await;
+
+        let mut guard = lock(&inner);
+        if guard.generation != generation {
+            return;
+        }
+        let Some(binary) = guard.binary.clone() else {
+            return;
+        };
+        guard.restarts += 1;
+        guard.started_at = None;
+        match spawn_server(&binary, &guard.bind) {
+            Ok(child) => {
+                log::info!("Restarted Ollama server (PID {}, restart {})", child.id(), guard.restarts);
+                guard.child = Some(child);
+                guard.state = HealthState::Running;
+                guard.started_at = Some(Instant::now());
+                emit_health(&app_handle, health(&guard, None));
+            }
+            // Counted as another crash on the next poll
+            Err(e) => log::error!("{}", e),
+        }
+    }
+}
+
+/// Time a GET of /api/version, or None if the server didn't answer successfully
+async fn ping(url: &str) -> Option<u64> {
+    let started = Instant::now();
+    let response = http::get(&format!("{}/api/version", url)).ok()?
+        .timeout(http::status_timeout())
+        .send()
+        .await
+        .ok()?;
+    response
+        .status()
+        .is_success()
+        .then(|| started.elapsed().as_millis() as u64)
+}
+
+/// Ping the Ollama API every `health_check_interval_secs` and emit `ollama_health_changed`
+/// when it goes down or comes back
+///
+/// Unlike `monitor` this also covers servers PrivatePDF didn't start, so the UI
+/// learns about an outage before a chat fails. Started from `setup()`; a zero
+/// interval disables it. Nothing is pinged while another backend is selected.
+pub fn start_health_monitor(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settings = settings::read_settings(&app_handle);
+            if settings.health_check_interval_secs == 0 {
+                log::info!("Ollama health monitor disabled");
+                return;
+            }
+
+            if settings.llm_backend == BackendKind::Ollama {
+                let latency = ping(&ollama::ollama_url(&app_handle)).await;
+                let supervisor = app_handle.state::<OllamaSupervisor>();
+                let mut guard = lock(&supervisor.inner);
+                let changed = guard.reachable != latency.is_some();
+                guard.reachable = latency.is_some();
+                guard.latency_ms = latency;
+                if changed {
+                    match latency {
+                        Some(ms) => log::info!("Ollama is reachable ({} ms)", ms),
+                        None => log::warn!("Ollama stopped responding"),
+                    }
+                    emit_health(&app_handle, health(&guard, None));
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(settings.health_check_interval_secs)).await;
+        }
+    });
+}
+
+/// Current state of the Ollama server started by PrivatePDF
+#[tauri::command]
+pub async fn get_ollama_health(supervisor: tauri::State<'_, OllamaSupervisor>) -> Result<OllamaHealth, AppError> {
+    Ok(supervisor.health())
+}
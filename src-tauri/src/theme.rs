@@ -0,0 +1,89 @@
+use serde::Serialize;
+use tauri::{Emitter, Manager, Theme, WebviewWindow};
+
+use crate::error::AppError;
+use crate::settings;
+
+/// `theme` setting that follows the OS between light and dark
+pub const SYSTEM: &str = "system";
+
+/// The `theme` setting and the theme it resolves to, sent as `theme_changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeState {
+    /// "light", "dark" or "system"
+    setting: String,
+    /// "light" or "dark"
+    theme: &'static str,
+}
+
+fn name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Light => "light",
+        _ => "dark",
+    }
+}
+
+/// Native theme for a setting; None lets the window follow the OS
+fn native(setting: &str) -> Option<Theme> {
+    match setting {
+        SYSTEM => None,
+        "light" => Some(Theme::Light),
+        _ => Some(Theme::Dark),
+    }
+}
+
+fn state(window: &WebviewWindow, setting: &str) -> ThemeState {
+    let theme = match native(setting) {
+        Some(theme) => theme,
+        None => window.theme().unwrap_or(Theme::Dark),
+    };
+    ThemeState {
+        setting: setting.to_string(),
+        theme: name(theme),
+    }
+}
+
+/// Set the window's native theme (title bar, scrollbars, `prefers-color-scheme`)
+pub fn apply(window: &WebviewWindow, setting: &str) {
+    if let Err(e) = window.set_theme(native(setting)) {
+        log::warn!("Failed to set window theme: {}", e);
+    }
+}
+
+/// Apply a newly saved `theme` setting to every window and tell the UI
+pub fn update(app_handle: &tauri::AppHandle, setting: &str) {
+    let windows = app_handle.webview_windows();
+    for window in windows.values() {
+        apply(window, setting);
+    }
+    if let Some(window) = windows.values().next() {
+        app_handle.emit("theme_changed", state(window, setting)).ok();
+    }
+}
+
+/// The OS switched between light and dark; forwarded to the window as
+/// `theme_changed` when the setting follows the OS, so the UI doesn't need to poll
+pub fn os_theme_changed(window: &WebviewWindow, theme: Theme) {
+    let setting = settings::read_settings(window.app_handle()).theme;
+    if setting != SYSTEM {
+        return;
+    }
+    log::info!("OS theme changed to {}", name(theme));
+    window
+        .emit_to(
+            window.label(),
+            "theme_changed",
+            ThemeState {
+                setting,
+                theme: name(theme),
+            },
+        )
+        .ok();
+}
+
+/// Current `theme` setting and the light or dark theme it resolves to
+#[tauri::command]
+pub async fn get_theme(window: WebviewWindow) -> Result<ThemeState, AppError> {
+    let setting = settings::read_settings(window.app_handle()).theme;
+    Ok(state(&window, &setting))
+}
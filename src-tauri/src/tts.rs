@@ -0,0 +1,383 @@
+use base64::Engine;
+use serde::Serialize;
+use serde_json::json;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::secure_delete;
+use crate::settings;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Bytes of audio per `tts_audio_chunk` event (~0.35 s of 22 kHz 16-bit mono)
+const CHUNK_BYTES: usize = 16 * 1024;
+
+/// Piper voices are trained at 22.05 kHz unless their config says otherwise
+const DEFAULT_SAMPLE_RATE: u32 = 22050;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeechInfo {
+    request_id: String,
+    /// "piper" or "system"
+    engine: String,
+    /// "pcm_s16le" (raw mono samples) or "wav"
+    format: String,
+    sample_rate: u32,
+}
+
+/// Where an engine writes its audio
+enum Output {
+    /// Streamed from stdout as it is synthesized
+    Stdout,
+    /// Written to a temp file that is sent once synthesis finishes
+    #[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+    File(PathBuf),
+}
+
+struct Speech {
+    request_id: String,
+    child: Arc<Mutex<Child>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The utterance currently playing, so a new one or `stop_speaking` can interrupt it
+#[derive(Default)]
+pub struct SpeechState {
+    current: Mutex<Option<Speech>>,
+}
+
+impl SpeechState {
+    /// Kill the running synthesizer, if any; returns whether one was running
+    fn stop(&self) -> bool {
+        let speech = self.current.lock().unwrap_or_else(|e| e.into_inner()).take();
+        match speech {
+            Some(speech) => {
+                log::info!("Stopping speech {}", speech.request_id);
+                speech.cancelled.store(true, Ordering::SeqCst);
+                let _ = speech.child.lock().unwrap_or_else(|e| e.into_inner()).kill();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn finish(&self, request_id: &str) {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if current.as_ref().map(|s| s.request_id == request_id).unwrap_or(false) {
+            *current = None;
+        }
+    }
+}
+
+/// Create a command for an external tool without flashing a console window on Windows
+fn tool_command(program: &Path) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// Find the Piper binary on PATH, in the app data directory or its usual install locations
+fn find_piper(app_data: &Path) -> Option<PathBuf> {
+    let exe = if cfg!(target_os = "windows") { "piper.exe" } else { "piper" };
+    let mut candidates = vec![PathBuf::from(exe), app_data.join("piper").join(exe)];
+
+    #[cfg(target_os = "windows")]
+    {
+        let localappdata = std::env::var("LOCALAPPDATA").unwrap_or_default();
+        candidates.push(Path::new(&localappdata).join("Programs").join("piper").join(exe));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        for dir in ["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"] {
+            candidates.push(Path::new(dir).join(exe));
+        }
+    }
+
+    candidates.into_iter().find(|candidate| {
+        tool_command(candidate)
+            .arg("--help")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Resolve a voice name (`en_US-lessac-medium`) or path to a Piper `.onnx` model
+fn find_voice(app_data: &Path, voice: &str) -> Option<PathBuf> {
+    let path = Path::new(voice);
+    if path.extension().map(|ext| ext == "onnx").unwrap_or(false) && path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let path = app_data.join("voices").join(format!("{}.onnx", voice));
+    path.is_file().then_some(path)
+}
+
+/// Sample rate from the voice's `.onnx.json` config
+fn voice_sample_rate(model: &Path) -> u32 {
+    let config = PathBuf::from(format!("{}.json", model.display()));
+    std::fs::read_to_string(config)
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|config| config["audio"]["sample_rate"].as_u64())
+        .map(|rate| rate as u32)
+        .unwrap_or(DEFAULT_SAMPLE_RATE)
+}
+
+/// Drop Markdown markup and citation markers that would otherwise be read out
+fn speakable(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '_' | '#' | '`' | '>' | '|' => {}
+            // "[1]" and "[Source 2]" citations
+            '[' => {
+                let rest: String = chars.clone().take_while(|&c| c != ']').collect();
+                if rest.len() < 16 && rest.chars().any(|c| c.is_ascii_digit()) {
+                    for _ in 0..=rest.chars().count() {
+                        chars.next();
+                    }
+                } else {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The OS speech engine: SAPI on Windows, `say` on macOS, espeak-ng elsewhere
+fn system_command(voice: Option<&str>, wav_path: &Path) -> (Command, Output) {
+    #[cfg(target_os = "windows")]
+    {
+        // Text comes in on stdin and the voice through the environment, so neither is parsed as script
+        let script = "Add-Type -AssemblyName System.Speech; \
+            $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+            if ($env:PRIVATEPDF_VOICE) { $s.SelectVoice($env:PRIVATEPDF_VOICE) }; \
+            $s.SetOutputToWaveFile($env:PRIVATEPDF_WAV); \
+            $s.Speak([Console]::In.ReadToEnd()); \
+            $s.Dispose()";
+        let mut cmd = tool_command(Path::new("powershell"));
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .env("PRIVATEPDF_VOICE", voice.unwrap_or(""))
+            .env("PRIVATEPDF_WAV", wav_path);
+        (cmd, Output::File(wav_path.to_path_buf()))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = tool_command(Path::new("say"));
+        cmd.arg("-o").arg(wav_path).arg(format!("--data-format=LEI16@{}", DEFAULT_SAMPLE_RATE));
+        if let Some(voice) = voice {
+            cmd.args(["-v", voice]);
+        }
+        // Without text arguments `say` reads stdin
+        (cmd, Output::File(wav_path.to_path_buf()))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let _ = wav_path;
+        let mut cmd = tool_command(Path::new("espeak-ng"));
+        cmd.arg("--stdout");
+        if let Some(voice) = voice {
+            cmd.args(["-v", voice]);
+        }
+        (cmd, Output::Stdout)
+    }
+}
+
+fn emit_chunk(app_handle: &tauri::AppHandle, request_id: &str, data: &[u8], done: bool) {
+    let _ = app_handle.emit(
+        "tts_audio_chunk",
+        json!({
+            "request_id": request_id,
+            "data": base64::engine::general_purpose::STANDARD.encode(data),
+            "done": done,
+        }),
+    );
+}
+
+/// Send audio to the frontend until the synthesizer finishes or is stopped
+fn stream_audio(
+    app_handle: &tauri::AppHandle,
+    request_id: &str,
+    child: &Arc<Mutex<Child>>,
+    cancelled: &AtomicBool,
+    output: &Output,
+) -> Result<(), String> {
+    match output {
+        Output::Stdout => {
+            let mut stdout = child
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .stdout
+                .take()
+                .ok_or("Speech engine has no output")?;
+            let mut buf = vec![0u8; CHUNK_BYTES];
+            loop {
+                let n = stdout.read(&mut buf).map_err(|e| format!("Failed to read audio: {}", e))?;
+                if n == 0 || cancelled.load(Ordering::SeqCst) {
+                    break;
+                }
+                emit_chunk(app_handle, request_id, &buf[..n], false);
+            }
+            let _ = child.lock().unwrap_or_else(|e| e.into_inner()).wait();
+        }
+        Output::File(path) => {
+            // Poll rather than wait() so `stop_speaking` can take the lock and kill the process
+            let status = loop {
+                if let Some(status) = child
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .try_wait()
+                    .map_err(|e| format!("Failed to wait for speech engine: {}", e))?
+                {
+                    break status;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            };
+            if status.success() && !cancelled.load(Ordering::SeqCst) {
+                let audio = std::fs::read(path).map_err(|e| format!("Failed to read audio: {}", e))?;
+                for chunk in audio.chunks(CHUNK_BYTES) {
+                    if cancelled.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    emit_chunk(app_handle, request_id, chunk, false);
+                }
+            } else if !cancelled.load(Ordering::SeqCst) {
+                return Err(format!("Speech engine exited with {}", status));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read text aloud with a local voice, streaming audio as `tts_audio_chunk` events
+///
+/// Uses Piper when `voice` (or the `tts_voice` setting) names an installed `.onnx`
+/// voice and Piper is available, and the OS speech engine otherwise. Starting a new
+/// utterance stops the previous one.
+#[tauri::command]
+pub async fn speak(
+    text: String,
+    voice: Option<String>,
+    request_id: Option<String>,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, SpeechState>,
+) -> Result<SpeechInfo, AppError> {
+    log::info!("Speak request: {} chars", text.len());
+
+    let text = speakable(&text);
+    if text.is_empty() {
+        return Err(AppError::Other("Nothing to read aloud".to_string()));
+    }
+    let request_id = request_id.unwrap_or_else(|| {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        format!("tts_{:x}", nanos)
+    });
+    let voice = voice
+        .or_else(|| Some(settings::read_settings(&app_handle).tts_voice))
+        .filter(|v| !v.trim().is_empty());
+
+    let app_data = crate::portable::app_data_dir(&app_handle)
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let piper = voice
+        .as_deref()
+        .and_then(|voice| find_voice(&app_data, voice))
+        .and_then(|model| find_piper(&app_data).map(|piper| (piper, model)));
+
+    let (mut cmd, output, info) = match piper {
+        Some((piper, model)) => {
+            let mut cmd = tool_command(&piper);
+            cmd.arg("--model").arg(&model).arg("--output_raw");
+            let info = SpeechInfo {
+                request_id: request_id.clone(),
+                engine: "piper".to_string(),
+                format: "pcm_s16le".to_string(),
+                sample_rate: voice_sample_rate(&model),
+            };
+            (cmd, Output::Stdout, info)
+        }
+        None => {
+            let wav_path = secure_delete::temp_dir(&app_handle)?.join(format!("speech-{}.wav", request_id));
+            let (cmd, output) = system_command(voice.as_deref(), &wav_path);
+            let info = SpeechInfo {
+                request_id: request_id.clone(),
+                engine: "system".to_string(),
+                format: "wav".to_string(),
+                sample_rate: DEFAULT_SAMPLE_RATE,
+            };
+            (cmd, output, info)
+        }
+    };
+
+    state.stop();
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start speech engine ({}): {}", info.engine, e))?;
+    // Written from its own thread so a long answer can't fill the stdout pipe before
+    // all the text is sent; dropping stdin afterwards tells the engine the text is complete
+    let mut stdin = child.stdin.take().ok_or_else(|| AppError::Io("Speech engine has no input".to_string()))?;
+    std::thread::spawn(move || {
+        use std::io::Write;
+        if let Err(e) = stdin.write_all(text.as_bytes()) {
+            log::warn!("Failed to send text to speech engine: {}", e);
+        }
+    });
+
+    let child = Arc::new(Mutex::new(child));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    *state.current.lock().unwrap_or_else(|e| e.into_inner()) = Some(Speech {
+        request_id: request_id.clone(),
+        child: child.clone(),
+        cancelled: cancelled.clone(),
+    });
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = stream_audio(&handle, &request_id, &child, &cancelled, &output) {
+            log::warn!("Speech {} failed: {}", request_id, e);
+            let _ = handle.emit("tts_error", json!({ "request_id": request_id, "error": e }));
+        }
+        emit_chunk(&handle, &request_id, &[], true);
+        if let Output::File(path) = &output {
+            if path.exists() {
+                let _ = secure_delete::secure_delete(path);
+            }
+        }
+        handle.state::<SpeechState>().finish(&request_id);
+    });
+
+    Ok(info)
+}
+
+/// Stop the answer currently being read aloud; returns whether anything was playing
+#[tauri::command]
+pub async fn stop_speaking(state: tauri::State<'_, SpeechState>) -> Result<bool, AppError> {
+    log::info!("Stop speaking request");
+    Ok(state.stop())
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use tauri::Emitter;
+use tauri_plugin_updater::UpdaterExt;
+
+use crate::error::AppError;
+use crate::http;
+use crate::progress::ProgressThrottle;
+use crate::settings;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    available: bool,
+    current_version: String,
+    /// Newest release, when it's newer than the running app
+    version: Option<String>,
+    /// Release notes from the update manifest
+    notes: Option<String>,
+    date: Option<String>,
+    /// The update was downloaded and installed; it applies on restart
+    installed: bool,
+}
+
+/// The updater endpoint in tauri.conf.json
+const RELEASE_FEED_URL: &str = "https://github.com/ggeo/privatepdf/releases/latest/download/latest.json";
+
+/// Update signing key in the updater config; only release builds are given one
+fn has_signing_key(app_handle: &tauri::AppHandle) -> bool {
+    app_handle
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .is_some_and(|key| !key.trim().is_empty())
+}
+
+async fn check(app_handle: &tauri::AppHandle, install: bool) -> Result<UpdateInfo, String> {
+    if !has_signing_key(app_handle) {
+        return Err("Updates are disabled: this build has no update signing key".to_string());
+    }
+    // The updater doesn't use the shared client, so the guard (and offline mode) is asked first
+    http::check_url(RELEASE_FEED_URL)?;
+    let current_version = app_handle.package_info().version.to_string();
+    // The updater has its own HTTP client; give it the proxy the release feed needs
+    let mut builder = app_handle.updater_builder();
+    if let Some(proxy) = http::proxy_for("github.com") {
+        builder = builder.proxy(proxy);
+    }
+    let update = builder
+        .build()
+        .map_err(|e| format!("Failed to set up updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let Some(update) = update else {
+        log::info!("PrivatePDF {} is up to date", current_version);
+        return Ok(UpdateInfo {
+            available: false,
+            current_version,
+            version: None,
+            notes: None,
+            date: None,
+            installed: false,
+        });
+    };
+    log::info!("Update available: {} -> {}", current_version, update.version);
+
+    let mut info = UpdateInfo {
+        available: true,
+        current_version,
+        version: Some(update.version.clone()),
+        notes: update.body.clone(),
+        date: update.date.map(|d| d.to_string()),
+        installed: false,
+    };
+    if !install {
+        return Ok(info);
+    }
+
+    // The signature is checked against the public key in tauri.conf.json before installing
+    let mut throttle = ProgressThrottle::new();
+    let mut downloaded = 0u64;
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let percent = total.map(|t| downloaded as f64 / t.max(1) as f64 * 100.0).unwrap_or(0.0);
+                if throttle.should_emit(percent, false) {
+                    app_handle
+                        .emit("update_download_progress", serde_json::json!({
+                            "downloaded": downloaded,
+                            "total": total,
+                            "percent": percent
+                        }))
+                        .ok();
+                }
+            },
+            || log::info!("Update downloaded, installing"),
+        )
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+
+    log::info!("Update {} installed; it applies on restart", update.version);
+    info.installed = true;
+    Ok(info)
+}
+
+/// Check for an update in the background if `auto_update_check` is on
+///
+/// Only checks; emits `update_available` with the `UpdateInfo` when there is one
+/// and leaves installing to the user.
+pub fn check_in_background(app_handle: tauri::AppHandle) {
+    if !settings::read_settings(&app_handle).auto_update_check {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        match check(&app_handle, false).await {
+            Ok(info) if info.available => {
+                app_handle.emit("update_available", info).ok();
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("{}", e),
+        }
+    });
+}
+
+/// Check the release feed for a newer, signed version of PrivatePDF
+///
+/// This is the only request the app makes to the internet besides model and
+/// Ollama downloads, and it only happens when asked for or when automatic
+/// checks are enabled. With `install` the update is downloaded, its signature
+/// verified and installed, emitting `update_download_progress`; the frontend
+/// then restarts the app.
+#[tauri::command]
+pub async fn check_for_updates(app_handle: tauri::AppHandle, install: Option<bool>) -> Result<UpdateInfo, AppError> {
+    log::info!("Checking for updates");
+    Ok(check(&app_handle, install.unwrap_or(false)).await?)
+}
@@ -0,0 +1,112 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    -- INSERT OR REPLACE only fires the delete trigger below with recursive triggers on
+    PRAGMA recursive_triggers = ON;
+
+    CREATE TABLE IF NOT EXISTS indexes (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        dimension INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS embeddings (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        index_id TEXT NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+        chunk_id TEXT NOT NULL,
+        text TEXT NOT NULL,
+        metadata TEXT,
+        vector BLOB NOT NULL,
+        page INTEGER,
+        start_offset INTEGER,
+        end_offset INTEGER,
+        UNIQUE(index_id, chunk_id)
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_embeddings_index ON embeddings(index_id);
+
+    -- File each index was built from, to notice when it changes on disk
+    CREATE TABLE IF NOT EXISTS document_sources (
+        index_id TEXT PRIMARY KEY REFERENCES indexes(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        mtime INTEGER,
+        hash TEXT NOT NULL,
+        indexed_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_document_sources_path ON document_sources(path);
+
+    -- BM25 keyword index over chunk text, kept in sync with embeddings by triggers
+    CREATE VIRTUAL TABLE IF NOT EXISTS embeddings_fts USING fts5(
+        text,
+        content = 'embeddings',
+        content_rowid = 'id',
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS embeddings_fts_insert AFTER INSERT ON embeddings BEGIN
+        INSERT INTO embeddings_fts(rowid, text) VALUES (new.id, new.text);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS embeddings_fts_delete AFTER DELETE ON embeddings BEGIN
+        INSERT INTO embeddings_fts(embeddings_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS embeddings_fts_update AFTER UPDATE OF text ON embeddings BEGIN
+        INSERT INTO embeddings_fts(embeddings_fts, rowid, text) VALUES ('delete', old.id, old.text);
+        INSERT INTO embeddings_fts(rowid, text) VALUES (new.id, new.text);
+    END;
+";
+
+/// Columns added since the first release, added to older databases on open
+const MIGRATIONS: &[(&str, &str, &str)] = &[
+    ("embeddings", "page", "ALTER TABLE embeddings ADD COLUMN page INTEGER"),
+    ("embeddings", "start_offset", "ALTER TABLE embeddings ADD COLUMN start_offset INTEGER"),
+    ("embeddings", "end_offset", "ALTER TABLE embeddings ADD COLUMN end_offset INTEGER"),
+    // Embedding model the index was built with; NULL for indexes from before it was recorded
+    ("indexes", "model", "ALTER TABLE indexes ADD COLUMN model TEXT"),
+    // Detected language of the document (ISO 639-3); NULL until it's detected
+    ("indexes", "language", "ALTER TABLE indexes ADD COLUMN language TEXT"),
+    // Chunk text was stored with PII masked (sensitive documents)
+    ("indexes", "masked", "ALTER TABLE indexes ADD COLUMN masked INTEGER NOT NULL DEFAULT 0"),
+];
+
+fn migrate(conn: &Connection) -> Result<(), String> {
+    for (table, column, sql) in MIGRATIONS {
+        let columns: Vec<String> = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .and_then(|mut stmt| stmt.query_map([], |row| row.get(1))?.collect())
+            .map_err(|e| format!("Failed to read vector store schema: {}", e))?;
+        if !columns.iter().any(|c| c == column) {
+            log::info!("Adding column {}.{} to vector store", table, column);
+            conn.execute_batch(sql)
+                .map_err(|e| format!("Failed to migrate vector store: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE name = ?1",
+        params![name],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|row| row.is_some())
+    .map_err(|e| format!("Failed to read vector store schema: {}", e))
+}
+
+/// Create the vector store tables and bring older databases up to date
+///
+/// Returns whether the database has chunks from before keyword search existed;
+/// see `VectorStore::build_keyword_index`.
+pub(crate) fn apply(conn: &Connection) -> Result<bool, String> {
+    let had_fts = table_exists(conn, "embeddings_fts")?;
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize vector store: {}", e))?;
+    migrate(conn)?;
+    Ok(!had_fts)
+}
@@ -0,0 +1,373 @@
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::rerank;
+use crate::vectorstore::{cosine, decode_vector, norm, page_from_metadata, EmbeddingItem, SearchHit, VectorStore};
+
+/// Weight of the keyword score in hybrid search; the rest goes to cosine similarity
+const KEYWORD_WEIGHT: f32 = 0.3;
+/// Candidates taken from each of the vector and keyword searches before merging
+const HYBRID_CANDIDATES: usize = 50;
+
+/// FTS5 query matching any of the words in `query`, or None if it has no words
+///
+/// Every word is quoted, so user input can't inject FTS5 operators, and terms
+/// like "E-1234" become phrases that match the exact token sequence.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// Scale scores to 0..1 so cosine and BM25 can be combined
+fn normalize_scores(hits: &[SearchHit]) -> HashMap<String, f32> {
+    let max = hits.iter().map(|h| h.score).fold(f32::MIN, f32::max);
+    let min = hits.iter().map(|h| h.score).fold(f32::MAX, f32::min);
+    let range = max - min;
+    hits.iter()
+        .map(|h| {
+            let score = if range > f32::EPSILON { (h.score - min) / range } else { 1.0 };
+            (h.chunk_id.clone(), score)
+        })
+        .collect()
+}
+
+impl VectorStore {
+    /// Vectors of an index keyed by chunk text, so unchanged chunks can be reused on re-index
+    pub fn chunk_vectors(&self, index_id: &str) -> Result<HashMap<String, Vec<f32>>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare("SELECT text, vector FROM embeddings WHERE index_id = ?1")
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        let vectors = stmt
+            .query_map(params![index_id], |row| {
+                Ok((row.get::<_, String>(0)?, decode_vector(&row.get::<_, Vec<u8>>(1)?)))
+            })
+            .map_err(|e| format!("Failed to read chunks: {}", e))?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        Ok(vectors)
+    }
+
+    /// Every chunk of an index with its vector, in the order they were added
+    pub fn items(&self, index_id: &str) -> Result<Vec<EmbeddingItem>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT chunk_id, text, metadata, vector, page, start_offset, end_offset
+                 FROM embeddings WHERE index_id = ?1 ORDER BY id",
+            )
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        let items = stmt
+            .query_map(params![index_id], |row| {
+                Ok(EmbeddingItem {
+                    chunk_id: row.get(0)?,
+                    text: row.get(1)?,
+                    metadata: row
+                        .get::<_, Option<String>>(2)?
+                        .and_then(|m| serde_json::from_str(&m).ok()),
+                    vector: decode_vector(&row.get::<_, Vec<u8>>(3)?),
+                    page: row.get(4)?,
+                    start: row.get::<_, Option<i64>>(5)?.map(|o| o as usize),
+                    end: row.get::<_, Option<i64>>(6)?.map(|o| o as usize),
+                })
+            })
+            .map_err(|e| format!("Failed to read chunks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        Ok(items)
+    }
+
+    /// Chunk texts of an index in the order they were added
+    pub fn chunk_texts(&self, index_id: &str) -> Result<Vec<String>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare("SELECT text FROM embeddings WHERE index_id = ?1 ORDER BY id")
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        let texts = stmt
+            .query_map(params![index_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to read chunks: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        Ok(texts)
+    }
+
+    /// Top-k chunks by cosine similarity to `query`
+    pub fn search(&self, index_id: &str, query: &[f32], top_k: usize) -> Result<Vec<SearchHit>, String> {
+        let query_norm = norm(query);
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT chunk_id, text, metadata, vector, page, start_offset, end_offset
+                 FROM embeddings WHERE index_id = ?1",
+            )
+            .map_err(|e| format!("Failed to prepare search: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![index_id], |row| {
+                let metadata: Option<serde_json::Value> =
+                    row.get::<_, Option<String>>(2)?.and_then(|m| serde_json::from_str(&m).ok());
+                let vector: Vec<u8> = row.get(3)?;
+                // Chunks stored before the page column existed keep their page in metadata
+                let page = row.get::<_, Option<u32>>(4)?.or_else(|| page_from_metadata(metadata.as_ref()));
+                Ok(SearchHit {
+                    chunk_id: row.get(0)?,
+                    text: row.get(1)?,
+                    metadata,
+                    score: cosine(query, query_norm, &decode_vector(&vector)),
+                    page,
+                    start: row.get::<_, Option<i64>>(5)?.map(|o| o as usize),
+                    end: row.get::<_, Option<i64>>(6)?.map(|o| o as usize),
+                })
+            })
+            .map_err(|e| format!("Search failed: {}", e))?;
+
+        let mut hits: Vec<SearchHit> = rows.filter_map(|r| r.ok()).collect();
+        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        hits.truncate(top_k);
+        Ok(hits)
+    }
+
+    /// Chunks on pages `first..=last` in document order; scores are 0
+    pub fn page_chunks(&self, index_id: &str, first: u32, last: u32) -> Result<Vec<SearchHit>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT chunk_id, text, metadata, page, start_offset, end_offset
+                 FROM embeddings WHERE index_id = ?1 ORDER BY id",
+            )
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+
+        let hits = stmt
+            .query_map(params![index_id], |row| {
+                let metadata: Option<serde_json::Value> =
+                    row.get::<_, Option<String>>(2)?.and_then(|m| serde_json::from_str(&m).ok());
+                let page = row.get::<_, Option<u32>>(3)?.or_else(|| page_from_metadata(metadata.as_ref()));
+                Ok(SearchHit {
+                    chunk_id: row.get(0)?,
+                    text: row.get(1)?,
+                    metadata,
+                    score: 0.0,
+                    page,
+                    start: row.get::<_, Option<i64>>(4)?.map(|o| o as usize),
+                    end: row.get::<_, Option<i64>>(5)?.map(|o| o as usize),
+                })
+            })
+            .map_err(|e| format!("Failed to read chunks: {}", e))?
+            .filter_map(|r| r.ok())
+            .filter(|hit| hit.page.is_some_and(|page| page >= first && page <= last))
+            .collect();
+        Ok(hits)
+    }
+
+    /// Stored vectors of the given chunks; chunks that aren't in the index are left out
+    pub fn vectors(&self, index_id: &str, chunk_ids: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare("SELECT vector FROM embeddings WHERE index_id = ?1 AND chunk_id = ?2")
+            .map_err(|e| format!("Failed to read vectors: {}", e))?;
+        let mut vectors = Vec::with_capacity(chunk_ids.len());
+        for chunk_id in chunk_ids {
+            let vector: Option<Vec<u8>> = stmt
+                .query_row(params![index_id, chunk_id], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("Failed to read vectors: {}", e))?;
+            vectors.extend(vector.map(|v| decode_vector(&v)));
+        }
+        Ok(vectors)
+    }
+
+    /// The given chunks in the order asked for; scores are 0 and chunks that
+    /// aren't in the index are left out
+    pub fn chunks(&self, index_id: &str, chunk_ids: &[String]) -> Result<Vec<SearchHit>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT text, metadata, page, start_offset, end_offset
+                 FROM embeddings WHERE index_id = ?1 AND chunk_id = ?2",
+            )
+            .map_err(|e| format!("Failed to read chunks: {}", e))?;
+        let mut hits = Vec::with_capacity(chunk_ids.len());
+        for chunk_id in chunk_ids {
+            let hit = stmt
+                .query_row(params![index_id, chunk_id], |row| {
+                    let metadata: Option<serde_json::Value> =
+                        row.get::<_, Option<String>>(1)?.and_then(|m| serde_json::from_str(&m).ok());
+                    let page = row.get::<_, Option<u32>>(2)?.or_else(|| page_from_metadata(metadata.as_ref()));
+                    Ok(SearchHit {
+                        chunk_id: chunk_id.clone(),
+                        text: row.get(0)?,
+                        metadata,
+                        score: 0.0,
+                        page,
+                        start: row.get::<_, Option<i64>>(3)?.map(|o| o as usize),
+                        end: row.get::<_, Option<i64>>(4)?.map(|o| o as usize),
+                    })
+                })
+                .optional()
+                .map_err(|e| format!("Failed to read chunks: {}", e))?;
+            hits.extend(hit);
+        }
+        Ok(hits)
+    }
+
+    /// Top-k chunks by BM25 relevance to the words in `query`; scores are higher-is-better
+    pub fn keyword_search(&self, index_id: &str, query: &str, top_k: usize) -> Result<Vec<SearchHit>, String> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT e.chunk_id, e.text, e.metadata, bm25(embeddings_fts), e.page, e.start_offset, e.end_offset
+                 FROM embeddings_fts JOIN embeddings e ON e.id = embeddings_fts.rowid
+                 WHERE embeddings_fts MATCH ?1 AND e.index_id = ?2
+                 ORDER BY bm25(embeddings_fts)
+                 LIMIT ?3",
+            )
+            .map_err(|e| format!("Failed to prepare keyword search: {}", e))?;
+
+        let hits = stmt
+            .query_map(params![fts_query, index_id, top_k as i64], |row| {
+                let metadata: Option<serde_json::Value> =
+                    row.get::<_, Option<String>>(2)?.and_then(|m| serde_json::from_str(&m).ok());
+                let page = row.get::<_, Option<u32>>(4)?.or_else(|| page_from_metadata(metadata.as_ref()));
+                Ok(SearchHit {
+                    chunk_id: row.get(0)?,
+                    text: row.get(1)?,
+                    metadata,
+                    // bm25() is lower-is-better
+                    score: -row.get::<_, f64>(3)? as f32,
+                    page,
+                    start: row.get::<_, Option<i64>>(5)?.map(|o| o as usize),
+                    end: row.get::<_, Option<i64>>(6)?.map(|o| o as usize),
+                })
+            })
+            .map_err(|e| format!("Keyword search failed: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(hits)
+    }
+
+    /// Top-k chunks by a weighted mix of cosine similarity and BM25
+    ///
+    /// Both candidate lists are scaled to 0..1 before merging; a chunk missing
+    /// from one list scores 0 there. Catches exact terms (error codes, names)
+    /// that embeddings alone rank poorly. Chunks the user rated are then boosted
+    /// or demoted (see `feedback`).
+    pub fn hybrid_search(
+        &self,
+        index_id: &str,
+        query_vector: &[f32],
+        query: &str,
+        top_k: usize,
+    ) -> Result<Vec<SearchHit>, String> {
+        let candidates = top_k.max(HYBRID_CANDIDATES);
+        let vector_hits = self.search(index_id, query_vector, candidates)?;
+        let keyword_hits = self.keyword_search(index_id, query, candidates)?;
+        if keyword_hits.is_empty() {
+            let mut hits = vector_hits;
+            crate::feedback::apply(self, index_id, &mut hits);
+            hits.truncate(top_k);
+            return Ok(hits);
+        }
+
+        let vector_scores = normalize_scores(&vector_hits);
+        let keyword_scores = normalize_scores(&keyword_hits);
+
+        let mut merged: HashMap<String, SearchHit> = HashMap::new();
+        for hit in vector_hits.into_iter().chain(keyword_hits) {
+            merged.entry(hit.chunk_id.clone()).or_insert(hit);
+        }
+        let mut hits: Vec<SearchHit> = merged
+            .into_values()
+            .map(|mut hit| {
+                let vector = vector_scores.get(&hit.chunk_id).copied().unwrap_or(0.0);
+                let keyword = keyword_scores.get(&hit.chunk_id).copied().unwrap_or(0.0);
+                hit.score = (1.0 - KEYWORD_WEIGHT) * vector + KEYWORD_WEIGHT * keyword;
+                hit
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        crate::feedback::apply(self, index_id, &mut hits);
+        hits.truncate(top_k);
+        Ok(hits)
+    }
+}
+
+/// Find the chunks most similar to a query embedding
+///
+/// With `hybrid` and the query text, cosine similarity is mixed with BM25 keyword
+/// scores. With `rerank` and the query text, the top 50 hits are reordered by the
+/// chat model before the best `top_k` are returned.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_similar(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, VectorStore>,
+    index_id: String,
+    query_vector: Vec<f32>,
+    top_k: Option<usize>,
+    query: Option<String>,
+    rerank: Option<bool>,
+    hybrid: Option<bool>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let top_k = top_k.unwrap_or(5);
+    let query = query.filter(|q| !q.trim().is_empty());
+    let rerank = rerank.unwrap_or(false) && query.is_some();
+    let candidates = if rerank { top_k.max(rerank::CANDIDATES) } else { top_k };
+
+    let hits = match query.as_deref().filter(|_| hybrid.unwrap_or(false)) {
+        Some(query) => state.hybrid_search(&index_id, &query_vector, query, candidates)?,
+        None => state.search(&index_id, &query_vector, candidates)?,
+    };
+    match query.filter(|_| rerank) {
+        Some(query) => Ok(rerank::rerank(&app_handle, &query, hits, top_k).await),
+        None => Ok(hits),
+    }
+}
+
+/// Find chunks containing the words of `query`, ranked by BM25
+///
+/// For exact terms (error codes, names, citations) that embeddings match poorly.
+#[tauri::command]
+pub async fn keyword_search(
+    state: tauri::State<'_, VectorStore>,
+    index_id: String,
+    query: String,
+    top_k: Option<usize>,
+) -> Result<Vec<SearchHit>, AppError> {
+    log::info!("Keyword search in index {}: {} chars", index_id, query.len());
+    Ok(state.keyword_search(&index_id, &query, top_k.unwrap_or(5))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fts_query_quotes_every_word() {
+        assert_eq!(fts_query("invoice total"), Some(r#""invoice" OR "total""#.to_string()));
+        assert_eq!(fts_query("  E-1234, "), Some(r#""E-1234""#.to_string()));
+    }
+
+    #[test]
+    fn fts_query_neutralizes_operators() {
+        assert_eq!(
+            fts_query("a\"b NOT c* NEAR(d)"),
+            Some(r#""a""b" OR "NOT" OR "c" OR "NEAR(d""#.to_string())
+        );
+    }
+
+    #[test]
+    fn fts_query_without_words_is_none() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query(" \"* - () "), None);
+    }
+}
@@ -0,0 +1,475 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::document_sources::DocumentSource;
+use crate::error::AppError;
+use crate::vector_schema;
+
+/// Suffix of the staging index chunks are written to while a document is embedded
+const STAGING_SUFFIX: &str = ".staging";
+
+/// Persistent embedding store backed by SQLite (`vectors.db` in the app data dir)
+///
+/// Vectors are stored as little-endian f32 blobs and searched with a flat cosine
+/// scan, which is fast enough for the tens of thousands of chunks a library holds.
+pub struct VectorStore {
+    conn: Mutex<Connection>,
+    /// Chunks stored before keyword search existed still need indexing; see `build_keyword_index`
+    keyword_index_pending: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexInfo {
+    id: String,
+    name: String,
+    dimension: usize,
+    chunk_count: usize,
+    /// Embedding model the chunks were embedded with, when known
+    model: Option<String>,
+    /// Language of the document's text (ISO 639-3), when detected
+    language: Option<String>,
+    /// Chunks hold PII-masked text only; the document was indexed as sensitive
+    masked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingItem {
+    pub chunk_id: String,
+    pub text: String,
+    /// Free-form metadata (page number, section, ...) returned with search hits
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    pub vector: Vec<f32>,
+    /// 1-based page the chunk starts on; read from `metadata` when not set
+    #[serde(default)]
+    pub page: Option<u32>,
+    /// Character offsets of the chunk in its page's text
+    #[serde(default)]
+    pub start: Option<usize>,
+    #[serde(default)]
+    pub end: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub chunk_id: String,
+    pub text: String,
+    pub metadata: Option<serde_json::Value>,
+    pub score: f32,
+    pub page: Option<u32>,
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+}
+
+pub(crate) fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub(crate) fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+pub(crate) fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+/// Cosine similarity, given the precomputed norm of `a`
+pub(crate) fn cosine(a: &[f32], a_norm: f32, b: &[f32]) -> f32 {
+    let b_norm = norm(b);
+    if a_norm == 0.0 || b_norm == 0.0 {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    dot / (a_norm * b_norm)
+}
+
+/// Page number stored in chunk metadata by the indexer, under any of its usual keys
+pub(crate) fn page_from_metadata(metadata: Option<&serde_json::Value>) -> Option<u32> {
+    let metadata = metadata?;
+    ["page", "pageNumber", "page_number"]
+        .iter()
+        .find_map(|key| metadata.get(key).and_then(|v| v.as_u64()))
+        .and_then(|page| u32::try_from(page).ok())
+}
+
+pub(crate) fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Columns read into an `IndexInfo` by `index_info_row`
+const INDEX_INFO_QUERY: &str = "SELECT i.id, i.name, i.dimension,
+        (SELECT COUNT(*) FROM embeddings e WHERE e.index_id = i.id), i.model, i.language, i.masked
+    FROM indexes i";
+
+fn index_info_row(row: &rusqlite::Row) -> rusqlite::Result<IndexInfo> {
+    Ok(IndexInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        dimension: row.get::<_, i64>(2)? as usize,
+        chunk_count: row.get::<_, i64>(3)? as usize,
+        model: row.get(4)?,
+        language: row.get(5)?,
+        masked: row.get(6)?,
+    })
+}
+
+impl IndexInfo {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_count
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn is_masked(&self) -> bool {
+        self.masked
+    }
+
+    /// Error unless the index was built with `model`
+    ///
+    /// Vectors from different embedding models aren't comparable, so a query
+    /// embedded with one model can't search an index built with another. Indexes
+    /// from before the model was recorded are let through; the dimension check
+    /// still catches most mismatches there.
+    pub fn check_model(&self, model: &str) -> Result<(), String> {
+        match self.model() {
+            Some(indexed) if indexed != model => Err(format!(
+                "{} was indexed with the embedding model {} but {} is selected; re-index it or switch the embedding model back",
+                self.name, indexed, model
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl VectorStore {
+    /// Open (or create) the store at `path`
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = crate::storage::open(path).map_err(|e| format!("Failed to open vector store: {}", e))?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .map_err(|e| format!("Failed to configure vector store: {}", e))?;
+        let keyword_index_pending = vector_schema::apply(&conn)?;
+        // Left behind by an indexing run that was interrupted; see `promote`
+        conn.execute("DELETE FROM indexes WHERE id GLOB ?1", params![format!("*{}", STAGING_SUFFIX)])
+            .map_err(|e| format!("Failed to remove staging indexes: {}", e))?;
+
+        log::info!("Vector store opened at {}", path.display());
+        Ok(Self {
+            conn: Mutex::new(conn),
+            keyword_index_pending: AtomicBool::new(keyword_index_pending),
+        })
+    }
+
+    /// Index chunks stored before keyword search existed
+    ///
+    /// A one-off that can take a while on a large store, so it runs after startup
+    /// rather than in `open`; keyword search misses those chunks until then.
+    pub fn build_keyword_index(&self) -> Result<(), String> {
+        if !self.keyword_index_pending.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        log::info!("Building keyword index for existing chunks");
+        self.conn()
+            .execute("INSERT INTO embeddings_fts(embeddings_fts) VALUES ('rebuild')", [])
+            .map_err(|e| format!("Failed to build keyword index: {}", e))?;
+        Ok(())
+    }
+
+    pub fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn index_info(&self, index_id: &str) -> Result<Option<IndexInfo>, String> {
+        self.conn()
+            .query_row(&format!("{} WHERE i.id = ?1", INDEX_INFO_QUERY), params![index_id], index_info_row)
+            .optional()
+            .map_err(|e| format!("Failed to read index: {}", e))
+    }
+
+    /// Every index by name, leaving out the staging indexes of running indexing jobs
+    pub fn list(&self) -> Result<Vec<IndexInfo>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(&format!("{} WHERE i.id NOT GLOB '*{}' ORDER BY i.name", INDEX_INFO_QUERY, STAGING_SUFFIX))
+            .map_err(|e| format!("Failed to list indexes: {}", e))?;
+        let indexes = stmt
+            .query_map([], index_info_row)
+            .map_err(|e| format!("Failed to list indexes: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to list indexes: {}", e))?;
+        Ok(indexes)
+    }
+
+    /// Record the detected language of an index's document
+    pub fn set_language(&self, index_id: &str, language: &str) -> Result<(), String> {
+        self.conn()
+            .execute("UPDATE indexes SET language = ?1 WHERE id = ?2", params![language, index_id])
+            .map_err(|e| format!("Failed to save document language: {}", e))?;
+        Ok(())
+    }
+
+    /// Record whether an index's chunks hold PII-masked text
+    pub fn set_masked(&self, index_id: &str, masked: bool) -> Result<(), String> {
+        self.conn()
+            .execute("UPDATE indexes SET masked = ?1 WHERE id = ?2", params![masked, index_id])
+            .map_err(|e| format!("Failed to update index: {}", e))?;
+        Ok(())
+    }
+
+    /// Create an index, or return the existing one if the dimension and model match
+    pub fn create(&self, index_id: &str, name: &str, dimension: usize, model: Option<&str>) -> Result<IndexInfo, String> {
+        if let Some(existing) = self.index_info(index_id)? {
+            if existing.dimension != dimension {
+                return Err(format!(
+                    "Index {} already exists with dimension {} (requested {})",
+                    index_id, existing.dimension, dimension
+                ));
+            }
+            if let Some(model) = model {
+                existing.check_model(model)?;
+            }
+            return Ok(existing);
+        }
+
+        self.conn()
+            .execute(
+                "INSERT INTO indexes (id, name, dimension, created_at, model) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![index_id, name, dimension as i64, now_secs(), model],
+            )
+            .map_err(|e| format!("Failed to create index: {}", e))?;
+
+        Ok(IndexInfo {
+            id: index_id.to_string(),
+            name: name.to_string(),
+            dimension,
+            chunk_count: 0,
+            model: model.map(str::to_string),
+            language: None,
+            masked: false,
+        })
+    }
+
+    /// Insert or replace embeddings (keyed by chunk id) in one transaction
+    pub fn add(&self, index_id: &str, items: &[EmbeddingItem]) -> Result<usize, String> {
+        let info = self
+            .index_info(index_id)?
+            .ok_or_else(|| format!("Index not found: {}", index_id))?;
+
+        if let Some(bad) = items.iter().find(|item| item.vector.len() != info.dimension) {
+            return Err(format!(
+                "Embedding for chunk {} has dimension {}, index expects {}",
+                bad.chunk_id,
+                bad.vector.len(),
+                info.dimension
+            ));
+        }
+
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT OR REPLACE INTO embeddings
+                        (index_id, chunk_id, text, metadata, vector, page, start_offset, end_offset)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                )
+                .map_err(|e| format!("Failed to prepare insert: {}", e))?;
+
+            for item in items {
+                let metadata = item.metadata.as_ref().map(|m| m.to_string());
+                let page = item.page.or_else(|| page_from_metadata(item.metadata.as_ref()));
+                stmt.execute(params![
+                    index_id,
+                    item.chunk_id,
+                    item.text,
+                    metadata,
+                    encode_vector(&item.vector),
+                    page,
+                    item.start.map(|o| o as i64),
+                    item.end.map(|o| o as i64)
+                ])
+                .map_err(|e| format!("Failed to store embedding: {}", e))?;
+            }
+        }
+        tx.commit().map_err(|e| format!("Failed to commit embeddings: {}", e))?;
+
+        Ok(items.len())
+    }
+
+    /// Delete every chunk of an index, keeping the index itself, and set the
+    /// dimension and model its new chunks will be embedded with
+    pub fn reset(&self, index_id: &str, dimension: usize, model: &str) -> Result<(), String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+        tx.execute("DELETE FROM embeddings WHERE index_id = ?1", params![index_id])
+            .map_err(|e| format!("Failed to clear index: {}", e))?;
+        tx.execute(
+            "UPDATE indexes SET dimension = ?2, model = ?3 WHERE id = ?1",
+            params![index_id, dimension as i64, model],
+        )
+        .map_err(|e| format!("Failed to update index: {}", e))?;
+        tx.commit().map_err(|e| format!("Failed to commit index reset: {}", e))
+    }
+
+    /// Id of the staging index for `index_id`
+    pub fn staging_id(index_id: &str) -> String {
+        format!("{}{}", index_id, STAGING_SUFFIX)
+    }
+
+    /// Move every chunk of the staging index `from` into `to`, replacing its chunks
+    ///
+    /// Indexing writes chunks to a staging index in batches and moves them over in
+    /// one transaction at the end, so a failed or interrupted run never leaves a
+    /// partial index behind.
+    pub fn promote(&self, from: &str, to: &str) -> Result<(), String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+        tx.execute("DELETE FROM embeddings WHERE index_id = ?1", params![to])
+            .map_err(|e| format!("Failed to clear index: {}", e))?;
+        tx.execute("UPDATE embeddings SET index_id = ?2 WHERE index_id = ?1", params![from, to])
+            .map_err(|e| format!("Failed to move chunks: {}", e))?;
+        tx.execute("DELETE FROM indexes WHERE id = ?1", params![from])
+            .map_err(|e| format!("Failed to delete staging index: {}", e))?;
+        tx.commit().map_err(|e| format!("Failed to commit index: {}", e))
+    }
+
+    /// Delete an index and all its embeddings
+    pub fn delete(&self, index_id: &str) -> Result<(), String> {
+        self.conn()
+            .execute("DELETE FROM indexes WHERE id = ?1", params![index_id])
+            .map_err(|e| format!("Failed to delete index: {}", e))?;
+        Ok(())
+    }
+
+    /// Delete chunks whose index no longer exists
+    ///
+    /// Foreign keys cascade on delete, but databases written before they were
+    /// enabled, or by interrupted imports, can still hold such chunks.
+    pub fn remove_orphans(&self) -> Result<usize, String> {
+        let conn = self.conn();
+        let removed = conn
+            .execute("DELETE FROM embeddings WHERE index_id NOT IN (SELECT id FROM indexes)", [])
+            .map_err(|e| format!("Failed to remove orphaned chunks: {}", e))?;
+        conn.execute("DELETE FROM document_sources WHERE index_id NOT IN (SELECT id FROM indexes)", [])
+            .map_err(|e| format!("Failed to remove orphaned sources: {}", e))?;
+        if removed > 0 {
+            log::info!("Removed {} orphaned chunks", removed);
+        }
+        Ok(removed)
+    }
+
+
+}
+
+/// Create a vector index for a document (idempotent for the same dimension)
+#[tauri::command]
+pub async fn create_index(
+    state: tauri::State<'_, VectorStore>,
+    index_id: String,
+    name: String,
+    dimension: usize,
+    source_path: Option<String>,
+    model: Option<String>,
+) -> Result<IndexInfo, AppError> {
+    log::info!("Creating vector index {} ({} dims)", index_id, dimension);
+    let info = state.create(&index_id, &name, dimension, model.as_deref())?;
+
+    // Lets `reindex_document` notice when the file changes
+    if let Some(path) = source_path {
+        let hash_path = path.clone();
+        let hash = tauri::async_runtime::spawn_blocking(move || crate::embedding_cache::hash_file(&hash_path))
+            .await
+            .map_err(|e| format!("Hashing task failed: {}", e))??;
+        let mtime = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        state.set_source(&DocumentSource {
+            index_id,
+            path,
+            mtime,
+            hash,
+        })?;
+    }
+    Ok(info)
+}
+
+/// Store chunk embeddings in an index
+#[tauri::command]
+pub async fn add_embeddings(
+    state: tauri::State<'_, VectorStore>,
+    index_id: String,
+    items: Vec<EmbeddingItem>,
+) -> Result<usize, AppError> {
+    log::info!("Adding {} embeddings to index {}", items.len(), index_id);
+    Ok(state.add(&index_id, &items)?)
+}
+
+/// Delete an index and all its embeddings
+#[tauri::command]
+pub async fn delete_index(state: tauri::State<'_, VectorStore>, index_id: String) -> Result<(), AppError> {
+    log::info!("Deleting vector index {}", index_id);
+    Ok(state.delete(&index_id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(chunk_id: &str, text: &str) -> EmbeddingItem {
+        EmbeddingItem {
+            chunk_id: chunk_id.to_string(),
+            text: text.to_string(),
+            metadata: None,
+            vector: vec![1.0, 0.0],
+            page: Some(1),
+            start: None,
+            end: None,
+        }
+    }
+
+    #[test]
+    fn promote_replaces_chunks_and_drops_staging() {
+        let store = VectorStore::open(Path::new(":memory:")).unwrap();
+        store.create("doc", "Doc", 2, None).unwrap();
+        store.add("doc", &[item("doc-0", "old text"), item("doc-1", "stale")]).unwrap();
+
+        let staging = VectorStore::staging_id("doc");
+        store.create(&staging, "Doc", 2, None).unwrap();
+        store.add(&staging, &[item("doc-0", "new text")]).unwrap();
+        assert_eq!(store.chunk_texts("doc").unwrap(), vec!["old text", "stale"]);
+
+        store.promote(&staging, "doc").unwrap();
+        assert_eq!(store.chunk_texts("doc").unwrap(), vec!["new text"]);
+        assert!(store.index_info(&staging).unwrap().is_none());
+        assert_eq!(store.keyword_search("doc", "new", 5).unwrap().len(), 1);
+    }
+}
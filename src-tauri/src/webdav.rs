@@ -0,0 +1,238 @@
+use futures::StreamExt;
+use regex::Regex;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::http::{self, RetryExt};
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getcontentlength/><d:getlastmodified/><d:getetag/></d:prop></d:propfind>"#;
+
+/// Basic auth for a WebDAV server; the password comes from the keychain
+pub struct Credentials {
+    pub username: String,
+    pub password: Option<String>,
+}
+
+/// A file found below a collection
+#[derive(Debug, Clone, PartialEq)]
+pub struct DavFile {
+    /// Relative to the collection, decoded, with `/` separators
+    pub path: String,
+    pub size: Option<i64>,
+    pub modified: Option<String>,
+    pub etag: Option<String>,
+}
+
+/// One `<response>` of a multistatus reply
+#[derive(Debug, PartialEq)]
+struct DavItem {
+    href: String,
+    collection: bool,
+    size: Option<i64>,
+    modified: Option<String>,
+    etag: Option<String>,
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Text of the first `<name>` element in `xml`, whatever its namespace prefix
+fn element(xml: &str, name: &str) -> Option<String> {
+    let pattern = format!(r"(?s)<(?:[\w.-]+:)?{0}\b[^>]*?(?:/>|>(.*?)</(?:[\w.-]+:)?{0}>)", name);
+    let captures = Regex::new(&pattern).ok()?.captures(xml)?;
+    Some(unescape_xml(captures.get(1).map_or("", |m| m.as_str()).trim()))
+}
+
+/// Read a PROPFIND reply without a full XML parser; servers differ only in namespace prefixes
+fn parse_multistatus(xml: &str) -> Vec<DavItem> {
+    static RESPONSE: OnceLock<Regex> = OnceLock::new();
+    let response = RESPONSE.get_or_init(|| {
+        Regex::new(r"(?s)<(?:[\w.-]+:)?response\b[^>]*>(.*?)</(?:[\w.-]+:)?response>").expect("valid regex")
+    });
+    response
+        .captures_iter(xml)
+        .filter_map(|captures| {
+            let block = captures.get(1)?.as_str();
+            Some(DavItem {
+                href: element(block, "href")?,
+                collection: element(block, "resourcetype").is_some_and(|t| t.contains("collection")),
+                size: element(block, "getcontentlength").and_then(|s| s.parse().ok()),
+                modified: element(block, "getlastmodified").filter(|m| !m.is_empty()),
+                etag: element(block, "getetag").filter(|e| !e.is_empty()),
+            })
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The URL of a collection, with the trailing slash relative paths resolve against
+pub fn collection_url(location: &str) -> Result<reqwest::Url, String> {
+    let location = if location.ends_with('/') { location.to_string() } else { format!("{}/", location) };
+    let url = reqwest::Url::parse(&location).map_err(|e| format!("Invalid WebDAV URL {}: {}", location, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("WebDAV URLs start with http:// or https://, not {}", location));
+    }
+    Ok(url)
+}
+
+/// URL of a file given by its path relative to the collection
+pub fn file_url(base: &reqwest::Url, path: &str) -> Result<reqwest::Url, String> {
+    let mut url = base.clone();
+    url.path_segments_mut()
+        .map_err(|_| format!("{} can't hold files", base))?
+        .pop_if_empty()
+        .extend(path.split('/'));
+    Ok(url)
+}
+
+fn authorize(request: reqwest::RequestBuilder, credentials: Option<&Credentials>) -> reqwest::RequestBuilder {
+    match credentials {
+        Some(c) => request.basic_auth(&c.username, c.password.as_ref()),
+        None => request,
+    }
+}
+
+async fn propfind(url: &reqwest::Url, credentials: Option<&Credentials>) -> Result<String, String> {
+    let method = reqwest::Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+    let request = http::request(method, url.as_str())?
+        .header("Depth", "1")
+        .header(reqwest::header::CONTENT_TYPE, "application/xml")
+        .body(PROPFIND_BODY);
+    let response = authorize(request, credentials)
+        .send_with_retry()
+        .await
+        .map_err(|e| format!("Failed to list {}: {}", url, e))?;
+    if response.status().as_u16() != 207 {
+        return Err(format!("Failed to list {}: the server answered {}", url, response.status()));
+    }
+    response.text().await.map_err(|e| format!("Failed to list {}: {}", url, e))
+}
+
+/// Files below a collection for which `keep` holds, one PROPFIND per folder
+///
+/// Many servers refuse `Depth: infinity`, so folders are walked one level at a
+/// time, at most `max_depth` levels down.
+pub async fn list(
+    base: &reqwest::Url,
+    credentials: Option<&Credentials>,
+    max_depth: usize,
+    keep: impl Fn(&str) -> bool,
+) -> Result<Vec<DavFile>, String> {
+    let mut files = Vec::new();
+    let mut folders = vec![(base.clone(), 0)];
+    while let Some((folder, depth)) = folders.pop() {
+        for item in parse_multistatus(&propfind(&folder, credentials).await?) {
+            let Ok(url) = folder.join(&item.href) else { continue };
+            // The folder lists itself too
+            if url.path().trim_end_matches('/') == folder.path().trim_end_matches('/') {
+                continue;
+            }
+            let Some(relative) = url.path().strip_prefix(base.path()) else { continue };
+            let path = percent_decode(relative.trim_end_matches('/'));
+            if item.collection {
+                if depth < max_depth {
+                    folders.push((collection_url(url.as_str())?, depth + 1));
+                }
+            } else if keep(&path) {
+                files.push(DavFile {
+                    path,
+                    size: item.size,
+                    modified: item.modified,
+                    etag: item.etag,
+                });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Download a file to `dest`, replacing an earlier copy only once complete
+pub async fn download(url: &reqwest::Url, credentials: Option<&Credentials>, dest: &Path) -> Result<(), String> {
+    let response = authorize(http::get(url.as_str())?, credentials)
+        .send_with_retry()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {}: the server answered {}", url, response.status()));
+    }
+    let mut part = dest.as_os_str().to_os_string();
+    part.push(".part");
+    let part = std::path::PathBuf::from(part);
+    let mut file = std::fs::File::create(&part).map_err(|e| format!("Failed to create {}: {}", part.display(), e))?;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to download {}: {}", url, e))?;
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+    }
+    drop(file);
+    std::fs::rename(&part, dest).map_err(|e| format!("Failed to save {}: {}", dest.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTISTATUS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/dav/papers/</d:href><d:propstat><d:prop>
+    <d:resourcetype><d:collection/></d:resourcetype><d:getlastmodified>Mon, 12 Oct 2026 09:00:00 GMT</d:getlastmodified>
+  </d:prop></d:propstat></d:response>
+  <d:response><d:href>/dav/papers/Q3%20report%20&amp;%20notes.pdf</d:href><d:propstat><d:prop>
+    <d:resourcetype/><d:getcontentlength>52431</d:getcontentlength>
+    <d:getlastmodified>Tue, 13 Oct 2026 10:30:00 GMT</d:getlastmodified><d:getetag>"5f2a-1"</d:getetag>
+  </d:prop></d:propstat></d:response>
+  <D:response xmlns:D="DAV:"><D:href>/dav/papers/archive/</D:href><D:propstat><D:prop>
+    <D:resourcetype><D:collection/></D:resourcetype>
+  </D:prop></D:propstat></D:response>
+</d:multistatus>"#;
+
+    #[test]
+    fn multistatus_replies_are_read_whatever_the_prefix() {
+        let items = parse_multistatus(MULTISTATUS);
+        assert_eq!(items.len(), 3);
+        assert!(items[0].collection);
+        assert_eq!(items[1].href, "/dav/papers/Q3%20report%20&%20notes.pdf");
+        assert!(!items[1].collection);
+        assert_eq!(items[1].size, Some(52431));
+        assert_eq!(items[1].etag.as_deref(), Some("\"5f2a-1\""));
+        assert_eq!(items[2].href, "/dav/papers/archive/");
+        assert!(items[2].collection);
+        assert_eq!(percent_decode("Q3%20report%20&%20notes.pdf"), "Q3 report & notes.pdf");
+    }
+
+    #[test]
+    fn file_urls_are_encoded_below_the_collection() {
+        let base = collection_url("https://nas.local/dav/papers").unwrap();
+        let url = file_url(&base, "2026/Q3 report.pdf").unwrap();
+        assert_eq!(url.as_str(), "https://nas.local/dav/papers/2026/Q3%20report.pdf");
+        assert!(collection_url("ftp://nas.local/papers").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+use crate::error::AppError;
+use crate::vectorstore::now_secs;
+
+/// Per-document zoom levels kept; the least recently used are dropped beyond this
+const MAX_ZOOM_ENTRIES: usize = 500;
+/// Smallest window we restore, so a bad file can't leave the window unusable
+const MIN_WIDTH: u32 = 400;
+const MIN_HEIGHT: u32 = 300;
+
+/// Serializes read-modify-write cycles on window_state.json
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ZoomEntry {
+    zoom: f64,
+    updated_at: i64,
+}
+
+/// Window geometry and PDF viewer zoom, stored in `window_state.json` next to `settings.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WindowState {
+    #[serde(default)]
+    geometry: Option<Geometry>,
+    /// Keyed by document id
+    #[serde(default)]
+    zoom: HashMap<String, ZoomEntry>,
+}
+
+fn state_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::portable::app_data_dir(app_handle)
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join("window_state.json"))
+}
+
+fn read_state(app_handle: &tauri::AppHandle) -> WindowState {
+    let Ok(path) = state_path(app_handle) else {
+        return WindowState::default();
+    };
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            log::warn!("Ignoring invalid window state: {}", e);
+            WindowState::default()
+        }),
+        Err(_) => WindowState::default(),
+    }
+}
+
+fn write_state(app_handle: &tauri::AppHandle, state: &WindowState) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(state).map_err(|e| format!("Failed to serialize window state: {}", e))?;
+    fs::write(state_path(app_handle)?, json).map_err(|e| format!("Failed to write window state: {}", e))
+}
+
+/// Whether the window's top-left area is on one of the connected monitors
+///
+/// A monitor that was unplugged since the last run would otherwise leave the
+/// window off-screen.
+fn is_visible(window: &WebviewWindow, geometry: &Geometry) -> bool {
+    let monitors = window.available_monitors().unwrap_or_default();
+    // Probe a point inside the title bar rather than the exact corner
+    let (x, y) = (geometry.x + 50, geometry.y + 20);
+    monitors.iter().any(|m| {
+        let (position, size) = (m.position(), m.size());
+        x >= position.x
+            && y >= position.y
+            && x < position.x + size.width as i32
+            && y < position.y + size.height as i32
+    })
+}
+
+/// Save the window's size, position and maximized state; called when it closes
+///
+/// While maximized or minimized the previous normal geometry is kept, so
+/// un-maximizing after a restart returns to the size the user chose.
+pub fn save(window: &WebviewWindow) {
+    let app_handle = window.app_handle();
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = read_state(app_handle);
+
+    let maximized = window.is_maximized().unwrap_or(false);
+    let minimized = window.is_minimized().unwrap_or(false);
+    let previous = state.geometry.take();
+
+    state.geometry = if maximized || minimized {
+        previous.map(|g| Geometry { maximized, ..g })
+    } else {
+        match (window.outer_position(), window.outer_size()) {
+            (Ok(position), Ok(size)) => Some(Geometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized: false,
+            }),
+            _ => previous,
+        }
+    };
+
+    match write_state(app_handle, &state) {
+        Ok(()) => log::info!("Saved window state: {:?}", state.geometry),
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+/// Restore the geometry saved by `save`; called from `setup()`
+pub fn restore(window: &WebviewWindow) {
+    let Some(geometry) = read_state(window.app_handle()).geometry else {
+        return;
+    };
+    log::info!("Restoring window state: {:?}", geometry);
+
+    let size = PhysicalSize::new(geometry.width.max(MIN_WIDTH), geometry.height.max(MIN_HEIGHT));
+    if let Err(e) = window.set_size(size) {
+        log::warn!("Failed to restore window size: {}", e);
+    }
+    if is_visible(window, &geometry) {
+        if let Err(e) = window.set_position(PhysicalPosition::new(geometry.x, geometry.y)) {
+            log::warn!("Failed to restore window position: {}", e);
+        }
+    } else {
+        log::info!("Saved window position is off-screen, keeping the default");
+    }
+    if geometry.maximized {
+        if let Err(e) = window.maximize() {
+            log::warn!("Failed to maximize window: {}", e);
+        }
+    }
+}
+
+/// Saved PDF viewer zoom for a document, if any
+#[tauri::command]
+pub async fn get_document_zoom(app_handle: tauri::AppHandle, document_id: String) -> Result<Option<f64>, AppError> {
+    Ok(read_state(&app_handle).zoom.get(&document_id).map(|entry| entry.zoom))
+}
+
+/// Remember the PDF viewer zoom for a document
+#[tauri::command]
+pub async fn set_document_zoom(app_handle: tauri::AppHandle, document_id: String, zoom: f64) -> Result<(), AppError> {
+    if !zoom.is_finite() || zoom <= 0.0 {
+        return Err(AppError::Other(format!("Invalid zoom: {}", zoom)));
+    }
+
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = read_state(&app_handle);
+    state.zoom.insert(
+        document_id,
+        ZoomEntry {
+            zoom,
+            updated_at: now_secs(),
+        },
+    );
+    if state.zoom.len() > MAX_ZOOM_ENTRIES {
+        let mut by_age: Vec<(String, i64)> = state.zoom.iter().map(|(id, e)| (id.clone(), e.updated_at)).collect();
+        by_age.sort_by_key(|(_, updated_at)| *updated_at);
+        for (id, _) in by_age.into_iter().take(state.zoom.len() - MAX_ZOOM_ENTRIES) {
+            state.zoom.remove(&id);
+        }
+    }
+    Ok(write_state(&app_handle, &state)?)
+}
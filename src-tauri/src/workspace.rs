@@ -0,0 +1,334 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::embedding_model;
+use crate::error::AppError;
+use crate::folder_watch::FolderWatches;
+use crate::ollama_chat;
+use crate::scheduler::Lane;
+use crate::settings;
+use crate::vectorstore::{now_secs, VectorStore};
+use crate::workspace_settings;
+
+/// Workspace tables live in `vectors.db` next to the indexes they group
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS workspaces (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS workspace_documents (
+        workspace_id TEXT NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+        index_id TEXT NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        path TEXT,
+        added_at INTEGER NOT NULL,
+        PRIMARY KEY (workspace_id, index_id)
+    );
+";
+
+/// Hits returned when the caller doesn't ask for a specific number
+pub(crate) const DEFAULT_TOP_K: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceDocument {
+    index_id: String,
+    name: String,
+    path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Workspace {
+    id: String,
+    name: String,
+    documents: Vec<WorkspaceDocument>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceHit {
+    document_id: String,
+    document_name: String,
+    page: Option<u32>,
+    chunk_id: String,
+    text: String,
+    score: f32,
+}
+
+/// Create the workspace tables in the vector store database
+pub fn init(store: &VectorStore) -> Result<(), String> {
+    store
+        .conn()
+        .execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to initialize workspaces: {}", e))
+}
+
+fn new_workspace_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("ws_{:x}", nanos)
+}
+
+fn documents(store: &VectorStore, workspace_id: &str) -> Result<Vec<WorkspaceDocument>, String> {
+    let conn = store.conn();
+    let mut stmt = conn
+        .prepare("SELECT index_id, name, path FROM workspace_documents WHERE workspace_id = ?1 ORDER BY added_at")
+        .map_err(|e| format!("Failed to read workspace: {}", e))?;
+    let docs = stmt
+        .query_map(params![workspace_id], |row| {
+            Ok(WorkspaceDocument {
+                index_id: row.get(0)?,
+                name: row.get(1)?,
+                path: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read workspace: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read workspace: {}", e))?;
+    Ok(docs)
+}
+
+fn workspace(store: &VectorStore, workspace_id: &str) -> Result<Workspace, String> {
+    let name: String = store
+        .conn()
+        .query_row("SELECT name FROM workspaces WHERE id = ?1", params![workspace_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to read workspace: {}", e))?
+        .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
+
+    Ok(Workspace {
+        id: workspace_id.to_string(),
+        name,
+        documents: documents(store, workspace_id)?,
+    })
+}
+
+#[tauri::command]
+pub async fn create_workspace(store: tauri::State<'_, VectorStore>, name: String) -> Result<Workspace, AppError> {
+    let id = new_workspace_id();
+    log::info!("Creating workspace {} ({})", name, id);
+    store
+        .conn()
+        .execute(
+            "INSERT INTO workspaces (id, name, created_at) VALUES (?1, ?2, ?3)",
+            params![id, name, now_secs()],
+        )
+        .map_err(|e| format!("Failed to create workspace: {}", e))?;
+    Ok(Workspace {
+        id,
+        name,
+        documents: Vec::new(),
+    })
+}
+
+#[tauri::command]
+pub async fn list_workspaces(store: tauri::State<'_, VectorStore>) -> Result<Vec<Workspace>, AppError> {
+    let ids: Vec<String> = {
+        let conn = store.conn();
+        let mut stmt = conn
+            .prepare("SELECT id FROM workspaces ORDER BY created_at")
+            .map_err(|e| format!("Failed to list workspaces: {}", e))?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to list workspaces: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to list workspaces: {}", e))?;
+        ids
+    };
+    Ok(ids.iter().map(|id| workspace(&store, id)).collect::<Result<_, _>>()?)
+}
+
+/// Add an indexed document (its vector index id) to a workspace
+#[tauri::command]
+pub async fn add_to_workspace(
+    store: tauri::State<'_, VectorStore>,
+    workspace_id: String,
+    index_id: String,
+    name: String,
+    path: Option<String>,
+) -> Result<Workspace, AppError> {
+    log::info!("Adding {} to workspace {}", index_id, workspace_id);
+    if store.index_info(&index_id)?.is_none() {
+        return Err(AppError::Other(format!("Document {} has not been indexed", index_id)));
+    }
+    add_document(&store, &workspace_id, &index_id, &name, path.as_deref())?;
+    Ok(workspace(&store, &workspace_id)?)
+}
+
+pub(crate) fn add_document(
+    store: &VectorStore,
+    workspace_id: &str,
+    index_id: &str,
+    name: &str,
+    path: Option<&str>,
+) -> Result<(), String> {
+    store
+        .conn()
+        .execute(
+            "INSERT OR REPLACE INTO workspace_documents (workspace_id, index_id, name, path, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![workspace_id, index_id, name, path, now_secs()],
+        )
+        .map_err(|e| format!("Failed to add document to workspace: {}", e))?;
+    Ok(())
+}
+
+pub(crate) fn exists(store: &VectorStore, workspace_id: &str) -> Result<bool, String> {
+    store
+        .conn()
+        .query_row("SELECT 1 FROM workspaces WHERE id = ?1", params![workspace_id], |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| format!("Failed to read workspace: {}", e))
+}
+
+pub(crate) fn contains(store: &VectorStore, workspace_id: &str, index_id: &str) -> Result<bool, String> {
+    store
+        .conn()
+        .query_row(
+            "SELECT 1 FROM workspace_documents WHERE workspace_id = ?1 AND index_id = ?2",
+            params![workspace_id, index_id],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+        .map_err(|e| format!("Failed to read workspace: {}", e))
+}
+
+#[tauri::command]
+pub async fn remove_from_workspace(
+    store: tauri::State<'_, VectorStore>,
+    workspace_id: String,
+    index_id: String,
+) -> Result<Workspace, AppError> {
+    log::info!("Removing {} from workspace {}", index_id, workspace_id);
+    store
+        .conn()
+        .execute(
+            "DELETE FROM workspace_documents WHERE workspace_id = ?1 AND index_id = ?2",
+            params![workspace_id, index_id],
+        )
+        .map_err(|e| format!("Failed to remove document from workspace: {}", e))?;
+    Ok(workspace(&store, &workspace_id)?)
+}
+
+/// Delete a workspace; the documents' indexes are kept
+#[tauri::command]
+pub async fn delete_workspace(
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    workspace_id: String,
+) -> Result<(), AppError> {
+    log::info!("Deleting workspace {}", workspace_id);
+    store
+        .conn()
+        .execute("DELETE FROM workspaces WHERE id = ?1", params![workspace_id])
+        .map_err(|e| format!("Failed to delete workspace: {}", e))?;
+    if let Err(e) = workspace_settings::remove_overrides(&app_handle, &workspace_id) {
+        log::warn!("Failed to remove settings of workspace {}: {}", workspace_id, e);
+    }
+    // The folders' rows went with the workspace; stop watching them too
+    app_handle.state::<FolderWatches>().stop_workspace(&workspace_id);
+    Ok(())
+}
+
+/// Search every document in a workspace and merge the hits by score
+#[tauri::command]
+pub async fn search_workspace(
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, VectorStore>,
+    workspace_id: String,
+    query: String,
+    top_k: Option<usize>,
+) -> Result<Vec<WorkspaceHit>, AppError> {
+    let top_k = top_k.unwrap_or(DEFAULT_TOP_K);
+    let docs: Vec<(String, String)> = workspace(&store, &workspace_id)?
+        .documents
+        .into_iter()
+        .map(|doc| (doc.index_id, doc.name))
+        .collect();
+    log::info!("Searching workspace {} ({} documents)", workspace_id, docs.len());
+    Ok(search_documents(&app_handle, &store, &docs, query, top_k).await?)
+}
+
+/// Search one indexed document, or every one when `document_id` is unset
+pub(crate) async fn search_library(
+    app_handle: &tauri::AppHandle,
+    store: &VectorStore,
+    document_id: Option<&str>,
+    query: String,
+    top_k: usize,
+) -> Result<Vec<WorkspaceHit>, String> {
+    let docs: Vec<(String, String)> = match document_id {
+        Some(id) => {
+            let info = store.index_info(id)?.ok_or_else(|| format!("No document {}", id))?;
+            vec![(id.to_string(), info.name().to_string())]
+        }
+        None => store
+            .list()?
+            .iter()
+            .map(|info| (info.id().to_string(), info.name().to_string()))
+            .collect(),
+    };
+    search_documents(app_handle, store, &docs, query, top_k).await
+}
+
+/// Search several indexed documents, given as `(index_id, name)`, and merge the hits by score
+///
+/// Documents indexed with another embedding model or dimension are skipped.
+pub(crate) async fn search_documents(
+    app_handle: &tauri::AppHandle,
+    store: &VectorStore,
+    docs: &[(String, String)],
+    query: String,
+    top_k: usize,
+) -> Result<Vec<WorkspaceHit>, String> {
+    let embedding_model = embedding_model::from_settings(&settings::read_settings(app_handle));
+    let embedding = ollama_chat::embed_in(Lane::Interactive, embedding_model.clone(), query, app_handle).await?;
+    let query: Vec<f32> = embedding.iter().map(|&v| v as f32).collect();
+
+    let mut hits = Vec::new();
+    for (index_id, name) in docs {
+        match store.index_info(index_id)? {
+            Some(info) if info.dimension() == query.len() && info.check_model(&embedding_model).is_ok() => {}
+            Some(info) if info.dimension() == query.len() => {
+                log::warn!(
+                    "Skipping {}: indexed with {}, query uses {}",
+                    name,
+                    info.model().unwrap_or("an unknown model"),
+                    embedding_model
+                );
+                continue;
+            }
+            Some(info) => {
+                log::warn!(
+                    "Skipping {}: indexed with {} dimensions, query has {}",
+                    name,
+                    info.dimension(),
+                    query.len()
+                );
+                continue;
+            }
+            None => continue,
+        }
+
+        for hit in store.search(index_id, &query, top_k)? {
+            hits.push(WorkspaceHit {
+                document_id: index_id.clone(),
+                document_name: name.clone(),
+                page: hit.page,
+                chunk_id: hit.chunk_id,
+                text: hit.text,
+                score: hit.score,
+            });
+        }
+    }
+
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    hits.truncate(top_k);
+    Ok(hits)
+}
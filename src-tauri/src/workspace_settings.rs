@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::settings::{backup_path, get_settings_path, write_atomic, AppSettings};
+
+/// Settings a workspace or document can change from the app-wide ones
+///
+/// Unset fields keep the app-wide value. Generation options are merged field
+/// by field, so overriding `seed` keeps the global `keep_alive`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SettingsOverrides {
+    pub ollama_model: Option<String>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    /// Replaces the app-wide `system_prompt`
+    pub system_prompt: Option<String>,
+    pub rewrite_queries: Option<bool>,
+    pub strict_grounding: Option<bool>,
+    pub generation: Option<crate::backend::GenerationOptions>,
+}
+
+impl SettingsOverrides {
+    fn is_empty(&self) -> bool {
+        self.ollama_model.is_none()
+            && self.temperature.is_none()
+            && self.top_p.is_none()
+            && self.system_prompt.is_none()
+            && self.rewrite_queries.is_none()
+            && self.strict_grounding.is_none()
+            && self.generation.is_none()
+    }
+
+    fn apply(self, settings: &mut AppSettings) {
+        if let Some(model) = self.ollama_model.filter(|m| !m.trim().is_empty()) {
+            settings.ollama_model = model;
+        }
+        if let Some(temperature) = self.temperature {
+            settings.temperature = temperature;
+        }
+        if let Some(top_p) = self.top_p {
+            settings.top_p = top_p;
+        }
+        if let Some(system_prompt) = self.system_prompt {
+            settings.system_prompt = system_prompt;
+        }
+        if let Some(rewrite_queries) = self.rewrite_queries {
+            settings.rewrite_queries = rewrite_queries;
+        }
+        if let Some(strict_grounding) = self.strict_grounding {
+            settings.strict_grounding = strict_grounding;
+        }
+        if let Some(generation) = self.generation {
+            settings.generation = generation.or(&settings.generation);
+        }
+    }
+}
+
+/// Overrides by workspace id or document (index) id, kept next to settings.json
+fn overrides_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    Ok(get_settings_path(app_handle)?.with_file_name("workspace_settings.json"))
+}
+
+fn read_overrides(app_handle: &tauri::AppHandle) -> HashMap<String, SettingsOverrides> {
+    let Ok(path) = overrides_path(app_handle) else {
+        return HashMap::new();
+    };
+    let parse = |path: &Path| {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+    };
+    // The next save replaces a corrupt file; the backup keeps the overrides until then
+    parse(&path).or_else(|| parse(&backup_path(&path))).unwrap_or_default()
+}
+
+/// Apply the overrides of a workspace and then of a document, the more specific one winning
+pub(crate) fn merge_overrides(
+    app_handle: &tauri::AppHandle,
+    mut settings: AppSettings,
+    workspace_id: Option<&str>,
+    document_id: Option<&str>,
+) -> AppSettings {
+    if workspace_id.is_none() && document_id.is_none() {
+        return settings;
+    }
+    let mut overrides = read_overrides(app_handle);
+    for id in [workspace_id, document_id].into_iter().flatten() {
+        if let Some(scoped) = overrides.remove(id) {
+            scoped.apply(&mut settings);
+        }
+    }
+    settings
+}
+
+/// Drop the overrides of a deleted workspace
+pub fn remove_overrides(app_handle: &tauri::AppHandle, id: &str) -> Result<(), AppError> {
+    let mut all = read_overrides(app_handle);
+    if all.remove(id).is_none() {
+        return Ok(());
+    }
+    write_overrides(app_handle, &all)
+}
+
+fn write_overrides(app_handle: &tauri::AppHandle, all: &HashMap<String, SettingsOverrides>) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(all)
+        .map_err(|e| AppError::Parse(format!("Failed to serialize workspace settings: {}", e)))?;
+    write_atomic(&overrides_path(app_handle)?, &json)
+}
+
+/// Override settings for one workspace or document
+///
+/// `workspace_id` is a workspace id or a document's index id; document overrides
+/// apply on top of the workspace's. Empty or null `overrides` removes them.
+#[tauri::command]
+pub async fn save_workspace_settings(
+    workspace_id: String,
+    overrides: Option<SettingsOverrides>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    log::info!("Saving settings overrides for {}", workspace_id);
+
+    let mut all = read_overrides(&app_handle);
+    match overrides.filter(|o| !o.is_empty()) {
+        Some(overrides) => {
+            all.insert(workspace_id, overrides);
+        }
+        None => {
+            all.remove(&workspace_id);
+        }
+    }
+    write_overrides(&app_handle, &all)
+}
+
+/// Overrides saved for a workspace or document, if any
+#[tauri::command]
+pub async fn load_workspace_settings(
+    workspace_id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<SettingsOverrides>, AppError> {
+    log::info!("Loading settings overrides for {}", workspace_id);
+    Ok(read_overrides(&app_handle).remove(&workspace_id))
+}
@@ -6,6 +6,7 @@ import { Download, CheckCircle2, XCircle, Loader2, Terminal, Copy, ExternalLink
 import { ollamaInstaller } from '@/lib/services/ollama-installer';
 import type { InstallationStatus } from '@/lib/services/ollama-installer';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '@/lib/tauri/commands';
 import { listen } from '@tauri-apps/api/event';
 
 interface InstallationModalProps {
@@ -74,8 +75,8 @@ export function InstallationModal({ isOpen, onClose, onInstalled }: Installation
   const handleDownload = async () => {
     if (!status) return;
 
-    // Windows: Use automatic ZIP installation
-    if (status.platform === 'windows') {
+    // Windows and Linux: managed install (ZIP on Windows, tarball on Linux)
+    if (status.platform === 'windows' || status.platform === 'linux') {
       setIsInstalling(true);
       setInstallError('');
       setDownloadProgress(0);
@@ -84,7 +85,7 @@ export function InstallationModal({ isOpen, onClose, onInstalled }: Installation
 
       try {
         // Detect AMD GPU
-        const isAMD = await ollamaInstaller.detectAMDGpu();
+        const isAMD = status.platform === 'windows' && await ollamaInstaller.detectAMDGpu();
         const version = isAMD ? 'AMD version (359MB)' : 'standard version (1.9GB)';
         setInstallStatus(`Downloading ${version}...`);
 
@@ -108,7 +109,7 @@ export function InstallationModal({ isOpen, onClose, onInstalled }: Installation
         }, 1000);
       } catch (error: any) {
         console.error('Installation failed:', error);
-        setInstallError(error?.toString() || 'Installation failed');
+        setInstallError(errorMessage(error) || 'Installation failed');
         setIsInstalling(false);
       }
     } else {
@@ -0,0 +1,256 @@
+/**
+ * Tauri Commands - Chat, prompt templates, answer feedback, speech and transcription
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import type { GenerationOptions } from './commands';
+
+/**
+ * Named prompt preset; `template` uses {{input}}, {{document}} and {{date}}
+ */
+export interface PromptTemplate {
+  id: string;
+  name: string;
+  system_prompt?: string | null;
+  template: string;
+  builtin?: boolean;
+  updated_at?: number;
+}
+
+export async function listPromptTemplates(): Promise<PromptTemplate[]> {
+  return invoke<PromptTemplate[]>('list_prompt_templates');
+}
+
+/**
+ * Create (empty id) or update a prompt template
+ */
+export async function savePromptTemplate(template: PromptTemplate): Promise<PromptTemplate> {
+  return invoke<PromptTemplate>('save_prompt_template', { template });
+}
+
+export async function deletePromptTemplate(id: string): Promise<void> {
+  return invoke('delete_prompt_template', { id });
+}
+
+/**
+ * Chat with a GGUF model run in-process (no Ollama needed).
+ * Chunks arrive on the `ollama_stream_chunk` event tagged with requestId, like ollama_chat_stream.
+ */
+export async function localChatStream(
+  model: string,
+  messages: { role: string; content: string }[],
+  requestId: string,
+  options?: {
+    temperature?: number;
+    maxTokens?: number;
+    topP?: number;
+    generation?: GenerationOptions;
+    conversationId?: string;
+  }
+): Promise<void> {
+  return invoke('local_chat_stream', {
+    model,
+    messages,
+    temperature: options?.temperature,
+    maxTokens: options?.maxTokens,
+    topP: options?.topP,
+    options: options?.generation,
+    requestId,
+    conversationId: options?.conversationId,
+  });
+}
+
+/**
+ * Embed text with a GGUF embedding model run in-process
+ */
+export async function localEmbedding(model: string, text: string): Promise<number[]> {
+  return invoke<number[]>('local_embedding', { model, text });
+}
+
+/**
+ * Starter questions for an indexed document, for the empty chat screen.
+ * Also generated automatically after background indexing (`suggested_questions` event).
+ */
+export async function generateSuggestedQuestions(documentId: string, count?: number): Promise<string[]> {
+  return invoke<string[]>('generate_suggested_questions', { documentId, count });
+}
+
+export interface SpeechInfo {
+  request_id: string;
+  engine: 'piper' | 'system';
+  /** Raw mono 16-bit samples at `sample_rate`, or a WAV file split across chunks */
+  format: 'pcm_s16le' | 'wav';
+  sample_rate: number;
+}
+
+/** Payload of `tts_audio_chunk`; the last chunk has `done: true` and no data */
+export interface SpeechChunk {
+  request_id: string;
+  /** Base64-encoded audio */
+  data: string;
+  done: boolean;
+}
+
+/**
+ * Read text aloud with a local voice; audio arrives as `tts_audio_chunk` events.
+ * Starting a new utterance stops the current one.
+ */
+export async function speak(text: string, voice?: string, requestId?: string): Promise<SpeechInfo> {
+  return invoke<SpeechInfo>('speak', { text, voice, requestId });
+}
+
+/** Stop reading aloud; returns whether anything was playing */
+export async function stopSpeaking(): Promise<boolean> {
+  return invoke<boolean>('stop_speaking');
+}
+
+export interface FeedbackRecorded {
+  recorded: number;
+  /** Chunk ids not found in any index */
+  unknown: string[];
+}
+
+/**
+ * Rate the chunks an answer was grounded on. Helpful chunks rank higher in later
+ * searches of the same document, wrong ones lower; 'none' withdraws the rating.
+ */
+export async function recordFeedback(
+  conversationId: string,
+  messageId: string,
+  chunkIds: string[],
+  rating: 'helpful' | 'wrong' | 'none'
+): Promise<FeedbackRecorded> {
+  return invoke<FeedbackRecorded>('record_feedback', { conversationId, messageId, chunkIds, rating });
+}
+
+/** Payload of the `chat_stats` event, sent when a streamed answer finishes */
+export interface ChatStats {
+  request_id: string | null;
+  conversation_id: string | null;
+  model: string;
+  backend: string;
+  prompt_tokens: number | null;
+  completion_tokens: number | null;
+  tokens_per_second: number | null;
+  prompt_tokens_per_second: number | null;
+  first_token_ms: number | null;
+  total_ms: number;
+}
+
+export interface ModelStats {
+  model: string;
+  backend: string;
+  responses: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  average_tokens_per_second: number | null;
+  average_total_ms: number;
+}
+
+/** Token usage and generation speed of a conversation's answers, per model */
+export async function getConversationStats(conversationId: string): Promise<ModelStats[]> {
+  return invoke<ModelStats[]>('get_conversation_stats', { conversationId });
+}
+
+/** One of the answers generated for an assistant message; position 0 is the original */
+export interface AnswerCandidate {
+  position: number;
+  content: string;
+  temperature: number | null;
+  seed: number | null;
+  created_at: number;
+}
+
+export interface RegenerateOptions {
+  temperature?: number;
+  top_p?: number;
+  seed?: number;
+}
+
+/**
+ * Answer again from the same excerpts with a different seed and temperature.
+ * Streams as `ollama_stream_chunk` events; returns every candidate, the new one last.
+ */
+export async function regenerateAnswer(
+  conversationId: string,
+  messageId: string,
+  options?: RegenerateOptions,
+  requestId?: string
+): Promise<AnswerCandidate[]> {
+  return invoke<AnswerCandidate[]>('regenerate_answer', { conversationId, messageId, options, requestId });
+}
+
+export async function getAnswerCandidates(conversationId: string, messageId: string): Promise<AnswerCandidate[]> {
+  return invoke<AnswerCandidate[]>('get_answer_candidates', { conversationId, messageId });
+}
+
+/** A retrieved chunk as used for an answer */
+export interface SearchHit {
+  chunk_id: string;
+  text: string;
+  metadata: Record<string, unknown> | null;
+  score: number;
+  page: number | null;
+  start: number | null;
+  end: number | null;
+}
+
+/** Everything that went into an answer, for debugging why the model said what it said */
+export interface RetrievalTrace {
+  message_id: string;
+  conversation_id: string | null;
+  document_id: string;
+  /** The question, or its standalone rewrite, that chunks were retrieved for */
+  query: string;
+  chunks: SearchHit[];
+  /** Chunks left out of the prompt as suspected prompt injection */
+  excluded_chunks: string[];
+  /** Messages sent to the model, system prompt first */
+  messages: { role: string; content: string }[];
+  model: string;
+  temperature: number;
+  top_p: number;
+  generation: GenerationOptions;
+  created_at: number;
+}
+
+/** Trace of an answer asked with its message id; null when none was kept */
+export async function getRetrievalTrace(messageId: string): Promise<RetrievalTrace | null> {
+  return invoke<RetrievalTrace | null>('get_retrieval_trace', { messageId });
+}
+
+/** Payload of the `injection_warning` event, sent when retrieved text looks aimed at the model */
+export interface InjectionWarning {
+  document_id: string;
+  pages: number[];
+  /** Instruction-like phrases stripped from the excerpts */
+  phrases: string[];
+  /** Excerpts the classifier flagged and left out of the prompt */
+  excluded: number;
+}
+
+export interface Transcription {
+  request_id: string;
+  text: string;
+  /** Language the model heard, e.g. "en" */
+  language: string | null;
+  duration_ms: number;
+}
+
+/** Payload of the `transcription_partial` event: everything transcribed so far */
+export interface TranscriptionPartial {
+  request_id: string;
+  text: string;
+}
+
+/**
+ * Transcribe a dictated question locally with Whisper. `wav` is a WAV recording;
+ * omit `language` to detect it. Partial text arrives as `transcription_partial` events.
+ */
+export async function transcribeAudio(
+  wav: Uint8Array,
+  language?: string,
+  requestId?: string
+): Promise<Transcription> {
+  return invoke<Transcription>('transcribe_audio', { wavBytes: Array.from(wav), language, requestId });
+}
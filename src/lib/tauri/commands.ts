@@ -14,13 +14,161 @@ import { invoke } from '@tauri-apps/api/core';
 export interface OllamaStatus {
   running: boolean;
   models_available: boolean;
+  network_warning?: string | null;
+}
+
+/**
+ * Error returned by backend commands (the Rust `AppError`)
+ */
+export type AppErrorCode =
+  | 'OLLAMA_UNREACHABLE'
+  | 'OLLAMA_NOT_INSTALLED'
+  | 'MODEL_NOT_FOUND'
+  | 'TIMEOUT'
+  | 'NETWORK'
+  | 'HTTP'
+  | 'IO'
+  | 'PARSE'
+  | 'CANCELLED'
+  | 'CHECKSUM_MISMATCH'
+  | 'UNSUPPORTED'
+  | 'OTHER';
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as AppError).code === 'string' &&
+    typeof (error as AppError).message === 'string'
+  );
+}
+
+/**
+ * User-facing message for anything a command can reject with
+ */
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
+}
+
+/**
+ * Advanced generation options; unset fields use the server's defaults
+ */
+export interface GenerationOptions {
+  seed?: number | null;
+  stop?: string[];
+  repeat_penalty?: number | null;
+  repeat_last_n?: number | null;
+  mirostat?: 0 | 1 | 2 | null;
+  mirostat_tau?: number | null;
+  mirostat_eta?: number | null;
+  num_ctx?: number | null;
+  num_gpu?: number | null;
+  keep_alive?: string | null;
+}
+
+/** Timeouts and retry policy for requests to the model server */
+export interface NetworkSettings {
+  connect_timeout_secs: number;
+  read_timeout_secs: number;
+  status_timeout_secs: number;
+  retries: number;
+  backoff_ms: number;
+  proxy?: ProxySettings;
+}
+
+export interface ProxySettings {
+  /** system: env vars or OS proxy settings; none: direct; manual: `url` */
+  mode: 'system' | 'none' | 'manual';
+  url: string;
+  username: string;
+  /** Stored in the OS keychain */
+  password: string;
+  /** Hosts that bypass the proxy, e.g. "corp.example.com" or ".internal" */
+  no_proxy: string[];
+}
+
+/** How long data is kept; 0 keeps it forever. Applied every few hours (`retention_purged` events) */
+export interface RetentionSettings {
+  /** Delete conversations not updated for this many days */
+  chat_days: number;
+  /** Drop indexes of unpinned documents outside workspaces not opened for this many days */
+  index_days: number;
+  /** Delete retrieval traces and log files older than this many days */
+  log_days: number;
+}
+
+/** Localhost REST API: GET /documents, POST /ingest, /search and /ask with `Authorization: Bearer <token>` */
+export interface RestApiSettings {
+  enabled: boolean;
+  /** Port on 127.0.0.1 */
+  port: number;
 }
 
 export interface AppSettings {
+  /** "light", "dark", or "system" to follow the OS (`theme_changed` events report switches) */
   theme: string;
   ollama_model: string;
+  /** Model used to embed chunks and questions; changing it requires re-indexing */
+  embedding_model?: string;
   temperature: number;
   top_p: number;
+  privacy_filter?: boolean;
+  ollama_host?: string;
+  ollama_port?: number;
+  llm_backend?: 'ollama' | 'openai_compatible' | 'embedded';
+  openai_base_url?: string;
+  openai_api_key?: string;
+  /** Directory of GGUF models for the embedded backend; empty uses the app data directory */
+  local_model_dir?: string;
+  stop_on_exit?: boolean;
+  generation?: GenerationOptions;
+  network?: NetworkSettings;
+  /** Default level plus per-module overrides, e.g. "info,ollama=debug" */
+  log_level?: string;
+  /** Check for a new release at startup (opt-in) */
+  auto_update_check?: boolean;
+  /** Rewrite follow-up questions into standalone queries before retrieval */
+  rewrite_queries?: boolean;
+  /** Seconds between background Ollama pings (`ollama_health_changed` events); 0 disables */
+  health_check_interval_secs?: number;
+  /** Piper voice in `voices/` or an OS voice name for read-aloud; empty uses the OS default */
+  tts_voice?: string;
+  /** Whisper model for dictation: a ggml `.bin` path or a name in `whisper/`; empty uses "base" */
+  stt_model?: string;
+  /** Days between background model update checks (`model_updates_available` events); 0 disables */
+  model_update_check_days?: number;
+  /** Requests sent to the model server at once; others queue, chat answers first */
+  max_concurrent_requests?: number;
+  /** Extra instructions added to the system prompt of document chats */
+  system_prompt?: string;
+  /** Answer only from the retrieved excerpts; ungrounded answers are refused */
+  strict_grounding?: boolean;
+  /** Model that screens retrieved excerpts for prompt injection; empty only strips known phrases */
+  injection_classifier_model?: string;
+  /** Refuse requests to any host but the Ollama server; attempts show in `verifyNetworkIsolation` */
+  offline_mode?: boolean;
+  retention?: RetentionSettings;
+  /** Let MCP clients search the indexed documents by starting `privatepdf --mcp` */
+  mcp_server?: boolean;
+  rest_api?: RestApiSettings;
+}
+
+/** Settings a workspace or document overrides; unset fields keep the app-wide value */
+export interface SettingsOverrides {
+  ollama_model?: string | null;
+  temperature?: number | null;
+  top_p?: number | null;
+  system_prompt?: string | null;
+  rewrite_queries?: boolean | null;
+  strict_grounding?: boolean | null;
+  generation?: GenerationOptions | null;
 }
 
 // ============================================================================
@@ -42,6 +190,36 @@ export async function startOllamaService(): Promise<string> {
   return invoke<string>('start_ollama_service');
 }
 
+export interface OllamaStartupProgress {
+  status: 'starting' | 'waiting' | 'ready' | 'timeout';
+  attempt: number;
+  elapsed_ms: number;
+  message: string;
+}
+
+/**
+ * Start the Ollama service and resolve once its API is ready
+ * Progress is emitted as `ollama_startup_progress` events
+ */
+export async function startAndWaitOllama(timeoutSecs?: number): Promise<string> {
+  return invoke<string>('start_and_wait_ollama', { timeoutSecs });
+}
+
+/**
+ * Load a model into memory so the first question doesn't wait for it
+ * Defaults to the configured model and keep-alive
+ */
+export async function preloadModel(model?: string, keepAlive?: string): Promise<void> {
+  return invoke('preload_model', { model, keepAlive });
+}
+
+/**
+ * Unload a model immediately to free memory
+ */
+export async function unloadModel(model: string): Promise<void> {
+  return invoke('unload_model', { model });
+}
+
 /**
  * Save app settings to disk
  */
@@ -52,9 +230,38 @@ export async function saveSettings(settings: AppSettings): Promise<void> {
 /**
  * Load app settings from disk
  * Returns default settings if no saved settings exist
+ * With a workspace or document id, returns the settings in effect there
+ */
+export async function loadSettings(workspaceId?: string, documentId?: string): Promise<AppSettings> {
+  return invoke<AppSettings>('load_settings', { workspaceId, documentId });
+}
+
+/**
+ * Override settings for a workspace or document (by index id); null removes the overrides
+ */
+export async function saveWorkspaceSettings(workspaceId: string, overrides: SettingsOverrides | null): Promise<void> {
+  return invoke('save_workspace_settings', { workspaceId, overrides });
+}
+
+/**
+ * Overrides saved for a workspace or document, or null
+ */
+export async function loadWorkspaceSettings(workspaceId: string): Promise<SettingsOverrides | null> {
+  return invoke<SettingsOverrides | null>('load_workspace_settings', { workspaceId });
+}
+
+/**
+ * Store a secret in the OS keychain; an empty value deletes it
  */
-export async function loadSettings(): Promise<AppSettings> {
-  return invoke<AppSettings>('load_settings');
+export async function saveSecret(name: string, value: string): Promise<void> {
+  return invoke('save_secret', { name, value });
+}
+
+/**
+ * Read a secret from the OS keychain, or null if none is stored
+ */
+export async function loadSecret(name: string): Promise<string | null> {
+  return invoke<string | null>('load_secret', { name });
 }
 
 /**
@@ -75,3 +282,7 @@ export const tauriCommands = {
   loadSettings,
   resetSettings,
 };
+
+export * from './chat';
+export * from './documents';
+export * from './system';
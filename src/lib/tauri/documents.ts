@@ -0,0 +1,379 @@
+/**
+ * Tauri Commands - Indexing, PDF extraction, the document library and document sources
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export interface IndexingProgress {
+  document_id: string | null;
+  path: string;
+  name: string;
+  stage: 'extracting' | 'embedding' | 'storing';
+  percent: number;
+  queued: number;
+}
+
+export interface IndexingComplete {
+  document_id: string | null;
+  path: string;
+  name: string;
+  pages: number;
+  chunks: number;
+  already_indexed: boolean;
+  error: string | null;
+}
+
+/**
+ * Queue files for background indexing
+ * Progress is emitted as `indexing_progress` and `indexing_complete` events
+ * With `sensitive`, PII is masked before chunks are embedded and only the masked text is stored
+ * Returns the paths that were queued (unsupported files are skipped)
+ */
+export async function enqueueDocuments(paths: string[], sensitive?: boolean): Promise<string[]> {
+  return invoke<string[]>('enqueue_documents', { paths, sensitive });
+}
+
+export interface PdfTable {
+  page: number;
+  kind: 'ruled' | 'aligned';
+  rows: string[][];
+  csv: string;
+  markdown: string;
+}
+
+/**
+ * Detect tables in a PDF (all pages unless `pages` is given, 1-based)
+ */
+export async function extractPdfTables(path: string, pages?: number[]): Promise<PdfTable[]> {
+  return invoke<PdfTable[]>('extract_pdf_tables', { path, pages });
+}
+
+export interface PdfAnnotation {
+  /** 1-based page number */
+  page: number;
+  kind?: 'highlight' | 'comment';
+  /** Text to locate on the page, e.g. a citation snippet */
+  text?: string;
+  comment?: string;
+  /** [x0, y0, x1, y1] in PDF points, used when `text` isn't given or isn't found */
+  rect?: [number, number, number, number];
+  /** RGB from 0 to 1; yellow by default */
+  color?: [number, number, number];
+}
+
+export interface AnnotatedPdf {
+  output_path: string;
+  added: number;
+  /** Indexes of annotations whose text wasn't found on the page */
+  unlocated: number[];
+}
+
+/**
+ * Save a copy of a PDF with highlights and notes at the cited locations
+ */
+export async function annotatePdf(
+  path: string,
+  annotations: PdfAnnotation[],
+  outputPath: string
+): Promise<AnnotatedPdf> {
+  return invoke<AnnotatedPdf>('annotate_pdf', { path, annotations, outputPath });
+}
+
+export interface PdfImage {
+  page: number;
+  index: number;
+  width: number;
+  height: number;
+  png_base64: string;
+}
+
+/**
+ * Extract the images on one PDF page (1-based) as base64 PNG
+ */
+export async function extractPdfImages(path: string, page: number): Promise<PdfImage[]> {
+  return invoke<PdfImage[]>('extract_pdf_images', { path, page });
+}
+
+export interface PdfText {
+  page_count: number;
+  pages: { page_number: number; text: string }[];
+}
+
+/**
+ * Payload of `pdf_extraction_progress`, emitted as pages finish (out of order)
+ */
+export interface PdfExtractionProgress {
+  path: string;
+  page: number;
+  completed: number;
+  total: number;
+  percent: number;
+}
+
+/**
+ * Extract per-page PDF text on the Rust side, pages in parallel
+ */
+export async function extractPdfText(path: string): Promise<PdfText> {
+  return invoke<PdfText>('extract_text', { path });
+}
+
+/**
+ * Saved PDF viewer zoom for a document, or null if it was never zoomed
+ */
+export async function getDocumentZoom(documentId: string): Promise<number | null> {
+  return invoke<number | null>('get_document_zoom', { documentId });
+}
+
+export async function setDocumentZoom(documentId: string, zoom: number): Promise<void> {
+  return invoke('set_document_zoom', { documentId, zoom });
+}
+
+export interface ReindexReport {
+  status: 'unchanged' | 'reindexed';
+  /** New id (content hash) after re-indexing */
+  document_id: string;
+  previous_id: string;
+  chunks: number;
+  reused: number;
+  embedded: number;
+  orphans_removed: number;
+}
+
+/**
+ * Re-index a document whose file changed on disk, reusing embeddings of unchanged chunks.
+ * `indexing_complete` events carry `modified: true` when a queued file changed since indexing.
+ * `sensitive` switches the document to or from PII-masked indexing; unset keeps its mode.
+ */
+export async function reindexDocument(
+  documentId: string,
+  force?: boolean,
+  sensitive?: boolean
+): Promise<ReindexReport> {
+  return invoke<ReindexReport>('reindex_document', { documentId, force, sensitive });
+}
+
+export interface LibraryDocument {
+  path: string;
+  /** Content hash, which is also the document's index id */
+  hash: string | null;
+  title: string;
+  page_count: number | null;
+  /** Seconds since the epoch */
+  last_opened: number;
+  pinned: boolean;
+  index_status: 'indexed' | 'not_indexed';
+  /** The file moved or was deleted and couldn't be found */
+  missing: boolean;
+}
+
+/** Recently opened documents, pinned first */
+export async function listRecentDocuments(limit?: number): Promise<LibraryDocument[]> {
+  return invoke<LibraryDocument[]>('list_recent_documents', { limit });
+}
+
+/** Add a document opened in the viewer to the library; indexed files are added automatically */
+export async function addToLibrary(path: string, title?: string, pageCount?: number): Promise<void> {
+  return invoke<void>('add_to_library', { path, title, pageCount });
+}
+
+export async function pinDocument(path: string, pinned: boolean): Promise<void> {
+  return invoke<void>('pin_document', { path, pinned });
+}
+
+/** Remove a document from the recent list; the file and its index are kept */
+export async function removeFromLibrary(path: string): Promise<boolean> {
+  return invoke<boolean>('remove_from_library', { path });
+}
+
+/** File layouts for `exportIndex` */
+export type IndexFormat = 'jsonl' | 'parquet' | 'chroma';
+
+export interface IndexExport {
+  path: string;
+  chunks: number;
+  dimension: number;
+  model: string | null;
+  bytes: number;
+}
+
+/** Export a document's chunks and embeddings as JSONL, Parquet or a Chroma `collection.add()` payload */
+export async function exportIndex(documentId: string, format: IndexFormat, path: string): Promise<IndexExport> {
+  return invoke<IndexExport>('export_index', { documentId, format, path });
+}
+
+export interface IndexImport {
+  /** Id of the new index; query it like any indexed document */
+  document_id: string;
+  name: string;
+  chunks: number;
+  dimension: number;
+  model: string;
+  /** Records without text or a vector */
+  skipped: number;
+}
+
+/** Import a JSONL, Parquet or Chroma dump of chunks and vectors as a new index */
+export async function importIndex(path: string, format: IndexFormat, name?: string): Promise<IndexImport> {
+  return invoke<IndexImport>('import_index', { path, format, name });
+}
+
+/** Pasted text or screenshot text added to a conversation */
+export interface ContextDocument {
+  id: string;
+  kind: 'text' | 'screenshot';
+  title: string;
+  content: string;
+  /** Seconds since the epoch */
+  created_at: number;
+}
+
+/** Add the clipboard's text, or the OCR'd text of a copied image, to a conversation */
+export async function ingestClipboard(conversationId: string, languages?: string[]): Promise<ContextDocument> {
+  return invoke<ContextDocument>('ingest_clipboard', { conversationId, languages });
+}
+
+/** OCR a PNG or JPEG screenshot and add its text to a conversation */
+export async function ingestImage(
+  conversationId: string,
+  imageBytes: Uint8Array,
+  languages?: string[]
+): Promise<ContextDocument> {
+  return invoke<ContextDocument>('ingest_image', { conversationId, imageBytes: Array.from(imageBytes), languages });
+}
+
+export async function listContextDocuments(conversationId: string): Promise<ContextDocument[]> {
+  return invoke<ContextDocument[]>('list_context_documents', { conversationId });
+}
+
+export async function removeContextDocument(conversationId: string, documentId: string): Promise<boolean> {
+  return invoke<boolean>('remove_context_document', { conversationId, documentId });
+}
+
+export interface WatchedFolder {
+  path: string;
+  workspace_id: string;
+  /** False when the folder was missing at startup (e.g. an unplugged drive) */
+  active: boolean;
+}
+
+/** Add new documents in a folder to a workspace as they appear (`document_added` events) */
+export async function watchFolder(path: string, workspaceId: string): Promise<WatchedFolder> {
+  return invoke<WatchedFolder>('watch_folder', { path, workspaceId });
+}
+
+export async function unwatchFolder(path: string): Promise<boolean> {
+  return invoke<boolean>('unwatch_folder', { path });
+}
+
+export async function listWatchedFolders(): Promise<WatchedFolder[]> {
+  return invoke<WatchedFolder[]>('list_watched_folders');
+}
+
+export interface RemoteSource {
+  id: string;
+  kind: 'webdav' | 'share';
+  /** Collection URL for WebDAV, folder path for a share */
+  location: string;
+  username: string | null;
+  name: string;
+  /** When the files were last listed, in seconds since the epoch */
+  checked_at: number | null;
+}
+
+export interface RemoteFile {
+  /** Relative to the source, with `/` separators */
+  path: string;
+  size: number | null;
+  modified: string | null;
+  imported: boolean;
+  /** Imported, and changed on the source since */
+  changed: boolean;
+  local_path: string | null;
+}
+
+/** Register a WebDAV server or mounted share; the password goes to the OS keychain */
+export async function addRemoteSource(
+  kind: RemoteSource['kind'],
+  location: string,
+  name?: string,
+  username?: string,
+  password?: string
+): Promise<RemoteSource> {
+  return invoke<RemoteSource>('add_remote_source', { kind, location, name, username, password });
+}
+
+export async function listRemoteSources(): Promise<RemoteSource[]> {
+  return invoke<RemoteSource[]>('list_remote_sources');
+}
+
+export async function removeRemoteSource(sourceId: string): Promise<boolean> {
+  return invoke<boolean>('remove_remote_source', { sourceId });
+}
+
+/** List the documents on a source now, marking imported ones that changed */
+export async function listRemoteFiles(sourceId: string): Promise<RemoteFile[]> {
+  return invoke<RemoteFile[]>('list_remote_files', { sourceId });
+}
+
+/**
+ * Import listed files; changed files imported before are re-indexed.
+ * Changes are also picked up in the background (`remote_files_updated` events).
+ */
+export async function importRemoteFiles(sourceId: string, paths: string[], sensitive?: boolean): Promise<string[]> {
+  return invoke<string[]>('import_remote_files', { sourceId, paths, sensitive });
+}
+
+/** Open a document in its own window (or focus it); returns the window label */
+export async function openDocumentWindow(path: string): Promise<string> {
+  return invoke<string>('open_document_window', { path });
+}
+
+/** Document the current window was opened for; null in the main window */
+export async function getWindowDocument(): Promise<string | null> {
+  return invoke<string | null>('get_window_document');
+}
+
+/** A detected language */
+export interface Language {
+  /** ISO 639-3 code, e.g. "deu" */
+  code: string;
+  /** English name, e.g. "German" */
+  name: string;
+  /** 0 to 1 */
+  confidence: number;
+  /** Confident enough to act on */
+  reliable: boolean;
+}
+
+/** Payload of the `embedding_model_hint` event, sent when a non-English document is indexed with an English-only embedding model */
+export interface EmbeddingModelHint {
+  document_id: string;
+  language: Language;
+  model: string;
+  hint: string;
+}
+
+/** Detect the language of a text; null when it's too short to tell */
+export async function detectLanguage(text: string): Promise<Language | null> {
+  return invoke<Language | null>('detect_language', { text });
+}
+
+export interface PdfFinding {
+  keyword: string;
+  description: string;
+  count: number;
+}
+
+/** Result of `scan_pdf`, also sent as the `pdf_active_content` event when a PDF being indexed is suspicious */
+export interface PdfScanReport {
+  path: string;
+  suspicious: boolean;
+  findings: PdfFinding[];
+  /** Names of attachments that look like executables or scripts */
+  executable_attachments: string[];
+  /** Suspicious names written with #xx escapes */
+  obfuscated_names: number;
+  has_object_streams: boolean;
+  /** False when the file couldn't be parsed */
+  inspected: boolean;
+}
@@ -5,10 +5,13 @@
  */
 
 import { error as logError } from '@tauri-apps/plugin-log';
+import { detectModelContext, errorMessage, type GenerationOptions } from './commands';
 
 export interface Message {
   role: 'system' | 'user' | 'assistant';
   content: string;
+  /** Base64-encoded images for vision models (e.g. from extractPdfImages) */
+  images?: string[];
 }
 
 export interface OllamaStatus {
@@ -59,7 +62,7 @@ export async function checkOllamaStatus(): Promise<OllamaStatus> {
       return data;
     }
   } catch (error) {
-    throw new Error(`Failed to check Ollama status: ${error}`);
+    throw new Error(`Failed to check Ollama status: ${errorMessage(error)}`);
   }
 }
 
@@ -74,6 +77,9 @@ export async function ollamaChat(
     temperature?: number;
     maxTokens?: number;
     topP?: number;
+    generation?: GenerationOptions;
+    /** Prompt template applied server-side (Tauri command path only) */
+    templateId?: string;
   }
 ): Promise<string> {
   try {
@@ -89,6 +95,8 @@ export async function ollamaChat(
         temperature: options?.temperature,
         maxTokens: options?.maxTokens,
         topP: options?.topP,
+        options: options?.generation,
+        templateId: options?.templateId,
       });
 
       return response;
@@ -121,7 +129,7 @@ export async function ollamaChat(
       return data.message?.content || '';
     }
   } catch (error) {
-    throw new Error(`Chat failed: ${error}`);
+    throw new Error(`Chat failed: ${errorMessage(error)}`);
   }
 }
 
@@ -167,7 +175,7 @@ export async function ollamaGenerateEmbedding(
       return data.embedding || [];
     }
   } catch (error) {
-    throw new Error(`Embedding generation failed: ${error}`);
+    throw new Error(`Embedding generation failed: ${errorMessage(error)}`);
   }
 }
 
@@ -183,7 +191,10 @@ export async function* ollamaChatStream(
     temperature?: number;
     maxTokens?: number;
     topP?: number;
+    generation?: GenerationOptions;
     signal?: AbortSignal;
+    /** Adds the answer's token usage to this conversation's `chat_stats` totals */
+    conversationId?: string;
   }
 ): AsyncGenerator<string> {
   const isWindows = typeof navigator !== 'undefined' && navigator.userAgent.includes('Windows');
@@ -191,7 +202,7 @@ export async function* ollamaChatStream(
   // Windows: Use Tauri command with event-based streaming
   if (isWindows) {
     const { invoke } = await import('@tauri-apps/api/core');
-    const { listen } = await import('@tauri-apps/api/event');
+    const { getCurrentWebviewWindow } = await import('@tauri-apps/api/webviewWindow');
 
     // Use an async queue pattern to yield chunks as they arrive
     const chunkQueue: string[] = [];
@@ -199,9 +210,14 @@ export async function* ollamaChatStream(
     let streamError: Error | null = null;
     let resolveWaiting: (() => void) | null = null;
 
+    // Chunks of every concurrent stream arrive on the same event, tagged with their request id
+    const requestId = crypto.randomUUID();
+
     // Set up event listener BEFORE starting the stream
-    const unlisten = await listen('ollama_stream_chunk', (event: any) => {
-      const { content, done, error } = event.payload;
+    // Chunks are sent to the window that started the stream, so listen on this window only
+    const unlisten = await getCurrentWebviewWindow().listen('ollama_stream_chunk', (event: any) => {
+      const { request_id, content, done, error } = event.payload;
+      if (request_id !== requestId) return;
 
       if (error) {
         streamError = new Error(error);
@@ -232,8 +248,11 @@ export async function* ollamaChatStream(
       temperature: options?.temperature,
       maxTokens: options?.maxTokens,
       topP: options?.topP,
+      options: options?.generation,
+      requestId,
+      conversationId: options?.conversationId,
     }).catch(error => {
-      streamError = error instanceof Error ? error : new Error(String(error));
+      streamError = error instanceof Error ? error : new Error(errorMessage(error));
       isDone = true;
       if (resolveWaiting) resolveWaiting();
     });
@@ -269,6 +288,7 @@ export async function* ollamaChatStream(
 
   // Linux/Mac: Use native fetch
   const isGemma2 = model.includes('gemma2');
+  const { num_ctx } = await detectModelContext(model);
   const requestBody = {
     model,
     messages,
@@ -277,7 +297,7 @@ export async function* ollamaChatStream(
     options: {
       temperature: options?.temperature || 0.2,
       num_predict: options?.maxTokens || 4096,
-      num_ctx: 16384,
+      num_ctx,
       top_p: options?.topP || 0.9,
       repeat_penalty: isGemma2 ? undefined : 1.1,
       repeat_last_n: 64,
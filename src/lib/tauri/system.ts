@@ -0,0 +1,263 @@
+/**
+ * Tauri Commands - Hardware, models, diagnostics, bundles, retention and other app-wide commands
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export interface GpuInfo {
+  name: string;
+  vendor: 'nvidia' | 'amd' | 'apple' | 'intel';
+  vram_bytes: number | null;
+}
+
+export interface HardwareInfo {
+  os: string;
+  arch: string;
+  cpu_threads: number;
+  total_memory_bytes: number;
+  available_memory_bytes: number;
+  gpus: GpuInfo[];
+}
+
+export interface CatalogEntry {
+  name: string;
+  display_name: string;
+  kind: 'chat' | 'embedding';
+  tier: 'light' | 'medium' | 'large';
+  params: string;
+  size_bytes: number;
+  min_ram_bytes: number;
+  min_vram_bytes: number;
+  vision: boolean;
+  description: string;
+  fits: boolean;
+  gpu_accelerated: boolean;
+}
+
+export interface ModelCatalog {
+  hardware: HardwareInfo;
+  models: CatalogEntry[];
+  recommended_chat: string | null;
+}
+
+export async function detectHardware(): Promise<HardwareInfo> {
+  return invoke<HardwareInfo>('detect_hardware');
+}
+
+/**
+ * Recommended models for this machine; pass includeAll to also get ones that don't fit
+ */
+export async function getModelCatalog(includeAll?: boolean): Promise<ModelCatalog> {
+  return invoke<ModelCatalog>('get_model_catalog', { includeAll });
+}
+
+export interface BundleReport {
+  path: string;
+  /** Size of the encrypted file (export only) */
+  bytes: number;
+  indexes: number;
+  chunks: number;
+  workspaces: number;
+  conversations: number;
+}
+
+/**
+ * Write indexes, embeddings, workspaces and chat history to one passphrase-encrypted file
+ */
+export async function exportWorkspaceBundle(path: string, passphrase: string): Promise<BundleReport> {
+  return invoke<BundleReport>('export_workspace_bundle', { path, passphrase });
+}
+
+/**
+ * Merge a bundle from another machine; items with the same id are updated
+ */
+export async function importWorkspaceBundle(path: string, passphrase: string): Promise<BundleReport> {
+  return invoke<BundleReport>('import_workspace_bundle', { path, passphrase });
+}
+
+/**
+ * Bug-report JSON: versions, hardware, Ollama state and log tail, recent request errors.
+ * Prompts and document text are redacted on the Rust side.
+ */
+export async function getDiagnostics(): Promise<Record<string, unknown>> {
+  return invoke<Record<string, unknown>>('get_diagnostics');
+}
+
+/**
+ * Change log levels at runtime (e.g. "info" or "warn,ollama=debug"); persist also saves the setting
+ */
+export async function setLogLevel(level: string, persist?: boolean): Promise<void> {
+  return invoke('set_log_level', { level, persist });
+}
+
+export interface UpdateInfo {
+  available: boolean;
+  current_version: string;
+  version: string | null;
+  notes: string | null;
+  date: string | null;
+  /** Downloaded and installed; applies after a restart */
+  installed: boolean;
+}
+
+/**
+ * Check the release feed for a signed update; with install, download and install it
+ * (progress on `update_download_progress`). Relaunch afterwards to apply.
+ */
+export async function checkForUpdates(install?: boolean): Promise<UpdateInfo> {
+  return invoke<UpdateInfo>('check_for_updates', { install });
+}
+
+export interface EmbeddingModelStatus {
+  model: string;
+  /** null when the backend isn't Ollama */
+  installed: boolean | null;
+  /** Indexes built with another model; re-index them to search with the current one */
+  mismatched_indexes: number;
+}
+
+/**
+ * Verify the embedding model is installed; emits `embedding_model_missing` ({ model }) if not,
+ * so the UI can offer downloadOllamaModel
+ */
+export async function checkEmbeddingModel(): Promise<EmbeddingModelStatus> {
+  return invoke<EmbeddingModelStatus>('check_embedding_model');
+}
+
+/** Payload of `ollama_health_changed` and `getOllamaHealth` */
+export interface OllamaHealth {
+  /** State of the server process PrivatePDF started */
+  state: 'stopped' | 'running' | 'crashed' | 'failed';
+  pid: number | null;
+  restarts: number;
+  message: string | null;
+  /** The API answered the last background ping */
+  running: boolean;
+  latency_ms: number | null;
+}
+
+export async function getOllamaHealth(): Promise<OllamaHealth> {
+  return invoke<OllamaHealth>('get_ollama_health');
+}
+
+/** One entry of `checkModelUpdates` and the `model_updates_available` event */
+export interface ModelUpdate {
+  name: string;
+  status: 'up_to_date' | 'update_available' | 'not_in_registry' | 'unknown';
+  local_digest: string | null;
+  remote_digest: string | null;
+  error: string | null;
+}
+
+/** Compare installed models with the Ollama registry */
+export async function checkModelUpdates(): Promise<ModelUpdate[]> {
+  return invoke<ModelUpdate[]>('check_model_updates');
+}
+
+/** What `applyRetention` and the `retention_purged` event removed */
+export interface RetentionReport {
+  conversations: number;
+  indexes: number;
+  traces: number;
+  log_files: number;
+}
+
+/** Apply the retention settings now instead of at the next scheduled purge */
+export async function applyRetention(): Promise<RetentionReport> {
+  return invoke<RetentionReport>('apply_retention');
+}
+
+/** Token for the REST API, generated and kept in the OS keychain on first use */
+export async function getRestApiToken(): Promise<string> {
+  return invoke<string>('get_rest_api_token');
+}
+
+/** Replace the REST API token; scripts using the old one are refused */
+export async function resetRestApiToken(): Promise<string> {
+  return invoke<string>('reset_rest_api_token');
+}
+
+/** Pull the newest version of a model; progress arrives as `model_download_progress` */
+export async function updateModel(name: string): Promise<void> {
+  return invoke<void>('update_model', { name });
+}
+
+/** Payload of `getRequestQueue` and the `request_queue_changed` event */
+export interface QueueStatus {
+  in_flight: number;
+  max_in_flight: number;
+  interactive_waiting: number;
+  embedding_waiting: number;
+  background_waiting: number;
+  /** Queued requests with a request id; position 1 runs next */
+  queued: { request_id: string; lane: 'interactive' | 'embedding' | 'background'; position: number }[];
+}
+
+/** Model server requests running and waiting */
+export async function getRequestQueue(): Promise<QueueStatus> {
+  return invoke<QueueStatus>('get_request_queue');
+}
+
+/** Context sizes detected for a model */
+export interface ModelContext {
+  model: string;
+  /** Longest context the model was trained for */
+  context_length: number | null;
+  parameter_size: string | null;
+  /** Context requested from the server for chats */
+  num_ctx: number;
+  /** Chat history sent verbatim before older turns are summarized */
+  history_tokens: number;
+  /** Chunks retrieved per question by default */
+  retrieval_top_k: number;
+}
+
+/** Detect a model's context window (the selected chat model by default); call when a model is selected */
+export async function detectModelContext(model?: string): Promise<ModelContext> {
+  return invoke<ModelContext>('detect_model_context', { model });
+}
+
+/** The `theme` setting and what it resolves to; also the payload of `theme_changed` events */
+export interface ThemeState {
+  setting: 'light' | 'dark' | 'system';
+  theme: 'light' | 'dark';
+}
+
+export async function getTheme(): Promise<ThemeState> {
+  return invoke<ThemeState>('get_theme');
+}
+
+export interface PortableMode {
+  /** Started with `--portable` or a `portable.flag` file next to the executable */
+  enabled: boolean;
+  /** Where settings and indexes are stored */
+  data_dir: string | null;
+}
+
+export async function getPortableMode(): Promise<PortableMode> {
+  return invoke<PortableMode>('get_portable_mode');
+}
+
+/** An outgoing request the network guard refused */
+export interface BlockedRequest {
+  url: string;
+  reason: string;
+  /** Seconds since the epoch */
+  at: number;
+}
+
+export interface NetworkIsolationReport {
+  inference_hosts: string[];
+  download_hosts: string[];
+  inference_local_only: boolean;
+  /** WebDAV servers registered as remote sources */
+  remote_source_hosts: string[];
+  offline_mode: boolean;
+  /** Requests refused since launch, oldest first */
+  blocked_requests: BlockedRequest[];
+}
+
+/** Hosts the app may reach, and the requests the guard refused since launch */
+export async function verifyNetworkIsolation(): Promise<NetworkIsolationReport> {
+  return invoke<NetworkIsolationReport>('verify_network_isolation');
+}